@@ -1,11 +1,8 @@
-use gltf_json as json;
-
 use gltf_transform_rs as gltf;
-use std::{fs, mem};
 
-use json::validation::Checked::Valid;
-use std::borrow::Cow;
-use std::io::Write;
+use gltf::graph::{Accessor, Graph, Mesh, Node, Primitive, Scene};
+use gltf::Semantic;
+use std::fs;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 enum Output {
@@ -16,196 +13,43 @@ enum Output {
     Binary,
 }
 
-#[derive(Copy, Clone, Debug)]
-#[repr(C)]
-struct Vertex {
-    position: [f32; 3],
-    color: [f32; 3],
-}
-
-/// Calculate bounding coordinates of a list of vertices, used for the clipping distance of the model
-fn bounding_coords(points: &[Vertex]) -> ([f32; 3], [f32; 3]) {
-    let mut min = [f32::MAX, f32::MAX, f32::MAX];
-    let mut max = [f32::MIN, f32::MIN, f32::MIN];
-
-    for point in points {
-        let p = point.position;
-        for i in 0..3 {
-            min[i] = f32::min(min[i], p[i]);
-            max[i] = f32::max(max[i], p[i]);
-        }
-    }
-    (min, max)
-}
-
-fn align_to_multiple_of_four(n: &mut u32) {
-    *n = (*n + 3) & !3;
-}
-
-fn to_padded_byte_vector<T>(vec: Vec<T>) -> Vec<u8> {
-    let byte_length = vec.len() * mem::size_of::<T>();
-    let byte_capacity = vec.capacity() * mem::size_of::<T>();
-    let alloc = vec.into_boxed_slice();
-    let ptr = Box::<[T]>::into_raw(alloc) as *mut u8;
-    let mut new_vec = unsafe { Vec::from_raw_parts(ptr, byte_length, byte_capacity) };
-    while new_vec.len() % 4 != 0 {
-        new_vec.push(0); // pad to multiple of four bytes
-    }
-    new_vec
-}
-
 fn export(output: Output) {
-    let triangle_vertices = vec![
-        Vertex {
-            position: [0.0, 0.5, 0.0],
-            color: [1.0, 0.0, 0.0],
-        },
-        Vertex {
-            position: [-0.5, -0.5, 0.0],
-            color: [0.0, 1.0, 0.0],
-        },
-        Vertex {
-            position: [0.5, -0.5, 0.0],
-            color: [0.0, 0.0, 1.0],
-        },
-    ];
-
-    let (min, max) = bounding_coords(&triangle_vertices);
-
-    let buffer_length = (triangle_vertices.len() * mem::size_of::<Vertex>()) as u32;
-    let buffer = json::Buffer {
-        byte_length: buffer_length,
-        extensions: Default::default(),
-        extras: Default::default(),
-        name: None,
-        uri: if output == Output::Standard {
-            Some("buffer0.bin".into())
-        } else {
-            None
-        },
-    };
-    let buffer_view = json::buffer::View {
-        buffer: json::Index::new(0),
-        byte_length: buffer.byte_length,
-        byte_offset: None,
-        byte_stride: Some(mem::size_of::<Vertex>() as u32),
-        extensions: Default::default(),
-        extras: Default::default(),
-        name: None,
-        target: Some(Valid(json::buffer::Target::ArrayBuffer)),
-    };
-    let positions = json::Accessor {
-        buffer_view: Some(json::Index::new(0)),
-        byte_offset: 0,
-        count: triangle_vertices.len() as u32,
-        component_type: Valid(json::accessor::GenericComponentType(
-            json::accessor::ComponentType::F32,
-        )),
-        extensions: Default::default(),
-        extras: Default::default(),
-        type_: Valid(json::accessor::Type::Vec3),
-        min: Some(json::Value::from(Vec::from(min))),
-        max: Some(json::Value::from(Vec::from(max))),
-        name: None,
-        normalized: false,
-        sparse: None,
-    };
-    let colors = json::Accessor {
-        buffer_view: Some(json::Index::new(0)),
-        byte_offset: (3 * mem::size_of::<f32>()) as u32,
-        count: triangle_vertices.len() as u32,
-        component_type: Valid(json::accessor::GenericComponentType(
-            json::accessor::ComponentType::F32,
-        )),
-        extensions: Default::default(),
-        extras: Default::default(),
-        type_: Valid(json::accessor::Type::Vec3),
-        min: None,
-        max: None,
-        name: None,
-        normalized: false,
-        sparse: None,
-    };
+    let positions = [[0.0, 0.5, 0.0], [-0.5, -0.5, 0.0], [0.5, -0.5, 0.0]];
+    let colors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
 
-    let primitive = json::mesh::Primitive {
-        attributes: {
-            let mut map = std::collections::HashMap::new();
-            map.insert(Valid(json::mesh::Semantic::Positions), json::Index::new(0));
-            map.insert(Valid(json::mesh::Semantic::Colors(0)), json::Index::new(1));
-            map
-        },
-        extensions: Default::default(),
-        extras: Default::default(),
-        indices: None,
-        material: None,
-        mode: Valid(json::mesh::Mode::Triangles),
-        targets: None,
-    };
+    let mut graph = Graph::new();
+    let positions = graph.add(Accessor::from_f32(&positions));
+    let colors = graph.add(Accessor::from_f32(&colors));
 
-    let mesh = json::Mesh {
-        extensions: Default::default(),
-        extras: Default::default(),
-        name: None,
+    let mut primitive = Primitive::default();
+    primitive.set(Semantic::Positions, positions);
+    primitive.set(Semantic::Colors(0), colors);
+    let mesh = graph.add(Mesh {
         primitives: vec![primitive],
-        weights: None,
-    };
-
-    let node = json::Node {
-        camera: None,
-        children: None,
-        extensions: Default::default(),
-        extras: Default::default(),
-        matrix: None,
-        mesh: Some(json::Index::new(0)),
-        name: None,
-        rotation: None,
-        scale: None,
-        translation: None,
-        skin: None,
-        weights: None,
-    };
+        ..Default::default()
+    });
 
-    let root = json::Root {
-        accessors: vec![positions, colors],
-        buffers: vec![buffer],
-        buffer_views: vec![buffer_view],
-        meshes: vec![mesh],
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    let scene = graph.add(Scene {
         nodes: vec![node],
-        scenes: vec![json::Scene {
-            extensions: Default::default(),
-            extras: Default::default(),
-            name: None,
-            nodes: vec![json::Index::new(0)],
-        }],
         ..Default::default()
-    };
+    });
+    graph.default_scene = Some(scene);
 
     match output {
         Output::Standard => {
             let _ = fs::create_dir("triangle");
-
-            let writer = fs::File::create("triangle/triangle.gltf").expect("I/O error");
-            json::serialize::to_writer_pretty(writer, &root).expect("Serialization error");
-
-            let bin = to_padded_byte_vector(triangle_vertices);
-            let mut writer = fs::File::create("triangle/buffer0.bin").expect("I/O error");
-            writer.write_all(&bin).expect("I/O error");
+            graph
+                .export("triangle/triangle.gltf")
+                .expect("glTF output error");
         }
         Output::Binary => {
-            let json_string = json::serialize::to_string(&root).expect("Serialization error");
-            let mut json_offset = json_string.len() as u32;
-            align_to_multiple_of_four(&mut json_offset);
-            let glb = gltf::binary::Glb {
-                header: gltf::binary::Header {
-                    magic: *b"glTF",
-                    version: 2,
-                    length: json_offset + buffer_length,
-                },
-                bin: Some(Cow::Owned(to_padded_byte_vector(triangle_vertices))),
-                json: Cow::Owned(json_string.into_bytes()),
-            };
-            let writer = std::fs::File::create("triangle.glb").expect("I/O error");
-            glb.to_writer(writer).expect("glTF binary output error");
+            graph
+                .export("triangle.glb")
+                .expect("glTF binary output error");
         }
     }
 }
@@ -3,6 +3,9 @@ use crate::{accessor, scene, Document};
 #[cfg(feature = "utils")]
 use crate::Buffer;
 
+#[cfg(feature = "utils")]
+use self::util::{Keyframes, Value};
+
 pub use json::animation::{Interpolation, Property};
 
 /// Iterators.
@@ -13,10 +16,17 @@ pub mod iter;
 #[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
 pub mod util;
 
+#[cfg(feature = "utils")]
+mod player;
+
 #[cfg(feature = "utils")]
 #[doc(inline)]
 pub use self::util::Reader;
 
+#[cfg(feature = "utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+pub use self::player::{Player, Pose, Track, Wrap};
+
 /// A keyframe animation.
 #[derive(Clone, Debug)]
 pub struct Animation<'a> {
@@ -147,6 +157,33 @@ impl<'a> Channel<'a> {
         }
     }
 
+    /// Evaluates the channel at time `t`, returning a value of the kind its
+    /// target property expects.
+    ///
+    /// Returns `None` if the keyframes cannot be read or their outputs do not
+    /// match the property. See [`Sampler::evaluate`].
+    ///
+    /// [`Sampler::evaluate`]: struct.Sampler.html#method.evaluate
+    #[cfg(feature = "utils")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+    pub fn sample<'s, F>(&self, t: f32, get_buffer_data: F) -> Option<Value>
+    where
+        F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+    {
+        let keyframes = self.sampler().keyframes(get_buffer_data)?;
+        let property = self.target().property();
+        if !keyframes.fits(property) {
+            return None;
+        }
+        let value = keyframes.sample(t);
+        Some(match property {
+            Property::Translation => Value::Translation([value[0], value[1], value[2]]),
+            Property::Rotation => Value::Rotation([value[0], value[1], value[2], value[3]]),
+            Property::Scale => Value::Scale([value[0], value[1], value[2]]),
+            Property::MorphTargetWeights => Value::MorphTargetWeights(value),
+        })
+    }
+
     /// Optional application specific data.
     pub fn extras(&self) -> &'a json::Extras {
         &self.json.extras
@@ -210,6 +247,40 @@ impl<'a> Sampler<'a> {
             .unwrap()
     }
 
+    /// Reads the keyframes of the sampler so they can be evaluated at any
+    /// time.
+    ///
+    /// Normalized integer outputs are cast to `f32`, and four-component
+    /// outputs are treated as rotation quaternions. Returns `None` if the
+    /// buffer data is missing or the accessors do not hold animation data.
+    #[cfg(feature = "utils")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+    pub fn keyframes<'s, F>(&self, get_buffer_data: F) -> Option<Keyframes>
+    where
+        F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+    {
+        util::read_keyframes(self, get_buffer_data)
+    }
+
+    /// Evaluates the sampler at time `t`, returning one output value.
+    ///
+    /// Times outside the keyframe range are clamped. `STEP` holds each value
+    /// until the next keyframe, `LINEAR` interpolates linearly, or spherically
+    /// for rotations, and `CUBICSPLINE` evaluates the Hermite spline through
+    /// the keyframe values and tangents. For morph target weights the value
+    /// holds one weight per target. Use [`keyframes`] to evaluate the same
+    /// sampler many times without reading it again.
+    ///
+    /// [`keyframes`]: #method.keyframes
+    #[cfg(feature = "utils")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+    pub fn evaluate<'s, F>(&self, t: f32, get_buffer_data: F) -> Option<Vec<f32>>
+    where
+        F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+    {
+        Some(self.keyframes(get_buffer_data)?.sample(t))
+    }
+
     /// Returns the keyframe interpolation algorithm.
     pub fn interpolation(&self) -> Interpolation {
         self.json.interpolation.unwrap()
@@ -0,0 +1,357 @@
+use crate::animation::util::{normalize, Keyframes};
+use crate::animation::{Animation, Property};
+use crate::scene::{Hierarchy, Transform};
+use crate::{Buffer, Node, Scene};
+
+/// How a [`Track`] maps times beyond the end of its animation.
+///
+/// [`Track`]: struct.Track.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Wrap {
+    /// Holds the first and last keyframe values outside the animation.
+    #[default]
+    Clamp,
+
+    /// Restarts the animation from time zero each time it ends.
+    Loop,
+
+    /// Plays the animation forwards, then backwards, and so on.
+    PingPong,
+}
+
+/// An animation played by a [`Player`] at a given time and blend weight.
+///
+/// [`Player`]: struct.Player.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    /// The index of the animation to play.
+    pub animation: usize,
+
+    /// The playback time in seconds.
+    pub time: f32,
+
+    /// The blend weight of the animation. Tracks with a weight of zero or
+    /// less are ignored.
+    pub weight: f32,
+
+    /// How times beyond the end of the animation are mapped.
+    pub wrap: Wrap,
+}
+
+impl Track {
+    /// Creates a track that plays `animation` at `time` with a weight of
+    /// `1.0`, clamping times beyond its end.
+    pub fn new(animation: &Animation, time: f32) -> Self {
+        Self {
+            animation: animation.index(),
+            time,
+            weight: 1.0,
+            wrap: Wrap::Clamp,
+        }
+    }
+}
+
+/// The keyframes of a channel that targets a node of the scene.
+#[derive(Clone, Debug)]
+struct Target {
+    /// The index of the target node.
+    node: usize,
+
+    /// The node's property to modify.
+    property: Property,
+
+    /// The keyframes of the channel sampler.
+    keyframes: Keyframes,
+}
+
+/// The channels of an animation.
+#[derive(Clone, Debug, Default)]
+struct Clip {
+    /// The readable channels that target nodes of the scene.
+    targets: Vec<Target>,
+
+    /// The time of the last keyframe of any channel.
+    duration: f32,
+}
+
+/// Poses the nodes of a scene by applying the channels of its animations.
+///
+/// Keyframes are read once when the player is created. Each call to [`pose`]
+/// then evaluates any number of animations at their own times and blends
+/// them into one pose.
+///
+/// [`pose`]: #method.pose
+#[derive(Clone, Debug)]
+pub struct Player<'a> {
+    /// The node hierarchy of the document.
+    hierarchy: Hierarchy<'a>,
+
+    /// Whether each node belongs to the scene.
+    in_scene: Vec<bool>,
+
+    /// The transform of each node when no animation applies.
+    rest: Vec<Transform>,
+
+    /// The morph target weights of each node when no animation applies.
+    rest_weights: Vec<Option<Vec<f32>>>,
+
+    /// The channels of each animation, by index.
+    clips: Vec<Clip>,
+}
+
+/// The local and world matrices and morph target weights of the nodes of a
+/// scene at one point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pose {
+    /// The local matrix of each node, or `None` for nodes outside the scene.
+    locals: Vec<Option<[[f32; 4]; 4]>>,
+
+    /// The world matrix of each node, or `None` for nodes outside the scene
+    /// or on or below a cycle.
+    worlds: Vec<Option<[[f32; 4]; 4]>>,
+
+    /// The morph target weights of each node, if any.
+    weights: Vec<Option<Vec<f32>>>,
+}
+
+/// The weighted values that tracks give the translation, rotation, scale and
+/// morph target weights of a node.
+type Contributions = [Vec<(f32, Vec<f32>)>; 4];
+
+/// Returns the slot of `property` in the per-node list of contributions.
+fn slot(property: Property) -> usize {
+    match property {
+        Property::Translation => 0,
+        Property::Rotation => 1,
+        Property::Scale => 2,
+        Property::MorphTargetWeights => 3,
+    }
+}
+
+/// Maps a playback time onto the keyframe times of an animation.
+fn wrap_time(time: f32, duration: f32, wrap: Wrap) -> f32 {
+    if duration <= 0.0 {
+        return time;
+    }
+    match wrap {
+        Wrap::Clamp => time,
+        Wrap::Loop => time.rem_euclid(duration),
+        Wrap::PingPong => {
+            let time = time.rem_euclid(2.0 * duration);
+            if time > duration {
+                2.0 * duration - time
+            } else {
+                time
+            }
+        }
+    }
+}
+
+/// Blends weighted values with the rest value.
+///
+/// When the weights sum to less than one, the rest value makes up the
+/// difference; otherwise the values are averaged by weight. Rotations are
+/// aligned to the same hemisphere before they are summed, then normalized.
+fn blend(rest: &[f32], values: &[(f32, Vec<f32>)], rotation: bool) -> Vec<f32> {
+    let total: f32 = values.iter().map(|(weight, _)| weight).sum();
+    let mut result: Vec<f32> = rest.iter().map(|x| x * (1.0 - total).max(0.0)).collect();
+    let reference = if total < 1.0 { rest } else { &values[0].1 };
+    for (weight, value) in values {
+        let dot: f32 = reference.iter().zip(value).map(|(a, b)| a * b).sum();
+        let weight = if rotation && dot < 0.0 {
+            -weight
+        } else {
+            *weight
+        };
+        for (x, v) in result.iter_mut().zip(value) {
+            *x += weight * v;
+        }
+    }
+    if rotation {
+        normalize(&mut result);
+    } else if total > 1.0 {
+        result.iter_mut().for_each(|x| *x /= total);
+    }
+    result
+}
+
+impl<'a> Player<'a> {
+    /// Reads the animations of the document that contains `scene`.
+    ///
+    /// Nodes belong to the scene when the root of their tree is one of the
+    /// scene's root nodes. Channels that target other nodes, and channels
+    /// whose keyframes cannot be read or do not match their property, are
+    /// ignored.
+    pub fn new<'s, F>(scene: &Scene<'a>, get_buffer_data: F) -> Self
+    where
+        F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+    {
+        let document = scene.document();
+        let hierarchy = Hierarchy::new(document);
+        let roots: Vec<usize> = scene.nodes().map(|x| x.index()).collect();
+        let in_scene = document
+            .nodes()
+            .map(|node| {
+                let root = hierarchy
+                    .path_from_root(&node)
+                    .and_then(|x| x.first().map(|x| x.index()));
+                root.is_some_and(|x| roots.contains(&x))
+            })
+            .collect::<Vec<_>>();
+        let rest = document.nodes().map(|node| node.transform()).collect();
+        let rest_weights = document
+            .nodes()
+            .map(|node| {
+                let mesh = node.mesh();
+                let weights = node
+                    .weights()
+                    .or_else(|| mesh.as_ref().and_then(|x| x.weights()));
+                match weights {
+                    Some(weights) => Some(weights.to_vec()),
+                    None => mesh
+                        .and_then(|x| x.primitives().next())
+                        .map(|x| vec![0.0; x.morph_targets().count()])
+                        .filter(|x| !x.is_empty()),
+                }
+            })
+            .collect();
+        let clips = document
+            .animations()
+            .map(|animation| {
+                let mut clip = Clip::default();
+                for channel in animation.channels() {
+                    let node = channel.target().node().index();
+                    let property = channel.target().property();
+                    if !in_scene[node] {
+                        continue;
+                    }
+                    let keyframes = match channel.sampler().keyframes(get_buffer_data.clone()) {
+                        Some(keyframes) if keyframes.fits(property) => keyframes,
+                        _ => continue,
+                    };
+                    clip.duration = clip.duration.max(keyframes.time_range().1);
+                    clip.targets.push(Target {
+                        node,
+                        property,
+                        keyframes,
+                    });
+                }
+                clip
+            })
+            .collect();
+        Self {
+            hierarchy,
+            in_scene,
+            rest,
+            rest_weights,
+            clips,
+        }
+    }
+
+    /// Returns the time of the last keyframe of `animation` that targets the
+    /// scene, or `None` if the animation is not part of the document.
+    pub fn duration(&self, animation: &Animation) -> Option<f32> {
+        self.clips.get(animation.index()).map(|x| x.duration)
+    }
+
+    /// Evaluates and blends the animations of `tracks`.
+    ///
+    /// Each animated property is blended separately. Where the weights of the
+    /// tracks that animate a property sum to less than one, the rest pose
+    /// makes up the difference; otherwise the tracks are averaged by weight.
+    /// Properties no track animates keep their rest value. Tracks whose
+    /// animation is not part of the document are ignored.
+    pub fn pose(&self, tracks: &[Track]) -> Pose {
+        let count = self.rest.len();
+        let mut values: Vec<Contributions> = vec![Default::default(); count];
+        for track in tracks {
+            let clip = match self.clips.get(track.animation) {
+                Some(clip) if track.weight > 0.0 => clip,
+                _ => continue,
+            };
+            let time = wrap_time(track.time, clip.duration, track.wrap);
+            for target in &clip.targets {
+                let value = target.keyframes.sample(time);
+                values[target.node][slot(target.property)].push((track.weight, value));
+            }
+        }
+
+        let mut locals = Vec::with_capacity(count);
+        let mut weights = Vec::with_capacity(count);
+        for (node, values) in values.iter().enumerate() {
+            let rest = self.rest[node].clone();
+            let rest_weights = &self.rest_weights[node];
+            if values[..3].iter().all(|x| x.is_empty()) {
+                locals.push(rest.matrix());
+            } else {
+                let (translation, rotation, scale) = rest.decomposed();
+                let translation = blend(&translation, &values[0], false);
+                let rotation = blend(&rotation, &values[1], true);
+                let scale = blend(&scale, &values[2], false);
+                let transform = Transform::Decomposed {
+                    translation: [translation[0], translation[1], translation[2]],
+                    rotation: [rotation[0], rotation[1], rotation[2], rotation[3]],
+                    scale: [scale[0], scale[1], scale[2]],
+                };
+                locals.push(transform.matrix());
+            }
+            let animated = match values[3].first() {
+                Some((_, value)) => {
+                    let width = value.len();
+                    let rest = rest_weights.clone().unwrap_or_else(|| vec![0.0; width]);
+                    let values: Vec<_> = values[3]
+                        .iter()
+                        .filter(|(_, value)| value.len() == rest.len())
+                        .cloned()
+                        .collect();
+                    Some(blend(&rest, &values, false))
+                }
+                None => None,
+            };
+            weights.push(animated.or_else(|| rest_weights.clone()));
+        }
+
+        let worlds = self.hierarchy.world_matrices(&locals);
+        let mask = |x: Option<[[f32; 4]; 4]>, node: usize| x.filter(|_| self.in_scene[node]);
+        Pose {
+            locals: locals
+                .into_iter()
+                .enumerate()
+                .map(|(node, x)| mask(Some(x), node))
+                .collect(),
+            worlds: worlds
+                .into_iter()
+                .enumerate()
+                .map(|(node, x)| mask(x, node))
+                .collect(),
+            weights: weights
+                .into_iter()
+                .enumerate()
+                .map(|(node, x)| x.filter(|_| self.in_scene[node]))
+                .collect(),
+        }
+    }
+}
+
+impl Pose {
+    /// Returns the posed local matrix of `node`, or `None` if the node is not
+    /// part of the scene.
+    pub fn local_matrix(&self, node: &Node) -> Option<[[f32; 4]; 4]> {
+        *self.locals.get(node.index())?
+    }
+
+    /// Returns the posed world matrix of `node`, or `None` if the node is not
+    /// part of the scene.
+    pub fn world_matrix(&self, node: &Node) -> Option<[[f32; 4]; 4]> {
+        *self.worlds.get(node.index())?
+    }
+
+    /// Returns the posed morph target weights of `node`, or `None` if the
+    /// node is not part of the scene or has no morph targets.
+    ///
+    /// Unanimated weights are those of the node, else those of its mesh, else
+    /// zero for each morph target.
+    pub fn weights(&self, node: &Node) -> Option<&[f32]> {
+        self.weights.get(node.index())?.as_deref()
+    }
+}
@@ -0,0 +1,262 @@
+use crate::animation::{Interpolation, Property};
+
+/// The keyframes of an animation sampler, ready to be evaluated at any time.
+///
+/// Outputs are stored as `f32`, one run of [`width`] components per keyframe,
+/// or three runs (in-tangent, value, out-tangent) for cubic spline
+/// interpolation. Rotations are unit quaternions in `[x, y, z, w]` order.
+///
+/// [`width`]: #method.width
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframes {
+    /// The keyframe interpolation algorithm.
+    interpolation: Interpolation,
+
+    /// The keyframe times, in ascending order.
+    inputs: Vec<f32>,
+
+    /// The keyframe outputs.
+    outputs: Vec<f32>,
+
+    /// The number of components of each output value.
+    width: usize,
+
+    /// Whether outputs are rotation quaternions.
+    rotation: bool,
+}
+
+/// A value sampled from an animation channel.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// XYZ translation.
+    Translation([f32; 3]),
+
+    /// XYZW rotation quaternion.
+    Rotation([f32; 4]),
+
+    /// XYZ scale.
+    Scale([f32; 3]),
+
+    /// Morph target weights.
+    MorphTargetWeights(Vec<f32>),
+}
+
+/// Returns the dot product of two quaternions.
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Scales `q` to unit length, leaving zero quaternions unchanged.
+pub(crate) fn normalize(q: &mut [f32]) {
+    let length = dot(q, q).sqrt();
+    if length > 0.0 {
+        q.iter_mut().for_each(|x| *x /= length);
+    }
+}
+
+/// Spherically interpolates between two unit quaternions along the shortest
+/// path, falling back to normalized linear interpolation for nearly equal
+/// rotations.
+pub(crate) fn slerp(a: &[f32], b: &[f32], s: f32) -> [f32; 4] {
+    let mut cos = dot(a, b);
+    let sign = if cos < 0.0 { -1.0 } else { 1.0 };
+    cos *= sign;
+    let (wa, wb) = if cos > 0.9995 {
+        (1.0 - s, s)
+    } else {
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (((1.0 - s) * angle).sin() / sin, (s * angle).sin() / sin)
+    };
+    let mut q = [0.0; 4];
+    for (i, x) in q.iter_mut().enumerate() {
+        *x = wa * a[i] + wb * sign * b[i];
+    }
+    normalize(&mut q);
+    q
+}
+
+impl Keyframes {
+    /// Creates keyframes from their times and flattened outputs.
+    ///
+    /// The width of each output value is inferred from the number of outputs
+    /// per keyframe. Outputs are treated as rotation quaternions when
+    /// `rotation` is `true`. Returns `None` if there are no keyframes, the
+    /// times are not in ascending order, or the number of outputs is not a
+    /// multiple of the number of keyframes (times three for cubic splines).
+    pub fn new(
+        interpolation: Interpolation,
+        inputs: Vec<f32>,
+        outputs: Vec<f32>,
+        rotation: bool,
+    ) -> Option<Self> {
+        let per_key = match interpolation {
+            Interpolation::CubicSpline => 3 * inputs.len(),
+            _ => inputs.len(),
+        };
+        if per_key == 0 || !outputs.len().is_multiple_of(per_key) {
+            return None;
+        }
+        if !inputs.windows(2).all(|x| x[0] <= x[1]) {
+            return None;
+        }
+        let width = outputs.len() / per_key;
+        if width == 0 || (rotation && width != 4) {
+            return None;
+        }
+        Some(Self {
+            interpolation,
+            inputs,
+            outputs,
+            width,
+            rotation,
+        })
+    }
+
+    /// Returns the keyframe interpolation algorithm.
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Returns the keyframe times.
+    pub fn inputs(&self) -> &[f32] {
+        &self.inputs
+    }
+
+    /// Returns the flattened keyframe outputs.
+    pub fn outputs(&self) -> &[f32] {
+        &self.outputs
+    }
+
+    /// Returns the number of components of each output value, e.g. `3` for
+    /// translations or the number of morph targets for weights.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns `true` if the outputs are rotation quaternions.
+    pub fn is_rotation(&self) -> bool {
+        self.rotation
+    }
+
+    /// Returns the number of keyframes.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns `true` if there are no keyframes, which `new` does not allow.
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Returns `true` if the outputs hold values for `property`.
+    pub(crate) fn fits(&self, property: Property) -> bool {
+        match property {
+            Property::Translation | Property::Scale => self.width == 3,
+            Property::Rotation => self.rotation,
+            Property::MorphTargetWeights => !self.rotation,
+        }
+    }
+
+    /// Returns the time of the first and last keyframes.
+    pub fn time_range(&self) -> (f32, f32) {
+        (self.inputs[0], self.inputs[self.inputs.len() - 1])
+    }
+
+    /// Returns the output value of keyframe `key`, skipping cubic spline
+    /// tangents.
+    pub fn value(&self, key: usize) -> &[f32] {
+        let start = match self.interpolation {
+            Interpolation::CubicSpline => (3 * key + 1) * self.width,
+            _ => key * self.width,
+        };
+        &self.outputs[start..start + self.width]
+    }
+
+    /// Returns the in-tangent and out-tangent of keyframe `key`, or `None`
+    /// unless the interpolation is cubic spline.
+    pub fn tangents(&self, key: usize) -> Option<(&[f32], &[f32])> {
+        if self.interpolation != Interpolation::CubicSpline {
+            return None;
+        }
+        let start = 3 * key * self.width;
+        let in_tangent = &self.outputs[start..start + self.width];
+        let out_tangent = &self.outputs[start + 2 * self.width..start + 3 * self.width];
+        Some((in_tangent, out_tangent))
+    }
+
+    /// Returns the value and rate of change of cubic spline keyframes at time
+    /// `t`, which must lie between two keyframes with different times.
+    ///
+    /// Unlike [`sample`], rotations are not normalized, so the value lies on
+    /// the spline itself.
+    ///
+    /// [`sample`]: #method.sample
+    pub(crate) fn spline_point(&self, t: f32) -> (Vec<f32>, Vec<f32>) {
+        let next = self.inputs.partition_point(|x| *x <= t);
+        let key = next - 1;
+        let (t0, t1) = (self.inputs[key], self.inputs[next]);
+        let dt = t1 - t0;
+        let s = (t - t0) / dt;
+        let (a, b) = (self.value(key), self.value(next));
+        let (_, out_tangent) = self.tangents(key).unwrap();
+        let (in_tangent, _) = self.tangents(next).unwrap();
+        let (s2, s3) = (s * s, s * s * s);
+        let value = (0..self.width).map(|i| {
+            (2.0 * s3 - 3.0 * s2 + 1.0) * a[i]
+                + dt * (s3 - 2.0 * s2 + s) * out_tangent[i]
+                + (-2.0 * s3 + 3.0 * s2) * b[i]
+                + dt * (s3 - s2) * in_tangent[i]
+        });
+        let slope = (0..self.width).map(|i| {
+            ((6.0 * s2 - 6.0 * s) * (a[i] - b[i])) / dt
+                + (3.0 * s2 - 4.0 * s + 1.0) * out_tangent[i]
+                + (3.0 * s2 - 2.0 * s) * in_tangent[i]
+        });
+        (value.collect(), slope.collect())
+    }
+
+    /// Evaluates the keyframes at time `t`.
+    ///
+    /// Times before the first keyframe or after the last are clamped to the
+    /// first or last value. Step interpolation holds each value until the next
+    /// keyframe. Linear interpolation is spherical for rotations, and cubic
+    /// spline rotations are normalized.
+    pub fn sample(&self, t: f32) -> Vec<f32> {
+        let last = self.inputs.len() - 1;
+        // `partition_point` treats NaN as before every keyframe.
+        let next = self.inputs.partition_point(|x| *x <= t);
+        if next == 0 {
+            return self.value(0).to_vec();
+        }
+        if next > last {
+            return self.value(last).to_vec();
+        }
+        let key = next - 1;
+        let (t0, t1) = (self.inputs[key], self.inputs[next]);
+        let dt = t1 - t0;
+        let s = if dt > 0.0 { (t - t0) / dt } else { 0.0 };
+        let (a, b) = (self.value(key), self.value(next));
+        match self.interpolation {
+            Interpolation::Step => a.to_vec(),
+            Interpolation::Linear if self.rotation => slerp(a, b, s).to_vec(),
+            Interpolation::Linear => a.iter().zip(b).map(|(a, b)| a + (b - a) * s).collect(),
+            Interpolation::CubicSpline => {
+                let (_, out_tangent) = self.tangents(key).unwrap();
+                let (in_tangent, _) = self.tangents(next).unwrap();
+                let (s2, s3) = (s * s, s * s * s);
+                let ka = 2.0 * s3 - 3.0 * s2 + 1.0;
+                let kb = dt * (s3 - 2.0 * s2 + s);
+                let kc = -2.0 * s3 + 3.0 * s2;
+                let kd = dt * (s3 - s2);
+                let mut value: Vec<f32> = (0..self.width)
+                    .map(|i| ka * a[i] + kb * out_tangent[i] + kc * b[i] + kd * in_tangent[i])
+                    .collect();
+                if self.rotation {
+                    normalize(&mut value);
+                }
+                value
+            }
+        }
+    }
+}
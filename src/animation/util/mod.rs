@@ -4,11 +4,18 @@ pub mod rotations;
 /// Casting iterator adapters for morph target weights.
 pub mod morph_target_weights;
 
+mod keyframes;
+
 use crate::accessor;
 
-use crate::animation::Channel;
+use crate::animation::{Channel, Sampler};
 use crate::Buffer;
 
+#[doc(inline)]
+pub use self::keyframes::{Keyframes, Value};
+
+pub(crate) use self::keyframes::{normalize, slerp};
+
 /// Animation input sampler values of type `f32`.
 pub type ReadInputs<'a> = accessor::Iter<'a, f32>;
 
@@ -186,3 +193,53 @@ where
         }
     }
 }
+
+/// Reads the keyframes of `sampler`, casting normalized integer outputs to
+/// `f32`. Returns `None` if the buffer data is missing or the accessors do not
+/// hold animation data.
+pub(crate) fn read_keyframes<'a, 's, F>(
+    sampler: &Sampler<'a>,
+    get_buffer_data: F,
+) -> Option<Keyframes>
+where
+    F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+{
+    use accessor::{DataType, Dimensions, Iter};
+    let input = sampler.input();
+    if input.data_type() != DataType::F32 || input.dimensions() != Dimensions::Scalar {
+        return None;
+    }
+    let inputs: Vec<f32> = Iter::new(input, get_buffer_data.clone())?.collect();
+    let output = sampler.output();
+    let get = get_buffer_data;
+    let (outputs, rotation): (Vec<f32>, bool) = match (output.dimensions(), output.data_type()) {
+        (Dimensions::Vec3, DataType::F32) => {
+            let iter: Iter<[f32; 3]> = Iter::new(output, get)?;
+            (iter.flatten().collect(), false)
+        }
+        (Dimensions::Vec4, data_type) => {
+            let rotations = match data_type {
+                DataType::I8 => Rotations::I8(Iter::new(output, get)?),
+                DataType::U8 => Rotations::U8(Iter::new(output, get)?),
+                DataType::I16 => Rotations::I16(Iter::new(output, get)?),
+                DataType::U16 => Rotations::U16(Iter::new(output, get)?),
+                DataType::F32 => Rotations::F32(Iter::new(output, get)?),
+                DataType::U32 => return None,
+            };
+            (rotations.into_f32().flatten().collect(), true)
+        }
+        (Dimensions::Scalar, data_type) => {
+            let weights = match data_type {
+                DataType::I8 => MorphTargetWeights::I8(Iter::new(output, get)?),
+                DataType::U8 => MorphTargetWeights::U8(Iter::new(output, get)?),
+                DataType::I16 => MorphTargetWeights::I16(Iter::new(output, get)?),
+                DataType::U16 => MorphTargetWeights::U16(Iter::new(output, get)?),
+                DataType::F32 => MorphTargetWeights::F32(Iter::new(output, get)?),
+                DataType::U32 => return None,
+            };
+            (weights.into_f32().collect(), false)
+        }
+        _ => return None,
+    };
+    Keyframes::new(sampler.interpolation(), inputs, outputs, rotation)
+}
@@ -0,0 +1,270 @@
+//! Bounding volumes computed from vertex data.
+//!
+//! Unlike [`Primitive::bounding_box`], which reads the `min` and `max`
+//! properties declared on the `POSITION` accessor, these functions read the
+//! positions themselves, including sparse and quantized accessors. Morph
+//! targets are accounted for by assuming every weight may lie anywhere
+//! between `0` and `1`.
+//!
+//! [`Primitive::bounding_box`]: ../mesh/struct.Primitive.html#method.bounding_box
+
+use std::collections::HashMap;
+
+use crate::accessor::Dimensions;
+use crate::graph::load_accessor;
+use crate::math::Matrix4;
+use crate::mesh::BoundingBox;
+use crate::scene::Hierarchy;
+use crate::{buffer, Mesh, Node, Primitive, Result, Scene, Semantic};
+
+/// A bounding sphere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sphere {
+    /// The center of the sphere.
+    pub center: [f32; 3],
+
+    /// The radius of the sphere.
+    pub radius: f32,
+}
+
+/// Reads the `[x, y, z]` elements of `accessor`, or `None` if it does not
+/// hold three components.
+fn read_vec3(accessor: crate::Accessor, buffers: &[buffer::Data]) -> Result<Option<Vec<[f32; 3]>>> {
+    if accessor.dimensions() != Dimensions::Vec3 {
+        return Ok(None);
+    }
+    Ok(Some(load_accessor(accessor, buffers)?.to_f32()))
+}
+
+/// Returns the box spanning the positions each vertex of `primitive` can
+/// take under any morph target weights between `0` and `1`, or `None` if the
+/// primitive has no positions.
+fn vertex_extents(
+    primitive: &Primitive,
+    buffers: &[buffer::Data],
+) -> Result<Option<Vec<BoundingBox>>> {
+    let positions = match primitive.get(&Semantic::Positions) {
+        Some(accessor) => read_vec3(accessor, buffers)?,
+        None => None,
+    };
+    let mut extents: Vec<_> = match positions {
+        Some(positions) => positions
+            .into_iter()
+            .map(|x| BoundingBox { min: x, max: x })
+            .collect(),
+        None => return Ok(None),
+    };
+    for target in primitive.morph_targets() {
+        let displacements = match target.positions() {
+            Some(accessor) => read_vec3(accessor, buffers)?,
+            None => None,
+        };
+        let displacements = match displacements {
+            Some(x) if x.len() == extents.len() => x,
+            _ => continue,
+        };
+        for (extent, d) in extents.iter_mut().zip(displacements) {
+            for (i, d) in d.iter().enumerate() {
+                extent.min[i] += d.min(0.0);
+                extent.max[i] += d.max(0.0);
+            }
+        }
+    }
+    Ok(Some(extents).filter(|x| !x.is_empty()))
+}
+
+/// Returns the smallest box containing `a` and `b`.
+fn union(a: Option<BoundingBox>, b: &BoundingBox) -> BoundingBox {
+    match a {
+        Some(a) => BoundingBox {
+            min: [0, 1, 2].map(|i| a.min[i].min(b.min[i])),
+            max: [0, 1, 2].map(|i| a.max[i].max(b.max[i])),
+        },
+        None => b.clone(),
+    }
+}
+
+/// Returns the center of `aabb`.
+fn center(aabb: &BoundingBox) -> [f32; 3] {
+    [0, 1, 2].map(|i| (aabb.min[i] + aabb.max[i]) * 0.5)
+}
+
+/// Returns the sphere around the center of `aabb` that contains every
+/// vertex extent.
+fn enclose(aabb: &BoundingBox, extents: &[BoundingBox]) -> Sphere {
+    let center = center(aabb);
+    let radius = extents
+        .iter()
+        .map(|extent| {
+            (0..3)
+                .map(|i| {
+                    let min = (extent.min[i] - center[i]).abs();
+                    let d = min.max((extent.max[i] - center[i]).abs());
+                    d * d
+                })
+                .sum::<f32>()
+        })
+        .fold(0.0f32, f32::max)
+        .sqrt();
+    Sphere { center, radius }
+}
+
+/// Returns the bounding box of vertex extents.
+fn extents_aabb(extents: &[BoundingBox]) -> BoundingBox {
+    let mut aabb = None;
+    for extent in extents {
+        aabb = Some(union(aabb, extent));
+    }
+    aabb.unwrap()
+}
+
+/// Returns the bounding box of the transformed corners of `aabb`.
+///
+/// `matrix` is a 4x4 affine transform in column-major order, such as
+/// [`Hierarchy::world_matrix`].
+///
+/// [`Hierarchy::world_matrix`]: ../scene/struct.Hierarchy.html#method.world_matrix
+pub fn transform_aabb(aabb: &BoundingBox, matrix: &[[f32; 4]; 4]) -> BoundingBox {
+    let mut result = None;
+    for corner in 0..8 {
+        let p = [0, 1, 2].map(|i| {
+            if corner & (1 << i) == 0 {
+                aabb.min[i]
+            } else {
+                aabb.max[i]
+            }
+        });
+        let q = [0, 1, 2].map(|i| {
+            matrix[0][i] * p[0] + matrix[1][i] * p[1] + matrix[2][i] * p[2] + matrix[3][i]
+        });
+        result = Some(union(result, &BoundingBox { min: q, max: q }));
+    }
+    result.unwrap()
+}
+
+/// Returns the bounding box of the positions of `primitive`, or `None` if it
+/// has no `POSITION` attribute or no vertices.
+pub fn primitive_aabb(
+    primitive: &Primitive,
+    buffers: &[buffer::Data],
+) -> Result<Option<BoundingBox>> {
+    Ok(vertex_extents(primitive, buffers)?.map(|x| extents_aabb(&x)))
+}
+
+/// Returns a bounding sphere of the positions of `primitive`, centered on
+/// its bounding box, or `None` if it has no `POSITION` attribute or no
+/// vertices.
+pub fn primitive_sphere(primitive: &Primitive, buffers: &[buffer::Data]) -> Result<Option<Sphere>> {
+    Ok(vertex_extents(primitive, buffers)?.map(|x| enclose(&extents_aabb(&x), &x)))
+}
+
+/// Returns the bounding box of every primitive of `mesh`, or `None` if no
+/// primitive has positions.
+pub fn mesh_aabb(mesh: &Mesh, buffers: &[buffer::Data]) -> Result<Option<BoundingBox>> {
+    let mut result = None;
+    for primitive in mesh.primitives() {
+        if let Some(aabb) = primitive_aabb(&primitive, buffers)? {
+            result = Some(union(result, &aabb));
+        }
+    }
+    Ok(result)
+}
+
+/// Returns a bounding sphere of every primitive of `mesh`, centered on its
+/// bounding box, or `None` if no primitive has positions.
+pub fn mesh_sphere(mesh: &Mesh, buffers: &[buffer::Data]) -> Result<Option<Sphere>> {
+    let mut extents = Vec::new();
+    for primitive in mesh.primitives() {
+        extents.extend(vertex_extents(&primitive, buffers)?.into_iter().flatten());
+    }
+    if extents.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(enclose(&extents_aabb(&extents), &extents)))
+}
+
+/// Returns the world-space bounding box of the mesh of `node`, with every
+/// instance of the `EXT_mesh_gpu_instancing` extension, or `None` if the
+/// node has no mesh with positions, its world matrix is undefined, or it has
+/// no readable instance transforms.
+///
+/// Skinning is not taken into account.
+pub fn node_aabb(
+    node: &Node,
+    hierarchy: &Hierarchy,
+    buffers: &[buffer::Data],
+) -> Result<Option<BoundingBox>> {
+    let aabb = match node.mesh() {
+        Some(mesh) => mesh_aabb(&mesh, buffers)?,
+        None => None,
+    };
+    Ok(match aabb {
+        Some(aabb) => world_aabb(node, hierarchy, buffers, &aabb),
+        None => None,
+    })
+}
+
+/// Transforms the local bounding box of the mesh of `node` into world space.
+fn world_aabb(
+    node: &Node,
+    hierarchy: &Hierarchy,
+    buffers: &[buffer::Data],
+    aabb: &BoundingBox,
+) -> Option<BoundingBox> {
+    let world = Matrix4::from_array(hierarchy.world_matrix(node)?);
+    let matrices: Vec<_> = match node.instancing() {
+        Some(instancing) => {
+            let reader = instancing.reader(|buffer| buffers.get(buffer.index()).map(|x| &x[..]));
+            reader
+                .read_transforms()?
+                .map(|x| (world * Matrix4::from_array(x.matrix())).as_array())
+                .collect()
+        }
+        None => vec![world.as_array()],
+    };
+    let mut result = None;
+    for matrix in &matrices {
+        result = Some(union(result, &transform_aabb(aabb, matrix)));
+    }
+    result
+}
+
+/// Returns the world-space bounding box of every mesh in `scene`, or `None`
+/// if the scene contains no mesh with positions.
+///
+/// Nodes belong to the scene when the root of their tree is one of the
+/// scene's root nodes. Nodes on or below a cycle in the node hierarchy are
+/// skipped, and skinning is not taken into account.
+pub fn scene_aabb(scene: &Scene, buffers: &[buffer::Data]) -> Result<Option<BoundingBox>> {
+    let document = scene.document();
+    let hierarchy = Hierarchy::new(document);
+    let roots: Vec<usize> = scene.nodes().map(|x| x.index()).collect();
+    let mut meshes = HashMap::new();
+    let mut result = None;
+    for node in document.nodes() {
+        let mesh = match node.mesh() {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        let root = hierarchy
+            .path_from_root(&node)
+            .and_then(|x| x.first().map(|x| x.index()));
+        if !root.is_some_and(|x| roots.contains(&x)) {
+            continue;
+        }
+        let aabb = match meshes.get(&mesh.index()) {
+            Some(aabb) => aabb,
+            None => {
+                let aabb = mesh_aabb(&mesh, buffers)?;
+                meshes.entry(mesh.index()).or_insert(aabb)
+            }
+        };
+        let aabb = aabb
+            .as_ref()
+            .and_then(|x| world_aabb(&node, &hierarchy, buffers, x));
+        if let Some(aabb) = aabb {
+            result = Some(union(result, &aabb));
+        }
+    }
+    Ok(result)
+}
@@ -1,4 +1,3 @@
-#[cfg(feature = "import")]
 use std::ops;
 
 use crate::Document;
@@ -9,7 +8,6 @@ pub use json::buffer::Target;
 #[derive(Clone, Debug)]
 pub struct Buffer<'a> {
     /// The parent `Document` struct.
-    #[allow(dead_code)]
     document: &'a Document,
 
     /// The corresponding JSON index.
@@ -47,13 +45,9 @@ pub enum Source<'a> {
 }
 
 /// Buffer data belonging to an imported glTF asset.
-#[cfg(feature = "import")]
-#[cfg_attr(docsrs, doc(cfg(feature = "import")))]
 #[derive(Clone, Debug)]
 pub struct Data(pub Vec<u8>);
 
-#[cfg(feature = "import")]
-#[cfg_attr(docsrs, doc(cfg(feature = "import")))]
 impl ops::Deref for Data {
     type Target = [u8];
     fn deref(&self) -> &Self::Target {
@@ -105,6 +99,15 @@ impl<'a> Buffer<'a> {
     pub fn extras(&self) -> &'a json::Extras {
         &self.json.extras
     }
+
+    /// Returns the raw JSON of the extension called `name`, if present.
+    ///
+    /// Unlike the typed extension data, this includes extensions that are
+    /// not modelled by `gltf-json`. It is only available for documents loaded
+    /// through [`Gltf`](../struct.Gltf.html).
+    pub fn extension_value(&self, name: &str) -> Option<&'a json::Value> {
+        self.document.1.get("buffers", self.index, name)
+    }
 }
 
 impl<'a> View<'a> {
@@ -172,4 +175,11 @@ impl<'a> View<'a> {
     pub fn extras(&self) -> &'a json::Extras {
         &self.json.extras
     }
+
+    /// Returns the raw JSON of the extension called `name`, if present.
+    ///
+    /// See [`Buffer::extension_value`](struct.Buffer.html#method.extension_value).
+    pub fn extension_value(&self, name: &str) -> Option<&'a json::Value> {
+        self.document.1.get("bufferViews", self.index, name)
+    }
 }
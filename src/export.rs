@@ -0,0 +1,60 @@
+use crate::buffer;
+use crate::graph::Graph;
+use std::path::Path;
+
+use crate::{Document, Result};
+
+/// Export glTF 2.0 to the file system.
+///
+/// Buffer views are laid out afresh: every accessor and embedded image gets
+/// its own view, aligned to four bytes, with `byteLength`, `byteOffset` and
+/// `byteStride` filled in. When `path` has a `.glb` extension binary glTF is
+/// written, otherwise a `.gltf` file is written alongside sidecar `.bin` and
+/// image files.
+///
+/// ```
+/// # use gltf_transform_rs as gltf;
+/// # fn run() -> Result<(), gltf::Error> {
+/// # let path = "examples/Box.gltf";
+/// let (document, buffers, _) = gltf::import(path)?;
+/// # let output = std::env::temp_dir().join("Box.glb");
+/// gltf::export(&output, &document, &buffers)?;
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// #     run().expect("test failure");
+/// # }
+/// ```
+///
+/// ### Note
+///
+/// Sparse accessors are written densely, and extensions of objects other than
+/// the root, nodes, materials, textures, samplers and images are dropped. See
+/// [`graph::Graph::export`] for details.
+///
+/// [`graph::Graph::export`]: graph/struct.Graph.html#method.export
+pub fn export<P>(path: P, document: &Document, buffers: &[buffer::Data]) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    Graph::from_document(document, buffers)?.export(path)
+}
+
+/// Export glTF 2.0 to a binary glTF byte vector.
+///
+/// ```
+/// # use gltf_transform_rs as gltf;
+/// # fn run() -> Result<(), gltf::Error> {
+/// # let path = "examples/Box.gltf";
+/// let (document, buffers, _) = gltf::import(path)?;
+/// let bytes = gltf::export_glb(&document, &buffers)?;
+/// # let _ = gltf::Glb::from_slice(&bytes)?;
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// #     run().expect("test failure");
+/// # }
+/// ```
+pub fn export_glb(document: &Document, buffers: &[buffer::Data]) -> Result<Vec<u8>> {
+    Graph::from_document(document, buffers)?.to_glb()
+}
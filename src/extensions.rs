@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+
+/// Raw `extensions` objects of the root and of glTF objects, and `extras`
+/// objects of top-level glTF objects.
+///
+/// `gltf-json` discards extensions that it does not model, such as
+/// `EXT_meshopt_compression`, and keeps `extras` only with the `extras`
+/// feature. The raw JSON is kept here so that such extensions, and the
+/// `extras` entries some of them define, can still be read, and written back
+/// on export.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Extensions {
+    /// The `extensions` object of the root.
+    root: Option<json::Value>,
+
+    /// The `extensions` objects of top-level objects and of the objects
+    /// nested in them, keyed by the name of the array of the top-level object
+    /// in the root, e.g. `"bufferViews"`, its index, and the JSON pointer of
+    /// the nested object relative to it, e.g. `"/normalTexture"`, which is
+    /// empty for the top-level object itself.
+    objects: HashMap<(String, usize, String), json::Value>,
+
+    /// The `extras` objects of top-level objects, keyed like `objects`.
+    extras: HashMap<(String, usize), json::Value>,
+}
+
+impl Extensions {
+    /// Collects the `extensions` objects of a deserialized glTF root.
+    pub(crate) fn from_value(value: &json::Value) -> Self {
+        let mut extensions = Self::default();
+        let root = match value.as_object() {
+            Some(root) => root,
+            None => return extensions,
+        };
+        for (key, value) in root {
+            match value {
+                json::Value::Object(_) if key == "extensions" => {
+                    extensions.root = Some(value.clone());
+                }
+                json::Value::Array(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        extensions.collect(key, index, String::new(), item);
+                        if let Some(value) = item.get("extras").filter(|x| x.is_object()) {
+                            extensions
+                                .extras
+                                .insert((key.clone(), index), value.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        extensions
+    }
+
+    /// Records the `extensions` objects of `item` and of the objects nested
+    /// in it, except within `extensions` and `extras`.
+    fn collect(&mut self, array: &str, index: usize, pointer: String, item: &json::Value) {
+        let object = match item.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+        for (key, value) in object {
+            match key.as_str() {
+                "extensions" if value.is_object() => {
+                    let key = (array.to_string(), index, pointer.clone());
+                    self.objects.insert(key, value.clone());
+                }
+                "extensions" | "extras" => {}
+                _ => {
+                    let pointer = format!("{}/{}", pointer, escape(key));
+                    if let Some(items) = value.as_array() {
+                        for (i, item) in items.iter().enumerate() {
+                            self.collect(array, index, format!("{}/{}", pointer, i), item);
+                        }
+                    } else {
+                        self.collect(array, index, pointer, value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the `extensions` object of the root.
+    pub(crate) fn root(&self) -> Option<&json::Value> {
+        self.root.as_ref()
+    }
+
+    /// Sets the extension called `name` of the root.
+    pub(crate) fn insert_root(&mut self, name: &str, value: json::Value) {
+        let extensions = self
+            .root
+            .get_or_insert_with(|| json::Value::Object(Default::default()));
+        if let Some(object) = extensions.as_object_mut() {
+            object.insert(name.to_string(), value);
+        }
+    }
+
+    /// Returns the `extensions` object at `pointer` below object `index` of
+    /// `array`.
+    pub(crate) fn object(&self, array: &str, index: usize, pointer: &str) -> Option<&json::Value> {
+        self.objects
+            .get(&(array.to_string(), index, pointer.to_string()))
+    }
+
+    /// Returns the extension called `name` of object `index` of `array`.
+    pub(crate) fn get(&self, array: &str, index: usize, name: &str) -> Option<&json::Value> {
+        self.object(array, index, "").and_then(|x| x.get(name))
+    }
+
+    /// Sets the extension called `name` of object `index` of `array`.
+    pub(crate) fn insert(&mut self, array: &str, index: usize, name: &str, value: json::Value) {
+        self.insert_at(array, index, "", name, value);
+    }
+
+    /// Sets the extension called `name` of the object at `pointer` below
+    /// object `index` of `array`.
+    pub(crate) fn insert_at(
+        &mut self,
+        array: &str,
+        index: usize,
+        pointer: &str,
+        name: &str,
+        value: json::Value,
+    ) {
+        let extensions = self
+            .objects
+            .entry((array.to_string(), index, pointer.to_string()))
+            .or_insert_with(|| json::Value::Object(Default::default()));
+        if let Some(object) = extensions.as_object_mut() {
+            object.insert(name.to_string(), value);
+        }
+    }
+
+    /// Returns the name of every extension used by the collected objects,
+    /// including extensions nested in other extensions.
+    pub(crate) fn names(&self) -> HashSet<String> {
+        let mut names = HashSet::new();
+        for extensions in self.root.iter().chain(self.objects.values()) {
+            if let Some(extensions) = extensions.as_object() {
+                extension_names(extensions, &mut names);
+            }
+        }
+        names
+    }
+
+    /// Returns the `extras` entry called `name` of object `index` of `array`.
+    pub(crate) fn extra(&self, array: &str, index: usize, name: &str) -> Option<&json::Value> {
+        self.extras
+            .get(&(array.to_string(), index))
+            .and_then(|x| x.get(name))
+    }
+
+    /// Sets the `extras` entry called `name` of object `index` of `array`.
+    pub(crate) fn insert_extra(
+        &mut self,
+        array: &str,
+        index: usize,
+        name: &str,
+        value: json::Value,
+    ) {
+        let extras = self
+            .extras
+            .entry((array.to_string(), index))
+            .or_insert_with(|| json::Value::Object(Default::default()));
+        if let Some(object) = extras.as_object_mut() {
+            object.insert(name.to_string(), value);
+        }
+    }
+
+    /// Adds the collected extensions and extras to a serialized glTF root.
+    ///
+    /// Entries already present in `value` take precedence.
+    pub(crate) fn merge_into(&self, value: &mut json::Value) {
+        if let Some(extensions) = &self.root {
+            merge_object(value, "extensions", extensions);
+        }
+        for ((array, index, pointer), extensions) in &self.objects {
+            let object = value
+                .get_mut(array)
+                .and_then(|x| x.get_mut(*index))
+                .and_then(|x| x.pointer_mut(pointer));
+            if let Some(object) = object {
+                merge_object(object, "extensions", extensions);
+            }
+        }
+        for ((array, index), extras) in &self.extras {
+            if let Some(item) = value.get_mut(array).and_then(|x| x.get_mut(*index)) {
+                merge_object(item, "extras", extras);
+            }
+        }
+    }
+}
+
+/// Escapes `key` for use as a JSON pointer token.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Adds the names of `extensions` to `names`, and those of the `extensions`
+/// objects nested in their values.
+pub(crate) fn extension_names<'a, I>(extensions: I, names: &mut HashSet<String>)
+where
+    I: IntoIterator<Item = (&'a String, &'a json::Value)>,
+{
+    fn nested(value: &json::Value, names: &mut HashSet<String>) {
+        match value {
+            json::Value::Object(object) => {
+                for (key, value) in object {
+                    match (key.as_str(), value.as_object()) {
+                        ("extensions", Some(extensions)) => extension_names(extensions, names),
+                        ("extras", _) => {}
+                        _ => nested(value, names),
+                    }
+                }
+            }
+            json::Value::Array(items) => {
+                for item in items {
+                    nested(item, names);
+                }
+            }
+            _ => {}
+        }
+    }
+    for (name, value) in extensions {
+        names.insert(name.clone());
+        nested(value, names);
+    }
+}
+
+/// Adds the entries of `extensions` to the object called `key` of `object`.
+fn merge_object(object: &mut json::Value, key: &str, extensions: &json::Value) {
+    let object = match object.as_object_mut() {
+        Some(object) => object,
+        None => return,
+    };
+    let target = object
+        .entry(key)
+        .or_insert_with(|| json::Value::Object(Default::default()));
+    if target.is_null() {
+        *target = json::Value::Object(Default::default());
+    }
+    if let (Some(target), Some(extensions)) = (target.as_object_mut(), extensions.as_object()) {
+        for (name, value) in extensions {
+            target.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use crate::accessor::{DataType, Dimensions};
+use crate::Normalize;
+
+use super::Handle;
+
+/// Typed component storage for an [`Accessor`].
+///
+/// Components are stored tightly packed, element after element.
+///
+/// [`Accessor`]: struct.Accessor.html
+#[derive(Clone, Debug, PartialEq)]
+pub enum Values {
+    /// Components of type `i8`.
+    I8(Vec<i8>),
+    /// Components of type `u8`.
+    U8(Vec<u8>),
+    /// Components of type `i16`.
+    I16(Vec<i16>),
+    /// Components of type `u16`.
+    U16(Vec<u16>),
+    /// Components of type `u32`.
+    U32(Vec<u32>),
+    /// Components of type `f32`.
+    F32(Vec<f32>),
+}
+
+/// A typed array of elements owned by a [`Graph`].
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Clone, Debug)]
+pub struct Accessor {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The buffer the accessor data is written to.
+    ///
+    /// When `None`, the data is written to the first buffer of the graph.
+    pub buffer: Option<Handle<Buffer>>,
+
+    /// Specifies if the elements are scalars, vectors, or matrices.
+    pub dimensions: Dimensions,
+
+    /// Specifies whether integer data values should be normalized.
+    pub normalized: bool,
+
+    /// The component data.
+    pub values: Values,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// A destination for binary data when a [`Graph`] is written.
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Clone, Debug, Default)]
+pub struct Buffer {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The URI of the buffer, or `None` for the `BIN` chunk of binary glTF.
+    pub uri: Option<String>,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+impl Values {
+    /// Returns the number of components.
+    pub fn len(&self) -> usize {
+        match self {
+            Values::I8(x) => x.len(),
+            Values::U8(x) => x.len(),
+            Values::I16(x) => x.len(),
+            Values::U16(x) => x.len(),
+            Values::U32(x) => x.len(),
+            Values::F32(x) => x.len(),
+        }
+    }
+
+    /// Returns `true` if there are no components.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the component data type.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Values::I8(_) => DataType::I8,
+            Values::U8(_) => DataType::U8,
+            Values::I16(_) => DataType::I16,
+            Values::U16(_) => DataType::U16,
+            Values::U32(_) => DataType::U32,
+            Values::F32(_) => DataType::F32,
+        }
+    }
+
+    /// Constructs empty storage with the given data type.
+    pub fn new(data_type: DataType) -> Self {
+        Self::with_capacity(data_type, 0)
+    }
+
+    /// Constructs empty storage with the given data type and capacity.
+    pub fn with_capacity(data_type: DataType, capacity: usize) -> Self {
+        match data_type {
+            DataType::I8 => Values::I8(Vec::with_capacity(capacity)),
+            DataType::U8 => Values::U8(Vec::with_capacity(capacity)),
+            DataType::I16 => Values::I16(Vec::with_capacity(capacity)),
+            DataType::U16 => Values::U16(Vec::with_capacity(capacity)),
+            DataType::U32 => Values::U32(Vec::with_capacity(capacity)),
+            DataType::F32 => Values::F32(Vec::with_capacity(capacity)),
+        }
+    }
+
+    /// Returns a component as `f32`, either converted or normalized.
+    pub fn get_f32(&self, index: usize, normalized: bool) -> f32 {
+        match (self, normalized) {
+            (Values::I8(x), true) => x[index].normalize(),
+            (Values::U8(x), true) => x[index].normalize(),
+            (Values::I16(x), true) => x[index].normalize(),
+            (Values::U16(x), true) => x[index].normalize(),
+            (Values::I8(x), false) => x[index] as f32,
+            (Values::U8(x), false) => x[index] as f32,
+            (Values::I16(x), false) => x[index] as f32,
+            (Values::U16(x), false) => x[index] as f32,
+            (Values::U32(x), _) => x[index] as f32,
+            (Values::F32(x), _) => x[index],
+        }
+    }
+
+    /// Returns a component as `u32`, truncating signed and float values.
+    pub fn get_u32(&self, index: usize) -> u32 {
+        match self {
+            Values::I8(x) => x[index].max(0) as u32,
+            Values::U8(x) => x[index] as u32,
+            Values::I16(x) => x[index].max(0) as u32,
+            Values::U16(x) => x[index] as u32,
+            Values::U32(x) => x[index],
+            Values::F32(x) => x[index].max(0.0) as u32,
+        }
+    }
+
+    /// Appends a component given as `f32`, either converted or normalized.
+    pub fn push_f32(&mut self, value: f32, normalized: bool) {
+        match (self, normalized) {
+            (Values::I8(x), true) => x.push((value * 127.0).round().clamp(-127.0, 127.0) as i8),
+            (Values::U8(x), true) => x.push((value * 255.0).round().clamp(0.0, 255.0) as u8),
+            (Values::I16(x), true) => {
+                x.push((value * 32767.0).round().clamp(-32767.0, 32767.0) as i16)
+            }
+            (Values::U16(x), true) => x.push((value * 65535.0).round().clamp(0.0, 65535.0) as u16),
+            (Values::I8(x), false) => x.push(value.round() as i8),
+            (Values::U8(x), false) => x.push(value.round() as u8),
+            (Values::I16(x), false) => x.push(value.round() as i16),
+            (Values::U16(x), false) => x.push(value.round() as u16),
+            (Values::U32(x), _) => x.push(value.round() as u32),
+            (Values::F32(x), _) => x.push(value),
+        }
+    }
+
+    /// Appends a component given as `u32`.
+    pub fn push_u32(&mut self, value: u32) {
+        match self {
+            Values::I8(x) => x.push(value as i8),
+            Values::U8(x) => x.push(value as u8),
+            Values::I16(x) => x.push(value as i16),
+            Values::U16(x) => x.push(value as u16),
+            Values::U32(x) => x.push(value),
+            Values::F32(x) => x.push(value as f32),
+        }
+    }
+
+    /// Appends the components `[start, end)` of `other`, which must have the
+    /// same data type.
+    pub fn extend_from(&mut self, other: &Values, start: usize, end: usize) {
+        match (self, other) {
+            (Values::I8(x), Values::I8(y)) => x.extend_from_slice(&y[start..end]),
+            (Values::U8(x), Values::U8(y)) => x.extend_from_slice(&y[start..end]),
+            (Values::I16(x), Values::I16(y)) => x.extend_from_slice(&y[start..end]),
+            (Values::U16(x), Values::U16(y)) => x.extend_from_slice(&y[start..end]),
+            (Values::U32(x), Values::U32(y)) => x.extend_from_slice(&y[start..end]),
+            (Values::F32(x), Values::F32(y)) => x.extend_from_slice(&y[start..end]),
+            _ => panic!("mismatched component data types"),
+        }
+    }
+
+    /// Overwrites the component at `dst` with component `src_index` of `src`.
+    pub(crate) fn copy_component(&mut self, dst: usize, src: &Values, src_index: usize) {
+        match (self, src) {
+            (Values::I8(x), Values::I8(y)) => x[dst] = y[src_index],
+            (Values::U8(x), Values::U8(y)) => x[dst] = y[src_index],
+            (Values::I16(x), Values::I16(y)) => x[dst] = y[src_index],
+            (Values::U16(x), Values::U16(y)) => x[dst] = y[src_index],
+            (Values::U32(x), Values::U32(y)) => x[dst] = y[src_index],
+            (Values::F32(x), Values::F32(y)) => x[dst] = y[src_index],
+            _ => panic!("mismatched component data types"),
+        }
+    }
+
+    /// Writes the component at `index` to `out` in little-endian byte order.
+    pub(crate) fn write_component(&self, index: usize, out: &mut Vec<u8>) {
+        match self {
+            Values::I8(x) => out.extend_from_slice(&x[index].to_le_bytes()),
+            Values::U8(x) => out.extend_from_slice(&x[index].to_le_bytes()),
+            Values::I16(x) => out.extend_from_slice(&x[index].to_le_bytes()),
+            Values::U16(x) => out.extend_from_slice(&x[index].to_le_bytes()),
+            Values::U32(x) => out.extend_from_slice(&x[index].to_le_bytes()),
+            Values::F32(x) => out.extend_from_slice(&x[index].to_le_bytes()),
+        }
+    }
+
+    /// Appends a component decoded from little-endian bytes.
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8]) {
+        use byteorder::{ByteOrder, LE};
+        match self {
+            Values::I8(x) => x.push(bytes[0] as i8),
+            Values::U8(x) => x.push(bytes[0]),
+            Values::I16(x) => x.push(LE::read_i16(bytes)),
+            Values::U16(x) => x.push(LE::read_u16(bytes)),
+            Values::U32(x) => x.push(LE::read_u32(bytes)),
+            Values::F32(x) => x.push(LE::read_f32(bytes)),
+        }
+    }
+}
+
+/// Returns the number of columns, the number of rows, and the column stride
+/// in bytes of an element, accounting for the column alignment rules of the
+/// glTF specification.
+pub(crate) fn column_layout(data_type: DataType, dimensions: Dimensions) -> (usize, usize, usize) {
+    let size = data_type.size();
+    let (columns, rows) = match dimensions {
+        Dimensions::Mat2 => (2, 2),
+        Dimensions::Mat3 => (3, 3),
+        Dimensions::Mat4 => (4, 4),
+        other => (1, other.multiplicity()),
+    };
+    let column_stride = if columns > 1 {
+        (rows * size + 3) & !3
+    } else {
+        rows * size
+    };
+    (columns, rows, column_stride)
+}
+
+/// Returns the size of one element in bytes, including matrix column padding.
+pub(crate) fn element_size(data_type: DataType, dimensions: Dimensions) -> usize {
+    let (columns, _, column_stride) = column_layout(data_type, dimensions);
+    columns * column_stride
+}
+
+impl Accessor {
+    /// Constructs an accessor from dimensions and component data.
+    pub fn new(dimensions: Dimensions, values: Values) -> Self {
+        debug_assert_eq!(values.len() % dimensions.multiplicity(), 0);
+        Self {
+            name: None,
+            buffer: None,
+            dimensions,
+            normalized: false,
+            values,
+            extras: Default::default(),
+        }
+    }
+
+    /// Constructs a `SCALAR` accessor with `u32` components.
+    pub fn from_u32(values: Vec<u32>) -> Self {
+        Self::new(Dimensions::Scalar, Values::U32(values))
+    }
+
+    /// Constructs an index accessor using the smallest component type that can
+    /// address `vertex_count` vertices.
+    ///
+    /// The largest value of each type is avoided since it is reserved for
+    /// primitive restart.
+    pub fn from_indices(indices: &[u32], vertex_count: usize) -> Self {
+        let values = if vertex_count <= u8::MAX as usize {
+            Values::U8(indices.iter().map(|x| *x as u8).collect())
+        } else if vertex_count <= u16::MAX as usize {
+            Values::U16(indices.iter().map(|x| *x as u16).collect())
+        } else {
+            Values::U32(indices.to_vec())
+        };
+        Self::new(Dimensions::Scalar, values)
+    }
+
+    /// Constructs an accessor with `f32` components from an array of
+    /// elements.
+    pub fn from_f32<const N: usize>(elements: &[[f32; N]]) -> Self {
+        let dimensions = match N {
+            1 => Dimensions::Scalar,
+            2 => Dimensions::Vec2,
+            3 => Dimensions::Vec3,
+            4 => Dimensions::Vec4,
+            9 => Dimensions::Mat3,
+            16 => Dimensions::Mat4,
+            _ => panic!("unsupported element size"),
+        };
+        let values = elements.iter().flat_map(|x| x.iter().copied()).collect();
+        Self::new(dimensions, Values::F32(values))
+    }
+
+    /// Returns the component data type.
+    pub fn data_type(&self) -> DataType {
+        self.values.data_type()
+    }
+
+    /// Returns the number of elements.
+    pub fn count(&self) -> usize {
+        self.values.len() / self.dimensions.multiplicity()
+    }
+
+    /// Returns the size of one element in bytes when tightly packed.
+    pub fn size(&self) -> usize {
+        element_size(self.data_type(), self.dimensions)
+    }
+
+    /// Returns the element at `index` as an `f32` array, normalizing integer
+    /// components if the accessor is normalized.
+    ///
+    /// Components beyond the accessor's multiplicity are left untouched.
+    pub fn get<const N: usize>(&self, index: usize) -> [f32; N] {
+        let n = self.dimensions.multiplicity();
+        let mut out = [0.0; N];
+        for (i, x) in out.iter_mut().enumerate().take(n) {
+            *x = self.values.get_f32(index * n + i, self.normalized);
+        }
+        out
+    }
+
+    /// Returns every element as an `f32` array.
+    pub fn to_f32<const N: usize>(&self) -> Vec<[f32; N]> {
+        (0..self.count()).map(|i| self.get(i)).collect()
+    }
+
+    /// Returns every component as `u32`, e.g. for index data.
+    pub fn to_u32(&self) -> Vec<u32> {
+        (0..self.values.len())
+            .map(|i| self.values.get_u32(i))
+            .collect()
+    }
+
+    /// Returns a copy of the accessor holding the elements at `indices`, in
+    /// that order.
+    pub fn gather(&self, indices: &[u32]) -> Self {
+        let n = self.dimensions.multiplicity();
+        let mut values = Values::with_capacity(self.data_type(), indices.len() * n);
+        for index in indices {
+            let start = *index as usize * n;
+            values.extend_from(&self.values, start, start + n);
+        }
+        Self {
+            values,
+            ..self.clone_without_values()
+        }
+    }
+
+    /// Returns a copy of the accessor with no elements.
+    fn clone_without_values(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            buffer: self.buffer,
+            dimensions: self.dimensions,
+            normalized: self.normalized,
+            values: Values::new(self.data_type()),
+            extras: self.extras.clone(),
+        }
+    }
+
+    /// Returns the per-component minimum and maximum as stored, i.e. without
+    /// normalization.
+    pub fn bounds(&self) -> Option<(Vec<f32>, Vec<f32>)> {
+        let n = self.dimensions.multiplicity();
+        if self.count() == 0 {
+            return None;
+        }
+        let mut min = vec![f32::INFINITY; n];
+        let mut max = vec![f32::NEG_INFINITY; n];
+        for i in 0..self.values.len() {
+            let x = self.values.get_f32(i, false);
+            min[i % n] = min[i % n].min(x);
+            max[i % n] = max[i % n].max(x);
+        }
+        Some((min, max))
+    }
+
+    /// Writes the element at `index` in little-endian byte order, including
+    /// matrix column padding.
+    pub(crate) fn write_element(&self, index: usize, out: &mut Vec<u8>) {
+        let (columns, rows, column_stride) = column_layout(self.data_type(), self.dimensions);
+        let n = self.dimensions.multiplicity();
+        for column in 0..columns {
+            let start = out.len();
+            for row in 0..rows {
+                self.values
+                    .write_component(index * n + column * rows + row, out);
+            }
+            out.resize(start + column_stride, 0);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::accessor::Dimensions;
+use crate::animation::util::Keyframes;
+use crate::animation::{Interpolation, Property};
+
+use super::{Accessor, Graph, Handle, Node};
+
+/// A keyframe animation.
+#[derive(Clone, Debug, Default)]
+pub struct Animation {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The animation channels.
+    pub channels: Vec<Channel>,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// Targets a keyframe graph at a node's property.
+///
+/// Unlike glTF, each channel owns its sampler; samplers shared between
+/// channels in the source document are duplicated on load.
+#[derive(Clone, Debug)]
+pub struct Channel {
+    /// The target node.
+    pub node: Handle<Node>,
+
+    /// The node's property to modify.
+    pub property: Property,
+
+    /// The accessor containing the keyframe input values (e.g. time).
+    pub input: Handle<Accessor>,
+
+    /// The accessor containing the keyframe output values.
+    pub output: Handle<Accessor>,
+
+    /// The keyframe interpolation algorithm.
+    pub interpolation: Interpolation,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+impl Channel {
+    /// Reads the keyframes of the channel sampler from `graph`, normalizing
+    /// integer outputs.
+    ///
+    /// Returns `None` if an accessor is missing from `graph`, the input is not
+    /// a `SCALAR` accessor, or the outputs do not match the target property.
+    pub fn keyframes(&self, graph: &Graph) -> Option<Keyframes> {
+        let input = graph.get(self.input)?;
+        let output = graph.get(self.output)?;
+        if input.dimensions != Dimensions::Scalar {
+            return None;
+        }
+        let inputs = input.to_f32::<1>().into_iter().map(|[x]| x).collect();
+        let outputs = (0..output.values.len())
+            .map(|i| output.values.get_f32(i, output.normalized))
+            .collect();
+        let rotation = self.property == Property::Rotation;
+        let keyframes = Keyframes::new(self.interpolation, inputs, outputs, rotation)?;
+        Some(keyframes).filter(|x| x.fits(self.property))
+    }
+}
@@ -0,0 +1,260 @@
+use crate::extensions::Extensions;
+use crate::meshopt::{self, Filter, Mode};
+
+use super::accessor::Values;
+use super::write::align4;
+use super::Accessor;
+
+/// Options for compressing buffer views with `EXT_meshopt_compression`.
+///
+/// The filters are lossy and disabled by default. Each filter is enabled by
+/// setting the number of bits it keeps per component, which is clamped to the
+/// size of the component type.
+#[derive(Clone, Debug)]
+pub struct MeshoptOptions {
+    /// Whether the extension is required to load the asset.
+    ///
+    /// When `true`, the uncompressed data is not written and compressed views
+    /// refer to a fallback buffer without data. Otherwise the uncompressed
+    /// data is kept in a fallback buffer for loaders without support for the
+    /// extension.
+    pub required: bool,
+
+    /// Bits of the octahedral filter, used for normalized `NORMAL` and
+    /// `TANGENT` attributes of type `i8` or `i16`, or 0 to disable it.
+    pub octahedral_bits: u8,
+
+    /// Bits of the quaternion filter, used for normalized `i16` rotation
+    /// outputs of linear and step animation samplers, or 0 to disable it.
+    pub quaternion_bits: u8,
+
+    /// Mantissa bits of the exponential filter, used for `f32` vertex
+    /// attributes, or 0 to disable it.
+    pub exponential_bits: u8,
+}
+
+impl Default for MeshoptOptions {
+    fn default() -> Self {
+        Self {
+            required: true,
+            octahedral_bits: 0,
+            quaternion_bits: 0,
+            exponential_bits: 0,
+        }
+    }
+}
+
+/// A buffer view to be compressed.
+pub(super) struct Stream {
+    /// The JSON index of the buffer view.
+    pub view: usize,
+
+    /// The compression mode.
+    pub mode: Mode,
+
+    /// The filter applied to `data`.
+    pub filter: Filter,
+
+    /// The size of one element in bytes.
+    pub stride: usize,
+
+    /// The element data with the filter applied, but not yet compressed.
+    pub data: Vec<u8>,
+}
+
+impl Stream {
+    /// Compresses the element data.
+    fn encode(&self) -> Vec<u8> {
+        match self.mode {
+            Mode::Attributes => meshopt::encode_vertex_buffer(&self.data, self.stride),
+            Mode::Triangles => meshopt::encode_index_buffer(&self.indices()),
+            Mode::Indices => meshopt::encode_index_sequence(&self.indices()),
+        }
+    }
+
+    /// Returns the element data as indices.
+    fn indices(&self) -> Vec<u32> {
+        use byteorder::{ByteOrder, LE};
+        self.data
+            .chunks_exact(self.stride)
+            .map(|x| match self.stride {
+                2 => LE::read_u16(x) as u32,
+                _ => LE::read_u32(x),
+            })
+            .collect()
+    }
+}
+
+/// Applies `filter` to the data of `accessor`, written with elements of
+/// `stride` bytes.
+///
+/// Returns the filtered data plus a copy of the accessor holding the values
+/// that decoding reconstructs, or `None` if the filter does not apply.
+pub(super) fn apply_filter(
+    accessor: &Accessor,
+    filter: Filter,
+    stride: usize,
+    bits: u8,
+) -> Option<(Vec<u8>, Accessor)> {
+    let count = accessor.count();
+    let bits = bits as u32;
+    let mut data = match filter {
+        Filter::None => return None,
+        Filter::Octahedral => {
+            let values: Vec<[f32; 4]> = accessor.to_f32();
+            let bits = bits.clamp(2, stride as u32 * 2);
+            meshopt::encode_filter_octahedral(&values, stride, bits)
+        }
+        Filter::Quaternion => {
+            meshopt::encode_filter_quaternion(&accessor.to_f32(), bits.clamp(4, 16))
+        }
+        Filter::Exponential => {
+            let values = match &accessor.values {
+                Values::F32(values) => values,
+                _ => return None,
+            };
+            meshopt::encode_filter_exponential(values, stride, bits.clamp(1, 24))
+        }
+    };
+    let filtered = data.clone();
+    meshopt::decode_filter(filter, stride, &mut data).ok()?;
+
+    // Read the decoded components back, skipping element padding.
+    let size = accessor.data_type().size();
+    let n = accessor.dimensions.multiplicity();
+    let mut values = Values::with_capacity(accessor.data_type(), count * n);
+    for element in data.chunks_exact(stride) {
+        for component in element[..n * size].chunks_exact(size) {
+            values.push_bytes(component);
+        }
+    }
+    let decoded = Accessor {
+        values,
+        ..accessor.gather(&[])
+    };
+    Some((filtered, decoded))
+}
+
+/// Moves the data of `streams` into compressed form.
+///
+/// Each buffer holding compressed views gets a fallback buffer that the views
+/// are redirected to, appended after the existing buffers. The compressed
+/// streams and the remaining views are laid out anew in the original buffer.
+/// The fallback buffer holds the uncompressed data unless the extension is
+/// required, in which case it has no data.
+pub(super) fn compress(
+    root: &mut json::Root,
+    data: &mut Vec<Vec<u8>>,
+    extensions: &mut Extensions,
+    streams: &[Stream],
+    options: &MeshoptOptions,
+) {
+    let mut compressed = vec![None; root.buffer_views.len()];
+    for stream in streams {
+        compressed[stream.view] = Some(stream);
+    }
+
+    for buffer in 0..root.buffers.len() {
+        let views: Vec<usize> = (0..root.buffer_views.len())
+            .filter(|x| root.buffer_views[*x].buffer.value() == buffer)
+            .collect();
+        if !views.iter().any(|x| compressed[*x].is_some()) {
+            continue;
+        }
+
+        let fallback = root.buffers.len();
+        let source = std::mem::take(&mut data[buffer]);
+        let mut fallback_data = Vec::new();
+        let mut fallback_length = 0;
+        for index in views {
+            let view = &mut root.buffer_views[index];
+            let start = view.byte_offset.unwrap_or(0) as usize;
+            let bytes = &source[start..start + view.byte_length as usize];
+            let stream = match compressed[index] {
+                Some(stream) => stream,
+                None => {
+                    let out = &mut data[buffer];
+                    out.resize(align4(out.len()), 0);
+                    view.byte_offset = Some(out.len() as u32);
+                    out.extend_from_slice(bytes);
+                    continue;
+                }
+            };
+
+            let encoded = stream.encode();
+            let out = &mut data[buffer];
+            out.resize(align4(out.len()), 0);
+            let mut value = object(&[
+                ("buffer", buffer.into()),
+                ("byteOffset", out.len().into()),
+                ("byteLength", encoded.len().into()),
+                ("byteStride", stream.stride.into()),
+                ("count", (stream.data.len() / stream.stride).into()),
+                ("mode", stream.mode.name().into()),
+            ]);
+            if stream.filter != Filter::None {
+                value["filter"] = stream.filter.name().into();
+            }
+            out.extend_from_slice(&encoded);
+            extensions.insert("bufferViews", index, meshopt::EXTENSION, value);
+
+            fallback_length = align4(fallback_length);
+            view.buffer = json::Index::new(fallback as u32);
+            view.byte_offset = Some(fallback_length as u32);
+            fallback_length += bytes.len();
+            if !options.required {
+                fallback_data.resize(fallback_length - bytes.len(), 0);
+                fallback_data.extend_from_slice(bytes);
+            }
+        }
+
+        root.buffers.push(json::Buffer {
+            byte_length: align4(fallback_length) as u32,
+            extensions: Default::default(),
+            extras: Default::default(),
+            #[cfg(feature = "names")]
+            name: None,
+            uri: None,
+        });
+        fallback_data.resize(
+            if options.required {
+                0
+            } else {
+                align4(fallback_length)
+            },
+            0,
+        );
+        data.push(fallback_data);
+        extensions.insert(
+            "buffers",
+            fallback,
+            meshopt::EXTENSION,
+            object(&[("fallback", true.into())]),
+        );
+    }
+
+    for (buffer, data) in root.buffers.iter_mut().zip(data.iter_mut()) {
+        if !data.is_empty() || buffer.byte_length == 0 {
+            data.resize(align4(data.len()), 0);
+            buffer.byte_length = data.len() as u32;
+        }
+    }
+
+    let name = meshopt::EXTENSION.to_string();
+    if !root.extensions_used.contains(&name) {
+        root.extensions_used.push(name.clone());
+    }
+    if options.required && !root.extensions_required.contains(&name) {
+        root.extensions_required.push(name);
+    }
+}
+
+/// Returns a JSON object with the given entries.
+fn object(entries: &[(&str, json::Value)]) -> json::Value {
+    json::Value::Object(
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect(),
+    )
+}
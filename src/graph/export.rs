@@ -0,0 +1,186 @@
+use std::borrow::Cow;
+use std::path::Path;
+use std::{fs, mem};
+
+use crate::binary::{Glb, Header};
+use crate::{buffer, meshopt, Document, Result};
+
+use super::write::{align4, guess_mime_type};
+use super::{Buffer, Graph, Image, WriteOptions};
+
+/// Percent-encodes a file name for use as a relative URI.
+fn encode_uri(name: &str) -> String {
+    let mut uri = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Returns the file extension for an image MIME type.
+fn image_extension(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/jpeg" => "jpg",
+        "image/webp" => "webp",
+        _ => "png",
+    }
+}
+
+/// Appends the data of every buffer after the first to the first buffer and
+/// removes them, redirecting their buffer views.
+///
+/// Buffers without data, such as fallback buffers of compressed views, are
+/// kept.
+fn embed_buffers(root: &mut json::Root, buffers: &mut Vec<buffer::Data>) {
+    for index in (1..buffers.len()).rev() {
+        if buffers[index].0.is_empty() {
+            continue;
+        }
+        let data = buffers.remove(index).0;
+        root.buffers.remove(index);
+        let first = &mut buffers[0].0;
+        first.resize(align4(first.len()), 0);
+        let offset = first.len() as u32;
+        first.extend_from_slice(&data);
+        root.buffers[0].byte_length = first.len() as u32;
+        for view in &mut root.buffer_views {
+            let buffer = view.buffer.value();
+            if buffer == index {
+                view.buffer = json::Index::new(0);
+                view.byte_offset = Some(view.byte_offset.unwrap_or(0) + offset);
+            } else if buffer > index {
+                view.buffer = json::Index::new(buffer as u32 - 1);
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Writes the graph to binary glTF.
+    ///
+    /// All accessor and image data is packed into the `BIN` chunk; any
+    /// additional buffers of the graph are merged into the first one. Images
+    /// without data are written as references to their URI.
+    pub fn to_glb(&self) -> Result<Vec<u8>> {
+        self.to_glb_with(&WriteOptions::default())
+    }
+
+    /// Writes the graph to binary glTF with the given options, see
+    /// [`to_glb`].
+    ///
+    /// When buffer views are compressed without requiring
+    /// `EXT_meshopt_compression`, the uncompressed data is stored in the `BIN`
+    /// chunk alongside the compressed data.
+    ///
+    /// [`to_glb`]: #method.to_glb
+    pub fn to_glb_with(&self, options: &WriteOptions) -> Result<Vec<u8>> {
+        let mut graph = self.clone();
+        let mut buffers = graph.handles::<Buffer>().into_iter();
+        if let Some(first) = buffers.next() {
+            graph[first].uri = None;
+        }
+        for buffer in buffers {
+            graph.remove(buffer);
+        }
+
+        let (Document(mut root, extensions), mut buffers) = graph.to_document_with(options)?;
+        embed_buffers(&mut root, &mut buffers);
+        let json = json::serialize::to_vec(&Document(root, extensions).into_json_value()?)?;
+        let glb = Glb {
+            header: Header {
+                magic: *b"glTF",
+                version: 2,
+                length: 0,
+            },
+            json: Cow::Owned(json),
+            bin: buffers.into_iter().next().map(|x| Cow::Owned(x.0)),
+        };
+        glb.to_vec()
+    }
+
+    /// Writes the graph to `path`.
+    ///
+    /// When `path` has a `.glb` extension the graph is written as binary glTF,
+    /// see [`to_glb`]. Otherwise a `.gltf` file is written alongside one
+    /// `.bin` file per buffer and one file per embedded image, all named after
+    /// the file stem of `path`. Images without data are written as references
+    /// to their URI; the referenced files are not copied.
+    ///
+    /// [`to_glb`]: #method.to_glb
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.export_with(path, &WriteOptions::default())
+    }
+
+    /// Writes the graph to `path` with the given options, see [`export`].
+    ///
+    /// No file is written for fallback buffers of compressed buffer views
+    /// that have no data.
+    ///
+    /// [`export`]: #method.export
+    pub fn export_with<P: AsRef<Path>>(&self, path: P, options: &WriteOptions) -> Result<()> {
+        let path = path.as_ref();
+        let binary = path
+            .extension()
+            .is_some_and(|x| x.eq_ignore_ascii_case("glb"));
+        if binary {
+            fs::write(path, self.to_glb_with(options)?)?;
+            return Ok(());
+        }
+
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let stem = path
+            .file_stem()
+            .map_or(Cow::Borrowed("scene"), |x| x.to_string_lossy());
+        let mut files = Vec::new();
+
+        let mut graph = self.clone();
+        for (i, handle) in graph.handles::<Image>().into_iter().enumerate() {
+            let image = &mut graph[handle];
+            if image.data.is_empty() {
+                continue;
+            }
+            let mime_type = image
+                .mime_type
+                .clone()
+                .unwrap_or_else(|| guess_mime_type(&image.data).to_string());
+            let name = format!("{}{}.{}", stem, i, image_extension(&mime_type));
+            image.uri = Some(encode_uri(&name));
+            image.mime_type = Some(mime_type);
+            files.push((name, mem::take(&mut image.data)));
+        }
+
+        let (document, buffers) = graph.to_document_with(options)?;
+        let written: Vec<bool> = document
+            .buffers()
+            .zip(&buffers)
+            .map(|(buffer, data)| !(data.is_empty() && meshopt::is_fallback(&buffer)))
+            .collect();
+        let Document(mut root, extensions) = document;
+        let single = written.iter().filter(|x| **x).count() == 1;
+        for (i, (buffer, data)) in root.buffers.iter_mut().zip(buffers).enumerate() {
+            if !written[i] {
+                continue;
+            }
+            let name = if single {
+                format!("{}.bin", stem)
+            } else {
+                format!("{}{}.bin", stem, i)
+            };
+            buffer.uri = Some(encode_uri(&name));
+            files.push((name, data.0));
+        }
+
+        let writer = fs::File::create(path)?;
+        let value = Document(root, extensions).into_json_value()?;
+        json::serialize::to_writer_pretty(writer, &value)?;
+        for (name, data) in files {
+            fs::write(directory.join(name), data)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+
+use crate::accessor::DataType;
+use crate::extensions::extension_names;
+use crate::{meshopt, Semantic};
+
+use super::{Accessor, Graph, Handle, Image, Material, Mesh, Node, Sampler, Texture};
+
+/// The `EXT_mesh_gpu_instancing` extension name.
+pub(super) const EXT_MESH_GPU_INSTANCING: &str = "EXT_mesh_gpu_instancing";
+
+/// The `KHR_mesh_quantization` extension name.
+pub(super) const KHR_MESH_QUANTIZATION: &str = "KHR_mesh_quantization";
+
+/// The `MSFT_lod` extension name.
+pub(super) const MSFT_LOD: &str = "MSFT_lod";
+
+/// The name of the `extras` entry holding `MSFT_lod` screen coverage hints.
+pub(super) const MSFT_SCREENCOVERAGE: &str = "MSFT_screencoverage";
+
+/// Returns `true` if `accessor` holds a vertex attribute type that the core
+/// specification does not allow for `semantic`.
+fn is_quantized(semantic: &Semantic, accessor: &Accessor) -> bool {
+    let data_type = accessor.data_type();
+    match semantic {
+        Semantic::Positions | Semantic::Normals | Semantic::Tangents => data_type != DataType::F32,
+        Semantic::TexCoords(_) => match data_type {
+            DataType::F32 => false,
+            DataType::U8 | DataType::U16 => !accessor.normalized,
+            _ => true,
+        },
+        _ => false,
+    }
+}
+
+impl Graph {
+    /// Returns `true` if any vertex attribute requires `KHR_mesh_quantization`.
+    pub(super) fn uses_mesh_quantization(&self) -> bool {
+        let quantized = |semantic: &Semantic, handle: Option<Handle<Accessor>>| {
+            handle
+                .and_then(|x| self.get(x))
+                .is_some_and(|accessor| is_quantized(semantic, accessor))
+        };
+        self.iter::<Mesh>()
+            .flat_map(|(_, mesh)| &mesh.primitives)
+            .any(|primitive| {
+                primitive
+                    .attributes
+                    .iter()
+                    .any(|(semantic, accessor)| quantized(semantic, Some(*accessor)))
+                    || primitive.targets.iter().any(|target| {
+                        quantized(&Semantic::Positions, target.positions)
+                            || quantized(&Semantic::Normals, target.normals)
+                            || quantized(&Semantic::Tangents, target.tangents)
+                    })
+            })
+    }
+
+    /// Returns the names of the extensions in the extension objects of the
+    /// graph.
+    fn extension_names(&self) -> HashSet<String> {
+        let mut names = HashSet::new();
+        extension_names(&self.extensions, &mut names);
+        for (_, material) in self.iter::<Material>() {
+            extension_names(&material.extensions, &mut names);
+            for info in material.textures().iter().filter_map(|x| x.as_ref()) {
+                extension_names(&info.extensions, &mut names);
+            }
+        }
+        for (_, texture) in self.iter::<Texture>() {
+            extension_names(&texture.extensions, &mut names);
+        }
+        for (_, sampler) in self.iter::<Sampler>() {
+            extension_names(&sampler.extensions, &mut names);
+        }
+        for (_, image) in self.iter::<Image>() {
+            extension_names(&image.extensions, &mut names);
+        }
+        for (_, node) in self.iter::<Node>() {
+            extension_names(&node.extensions, &mut names);
+        }
+        names
+    }
+
+    /// Returns a required extension that is neither modelled by the graph
+    /// nor kept in its extension objects.
+    pub(super) fn unsupported_extension(&self) -> Option<&str> {
+        let kept = self.extension_names();
+        let modelled = [
+            EXT_MESH_GPU_INSTANCING,
+            KHR_MESH_QUANTIZATION,
+            MSFT_LOD,
+            meshopt::EXTENSION,
+        ];
+        self.extensions_required
+            .iter()
+            .map(String::as_str)
+            .find(|x| !modelled.contains(x) && !kept.contains(*x))
+    }
+
+    /// Declares or removes extensions whose use follows from the contents of
+    /// the graph.
+    ///
+    /// Currently this covers `KHR_mesh_quantization`, which is added to both
+    /// `extensions_used` and `extensions_required` when a vertex attribute
+    /// uses a component type the core specification does not allow,
+    /// `MSFT_lod`, which is added to `extensions_used` when a node has levels
+    /// of detail, and `EXT_mesh_gpu_instancing`, which is added to
+    /// `extensions_used` when a node has per-instance attributes. Each is
+    /// removed from both lists otherwise, while an existing required entry is
+    /// kept as long as the extension is used. Other extension names are left
+    /// untouched.
+    pub fn update_extensions(&mut self) {
+        let used = self.uses_mesh_quantization();
+        self.set_extension(KHR_MESH_QUANTIZATION, used, used);
+        let used = self.iter::<Node>().any(|(_, node)| !node.lods.is_empty());
+        self.set_extension(MSFT_LOD, used, false);
+        let used = self
+            .iter::<Node>()
+            .any(|(_, node)| node.instancing.is_some());
+        self.set_extension(EXT_MESH_GPU_INSTANCING, used, false);
+    }
+
+    /// Adds a lower level of detail to `node` that renders `mesh` instead of
+    /// the node's own mesh, and returns the new LOD node.
+    ///
+    /// The LOD node copies the transform, skin and morph target weights of
+    /// `node` and is appended after its existing levels of detail, as defined
+    /// by the `MSFT_lod` extension, which is declared as used. The LOD node
+    /// is not added to any scene; it is reachable through [`Node::lods`].
+    /// Screen coverage hints are not changed.
+    ///
+    /// [`Node::lods`]: struct.Node.html#structfield.lods
+    pub fn add_lod(&mut self, node: Handle<Node>, mesh: Handle<Mesh>) -> Handle<Node> {
+        let base = &self[node];
+        let lod = Node {
+            name: base
+                .name
+                .as_ref()
+                .map(|x| format!("{}_LOD{}", x, base.lods.len() + 1)),
+            transform: base.transform.clone(),
+            mesh: Some(mesh),
+            skin: base.skin,
+            weights: base.weights.clone(),
+            ..Default::default()
+        };
+        let lod = self.add(lod);
+        self[node].lods.push(lod);
+        self.set_extension(MSFT_LOD, true, false);
+        lod
+    }
+
+    /// Adds `name` to the extension lists when `used`, to the required list
+    /// only if `required`, and removes it from both lists otherwise.
+    ///
+    /// An existing required entry is kept while the extension is used.
+    fn set_extension(&mut self, name: &str, used: bool, required: bool) {
+        if !used {
+            self.extensions_used.retain(|x| x != name);
+            self.extensions_required.retain(|x| x != name);
+            return;
+        }
+        for (list, add) in [
+            (&mut self.extensions_used, true),
+            (&mut self.extensions_required, required),
+        ] {
+            if add && !list.iter().any(|x| x == name) {
+                list.push(name.to_string());
+            }
+        }
+    }
+}
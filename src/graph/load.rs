@@ -0,0 +1,543 @@
+use crate::accessor::{sparse, DataType, Dimensions};
+use crate::{buffer, camera, image, meshopt, Document, Error, Result};
+
+use super::accessor::{column_layout, element_size};
+use super::extensions::{EXT_MESH_GPU_INSTANCING, MSFT_LOD, MSFT_SCREENCOVERAGE};
+use super::*;
+
+/// Returns the user-defined name of a wrapper object, if names are enabled.
+macro_rules! name {
+    ($object:expr) => {{
+        #[cfg(feature = "names")]
+        let name = $object.name().map(String::from);
+        #[cfg(not(feature = "names"))]
+        let name = None;
+        name
+    }};
+}
+
+/// Returns the bytes of a buffer view.
+fn view_slice<'s>(view: &buffer::View, buffers: &'s [buffer::Data]) -> Result<&'s [u8]> {
+    let start = view.offset();
+    let end = start + view.length();
+    buffers
+        .get(view.buffer().index())
+        .and_then(|data| data.get(start..end))
+        .ok_or(Error::ViewBounds { view: view.index() })
+}
+
+/// Appends `count` elements read from `data` to `values`.
+fn read_elements(
+    values: &mut Values,
+    data: &[u8],
+    offset: usize,
+    stride: usize,
+    count: usize,
+    dimensions: Dimensions,
+) -> Option<()> {
+    let data_type = values.data_type();
+    let size = data_type.size();
+    let (columns, rows, column_stride) = column_layout(data_type, dimensions);
+    let end = offset + stride * count.saturating_sub(1) + element_size(data_type, dimensions);
+    if count > 0 && end > data.len() {
+        return None;
+    }
+    for i in 0..count {
+        for column in 0..columns {
+            for row in 0..rows {
+                let start = offset + i * stride + column * column_stride + row * size;
+                values.push_bytes(&data[start..start + size]);
+            }
+        }
+    }
+    Some(())
+}
+
+/// Reads an accessor, applying sparse substitutions.
+pub(crate) fn load_accessor(
+    accessor: crate::Accessor,
+    buffers: &[buffer::Data],
+) -> Result<Accessor> {
+    let data_type = accessor.data_type();
+    let dimensions = accessor.dimensions();
+    let n = dimensions.multiplicity();
+    let count = accessor.count();
+    let mut values = Values::with_capacity(data_type, count * n);
+    if let Some(view) = accessor.view() {
+        let data = view_slice(&view, buffers)?;
+        let stride = view
+            .stride()
+            .unwrap_or_else(|| element_size(data_type, dimensions));
+        read_elements(
+            &mut values,
+            data,
+            accessor.offset(),
+            stride,
+            count,
+            dimensions,
+        )
+        .ok_or(Error::ViewBounds { view: view.index() })?;
+    } else {
+        for _ in 0..count * n {
+            values.push_u32(0);
+        }
+    }
+
+    if let Some(sparse) = accessor.sparse() {
+        let sparse_count = sparse.count() as usize;
+        let indices = sparse.indices();
+        let index_view = indices.view();
+        let index_type = match indices.index_type() {
+            sparse::IndexType::U8 => DataType::U8,
+            sparse::IndexType::U16 => DataType::U16,
+            sparse::IndexType::U32 => DataType::U32,
+        };
+        let mut index_values = Values::with_capacity(index_type, sparse_count);
+        read_elements(
+            &mut index_values,
+            view_slice(&index_view, buffers)?,
+            indices.offset() as usize,
+            index_type.size(),
+            sparse_count,
+            Dimensions::Scalar,
+        )
+        .ok_or(Error::ViewBounds {
+            view: index_view.index(),
+        })?;
+
+        let value_view = sparse.values().view();
+        let mut substitutes = Values::with_capacity(data_type, sparse_count * n);
+        read_elements(
+            &mut substitutes,
+            view_slice(&value_view, buffers)?,
+            sparse.values().offset() as usize,
+            element_size(data_type, dimensions),
+            sparse_count,
+            dimensions,
+        )
+        .ok_or(Error::ViewBounds {
+            view: value_view.index(),
+        })?;
+
+        for i in 0..sparse_count {
+            let target = index_values.get_u32(i) as usize;
+            if target >= count {
+                return Err(Error::ViewBounds {
+                    view: index_view.index(),
+                });
+            }
+            for j in 0..n {
+                values.copy_component(target * n + j, &substitutes, i * n + j);
+            }
+        }
+    }
+
+    Ok(Accessor {
+        name: name!(accessor),
+        buffer: None,
+        dimensions,
+        normalized: accessor.normalized(),
+        values,
+        extras: accessor.extras().clone(),
+    })
+}
+
+/// Splits a `data:` URI into its media type and decoded payload.
+#[cfg(feature = "import")]
+fn decode_data_uri(uri: &str) -> Option<Result<(Option<String>, Vec<u8>)>> {
+    use base64::Engine;
+    let rest = uri.strip_prefix("data:")?;
+    let (media_type, payload) = rest.split_once(";base64,")?;
+    let media_type = Some(media_type).filter(|x| !x.is_empty()).map(String::from);
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(payload)
+        .map_err(Error::Base64);
+    Some(data.map(|data| (media_type, data)))
+}
+
+/// Returns the extension objects of the object at `pointer` below object
+/// `index` of `array`, except the ones called `skip`, which the graph models.
+fn load_extensions(
+    document: &Document,
+    array: &str,
+    index: usize,
+    pointer: &str,
+    skip: &[&str],
+) -> ExtensionMap {
+    document
+        .1
+        .object(array, index, pointer)
+        .and_then(|x| x.as_object())
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| !skip.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+fn load_image(image: crate::Image, buffers: &[buffer::Data]) -> Result<Image> {
+    let (mime_type, uri, data) = match image.source() {
+        image::Source::View { view, mime_type } => (
+            Some(mime_type.to_string()),
+            None,
+            view_slice(&view, buffers)?.to_vec(),
+        ),
+        image::Source::Uri { uri, mime_type } => {
+            #[cfg(feature = "import")]
+            if let Some(decoded) = decode_data_uri(uri) {
+                let (media_type, data) = decoded?;
+                let mime_type = mime_type.map(String::from).or(media_type);
+                return Ok(Image {
+                    name: name!(image),
+                    mime_type,
+                    uri: None,
+                    data,
+                    extensions: Default::default(),
+                    extras: image.extras().clone(),
+                });
+            }
+            (
+                mime_type.map(String::from),
+                Some(uri.to_string()),
+                Vec::new(),
+            )
+        }
+    };
+    Ok(Image {
+        name: name!(image),
+        mime_type,
+        uri,
+        data,
+        extensions: Default::default(),
+        extras: image.extras().clone(),
+    })
+}
+
+fn load_material(document: &Document, material: crate::Material) -> Material {
+    let index = material.index().unwrap_or_default();
+    let extensions = |pointer| load_extensions(document, "materials", index, pointer, &[]);
+    let info = |info: Option<crate::texture::Info>, pointer| {
+        info.map(|info| TextureInfo {
+            texture: Handle::new(info.texture().index()),
+            tex_coord: info.tex_coord(),
+            extensions: extensions(pointer),
+        })
+    };
+    let pbr = material.pbr_metallic_roughness();
+    let normal = material.normal_texture();
+    let occlusion = material.occlusion_texture();
+    Material {
+        name: name!(material),
+        base_color_factor: pbr.base_color_factor(),
+        base_color_texture: info(
+            pbr.base_color_texture(),
+            "/pbrMetallicRoughness/baseColorTexture",
+        ),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        metallic_roughness_texture: info(
+            pbr.metallic_roughness_texture(),
+            "/pbrMetallicRoughness/metallicRoughnessTexture",
+        ),
+        normal_scale: normal.as_ref().map_or(1.0, |x| x.scale()),
+        normal_texture: normal.map(|x| TextureInfo {
+            texture: Handle::new(x.texture().index()),
+            tex_coord: x.tex_coord(),
+            extensions: extensions("/normalTexture"),
+        }),
+        occlusion_strength: occlusion.as_ref().map_or(1.0, |x| x.strength()),
+        occlusion_texture: occlusion.map(|x| TextureInfo {
+            texture: Handle::new(x.texture().index()),
+            tex_coord: x.tex_coord(),
+            extensions: extensions("/occlusionTexture"),
+        }),
+        emissive_texture: info(material.emissive_texture(), "/emissiveTexture"),
+        emissive_factor: material.emissive_factor(),
+        alpha_mode: material.alpha_mode(),
+        alpha_cutoff: material.alpha_cutoff(),
+        double_sided: material.double_sided(),
+        extensions: extensions(""),
+        extras: material.extras().clone(),
+    }
+}
+
+fn load_primitive(primitive: crate::Primitive) -> Primitive {
+    let handle = |accessor: crate::Accessor| Handle::new(accessor.index());
+    let mut attributes: Vec<_> = primitive
+        .attributes()
+        .map(|(semantic, accessor)| (semantic, handle(accessor)))
+        .collect();
+    // Attributes are stored in a hash map, so sort them for a stable order.
+    attributes.sort_by_key(|(semantic, _)| semantic.to_string());
+    Primitive {
+        mode: primitive.mode(),
+        attributes,
+        indices: primitive.indices().map(handle),
+        material: primitive.material().index().map(Handle::new),
+        targets: primitive
+            .morph_targets()
+            .map(|target| MorphTarget {
+                positions: target.positions().map(handle),
+                normals: target.normals().map(handle),
+                tangents: target.tangents().map(handle),
+            })
+            .collect(),
+        extras: primitive.extras().clone(),
+    }
+}
+
+fn load_camera(camera: crate::Camera) -> Camera {
+    let projection = match camera.projection() {
+        camera::Projection::Orthographic(x) => Projection::Orthographic {
+            xmag: x.xmag(),
+            ymag: x.ymag(),
+            zfar: x.zfar(),
+            znear: x.znear(),
+        },
+        camera::Projection::Perspective(x) => Projection::Perspective {
+            aspect_ratio: x.aspect_ratio(),
+            yfov: x.yfov(),
+            zfar: x.zfar(),
+            znear: x.znear(),
+        },
+    };
+    Camera {
+        name: name!(camera),
+        projection,
+        extras: camera.extras().clone(),
+    }
+}
+
+/// Returns `extras` without the entry called `name`, which the graph models
+/// as a separate field.
+#[cfg(feature = "extras")]
+fn without_extra(extras: &json::Extras, name: &str) -> json::Extras {
+    let mut value: json::Value = extras
+        .as_ref()
+        .and_then(|x| json::deserialize::from_str(x.get()).ok())?;
+    let object = value.as_object_mut()?;
+    if object.remove(name).is_none() {
+        return extras.clone();
+    }
+    if object.is_empty() {
+        return None;
+    }
+    json::serialize::to_string(&value)
+        .ok()
+        .and_then(|x| json::extras::RawValue::from_string(x).ok())
+}
+
+#[cfg(not(feature = "extras"))]
+fn without_extra(extras: &json::Extras, _: &str) -> json::Extras {
+    extras.clone()
+}
+
+impl Graph {
+    /// Loads a graph from a glTF document and its buffer data.
+    ///
+    /// Accessor data is copied out of the buffers, densifying sparse
+    /// accessors. Images stored in buffer views or `data:` URIs are copied
+    /// as encoded bytes; other images keep their URI and no data.
+    ///
+    /// The JSON index of every object becomes the slot index of its handle.
+    ///
+    /// Extensions that the graph does not model are kept as raw JSON where
+    /// the graph has an `extensions` field, if `document` was read with
+    /// them, e.g. by [`Gltf::from_slice`] or [`import`]. Other extension
+    /// objects are dropped. Returns [`Error::UnsupportedExtension`] if a
+    /// required extension is neither modelled nor kept.
+    ///
+    /// [`Gltf::from_slice`]: ../struct.Gltf.html#method.from_slice
+    /// [`import`]: ../fn.import.html
+    /// [`Error::UnsupportedExtension`]: ../enum.Error.html#variant.UnsupportedExtension
+    pub fn from_document(document: &Document, buffers: &[buffer::Data]) -> Result<Self> {
+        let mut graph = Graph::new();
+        let root = &document.0;
+        graph.asset = root.asset.clone();
+        graph.default_scene = document.default_scene().map(|x| Handle::new(x.index()));
+        graph.extensions_used = root.extensions_used.clone();
+        graph.extensions_required = root.extensions_required.clone();
+        graph.extensions = document
+            .1
+            .root()
+            .and_then(|x| x.as_object())
+            .into_iter()
+            .flatten()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        graph.extras = root.extras.clone();
+
+        for buffer in document.buffers() {
+            graph.add(Buffer {
+                name: name!(buffer),
+                uri: match buffer.source() {
+                    buffer::Source::Uri(uri) if !uri.starts_with("data:") => Some(uri.to_string()),
+                    _ => None,
+                },
+                extras: buffer.extras().clone(),
+            });
+        }
+
+        for accessor in document.accessors() {
+            let buffer = accessor.view().map(|view| {
+                // Keep compressed data with the buffer of its stream.
+                match meshopt::Compression::from_view(&view) {
+                    Ok(Some(compression)) => Handle::new(compression.buffer),
+                    _ => Handle::new(view.buffer().index()),
+                }
+            });
+            let mut loaded = load_accessor(accessor, buffers)?;
+            loaded.buffer = buffer;
+            graph.add(loaded);
+        }
+
+        // The data of compressed views has been decoded on import, so the
+        // graph no longer uses the extension or its fallback buffers.
+        for buffer in document.buffers() {
+            if meshopt::is_fallback(&buffer) {
+                graph.remove(Handle::<Buffer>::new(buffer.index()));
+            }
+        }
+        graph.extensions_used.retain(|x| x != meshopt::EXTENSION);
+        graph
+            .extensions_required
+            .retain(|x| x != meshopt::EXTENSION);
+
+        for image in document.images() {
+            let index = image.index();
+            let mut loaded = load_image(image, buffers)?;
+            loaded.extensions = load_extensions(document, "images", index, "", &[]);
+            graph.add(loaded);
+        }
+
+        for sampler in document.samplers() {
+            graph.add(Sampler {
+                name: name!(sampler),
+                mag_filter: sampler.mag_filter(),
+                min_filter: sampler.min_filter(),
+                wrap_s: sampler.wrap_s(),
+                wrap_t: sampler.wrap_t(),
+                extensions: sampler
+                    .index()
+                    .map(|x| load_extensions(document, "samplers", x, "", &[]))
+                    .unwrap_or_default(),
+                extras: sampler.extras().clone(),
+            });
+        }
+
+        for texture in document.textures() {
+            graph.add(Texture {
+                name: name!(texture),
+                source: Handle::new(texture.source().index()),
+                sampler: texture.sampler().index().map(Handle::new),
+                extensions: load_extensions(document, "textures", texture.index(), "", &[]),
+                extras: texture.extras().clone(),
+            });
+        }
+
+        for material in document.materials() {
+            graph.add(load_material(document, material));
+        }
+
+        for mesh in document.meshes() {
+            graph.add(Mesh {
+                name: name!(mesh),
+                primitives: mesh.primitives().map(load_primitive).collect(),
+                weights: mesh.weights().map(<[f32]>::to_vec),
+                extras: mesh.extras().clone(),
+            });
+        }
+
+        for camera in document.cameras() {
+            graph.add(load_camera(camera));
+        }
+
+        for skin in document.skins() {
+            graph.add(Skin {
+                name: name!(skin),
+                inverse_bind_matrices: skin.inverse_bind_matrices().map(|x| Handle::new(x.index())),
+                joints: skin.joints().map(|x| Handle::new(x.index())).collect(),
+                skeleton: skin.skeleton().map(|x| Handle::new(x.index())),
+                extras: skin.extras().clone(),
+            });
+        }
+
+        for node in document.nodes() {
+            let levels = node.lods();
+            let mut screen_coverage: Option<Vec<f32>> = document
+                .1
+                .extra("nodes", node.index(), MSFT_SCREENCOVERAGE)
+                .and_then(|x| json::deserialize::from_value(x.clone()).ok());
+            if !levels.is_empty() {
+                // Drop the hints of levels whose node is out of range.
+                screen_coverage = screen_coverage
+                    .map(|_| levels.iter().map_while(|x| x.screen_coverage()).collect());
+            }
+            graph.add(Node {
+                name: name!(node),
+                transform: node.transform(),
+                children: node.children().map(|x| Handle::new(x.index())).collect(),
+                camera: node.camera().map(|x| Handle::new(x.index())),
+                mesh: node.mesh().map(|x| Handle::new(x.index())),
+                skin: node.skin().map(|x| Handle::new(x.index())),
+                weights: node.weights().map(<[f32]>::to_vec),
+                lods: levels
+                    .iter()
+                    .skip(1)
+                    .map(|x| Handle::new(x.node().index()))
+                    .collect(),
+                screen_coverage,
+                instancing: node.instancing().map(|instancing| Instancing {
+                    attributes: instancing
+                        .attributes()
+                        .into_iter()
+                        .map(|(name, x)| (name.to_string(), Handle::new(x.index())))
+                        .collect(),
+                }),
+                extensions: load_extensions(
+                    document,
+                    "nodes",
+                    node.index(),
+                    "",
+                    &[MSFT_LOD, EXT_MESH_GPU_INSTANCING],
+                ),
+                extras: without_extra(node.extras(), MSFT_SCREENCOVERAGE),
+            });
+        }
+
+        for scene in document.scenes() {
+            graph.add(Scene {
+                name: name!(scene),
+                nodes: scene.nodes().map(|x| Handle::new(x.index())).collect(),
+                extras: scene.extras().clone(),
+            });
+        }
+
+        for animation in document.animations() {
+            let channels = animation
+                .channels()
+                .map(|channel| {
+                    let sampler = channel.sampler();
+                    Channel {
+                        node: Handle::new(channel.target().node().index()),
+                        property: channel.target().property(),
+                        input: Handle::new(sampler.input().index()),
+                        output: Handle::new(sampler.output().index()),
+                        interpolation: sampler.interpolation(),
+                        extras: channel.extras().clone(),
+                    }
+                })
+                .collect();
+            graph.add(Animation {
+                name: name!(animation),
+                channels,
+                extras: animation.extras().clone(),
+            });
+        }
+
+        if let Some(name) = graph.unsupported_extension() {
+            return Err(Error::UnsupportedExtension(name.to_string()));
+        }
+        Ok(graph)
+    }
+}
@@ -0,0 +1,309 @@
+use crate::material::AlphaMode;
+use crate::texture::{MagFilter, MinFilter, WrappingMode};
+
+use super::{ExtensionMap, Handle};
+
+/// A reference to a [`Texture`] from a [`Material`].
+///
+/// [`Texture`]: struct.Texture.html
+/// [`Material`]: struct.Material.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureInfo {
+    /// The referenced texture.
+    pub texture: Handle<Texture>,
+
+    /// The set index of the texture's `TEXCOORD` attribute.
+    pub tex_coord: u32,
+
+    /// Extension objects, such as `KHR_texture_transform`.
+    pub extensions: ExtensionMap,
+}
+
+/// The material appearance of a primitive.
+///
+/// Only the core metallic-roughness model is represented by fields; material
+/// extensions are kept as raw JSON in `extensions`.
+#[derive(Clone, Debug)]
+pub struct Material {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The base color factor in linear RGBA.
+    pub base_color_factor: [f32; 4],
+
+    /// The base color texture.
+    pub base_color_texture: Option<TextureInfo>,
+
+    /// The metalness of the material.
+    pub metallic_factor: f32,
+
+    /// The roughness of the material.
+    pub roughness_factor: f32,
+
+    /// The metallic-roughness texture.
+    pub metallic_roughness_texture: Option<TextureInfo>,
+
+    /// The tangent space normal texture.
+    pub normal_texture: Option<TextureInfo>,
+
+    /// The scalar multiplier applied to each normal vector of the normal
+    /// texture.
+    pub normal_scale: f32,
+
+    /// The occlusion texture.
+    pub occlusion_texture: Option<TextureInfo>,
+
+    /// The scalar multiplier controlling the amount of occlusion applied.
+    pub occlusion_strength: f32,
+
+    /// The emissive texture.
+    pub emissive_texture: Option<TextureInfo>,
+
+    /// The emissive color of the material.
+    pub emissive_factor: [f32; 3],
+
+    /// The alpha rendering mode of the material.
+    pub alpha_mode: AlphaMode,
+
+    /// The alpha cutoff value of the material in `Mask` mode.
+    pub alpha_cutoff: Option<f32>,
+
+    /// Specifies whether the material is double-sided.
+    pub double_sided: bool,
+
+    /// Extension objects, such as `KHR_materials_clearcoat`.
+    ///
+    /// The `index` of the texture infos inside, the objects stored under
+    /// keys ending in `Texture`, is the slot index of a [`Texture`] handle.
+    ///
+    /// [`Texture`]: struct.Texture.html
+    pub extensions: ExtensionMap,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// A texture and its sampler.
+#[derive(Clone, Debug)]
+pub struct Texture {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The image used by this texture.
+    pub source: Handle<Image>,
+
+    /// The sampler used by this texture, or `None` for the default sampler.
+    pub sampler: Option<Handle<Sampler>>,
+
+    /// Extension objects, such as `KHR_texture_basisu`.
+    ///
+    /// The `source` of an extension object is the slot index of an
+    /// [`Image`] handle.
+    ///
+    /// [`Image`]: struct.Image.html
+    pub extensions: ExtensionMap,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// Texture sampler properties for filtering and wrapping modes.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// Magnification filter.
+    pub mag_filter: Option<MagFilter>,
+
+    /// Minification filter.
+    pub min_filter: Option<MinFilter>,
+
+    /// `s` wrapping mode.
+    pub wrap_s: WrappingMode,
+
+    /// `t` wrapping mode.
+    pub wrap_t: WrappingMode,
+
+    /// Extension objects.
+    pub extensions: ExtensionMap,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// Encoded image data used by textures.
+#[derive(Clone, Debug, Default)]
+pub struct Image {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The image MIME type, e.g. `image/png`.
+    pub mime_type: Option<String>,
+
+    /// The URI the image was loaded from.
+    ///
+    /// When `data` is empty the image is written as a reference to this URI.
+    pub uri: Option<String>,
+
+    /// The encoded image bytes.
+    pub data: Vec<u8>,
+
+    /// Extension objects.
+    pub extensions: ExtensionMap,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color_factor: [1.0, 1.0, 1.0, 1.0],
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive_texture: None,
+            emissive_factor: [0.0, 0.0, 0.0],
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: None,
+            double_sided: false,
+            extensions: Default::default(),
+            extras: Default::default(),
+        }
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            name: None,
+            mag_filter: None,
+            min_filter: None,
+            wrap_s: WrappingMode::Repeat,
+            wrap_t: WrappingMode::Repeat,
+            extensions: Default::default(),
+            extras: Default::default(),
+        }
+    }
+}
+
+impl Material {
+    /// Returns mutable references to every texture slot of the material.
+    pub fn textures_mut(&mut self) -> [&mut Option<TextureInfo>; 5] {
+        [
+            &mut self.base_color_texture,
+            &mut self.metallic_roughness_texture,
+            &mut self.normal_texture,
+            &mut self.occlusion_texture,
+            &mut self.emissive_texture,
+        ]
+    }
+
+    /// Returns every texture slot of the material.
+    pub fn textures(&self) -> [&Option<TextureInfo>; 5] {
+        [
+            &self.base_color_texture,
+            &self.metallic_roughness_texture,
+            &self.normal_texture,
+            &self.occlusion_texture,
+            &self.emissive_texture,
+        ]
+    }
+
+    /// Returns the textures referenced by the texture infos in `extensions`.
+    pub fn extension_textures(&self) -> Vec<Handle<Texture>> {
+        let mut textures = Vec::new();
+        let mut extensions = self.extensions.clone();
+        for value in extensions.values_mut() {
+            map_texture_infos(value, &mut |index| {
+                textures.push(Handle::new(index));
+                Some(index)
+            });
+        }
+        textures
+    }
+
+    /// Replaces every texture referenced by the texture infos in `extensions`
+    /// with the result of `f`, removing the texture infos for which `f`
+    /// returns `None`.
+    pub fn map_extension_textures<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Handle<Texture>) -> Option<Handle<Texture>>,
+    {
+        for value in self.extensions.values_mut() {
+            map_texture_infos(value, &mut |index| f(Handle::new(index)).map(|x| x.index()));
+        }
+    }
+}
+
+impl Texture {
+    /// Returns the images referenced by the `source` of the extension
+    /// objects.
+    pub fn extension_images(&self) -> Vec<Handle<Image>> {
+        self.extensions
+            .values()
+            .filter_map(|x| x.get("source")?.as_u64())
+            .map(|x| Handle::new(x as usize))
+            .collect()
+    }
+
+    /// Replaces every image referenced by the `source` of the extension
+    /// objects with the result of `f`, removing the extension objects for
+    /// which `f` returns `None`.
+    pub fn map_extension_images<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Handle<Image>) -> Option<Handle<Image>>,
+    {
+        self.extensions.retain(|_, value| {
+            let source = match value.get("source").and_then(|x| x.as_u64()) {
+                Some(source) => source as usize,
+                None => return true,
+            };
+            match f(Handle::new(source)) {
+                Some(image) => {
+                    value["source"] = json::Value::from(image.index());
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+}
+
+/// Replaces the `index` of every texture info in `value`, i.e. every object
+/// stored under a key ending in `Texture` that has an integer `index`, with
+/// the result of `f`, removing the texture infos for which `f` returns
+/// `None`.
+fn map_texture_infos(value: &mut json::Value, f: &mut dyn FnMut(usize) -> Option<usize>) {
+    match value {
+        json::Value::Object(object) => object.retain(|key, value| {
+            let index = value.get("index").and_then(|x| x.as_u64());
+            match index {
+                Some(index) if key.ends_with("Texture") => match f(index as usize) {
+                    Some(index) => {
+                        value["index"] = json::Value::from(index);
+                        true
+                    }
+                    None => false,
+                },
+                _ => {
+                    map_texture_infos(value, f);
+                    true
+                }
+            }
+        }),
+        json::Value::Array(items) => {
+            for item in items {
+                map_texture_infos(item, f);
+            }
+        }
+        _ => {}
+    }
+}
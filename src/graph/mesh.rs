@@ -0,0 +1,232 @@
+use crate::mesh::{Mode, Semantic};
+
+use super::{Accessor, Graph, Handle, Material, Values};
+
+/// A set of primitives to be rendered.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The geometry to be rendered with a material.
+    pub primitives: Vec<Primitive>,
+
+    /// Defines the weights to be applied to the morph targets.
+    pub weights: Option<Vec<f32>>,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// Geometry to be rendered with the given material.
+#[derive(Clone, Debug)]
+pub struct Primitive {
+    /// The type of primitives to render.
+    pub mode: Mode,
+
+    /// Vertex attributes in the order they are written.
+    pub attributes: Vec<(Semantic, Handle<Accessor>)>,
+
+    /// The accessor containing the vertex indices, if any.
+    pub indices: Option<Handle<Accessor>>,
+
+    /// The material to apply to this primitive when rendering.
+    pub material: Option<Handle<Material>>,
+
+    /// The morph targets of the primitive.
+    pub targets: Vec<MorphTarget>,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// An iterator over the triangles of a primitive.
+///
+/// See [`Primitive::triangles`].
+///
+/// [`Primitive::triangles`]: struct.Primitive.html#method.triangles
+#[derive(Clone, Debug)]
+pub struct Triangles<'a> {
+    /// The index values, or `None` for non-indexed primitives.
+    indices: Option<&'a Values>,
+
+    /// The mode the indices are read in.
+    mode: Mode,
+
+    /// The number of triangles.
+    count: usize,
+
+    /// The next triangle to be yielded.
+    next: usize,
+}
+
+/// A single morph target for a mesh primitive.
+#[derive(Clone, Debug, Default)]
+pub struct MorphTarget {
+    /// XYZ vertex position displacements.
+    pub positions: Option<Handle<Accessor>>,
+
+    /// XYZ vertex normal displacements.
+    pub normals: Option<Handle<Accessor>>,
+
+    /// XYZ vertex tangent displacements.
+    pub tangents: Option<Handle<Accessor>>,
+}
+
+impl Default for Primitive {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Triangles,
+            attributes: Vec::new(),
+            indices: None,
+            material: None,
+            targets: Vec::new(),
+            extras: Default::default(),
+        }
+    }
+}
+
+impl Primitive {
+    /// Returns the accessor with the given semantic.
+    pub fn get(&self, semantic: &Semantic) -> Option<Handle<Accessor>> {
+        self.attributes
+            .iter()
+            .find(|(x, _)| x == semantic)
+            .map(|(_, accessor)| *accessor)
+    }
+
+    /// Sets the accessor for the given semantic, returning the previous one.
+    pub fn set(
+        &mut self,
+        semantic: Semantic,
+        accessor: Handle<Accessor>,
+    ) -> Option<Handle<Accessor>> {
+        if let Some(entry) = self.attributes.iter_mut().find(|(x, _)| *x == semantic) {
+            Some(std::mem::replace(&mut entry.1, accessor))
+        } else {
+            self.attributes.push((semantic, accessor));
+            None
+        }
+    }
+
+    /// Removes the accessor with the given semantic, returning it.
+    pub fn remove(&mut self, semantic: &Semantic) -> Option<Handle<Accessor>> {
+        let position = self.attributes.iter().position(|(x, _)| x == semantic)?;
+        Some(self.attributes.remove(position).1)
+    }
+
+    /// Returns the number of vertices the primitive draws: the number of
+    /// indices, or the number of elements of its first attribute if it is
+    /// not indexed.
+    pub(crate) fn draw_count(&self, graph: &Graph) -> Option<usize> {
+        match self.indices {
+            Some(indices) => graph.get(indices).map(Accessor::count),
+            None => match self.attributes.first() {
+                Some((_, accessor)) => graph.get(*accessor).map(Accessor::count),
+                None => Some(0),
+            },
+        }
+    }
+
+    /// Returns the vertex indices of the triangles of the primitive, with
+    /// strips and fans unrolled following the glTF specification so that
+    /// every triangle keeps its winding order.
+    ///
+    /// Non-indexed primitives yield the positions of their vertices. Points
+    /// and lines, and primitives whose accessors are missing from `graph`,
+    /// yield nothing.
+    pub fn triangles<'a>(&self, graph: &'a Graph) -> Triangles<'a> {
+        let indices = self.indices.and_then(|x| graph.get(x)).map(|x| &x.values);
+        let vertices = match self.draw_count(graph) {
+            Some(count) if self.indices.is_none() || indices.is_some() => count,
+            _ => 0,
+        };
+        let count = match self.mode {
+            Mode::Triangles => vertices / 3,
+            Mode::TriangleStrip | Mode::TriangleFan => vertices.saturating_sub(2),
+            _ => 0,
+        };
+        Triangles {
+            indices,
+            mode: self.mode,
+            count,
+            next: 0,
+        }
+    }
+
+    /// Returns every accessor referenced by the primitive: attributes,
+    /// indices, and morph targets.
+    pub fn accessors(&self) -> impl Iterator<Item = Handle<Accessor>> + '_ {
+        self.attributes
+            .iter()
+            .map(|(_, accessor)| *accessor)
+            .chain(self.indices)
+            .chain(self.targets.iter().flat_map(|target| {
+                target
+                    .positions
+                    .into_iter()
+                    .chain(target.normals)
+                    .chain(target.tangents)
+            }))
+    }
+
+    /// Visits every accessor referenced by the primitive: attributes, indices,
+    /// and morph targets.
+    pub fn accessors_mut(&mut self) -> impl Iterator<Item = &mut Handle<Accessor>> {
+        self.attributes
+            .iter_mut()
+            .map(|(_, accessor)| accessor)
+            .chain(self.indices.as_mut())
+            .chain(self.targets.iter_mut().flat_map(|target| {
+                target
+                    .positions
+                    .as_mut()
+                    .into_iter()
+                    .chain(target.normals.as_mut())
+                    .chain(target.tangents.as_mut())
+            }))
+    }
+}
+
+impl<'a> Triangles<'a> {
+    /// Returns the vertex at position `i` of the index sequence.
+    fn vertex(&self, i: usize) -> u32 {
+        match self.indices {
+            Some(values) => values.get_u32(i),
+            None => i as u32,
+        }
+    }
+}
+
+impl<'a> Iterator for Triangles<'a> {
+    type Item = [u32; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.count {
+            return None;
+        }
+        let i = self.next;
+        self.next += 1;
+        let triangle = match self.mode {
+            Mode::TriangleStrip if i % 2 == 1 => [i, i + 2, i + 1],
+            Mode::TriangleStrip => [i, i + 1, i + 2],
+            Mode::TriangleFan => [i + 1, i + 2, 0],
+            _ => [i * 3, i * 3 + 1, i * 3 + 2],
+        };
+        Some(triangle.map(|x| self.vertex(x)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for Triangles<'a> {}
+
+impl MorphTarget {
+    /// Returns the position, normal, and tangent displacements in that order.
+    pub fn accessors(&self) -> [Option<Handle<Accessor>>; 3] {
+        [self.positions, self.normals, self.tangents]
+    }
+}
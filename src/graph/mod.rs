@@ -0,0 +1,382 @@
+//! # Basic usage
+//!
+//! Loading an asset into an editable graph, renaming its nodes, and
+//! converting it back into a `Document` plus buffer data.
+//!
+//! ```
+//! # use gltf_transform_rs as gltf;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use gltf::graph::{Graph, Node};
+//! let (document, buffers, _) = gltf::import("examples/Box.gltf")?;
+//! let mut graph = Graph::from_document(&document, &buffers)?;
+//! for node in graph.handles::<Node>() {
+//!     graph[node].name = Some(format!("node{}", node.index()));
+//! }
+//! let (document, buffers) = graph.to_document()?;
+//! assert_eq!(buffers.len(), document.buffers().count());
+//! # Ok(())
+//! # }
+//! # fn main() {
+//! #    let _ = run().expect("runtime error");
+//! # }
+//! ```
+//!
+//! # Handles
+//!
+//! Every object in a [`Graph`] is referred to by a typed [`Handle`]. Handles
+//! stay valid when other objects are added or removed; removing an object
+//! leaves a hole that is skipped when the graph is converted back into a
+//! `Document`. References to removed objects are dropped at that point.
+//!
+//! [`Graph`]: struct.Graph.html
+//! [`Handle`]: struct.Handle.html
+//!
+//! # Extensions
+//!
+//! Extensions that the graph does not model are kept as raw JSON in the
+//! [`ExtensionMap`] of the root, nodes, materials, texture infos, textures,
+//! samplers and images, and written back unchanged apart from references to
+//! textures and images. Extensions used by other objects, such as primitives,
+//! are dropped on load, and loading fails if such an extension is required.
+//!
+//! [`ExtensionMap`]: type.ExtensionMap.html
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::{fmt, hash, iter, ops, slice};
+
+mod accessor;
+mod animation;
+mod compress;
+mod export;
+mod extensions;
+mod load;
+mod material;
+mod mesh;
+mod scene;
+mod write;
+
+#[doc(inline)]
+pub use self::accessor::{Accessor, Buffer, Values};
+#[doc(inline)]
+pub use self::animation::{Animation, Channel};
+#[doc(inline)]
+pub use self::compress::MeshoptOptions;
+#[doc(inline)]
+pub use self::material::{Image, Material, Sampler, Texture, TextureInfo};
+#[doc(inline)]
+pub use self::mesh::{Mesh, MorphTarget, Primitive, Triangles};
+#[doc(inline)]
+pub use self::scene::{Camera, Instancing, Node, Projection, Scene, Skin};
+#[doc(inline)]
+pub use self::write::WriteOptions;
+
+pub(crate) use self::load::load_accessor;
+
+/// Raw extension objects that the graph does not model, keyed by extension
+/// name.
+pub type ExtensionMap = BTreeMap<String, json::Value>;
+
+/// A typed reference to an object stored in a [`Graph`].
+///
+/// [`Graph`]: struct.Graph.html
+pub struct Handle<T> {
+    index: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// Storage for the objects of one kind in a [`Graph`].
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Clone, Debug)]
+pub struct Arena<T> {
+    items: Vec<Option<T>>,
+}
+
+/// An object kind that can be stored in a [`Graph`].
+///
+/// [`Graph`]: struct.Graph.html
+pub trait Entity: Sized {
+    /// Returns the storage for this kind of object.
+    #[doc(hidden)]
+    fn arena(graph: &Graph) -> &Arena<Self>;
+
+    /// Returns the mutable storage for this kind of object.
+    #[doc(hidden)]
+    fn arena_mut(graph: &mut Graph) -> &mut Arena<Self>;
+}
+
+/// An `Iterator` that visits the live objects of one kind in a [`Graph`].
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    iter: iter::Enumerate<slice::Iter<'a, Option<T>>>,
+}
+
+/// An `Iterator` that visits every live object of one kind in a [`Graph`]
+/// mutably.
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Debug)]
+pub struct IterMut<'a, T> {
+    iter: iter::Enumerate<slice::IterMut<'a, Option<T>>>,
+}
+
+/// An owned, editable glTF scene graph.
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    /// Metadata about the glTF asset.
+    pub asset: json::Asset,
+
+    /// The default scene.
+    pub default_scene: Option<Handle<Scene>>,
+
+    /// Names of glTF extensions used somewhere in this asset.
+    pub extensions_used: Vec<String>,
+
+    /// Names of glTF extensions required to properly load this asset.
+    pub extensions_required: Vec<String>,
+
+    /// Extension objects of the root, such as the lights of
+    /// `KHR_lights_punctual`.
+    pub extensions: ExtensionMap,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+
+    accessors: Arena<Accessor>,
+    animations: Arena<Animation>,
+    buffers: Arena<Buffer>,
+    cameras: Arena<Camera>,
+    images: Arena<Image>,
+    materials: Arena<Material>,
+    meshes: Arena<Mesh>,
+    nodes: Arena<Node>,
+    samplers: Arena<Sampler>,
+    scenes: Arena<Scene>,
+    skins: Arena<Skin>,
+    textures: Arena<Texture>,
+}
+
+impl<T> Handle<T> {
+    /// Constructs a `Handle` from a slot index.
+    pub(crate) fn new(index: usize) -> Self {
+        Self {
+            index: index as u32,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the slot index of the referenced object.
+    ///
+    /// Slot indices are stable for the lifetime of the graph and do not
+    /// necessarily match the JSON index the object is written to.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> PartialOrd for Handle<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Handle<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl<T> hash::Hash for Handle<T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.index.hash(state)
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self { items: Vec::new() }
+    }
+}
+
+impl<T> Arena<T> {
+    /// Returns the number of slots, including removed ones.
+    pub(crate) fn capacity(&self) -> usize {
+        self.items.len()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Handle<T>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, item) in &mut self.iter {
+            if let Some(item) = item.as_ref() {
+                return Some((Handle::new(index), item));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (Handle<T>, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, item) in &mut self.iter {
+            if let Some(item) = item.as_mut() {
+                return Some((Handle::new(index), item));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+macro_rules! impl_entity {
+    ($ty:ty, $field:ident) => {
+        impl Entity for $ty {
+            fn arena(graph: &Graph) -> &Arena<Self> {
+                &graph.$field
+            }
+
+            fn arena_mut(graph: &mut Graph) -> &mut Arena<Self> {
+                &mut graph.$field
+            }
+        }
+    };
+}
+
+impl_entity!(Accessor, accessors);
+impl_entity!(Animation, animations);
+impl_entity!(Buffer, buffers);
+impl_entity!(Camera, cameras);
+impl_entity!(Image, images);
+impl_entity!(Material, materials);
+impl_entity!(Mesh, meshes);
+impl_entity!(Node, nodes);
+impl_entity!(Sampler, samplers);
+impl_entity!(Scene, scenes);
+impl_entity!(Skin, skins);
+impl_entity!(Texture, textures);
+
+impl Graph {
+    /// Constructs an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an object to the graph and returns its handle.
+    pub fn add<T: Entity>(&mut self, value: T) -> Handle<T> {
+        let arena = T::arena_mut(self);
+        arena.items.push(Some(value));
+        Handle::new(arena.items.len() - 1)
+    }
+
+    /// Returns the referenced object, or `None` if it has been removed.
+    pub fn get<T: Entity>(&self, handle: Handle<T>) -> Option<&T> {
+        T::arena(self)
+            .items
+            .get(handle.index())
+            .and_then(Option::as_ref)
+    }
+
+    /// Returns the referenced object mutably, or `None` if it has been removed.
+    pub fn get_mut<T: Entity>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        T::arena_mut(self)
+            .items
+            .get_mut(handle.index())
+            .and_then(Option::as_mut)
+    }
+
+    /// Removes an object from the graph, returning it if it was present.
+    ///
+    /// References to the removed object held by other objects are not
+    /// updated; they are dropped when the graph is written.
+    pub fn remove<T: Entity>(&mut self, handle: Handle<T>) -> Option<T> {
+        T::arena_mut(self)
+            .items
+            .get_mut(handle.index())
+            .and_then(Option::take)
+    }
+
+    /// Returns `true` if the handle refers to an object that has not been
+    /// removed.
+    pub fn contains<T: Entity>(&self, handle: Handle<T>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    /// Returns an `Iterator` that visits every live object of one kind.
+    pub fn iter<T: Entity>(&self) -> Iter<'_, T> {
+        Iter {
+            iter: T::arena(self).items.iter().enumerate(),
+        }
+    }
+
+    /// Returns an `Iterator` that visits every live object of one kind
+    /// mutably.
+    pub fn iter_mut<T: Entity>(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            iter: T::arena_mut(self).items.iter_mut().enumerate(),
+        }
+    }
+
+    /// Returns the handles of every live object of one kind.
+    ///
+    /// This is convenient when the graph must be mutated while visiting
+    /// its objects.
+    pub fn handles<T: Entity>(&self) -> Vec<Handle<T>> {
+        self.iter::<T>().map(|(handle, _)| handle).collect()
+    }
+
+    /// Returns the number of live objects of one kind.
+    pub fn count<T: Entity>(&self) -> usize {
+        self.iter::<T>().count()
+    }
+}
+
+impl<T: Entity> ops::Index<Handle<T>> for Graph {
+    type Output = T;
+
+    fn index(&self, handle: Handle<T>) -> &T {
+        self.get(handle).expect("handle refers to a removed object")
+    }
+}
+
+impl<T: Entity> ops::IndexMut<Handle<T>> for Graph {
+    fn index_mut(&mut self, handle: Handle<T>) -> &mut T {
+        self.get_mut(handle)
+            .expect("handle refers to a removed object")
+    }
+}
@@ -0,0 +1,196 @@
+use crate::scene::Transform;
+
+use super::{Accessor, ExtensionMap, Handle, Mesh};
+
+/// A node in the node hierarchy.
+#[derive(Clone, Debug)]
+pub struct Node {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The node's local transform.
+    pub transform: Transform,
+
+    /// The node's children.
+    pub children: Vec<Handle<Node>>,
+
+    /// The camera referenced by this node.
+    pub camera: Option<Handle<Camera>>,
+
+    /// The mesh referenced by this node.
+    pub mesh: Option<Handle<Mesh>>,
+
+    /// The skin referenced by this node.
+    pub skin: Option<Handle<Skin>>,
+
+    /// The weights of the instantiated morph target.
+    pub weights: Option<Vec<f32>>,
+
+    /// Lower levels of detail of this node, from highest to lowest detail,
+    /// as defined by the `MSFT_lod` extension.
+    pub lods: Vec<Handle<Node>>,
+
+    /// The minimum screen coverage of each level of detail, starting with
+    /// this node, stored in the `MSFT_screencoverage` entry of the extras.
+    pub screen_coverage: Option<Vec<f32>>,
+
+    /// Per-instance attributes, as defined by the `EXT_mesh_gpu_instancing`
+    /// extension.
+    pub instancing: Option<Instancing>,
+
+    /// Extension objects other than `MSFT_lod` and
+    /// `EXT_mesh_gpu_instancing`, such as `KHR_lights_punctual`.
+    pub extensions: ExtensionMap,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// The per-instance attributes of a node, as defined by the
+/// `EXT_mesh_gpu_instancing` extension.
+///
+/// The node's mesh is drawn once per instance, with the instance transform
+/// applied before the node's own transform.
+#[derive(Clone, Debug, Default)]
+pub struct Instancing {
+    /// Instance attributes in the order they are written, such as
+    /// `TRANSLATION`, `ROTATION`, `SCALE`, or custom attributes starting with
+    /// an underscore.
+    pub attributes: Vec<(String, Handle<Accessor>)>,
+}
+
+/// The root nodes of a scene.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The root nodes of the scene.
+    pub nodes: Vec<Handle<Node>>,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// Joints and matrices defining a skin.
+#[derive(Clone, Debug, Default)]
+pub struct Skin {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The accessor containing the 4x4 inverse-bind matrices.
+    pub inverse_bind_matrices: Option<Handle<Accessor>>,
+
+    /// The joints used in this skin.
+    pub joints: Vec<Handle<Node>>,
+
+    /// The node used as a skeleton root.
+    pub skeleton: Option<Handle<Node>>,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+/// The projection of a [`Camera`].
+///
+/// [`Camera`]: struct.Camera.html
+#[derive(Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// Describes an orthographic projection.
+    Orthographic {
+        /// The horizontal magnification of the view.
+        xmag: f32,
+
+        /// The vertical magnification of the view.
+        ymag: f32,
+
+        /// The distance to the far clipping plane.
+        zfar: f32,
+
+        /// The distance to the near clipping plane.
+        znear: f32,
+    },
+
+    /// Describes a perspective projection.
+    Perspective {
+        /// The aspect ratio of the field of view.
+        aspect_ratio: Option<f32>,
+
+        /// The vertical field of view in radians.
+        yfov: f32,
+
+        /// The distance to the far clipping plane, or `None` for an infinite
+        /// projection.
+        zfar: Option<f32>,
+
+        /// The distance to the near clipping plane.
+        znear: f32,
+    },
+}
+
+/// A camera's projection.
+#[derive(Clone, Debug)]
+pub struct Camera {
+    /// Optional user-defined name for this object.
+    pub name: Option<String>,
+
+    /// The camera projection.
+    pub projection: Projection,
+
+    /// Optional application specific data.
+    pub extras: json::Extras,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self {
+            name: None,
+            transform: Transform::Decomposed {
+                translation: [0.0, 0.0, 0.0],
+                rotation: [0.0, 0.0, 0.0, 1.0],
+                scale: [1.0, 1.0, 1.0],
+            },
+            children: Vec::new(),
+            camera: None,
+            mesh: None,
+            skin: None,
+            weights: None,
+            lods: Vec::new(),
+            screen_coverage: None,
+            instancing: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+        }
+    }
+}
+
+impl Instancing {
+    /// Returns the accessor for the attribute called `name`.
+    pub fn get(&self, name: &str) -> Option<Handle<Accessor>> {
+        self.attributes
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, accessor)| *accessor)
+    }
+
+    /// Sets the accessor for the attribute called `name`, returning the
+    /// previous one.
+    pub fn set(&mut self, name: &str, accessor: Handle<Accessor>) -> Option<Handle<Accessor>> {
+        if let Some(entry) = self.attributes.iter_mut().find(|(x, _)| x == name) {
+            Some(std::mem::replace(&mut entry.1, accessor))
+        } else {
+            self.attributes.push((name.to_string(), accessor));
+            None
+        }
+    }
+
+    /// Returns every accessor referenced by the attributes.
+    pub fn accessors(&self) -> impl Iterator<Item = Handle<Accessor>> + '_ {
+        self.attributes.iter().map(|(_, accessor)| *accessor)
+    }
+
+    /// Visits every accessor referenced by the attributes.
+    pub fn accessors_mut(&mut self) -> impl Iterator<Item = &mut Handle<Accessor>> {
+        self.attributes.iter_mut().map(|(_, accessor)| accessor)
+    }
+}
@@ -0,0 +1,868 @@
+use std::collections::{HashMap, HashSet};
+
+use json::validation::Checked::Valid;
+
+use crate::accessor::{DataType, Dimensions};
+use crate::animation::{Interpolation, Property};
+use crate::buffer::Target;
+use crate::extensions::Extensions;
+use crate::mesh::Mode;
+use crate::meshopt::{self, Filter};
+use crate::scene::Transform;
+use crate::{buffer, Document, Result, Semantic};
+
+use super::compress::{self, MeshoptOptions, Stream};
+use super::extensions::{
+    EXT_MESH_GPU_INSTANCING, KHR_MESH_QUANTIZATION, MSFT_LOD, MSFT_SCREENCOVERAGE,
+};
+use super::*;
+
+/// Options for converting a graph into a glTF document.
+#[derive(Clone, Debug, Default)]
+pub struct WriteOptions {
+    /// Compresses buffer views with `EXT_meshopt_compression` when set.
+    pub meshopt: Option<MeshoptOptions>,
+}
+
+/// Maps slot indices of one kind of object to JSON indices.
+struct Remap(Vec<Option<u32>>);
+
+impl Remap {
+    fn new<T: Entity>(graph: &Graph, keep: impl Fn(&T) -> bool) -> Self {
+        let mut next = 0;
+        let mut map = vec![None; T::arena(graph).capacity()];
+        for (handle, item) in graph.iter::<T>() {
+            if keep(item) {
+                map[handle.index()] = Some(next);
+                next += 1;
+            }
+        }
+        Remap(map)
+    }
+
+    fn get<T, U>(&self, handle: Handle<T>) -> Option<json::Index<U>> {
+        self.0
+            .get(handle.index())
+            .copied()
+            .flatten()
+            .map(json::Index::new)
+    }
+}
+
+/// Converts a JSON index into a handle with the same index, for references
+/// inside raw extension objects.
+fn index_handle<T, U>(index: json::Index<T>) -> Handle<U> {
+    Handle::new(index.value())
+}
+
+/// Rounds `n` up to the next multiple of four.
+pub(super) fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+/// Returns the JSON representation of per-component bounds.
+fn bounds_value(data_type: DataType, bounds: &[f32]) -> json::Value {
+    if data_type == DataType::F32 {
+        json::Value::from(bounds.to_vec())
+    } else {
+        json::Value::from(bounds.iter().map(|x| *x as i64).collect::<Vec<_>>())
+    }
+}
+
+/// Guesses the MIME type of encoded image data from its signature.
+pub(super) fn guess_mime_type(data: &[u8]) -> &'static str {
+    if data.starts_with(b"\xFF\xD8\xFF") {
+        "image/jpeg"
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        "image/webp"
+    } else {
+        "image/png"
+    }
+}
+
+/// Accumulates buffer views and binary data while a graph is written.
+struct Layout {
+    data: Vec<Vec<u8>>,
+    views: Vec<json::buffer::View>,
+}
+
+impl Layout {
+    /// Appends a buffer view and returns its JSON index.
+    fn push(
+        &mut self,
+        buffer: usize,
+        bytes: &[u8],
+        stride: Option<usize>,
+        target: Option<Target>,
+    ) -> json::Index<json::buffer::View> {
+        let data = &mut self.data[buffer];
+        data.resize(align4(data.len()), 0);
+        let offset = data.len();
+        data.extend_from_slice(bytes);
+        self.views.push(json::buffer::View {
+            buffer: json::Index::new(buffer as u32),
+            byte_length: bytes.len() as u32,
+            byte_offset: Some(offset as u32),
+            byte_stride: stride.map(|x| x as u32),
+            extensions: Default::default(),
+            extras: Default::default(),
+            #[cfg(feature = "names")]
+            name: None,
+            target: target.map(Valid),
+        });
+        json::Index::new(self.views.len() as u32 - 1)
+    }
+}
+
+impl Graph {
+    /// Returns the buffer view target implied by how each accessor is used.
+    fn accessor_targets(&self) -> HashMap<Handle<Accessor>, Target> {
+        let mut targets = HashMap::new();
+        for (_, mesh) in self.iter::<Mesh>() {
+            for primitive in &mesh.primitives {
+                for (_, accessor) in &primitive.attributes {
+                    targets.insert(*accessor, Target::ArrayBuffer);
+                }
+                for target in &primitive.targets {
+                    for accessor in target.accessors().iter().flatten() {
+                        targets.insert(*accessor, Target::ArrayBuffer);
+                    }
+                }
+                if let Some(accessor) = primitive.indices {
+                    targets.insert(accessor, Target::ElementArrayBuffer);
+                }
+            }
+        }
+        targets
+    }
+
+    /// Returns whether `primitive` is written, which it is not when its
+    /// indices or one of its attributes are empty, since it draws nothing and
+    /// glTF does not allow empty accessors.
+    fn writes_primitive(&self, primitive: &Primitive) -> bool {
+        let empty = |x: &Handle<Accessor>| self.get(*x).is_some_and(|x| x.count() == 0);
+        !primitive.indices.iter().any(empty) && !primitive.attributes.iter().any(|(_, x)| empty(x))
+    }
+
+    /// Returns the index accessors used only by triangle list primitives.
+    fn triangle_indices(&self) -> HashSet<Handle<Accessor>> {
+        let mut triangles = HashSet::new();
+        let mut other = HashSet::new();
+        for (_, mesh) in self.iter::<Mesh>() {
+            for primitive in &mesh.primitives {
+                if let Some(accessor) = primitive.indices {
+                    if primitive.mode == Mode::Triangles {
+                        triangles.insert(accessor);
+                    } else {
+                        other.insert(accessor);
+                    }
+                }
+            }
+        }
+        &triangles - &other
+    }
+
+    /// Returns the lossy `EXT_meshopt_compression` filter and its number of
+    /// bits for accessors that a filter is enabled for.
+    fn meshopt_filters(&self, options: &MeshoptOptions) -> HashMap<Handle<Accessor>, (Filter, u8)> {
+        let mut filters = HashMap::new();
+        let signed_normalized = |handle: Handle<Accessor>| {
+            self.get(handle).is_some_and(|accessor| {
+                accessor.normalized && matches!(accessor.values, Values::I8(_) | Values::I16(_))
+            })
+        };
+        if options.octahedral_bits > 0 {
+            for (_, mesh) in self.iter::<Mesh>() {
+                for primitive in &mesh.primitives {
+                    let attributes = primitive
+                        .attributes
+                        .iter()
+                        .filter(|(semantic, _)| {
+                            matches!(semantic, Semantic::Normals | Semantic::Tangents)
+                        })
+                        .map(|(_, accessor)| *accessor);
+                    let targets = primitive
+                        .targets
+                        .iter()
+                        .flat_map(|target| target.normals.into_iter().chain(target.tangents));
+                    for accessor in attributes.chain(targets) {
+                        if signed_normalized(accessor) {
+                            filters.insert(accessor, (Filter::Octahedral, options.octahedral_bits));
+                        }
+                    }
+                }
+            }
+        }
+        if options.quaternion_bits > 0 {
+            for (_, animation) in self.iter::<Animation>() {
+                for channel in &animation.channels {
+                    let output = channel.output;
+                    let quaternions = self.get(output).is_some_and(|x| {
+                        x.normalized
+                            && x.dimensions == Dimensions::Vec4
+                            && matches!(x.values, Values::I16(_))
+                    });
+                    if channel.property == Property::Rotation
+                        && channel.interpolation != Interpolation::CubicSpline
+                        && quaternions
+                    {
+                        filters.insert(output, (Filter::Quaternion, options.quaternion_bits));
+                    }
+                }
+            }
+        }
+        filters
+    }
+
+    /// Converts the graph into a glTF document plus buffer data.
+    ///
+    /// Every accessor and embedded image is written to its own buffer view,
+    /// aligned to four bytes. Vertex attribute views are given a byte stride
+    /// padded to a multiple of four bytes. Removed objects are skipped and
+    /// references to them are dropped.
+    ///
+    /// Objects that glTF does not allow to be empty are skipped as well:
+    /// accessors with no elements, buffers with no data, animations with no
+    /// channels, and primitives with empty indices or attributes, along with
+    /// meshes left without primitives.
+    ///
+    /// The extension objects of the graph are written back, and
+    /// `extensionsUsed` and `extensionsRequired` are reduced to the extensions
+    /// that are written. Written extensions that the graph does not declare
+    /// are added to `extensionsUsed`, and `KHR_mesh_quantization` is also
+    /// required when a vertex attribute needs it. Extension objects are not
+    /// part of `json::Root`, so the document must be serialized with
+    /// [`Document::into_json_value`] to keep them.
+    ///
+    /// The first buffer of the result has no URI and is suitable for the
+    /// `BIN` chunk of binary glTF:
+    ///
+    /// ```
+    /// # use gltf_transform_rs as gltf;
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use gltf::graph::Graph;
+    /// # let (document, buffers, _) = gltf::import("examples/Box.gltf")?;
+    /// # let mut graph = Graph::from_document(&document, &buffers)?;
+    /// # for buffer in graph.handles::<gltf::graph::Buffer>() {
+    /// #     graph[buffer].uri = None;
+    /// # }
+    /// let (document, mut buffers) = graph.to_document()?;
+    /// let glb = gltf::binary::Glb {
+    ///     header: gltf::binary::Header {
+    ///         magic: *b"glTF",
+    ///         version: 2,
+    ///         length: 0,
+    ///     },
+    ///     json: gltf::json::serialize::to_vec(&document.into_json_value()?)?.into(),
+    ///     bin: buffers.pop().map(|x| x.0.into()),
+    /// };
+    /// let bytes = glb.to_vec()?;
+    /// # let _ = bytes;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// #    let _ = run().expect("runtime error");
+    /// # }
+    /// ```
+    ///
+    /// [`Document::into_json_value`]: ../struct.Document.html#method.into_json_value
+    pub fn to_document(&self) -> Result<(Document, Vec<buffer::Data>)> {
+        self.to_document_with(&WriteOptions::default())
+    }
+
+    /// Converts the graph into a glTF document plus buffer data, see
+    /// [`to_document`].
+    ///
+    /// With [`WriteOptions::meshopt`] set, vertex attribute, index and
+    /// animation data is compressed with `EXT_meshopt_compression`. Every
+    /// buffer holding compressed views is followed by a fallback buffer that
+    /// the views refer to, whose data is empty when the extension is
+    /// required.
+    ///
+    /// [`to_document`]: #method.to_document
+    /// [`WriteOptions::meshopt`]: struct.WriteOptions.html#structfield.meshopt
+    /// [`Document::into_json_value`]: ../struct.Document.html#method.into_json_value
+    pub fn to_document_with(
+        &self,
+        options: &WriteOptions,
+    ) -> Result<(Document, Vec<buffer::Data>)> {
+        let mut root = json::Root {
+            asset: self.asset.clone(),
+            extensions_used: self.extensions_used.clone(),
+            extensions_required: self.extensions_required.clone(),
+            extras: self.extras.clone(),
+            ..Default::default()
+        };
+
+        let buffers = Remap::new::<Buffer>(self, |_| true);
+        let accessors = Remap::new::<Accessor>(self, |x| x.count() > 0);
+        let images = Remap::new::<Image>(self, |x| !x.data.is_empty() || x.uri.is_some());
+        let samplers = Remap::new::<Sampler>(self, |_| true);
+        let textures = Remap::new::<Texture>(self, |x| images.get::<_, ()>(x.source).is_some());
+        let materials = Remap::new::<Material>(self, |_| true);
+        let meshes = Remap::new::<Mesh>(self, |x| {
+            x.primitives.iter().any(|x| self.writes_primitive(x))
+        });
+        let cameras = Remap::new::<Camera>(self, |_| true);
+        let skins = Remap::new::<Skin>(self, |_| true);
+        let nodes = Remap::new::<Node>(self, |_| true);
+        let scenes = Remap::new::<Scene>(self, |_| true);
+
+        let mut extensions = Extensions::default();
+        for (name, value) in &self.extensions {
+            extensions.insert_root(name, value.clone());
+        }
+
+        // Buffers.
+        for (_, buffer) in self.iter::<Buffer>() {
+            root.buffers.push(json::Buffer {
+                byte_length: 0,
+                extensions: Default::default(),
+                extras: buffer.extras.clone(),
+                #[cfg(feature = "names")]
+                name: buffer.name.clone(),
+                uri: buffer.uri.clone(),
+            });
+        }
+        let needs_buffer = self.count::<Accessor>() > 0
+            || self
+                .iter::<Image>()
+                .any(|(_, image)| !image.data.is_empty());
+        if root.buffers.is_empty() && needs_buffer {
+            root.buffers.push(json::Buffer {
+                byte_length: 0,
+                extensions: Default::default(),
+                extras: Default::default(),
+                #[cfg(feature = "names")]
+                name: None,
+                uri: None,
+            });
+        }
+        let buffer_index = |handle: Option<Handle<Buffer>>| {
+            handle
+                .and_then(|x| buffers.get::<_, ()>(x))
+                .map_or(0, |x| x.value())
+        };
+
+        let mut layout = Layout {
+            data: vec![Vec::new(); root.buffers.len()],
+            views: Vec::new(),
+        };
+
+        // Accessors.
+        let targets = self.accessor_targets();
+        let triangles = self.triangle_indices();
+        let filters = options
+            .meshopt
+            .as_ref()
+            .map(|x| self.meshopt_filters(x))
+            .unwrap_or_default();
+        let mut streams = Vec::new();
+        for (handle, accessor) in self.iter::<Accessor>() {
+            if accessor.count() == 0 {
+                continue;
+            }
+            let target = targets.get(&handle).copied();
+            let size = accessor.size();
+            let stride = match target {
+                Some(Target::ArrayBuffer) => align4(size),
+                _ => size,
+            };
+
+            // Lossy filters change the data, so write what decoding yields.
+            let mut filter = filters.get(&handle).copied();
+            if let Some(options) = &options.meshopt {
+                let vertex_f32 =
+                    target == Some(Target::ArrayBuffer) && accessor.data_type() == DataType::F32;
+                if filter.is_none() && vertex_f32 && options.exponential_bits > 0 {
+                    filter = Some((Filter::Exponential, options.exponential_bits));
+                }
+            }
+            let filtered = filter.and_then(|(filter, bits)| {
+                compress::apply_filter(accessor, filter, stride, bits).map(|x| (filter, x))
+            });
+            let (filter, filtered, accessor) = match &filtered {
+                Some((filter, (data, decoded))) => (*filter, Some(data), decoded),
+                None => (Filter::None, None, accessor),
+            };
+            let mut bytes = Vec::with_capacity(stride * accessor.count());
+            for i in 0..accessor.count() {
+                accessor.write_element(i, &mut bytes);
+                bytes.resize((i + 1) * stride, 0);
+            }
+            let byte_stride = Some(stride).filter(|_| target == Some(Target::ArrayBuffer));
+            let view = layout.push(buffer_index(accessor.buffer), &bytes, byte_stride, target);
+            let mode = match target {
+                Some(Target::ElementArrayBuffer) if stride != 2 && stride != 4 => None,
+                Some(Target::ElementArrayBuffer)
+                    if triangles.contains(&handle) && accessor.count() % 3 == 0 =>
+                {
+                    Some(meshopt::Mode::Triangles)
+                }
+                Some(Target::ElementArrayBuffer) => Some(meshopt::Mode::Indices),
+                _ if stride % 4 == 0 && stride <= 256 => Some(meshopt::Mode::Attributes),
+                _ => None,
+            };
+            if let (Some(mode), true) = (mode, options.meshopt.is_some()) {
+                streams.push(Stream {
+                    view: view.value(),
+                    mode,
+                    filter,
+                    stride,
+                    data: filtered.cloned().unwrap_or(bytes),
+                });
+            }
+            let bounds = accessor.bounds();
+            root.accessors.push(json::Accessor {
+                buffer_view: Some(view),
+                byte_offset: 0,
+                count: accessor.count() as u32,
+                component_type: Valid(json::accessor::GenericComponentType(accessor.data_type())),
+                extensions: Default::default(),
+                extras: accessor.extras.clone(),
+                type_: Valid(accessor.dimensions),
+                min: bounds
+                    .as_ref()
+                    .map(|(min, _)| bounds_value(accessor.data_type(), min)),
+                max: bounds
+                    .as_ref()
+                    .map(|(_, max)| bounds_value(accessor.data_type(), max)),
+                #[cfg(feature = "names")]
+                name: accessor.name.clone(),
+                normalized: accessor.normalized,
+                sparse: None,
+            });
+        }
+
+        // Images.
+        for (_, image) in self.iter::<Image>() {
+            if image.data.is_empty() && image.uri.is_none() {
+                continue;
+            }
+            for (name, value) in &image.extensions {
+                extensions.insert("images", root.images.len(), name, value.clone());
+            }
+            let (buffer_view, uri, mime_type) = if image.data.is_empty() {
+                (None, image.uri.clone(), image.mime_type.clone())
+            } else {
+                let mime_type = image
+                    .mime_type
+                    .clone()
+                    .unwrap_or_else(|| guess_mime_type(&image.data).to_string());
+                let view = layout.push(0, &image.data, None, None);
+                (Some(view), None, Some(mime_type))
+            };
+            root.images.push(json::Image {
+                buffer_view,
+                mime_type: mime_type.map(json::image::MimeType),
+                #[cfg(feature = "names")]
+                name: image.name.clone(),
+                uri,
+                extensions: Default::default(),
+                extras: image.extras.clone(),
+            });
+        }
+
+        // Samplers and textures.
+        for (_, sampler) in self.iter::<Sampler>() {
+            for (name, value) in &sampler.extensions {
+                extensions.insert("samplers", root.samplers.len(), name, value.clone());
+            }
+            root.samplers.push(json::texture::Sampler {
+                mag_filter: sampler.mag_filter.map(Valid),
+                min_filter: sampler.min_filter.map(Valid),
+                #[cfg(feature = "names")]
+                name: sampler.name.clone(),
+                wrap_s: Valid(sampler.wrap_s),
+                wrap_t: Valid(sampler.wrap_t),
+                extensions: Default::default(),
+                extras: sampler.extras.clone(),
+            });
+        }
+        for (_, texture) in self.iter::<Texture>() {
+            if let Some(source) = images.get(texture.source) {
+                let mut texture = texture.clone();
+                texture.map_extension_images(|x| images.get::<_, ()>(x).map(index_handle));
+                for (name, value) in texture.extensions {
+                    extensions.insert("textures", root.textures.len(), &name, value);
+                }
+                root.textures.push(json::Texture {
+                    #[cfg(feature = "names")]
+                    name: texture.name.clone(),
+                    sampler: texture.sampler.and_then(|x| samplers.get(x)),
+                    source,
+                    extensions: Default::default(),
+                    extras: texture.extras.clone(),
+                });
+            }
+        }
+
+        // Materials.
+        let info = |info: &Option<TextureInfo>| {
+            info.as_ref().and_then(|info| {
+                textures.get(info.texture).map(|index| json::texture::Info {
+                    index,
+                    tex_coord: info.tex_coord,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                })
+            })
+        };
+        for (_, material) in self.iter::<Material>() {
+            let index = root.materials.len();
+            let mut material = material.clone();
+            material.map_extension_textures(|x| textures.get::<_, ()>(x).map(index_handle));
+            for (name, value) in &material.extensions {
+                extensions.insert("materials", index, name, value.clone());
+            }
+            let slots = [
+                "/pbrMetallicRoughness/baseColorTexture",
+                "/pbrMetallicRoughness/metallicRoughnessTexture",
+                "/normalTexture",
+                "/occlusionTexture",
+                "/emissiveTexture",
+            ];
+            for (pointer, info) in slots.iter().zip(material.textures()) {
+                let info = info
+                    .as_ref()
+                    .filter(|x| textures.get::<_, ()>(x.texture).is_some());
+                for (name, value) in info.iter().flat_map(|x| &x.extensions) {
+                    extensions.insert_at("materials", index, pointer, name, value.clone());
+                }
+            }
+            root.materials.push(json::Material {
+                alpha_cutoff: material.alpha_cutoff.map(json::material::AlphaCutoff),
+                alpha_mode: Valid(material.alpha_mode),
+                double_sided: material.double_sided,
+                #[cfg(feature = "names")]
+                name: material.name.clone(),
+                pbr_metallic_roughness: json::material::PbrMetallicRoughness {
+                    base_color_factor: json::material::PbrBaseColorFactor(
+                        material.base_color_factor,
+                    ),
+                    base_color_texture: info(&material.base_color_texture),
+                    metallic_factor: json::material::StrengthFactor(material.metallic_factor),
+                    roughness_factor: json::material::StrengthFactor(material.roughness_factor),
+                    metallic_roughness_texture: info(&material.metallic_roughness_texture),
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                },
+                normal_texture: info(&material.normal_texture).map(|x| {
+                    json::material::NormalTexture {
+                        index: x.index,
+                        scale: material.normal_scale,
+                        tex_coord: x.tex_coord,
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                    }
+                }),
+                occlusion_texture: info(&material.occlusion_texture).map(|x| {
+                    json::material::OcclusionTexture {
+                        index: x.index,
+                        strength: json::material::StrengthFactor(material.occlusion_strength),
+                        tex_coord: x.tex_coord,
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                    }
+                }),
+                emissive_texture: info(&material.emissive_texture),
+                emissive_factor: json::material::EmissiveFactor(material.emissive_factor),
+                extensions: Default::default(),
+                extras: material.extras.clone(),
+            });
+        }
+
+        // Meshes.
+        for (handle, mesh) in self.iter::<Mesh>() {
+            if meshes.get::<_, ()>(handle).is_none() {
+                continue;
+            }
+            let primitives = mesh
+                .primitives
+                .iter()
+                .filter(|x| self.writes_primitive(x))
+                .map(|primitive| {
+                    let targets: Vec<_> = primitive
+                        .targets
+                        .iter()
+                        .map(|target| json::mesh::MorphTarget {
+                            positions: target.positions.and_then(|x| accessors.get(x)),
+                            normals: target.normals.and_then(|x| accessors.get(x)),
+                            tangents: target.tangents.and_then(|x| accessors.get(x)),
+                        })
+                        .collect();
+                    json::mesh::Primitive {
+                        attributes: primitive
+                            .attributes
+                            .iter()
+                            .filter_map(|(semantic, accessor)| {
+                                accessors
+                                    .get(*accessor)
+                                    .map(|index| (Valid(semantic.clone()), index))
+                            })
+                            .collect(),
+                        extensions: Default::default(),
+                        extras: primitive.extras.clone(),
+                        indices: primitive.indices.and_then(|x| accessors.get(x)),
+                        material: primitive.material.and_then(|x| materials.get(x)),
+                        mode: Valid(primitive.mode),
+                        targets: Some(targets).filter(|x| !x.is_empty()),
+                    }
+                })
+                .collect();
+            root.meshes.push(json::Mesh {
+                extensions: Default::default(),
+                extras: mesh.extras.clone(),
+                #[cfg(feature = "names")]
+                name: mesh.name.clone(),
+                primitives,
+                weights: mesh.weights.clone(),
+            });
+        }
+
+        // Cameras.
+        for (_, camera) in self.iter::<Camera>() {
+            let (type_, orthographic, perspective) = match camera.projection {
+                Projection::Orthographic {
+                    xmag,
+                    ymag,
+                    zfar,
+                    znear,
+                } => (
+                    json::camera::Type::Orthographic,
+                    Some(json::camera::Orthographic {
+                        xmag,
+                        ymag,
+                        zfar,
+                        znear,
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                    }),
+                    None,
+                ),
+                Projection::Perspective {
+                    aspect_ratio,
+                    yfov,
+                    zfar,
+                    znear,
+                } => (
+                    json::camera::Type::Perspective,
+                    None,
+                    Some(json::camera::Perspective {
+                        aspect_ratio,
+                        yfov,
+                        zfar,
+                        znear,
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                    }),
+                ),
+            };
+            root.cameras.push(json::Camera {
+                #[cfg(feature = "names")]
+                name: camera.name.clone(),
+                orthographic,
+                perspective,
+                type_: Valid(type_),
+                extensions: Default::default(),
+                extras: camera.extras.clone(),
+            });
+        }
+
+        // Skins.
+        for (_, skin) in self.iter::<Skin>() {
+            root.skins.push(json::Skin {
+                extensions: Default::default(),
+                extras: skin.extras.clone(),
+                inverse_bind_matrices: skin.inverse_bind_matrices.and_then(|x| accessors.get(x)),
+                joints: skin.joints.iter().filter_map(|x| nodes.get(*x)).collect(),
+                #[cfg(feature = "names")]
+                name: skin.name.clone(),
+                skeleton: skin.skeleton.and_then(|x| nodes.get(x)),
+            });
+        }
+
+        // Nodes.
+        for (_, node) in self.iter::<Node>() {
+            let index = root.nodes.len();
+            for (name, value) in &node.extensions {
+                extensions.insert("nodes", index, name, value.clone());
+            }
+            let levels: Vec<_> = node.lods.iter().map(|x| nodes.get::<_, ()>(*x)).collect();
+            let lods: Vec<_> = levels.iter().flatten().map(|x| x.value() as u32).collect();
+            if !lods.is_empty() {
+                let ids = iter::once(("ids".to_string(), json::Value::from(lods)));
+                extensions.insert("nodes", index, MSFT_LOD, json::Value::Object(ids.collect()));
+            }
+            if let Some(instancing) = &node.instancing {
+                let attributes = instancing
+                    .attributes
+                    .iter()
+                    .filter_map(|(name, x)| {
+                        let index = accessors.get::<_, ()>(*x)?.value();
+                        Some((name.clone(), json::Value::from(index)))
+                    })
+                    .collect();
+                let attributes =
+                    iter::once(("attributes".to_string(), json::Value::Object(attributes)));
+                let value = json::Value::Object(attributes.collect());
+                extensions.insert("nodes", index, EXT_MESH_GPU_INSTANCING, value);
+            }
+            if let Some(coverage) = &node.screen_coverage {
+                // Drop the hints of levels whose node was removed.
+                let coverage: Vec<_> = coverage
+                    .iter()
+                    .enumerate()
+                    .filter(|(level, _)| *level == 0 || levels.get(level - 1) != Some(&None))
+                    .map(|(_, x)| *x)
+                    .collect();
+                let coverage = json::Value::from(coverage);
+                extensions.insert_extra("nodes", index, MSFT_SCREENCOVERAGE, coverage);
+            }
+            let (matrix, translation, rotation, scale) = match node.transform.clone() {
+                Transform::Matrix { matrix } => {
+                    let mut m = [0.0; 16];
+                    for (i, column) in matrix.iter().enumerate() {
+                        m[i * 4..i * 4 + 4].copy_from_slice(column);
+                    }
+                    (Some(m), None, None, None)
+                }
+                Transform::Decomposed {
+                    translation,
+                    rotation,
+                    scale,
+                } => (
+                    None,
+                    Some(translation).filter(|x| *x != [0.0, 0.0, 0.0]),
+                    Some(rotation)
+                        .filter(|x| *x != [0.0, 0.0, 0.0, 1.0])
+                        .map(json::scene::UnitQuaternion),
+                    Some(scale).filter(|x| *x != [1.0, 1.0, 1.0]),
+                ),
+            };
+            let children: Vec<_> = node.children.iter().filter_map(|x| nodes.get(*x)).collect();
+            root.nodes.push(json::Node {
+                camera: node.camera.and_then(|x| cameras.get(x)),
+                children: Some(children).filter(|x| !x.is_empty()),
+                extensions: Default::default(),
+                extras: node.extras.clone(),
+                matrix,
+                mesh: node.mesh.and_then(|x| meshes.get(x)),
+                #[cfg(feature = "names")]
+                name: node.name.clone(),
+                rotation,
+                scale,
+                translation,
+                skin: node.skin.and_then(|x| skins.get(x)),
+                weights: node.weights.clone(),
+            });
+        }
+
+        // Scenes.
+        for (_, scene) in self.iter::<Scene>() {
+            root.scenes.push(json::Scene {
+                extensions: Default::default(),
+                extras: scene.extras.clone(),
+                #[cfg(feature = "names")]
+                name: scene.name.clone(),
+                nodes: scene.nodes.iter().filter_map(|x| nodes.get(*x)).collect(),
+            });
+        }
+        root.scene = self.default_scene.and_then(|x| scenes.get(x));
+
+        // Animations.
+        for (_, animation) in self.iter::<Animation>() {
+            let mut channels = Vec::new();
+            let mut samplers = Vec::new();
+            for channel in &animation.channels {
+                let node = nodes.get(channel.node);
+                let input = accessors.get(channel.input);
+                let output = accessors.get(channel.output);
+                if let (Some(node), Some(input), Some(output)) = (node, input, output) {
+                    channels.push(json::animation::Channel {
+                        sampler: json::Index::new(samplers.len() as u32),
+                        target: json::animation::Target {
+                            extensions: Default::default(),
+                            extras: Default::default(),
+                            node,
+                            path: Valid(channel.property),
+                        },
+                        extensions: Default::default(),
+                        extras: channel.extras.clone(),
+                    });
+                    samplers.push(json::animation::Sampler {
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                        input,
+                        interpolation: Valid(channel.interpolation),
+                        output,
+                    });
+                }
+            }
+            if channels.is_empty() {
+                continue;
+            }
+            root.animations.push(json::Animation {
+                extensions: Default::default(),
+                extras: animation.extras.clone(),
+                channels,
+                #[cfg(feature = "names")]
+                name: animation.name.clone(),
+                samplers,
+            });
+        }
+
+        // Buffers without data are dropped, since their length must be
+        // positive, and the views are pointed at the remaining buffers.
+        let Layout {
+            data: laid_out,
+            views,
+        } = layout;
+        let mut buffer_indices = Vec::with_capacity(laid_out.len());
+        let mut data = Vec::with_capacity(laid_out.len());
+        let written = root
+            .buffers
+            .drain(..)
+            .zip(laid_out)
+            .filter_map(|(mut buffer, mut bytes)| {
+                buffer_indices.push(data.len() as u32);
+                if bytes.is_empty() {
+                    return None;
+                }
+                bytes.resize(align4(bytes.len()), 0);
+                buffer.byte_length = bytes.len() as u32;
+                data.push(bytes);
+                Some(buffer)
+            });
+        root.buffers = written.collect();
+        root.buffer_views = views;
+        for view in &mut root.buffer_views {
+            view.buffer = json::Index::new(buffer_indices[view.buffer.value()]);
+        }
+        if let Some(options) = &options.meshopt {
+            compress::compress(&mut root, &mut data, &mut extensions, &streams, options);
+        }
+
+        // Declare the extensions that are written, and only those.
+        let mut written = extensions.names();
+        let quantized = self.uses_mesh_quantization();
+        if quantized {
+            written.insert(KHR_MESH_QUANTIZATION.to_string());
+        }
+        root.extensions_used.retain(|x| written.contains(x));
+        root.extensions_required.retain(|x| written.contains(x));
+        let mut undeclared: Vec<_> = written
+            .into_iter()
+            .filter(|x| !root.extensions_used.contains(x))
+            .collect();
+        undeclared.sort();
+        root.extensions_used.extend(undeclared);
+        let name = KHR_MESH_QUANTIZATION.to_string();
+        if quantized && !root.extensions_required.contains(&name) {
+            root.extensions_required.push(name);
+        }
+
+        let data = data.into_iter().map(buffer::Data).collect();
+        Ok((Document(root, extensions), data))
+    }
+}
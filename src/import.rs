@@ -1,15 +1,87 @@
 use crate::buffer;
 use crate::image;
+use crate::meshopt;
 use std::borrow::Cow;
 use std::{fs, io};
 
 use crate::{Document, Error, Gltf, Result};
 use image_crate::ImageFormat::{Jpeg, Png};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Return type of `import`.
 type Import = (Document, Vec<buffer::Data>, Vec<image::Data>);
 
+/// Loads the external resources referenced by a glTF asset.
+///
+/// Implement this trait to serve buffers and images from sources other than
+/// the file system, such as archives, in-memory maps, or caches. `data:` URIs
+/// are decoded by the importer and are never passed to the resolver.
+///
+/// Closures of the form `Fn(&str) -> Result<Vec<u8>>` implement `Resolver`.
+pub trait Resolver {
+    /// Returns the contents of the resource identified by `uri`.
+    ///
+    /// The URI is passed exactly as it appears in the glTF JSON, i.e. it may
+    /// still be percent-encoded.
+    fn resolve(&self, uri: &str) -> Result<Vec<u8>>;
+}
+
+impl<F> Resolver for F
+where
+    F: Fn(&str) -> Result<Vec<u8>>,
+{
+    fn resolve(&self, uri: &str) -> Result<Vec<u8>> {
+        self(uri)
+    }
+}
+
+/// Resolves `file:` URIs and relative paths on the file system.
+///
+/// This is the resolver used by [`import`].
+///
+/// [`import`]: fn.import.html
+#[derive(Clone, Debug)]
+pub struct FileSystemResolver {
+    /// The directory relative paths are resolved against.
+    base: PathBuf,
+}
+
+impl FileSystemResolver {
+    /// Constructs a resolver for relative paths against `base`.
+    pub fn new<P>(base: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            base: base.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Resolver for FileSystemResolver {
+    fn resolve(&self, uri: &str) -> Result<Vec<u8>> {
+        match Scheme::parse(uri) {
+            Scheme::Data(_, base64) => decode_base64(base64),
+            Scheme::File(path) => read_to_end(path),
+            Scheme::Relative(path) => read_to_end(self.base.join(&*path)),
+            Scheme::Unsupported => Err(Error::UnsupportedScheme),
+        }
+    }
+}
+
+/// Rejects every external reference; used when importing from a slice.
+struct SliceResolver;
+
+impl Resolver for SliceResolver {
+    fn resolve(&self, uri: &str) -> Result<Vec<u8>> {
+        match Scheme::parse(uri) {
+            Scheme::Data(_, base64) => decode_base64(base64),
+            Scheme::Unsupported => Err(Error::UnsupportedScheme),
+            _ => Err(Error::ExternalReferenceInSliceImport),
+        }
+    }
+}
+
 /// Represents the set of URI schemes the importer supports.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 enum Scheme<'a> {
@@ -51,19 +123,22 @@ impl<'a> Scheme<'a> {
         }
     }
 
-    fn read(base: Option<&Path>, uri: &str) -> Result<Vec<u8>> {
+    fn read(resolver: &dyn Resolver, uri: &str) -> Result<Vec<u8>> {
         match Scheme::parse(uri) {
-            // The path may be unused in the Scheme::Data case
             // Example: "uri" : "data:application/octet-stream;base64,wsVHPgA...."
-            Scheme::Data(_, base64) => base64::decode(&base64).map_err(Error::Base64),
-            Scheme::File(path) if base.is_some() => read_to_end(path),
-            Scheme::Relative(path) if base.is_some() => read_to_end(base.unwrap().join(&*path)),
-            Scheme::Unsupported => Err(Error::UnsupportedScheme),
-            _ => Err(Error::ExternalReferenceInSliceImport),
+            Scheme::Data(_, base64) => decode_base64(base64),
+            _ => resolver.resolve(uri),
         }
     }
 }
 
+fn decode_base64(data: &str) -> Result<Vec<u8>> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(Error::Base64)
+}
+
 fn read_to_end<P>(path: P) -> Result<Vec<u8>>
 where
     P: AsRef<Path>,
@@ -81,15 +156,31 @@ where
 }
 
 /// Import the buffer data referenced by a glTF document.
-pub fn import_buffer_data(
+///
+/// External buffers are loaded through `resolver`; `blob` is the `BIN` chunk
+/// of binary glTF, if any.
+pub fn import_buffer_data<R>(
+    document: &Document,
+    resolver: &R,
+    blob: Option<Vec<u8>>,
+) -> Result<Vec<buffer::Data>>
+where
+    R: Resolver,
+{
+    import_buffer_data_impl(document, resolver, blob)
+}
+
+fn import_buffer_data_impl(
     document: &Document,
-    base: Option<&Path>,
+    resolver: &dyn Resolver,
     mut blob: Option<Vec<u8>>,
 ) -> Result<Vec<buffer::Data>> {
     let mut buffers = Vec::new();
     for buffer in document.buffers() {
         let mut data = match buffer.source() {
-            buffer::Source::Uri(uri) => Scheme::read(base, uri),
+            buffer::Source::Uri(uri) => Scheme::read(resolver, uri),
+            // Filled in by decoding the compressed views below.
+            buffer::Source::Bin if meshopt::is_fallback(&buffer) => Ok(vec![0; buffer.length()]),
             buffer::Source::Bin => blob.take().ok_or(Error::MissingBlob),
         }?;
         if data.len() < buffer.length() {
@@ -104,13 +195,52 @@ pub fn import_buffer_data(
         }
         buffers.push(buffer::Data(data));
     }
+    decode_meshopt_views(document, &mut buffers)?;
     Ok(buffers)
 }
 
+/// Decodes the buffer views compressed with `EXT_meshopt_compression` into
+/// the buffers they refer to.
+fn decode_meshopt_views(document: &Document, buffers: &mut [buffer::Data]) -> Result<()> {
+    for view in document.views() {
+        let error = |error| Error::Meshopt {
+            view: view.index(),
+            error,
+        };
+        let compression = match meshopt::Compression::from_view(&view).map_err(error)? {
+            Some(compression) => compression,
+            None => continue,
+        };
+        let decoded = compression.decode(buffers).map_err(error)?;
+        if decoded.len() != view.length() {
+            return Err(error(meshopt::Error::Invalid));
+        }
+        buffers
+            .get_mut(view.buffer().index())
+            .and_then(|x| x.0.get_mut(view.offset()..view.offset() + view.length()))
+            .ok_or(Error::ViewBounds { view: view.index() })?
+            .copy_from_slice(&decoded);
+    }
+    Ok(())
+}
+
 /// Import the image data referenced by a glTF document.
-pub fn import_image_data(
+///
+/// External images are loaded through `resolver`.
+pub fn import_image_data<R>(
     document: &Document,
-    base: Option<&Path>,
+    resolver: &R,
+    buffer_data: &[buffer::Data],
+) -> Result<Vec<image::Data>>
+where
+    R: Resolver,
+{
+    import_image_data_impl(document, resolver, buffer_data)
+}
+
+fn import_image_data_impl(
+    document: &Document,
+    resolver: &dyn Resolver,
     buffer_data: &[buffer::Data],
 ) -> Result<Vec<image::Data>> {
     let mut images = Vec::new();
@@ -124,9 +254,9 @@ pub fn import_image_data(
     let guess_format = |_encoded_image: &[u8]| None;
     for image in document.images() {
         let decoded_image = match image.source() {
-            image::Source::Uri { uri, mime_type } if base.is_some() => match Scheme::parse(uri) {
+            image::Source::Uri { uri, mime_type } => match Scheme::parse(uri) {
                 Scheme::Data(Some(annoying_case), base64) => {
-                    let encoded_image = base64::decode(&base64).map_err(Error::Base64)?;
+                    let encoded_image = decode_base64(base64)?;
                     let encoded_format = match annoying_case {
                         "image/png" => Png,
                         "image/jpeg" => Jpeg,
@@ -138,9 +268,8 @@ pub fn import_image_data(
 
                     image_crate::load_from_memory_with_format(&encoded_image, encoded_format)?
                 }
-                Scheme::Unsupported => return Err(Error::UnsupportedScheme),
                 _ => {
-                    let encoded_image = Scheme::read(base, uri)?;
+                    let encoded_image = Scheme::read(resolver, uri)?;
                     let encoded_format = match mime_type {
                         Some("image/png") => Png,
                         Some("image/jpeg") => Jpeg,
@@ -175,7 +304,6 @@ pub fn import_image_data(
                 };
                 image_crate::load_from_memory_with_format(encoded_image, encoded_format)?
             }
-            _ => return Err(Error::ExternalReferenceInSliceImport),
         };
 
         images.push(image::Data::new(decoded_image)?);
@@ -184,18 +312,22 @@ pub fn import_image_data(
     Ok(images)
 }
 
-fn import_impl(Gltf { document, blob }: Gltf, base: Option<&Path>) -> Result<Import> {
-    let buffer_data = import_buffer_data(&document, base, blob)?;
-    let image_data = import_image_data(&document, base, &buffer_data)?;
+fn import_impl(Gltf { document, blob }: Gltf, resolver: &dyn Resolver) -> Result<Import> {
+    let buffer_data = import_buffer_data_impl(&document, resolver, blob)?;
+    let image_data = import_image_data_impl(&document, resolver, &buffer_data)?;
     let import = (document, buffer_data, image_data);
     Ok(import)
 }
 
-fn import_path(path: &Path) -> Result<Import> {
+fn import_path(path: &Path, resolver: Option<&dyn Resolver>) -> Result<Import> {
     let base = path.parent().unwrap_or_else(|| Path::new("./"));
     let file = fs::File::open(path).map_err(Error::Io)?;
     let reader = io::BufReader::new(file);
-    import_impl(Gltf::from_reader(reader)?, Some(base))
+    let gltf = Gltf::from_reader(reader)?;
+    match resolver {
+        Some(resolver) => import_impl(gltf, resolver),
+        None => import_impl(gltf, &FileSystemResolver::new(base)),
+    }
 }
 
 /// Import some glTF 2.0 from the file system.
@@ -229,11 +361,39 @@ pub fn import<P>(path: P) -> Result<Import>
 where
     P: AsRef<Path>,
 {
-    import_path(path.as_ref())
+    import_path(path.as_ref(), None)
+}
+
+/// Import some glTF 2.0 from the file system, loading external resources
+/// through a custom [`Resolver`].
+///
+/// ```
+/// # use gltf_transform_rs as gltf;
+/// # fn run() -> Result<(), gltf::Error> {
+/// use std::collections::HashMap;
+/// let files: HashMap<&str, Vec<u8>> =
+///     vec![("Box0.bin", std::fs::read("examples/Box0.bin")?)].into_iter().collect();
+/// let resolver = |uri: &str| files.get(uri).cloned().ok_or(gltf::Error::UnsupportedScheme);
+/// # #[allow(unused)]
+/// let (document, buffers, images) = gltf::import_with_resolver("examples/Box.gltf", &resolver)?;
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// #     run().expect("test failure");
+/// # }
+/// ```
+///
+/// [`Resolver`]: trait.Resolver.html
+pub fn import_with_resolver<P, R>(path: P, resolver: &R) -> Result<Import>
+where
+    P: AsRef<Path>,
+    R: Resolver,
+{
+    import_path(path.as_ref(), Some(resolver))
 }
 
 pub fn import_slice_impl(slice: &[u8]) -> Result<Import> {
-    import_impl(Gltf::from_slice(slice)?, None)
+    import_impl(Gltf::from_slice(slice)?, &SliceResolver)
 }
 
 /// Import some glTF 2.0 from a slice
@@ -261,3 +421,15 @@ where
 {
     import_slice_impl(slice.as_ref())
 }
+
+/// Import some glTF 2.0 from a slice, loading external resources through a
+/// custom [`Resolver`].
+///
+/// [`Resolver`]: trait.Resolver.html
+pub fn import_slice_with_resolver<S, R>(slice: S, resolver: &R) -> Result<Import>
+where
+    S: AsRef<[u8]>,
+    R: Resolver,
+{
+    import_impl(Gltf::from_slice(slice.as_ref())?, resolver)
+}
@@ -97,12 +97,30 @@ pub mod animation;
 /// Primitives for working with binary glTF.
 pub mod binary;
 
+/// Bounding volumes computed from vertex data.
+#[cfg(feature = "utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+pub mod bounds;
+
 /// Buffers and buffer views.
 pub mod buffer;
 
 /// Cameras and their projections.
 pub mod camera;
 
+/// The reference exporter.
+#[cfg(feature = "utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+mod export;
+
+/// Raw extension data not modelled by `gltf-json`.
+mod extensions;
+
+/// Owned, editable document graph.
+#[cfg(feature = "utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+pub mod graph;
+
 /// Images that may be used by textures.
 pub mod image;
 
@@ -127,6 +145,9 @@ pub mod khr_materials_variants;
 /// Material properties of primitives.
 pub mod material;
 
+/// Support for the `EXT_meshopt_compression` extension.
+pub mod meshopt;
+
 /// For internal use.
 mod math;
 
@@ -142,6 +163,11 @@ pub mod skin;
 /// Textures and their samplers.
 pub mod texture;
 
+/// Transforms that optimize or restructure a document graph.
+#[cfg(feature = "utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+pub mod transform;
+
 #[doc(inline)]
 pub use self::accessor::Accessor;
 #[doc(inline)]
@@ -152,6 +178,9 @@ pub use self::binary::Glb;
 pub use self::buffer::Buffer;
 #[doc(inline)]
 pub use self::camera::Camera;
+#[cfg(feature = "utils")]
+#[doc(inline)]
+pub use self::export::{export, export_glb};
 #[doc(inline)]
 pub use self::image::Image;
 #[cfg(feature = "import")]
@@ -160,6 +189,15 @@ pub use self::import::import;
 #[cfg(feature = "import")]
 #[doc(inline)]
 pub use self::import::import_slice;
+#[cfg(feature = "import")]
+#[doc(inline)]
+pub use self::import::{import_buffer_data, import_image_data};
+#[cfg(feature = "import")]
+#[doc(inline)]
+pub use self::import::{import_slice_with_resolver, import_with_resolver};
+#[cfg(feature = "import")]
+#[doc(inline)]
+pub use self::import::{FileSystemResolver, Resolver};
 #[doc(inline)]
 pub use self::material::Material;
 #[doc(inline)]
@@ -242,8 +280,30 @@ pub enum Error {
     #[cfg_attr(docsrs, doc(cfg(feature = "import")))]
     UnsupportedScheme,
 
+    /// A buffer view compressed with `EXT_meshopt_compression` could not be
+    /// decoded.
+    Meshopt {
+        /// The index of the offending buffer view.
+        view: usize,
+
+        /// The decoding error.
+        error: meshopt::Error,
+    },
+
+    /// A required extension is used by objects whose extensions are not
+    /// kept by a [`graph::Graph`], so it cannot be preserved.
+    ///
+    /// [`graph::Graph`]: graph/struct.Graph.html
+    UnsupportedExtension(String),
+
     /// glTF validation error.
     Validation(Vec<(json::Path, json::validation::Error)>),
+
+    /// A buffer view or accessor refers to data outside its buffer.
+    ViewBounds {
+        /// The index of the offending buffer view.
+        view: usize,
+    },
 }
 
 /// glTF JSON wrapper plus binary payload.
@@ -258,7 +318,7 @@ pub struct Gltf {
 
 /// glTF JSON wrapper.
 #[derive(Clone, Debug)]
-pub struct Document(json::Root);
+pub struct Document(json::Root, extensions::Extensions);
 
 impl Gltf {
     /// Convenience function that loads glTF from the file system.
@@ -280,7 +340,7 @@ impl Gltf {
         let mut magic = [0u8; 4];
         reader.read_exact(&mut magic)?;
         reader.seek(io::SeekFrom::Start(0))?;
-        let (json, blob): (json::Root, Option<Vec<u8>>);
+        let (json, blob): (json::Value, Option<Vec<u8>>);
         if magic.starts_with(b"glTF") {
             let mut glb = binary::Glb::from_reader(reader)?;
             // TODO: use `json::from_reader` instead of `json::from_slice`
@@ -290,7 +350,7 @@ impl Gltf {
             json = json::deserialize::from_reader(reader)?;
             blob = None;
         };
-        let document = Document::from_json_without_validation(json);
+        let document = Document::from_json_value(json)?;
         Ok(Gltf { document, blob })
     }
 
@@ -307,7 +367,7 @@ impl Gltf {
     /// Loads glTF from a slice of bytes without performing validation
     /// checks.
     pub fn from_slice_without_validation(slice: &[u8]) -> Result<Self> {
-        let (json, blob): (json::Root, Option<Vec<u8>>);
+        let (json, blob): (json::Value, Option<Vec<u8>>);
         if slice.starts_with(b"glTF") {
             let mut glb = binary::Glb::from_slice(slice)?;
             json = json::deserialize::from_slice(&glb.json)?;
@@ -316,7 +376,7 @@ impl Gltf {
             json = json::deserialize::from_slice(slice)?;
             blob = None;
         };
-        let document = Document::from_json_without_validation(json);
+        let document = Document::from_json_value(json)?;
         Ok(Gltf { document, blob })
     }
 
@@ -352,7 +412,15 @@ impl Document {
     /// Loads glTF from pre-deserialized JSON without performing
     /// validation checks.
     pub fn from_json_without_validation(json: json::Root) -> Self {
-        Document(json)
+        Document(json, Default::default())
+    }
+
+    /// Loads glTF from untyped JSON without performing validation checks,
+    /// keeping extensions that `gltf-json` does not model.
+    fn from_json_value(value: json::Value) -> Result<Self> {
+        let extensions = extensions::Extensions::from_value(&value);
+        let json = json::deserialize::from_value(value)?;
+        Ok(Document(json, extensions))
     }
 
     /// Unwraps the glTF document.
@@ -360,6 +428,14 @@ impl Document {
         self.0
     }
 
+    /// Converts the glTF document into untyped JSON, including extensions
+    /// that `gltf-json` does not model, such as `EXT_meshopt_compression`.
+    pub fn into_json_value(self) -> Result<json::Value> {
+        let mut value = json::serialize::to_value(&self.0)?;
+        self.1.merge_into(&mut value);
+        Ok(value)
+    }
+
     /// Perform validation checks on loaded glTF.
     pub(crate) fn validate(&self) -> Result<()> {
         use json::validation::Validate;
@@ -577,6 +653,10 @@ impl std::fmt::Display for Error {
             }
             #[cfg(feature = "import")]
             Error::UnsupportedScheme => write!(f, "unsupported URI scheme"),
+            Error::Meshopt { view, error } => write!(f, "buffer view {}: {}", view, error),
+            Error::UnsupportedExtension(name) => {
+                write!(f, "required extension {} cannot be preserved", name)
+            }
             Error::Validation(ref xs) => {
                 write!(f, "invalid glTF:")?;
                 for &(ref path, ref error) in xs {
@@ -584,6 +664,9 @@ impl std::fmt::Display for Error {
                 }
                 Ok(())
             }
+            Error::ViewBounds { view } => {
+                write!(f, "buffer view {}: data out of bounds", view)
+            }
         }
     }
 }
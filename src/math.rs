@@ -163,7 +163,6 @@ impl Vector4 {
         [self.x, self.y, self.z, self.w]
     }
 
-    #[cfg(test)]
     pub fn from_array([x, y, z, w]: [f32; 4]) -> Self {
         Self { x, y, z, w }
     }
@@ -250,7 +249,17 @@ impl Matrix4 {
         }
     }
 
-    #[cfg(test)]
+    /// Create an identity matrix.
+    #[rustfmt::skip]
+    pub fn identity() -> Matrix4 {
+        Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
+
     pub fn from_array([x, y, z, w]: [[f32; 4]; 4]) -> Matrix4 {
         Matrix4 {
             x: Vector4::from_array(x),
@@ -176,7 +176,25 @@ impl<'a> Primitive<'a> {
         Self { mesh, index, json }
     }
 
+    /// Returns the bounds of the `POSITION` vertex attribute, as declared by
+    /// the `min` and `max` properties of its accessor, or `None` if either is
+    /// missing or malformed.
+    ///
+    /// See the [`bounds`](../bounds/index.html) module to compute bounds from
+    /// the vertex data instead.
+    pub fn try_bounding_box(&self) -> Option<BoundingBox> {
+        let accessor = self.get(&Semantic::Positions)?;
+        let min = json::deserialize::from_value(accessor.min()?).ok()?;
+        let max = json::deserialize::from_value(accessor.max()?).ok()?;
+        Some(Bounds { min, max })
+    }
+
     /// Returns the bounds of the `POSITION` vertex attribute.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the accessor does not declare `min` and `max`; use
+    /// [`try_bounding_box`](#method.try_bounding_box) to handle that case.
     pub fn bounding_box(&self) -> BoundingBox {
         // NOTE: cannot panic if validated "minimally"
         let pos_accessor_index = self
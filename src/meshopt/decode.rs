@@ -0,0 +1,420 @@
+use super::{Error, Filter};
+
+/// The header byte of vertex buffer streams.
+pub(super) const VERTEX_HEADER: u8 = 0xa0;
+
+/// The high nibble of the header byte of index buffer streams.
+pub(super) const INDEX_HEADER: u8 = 0xe0;
+
+/// The high nibble of the header byte of index sequence streams.
+pub(super) const SEQUENCE_HEADER: u8 = 0xd0;
+
+/// The number of bytes encoded together in vertex buffer streams.
+pub(super) const BYTE_GROUP_SIZE: usize = 16;
+
+/// The minimum size of the tail of vertex buffer streams.
+pub(super) const TAIL_MAX_SIZE: usize = 32;
+
+/// Returns the number of vertices encoded per block.
+pub(super) fn vertex_block_size(stride: usize) -> usize {
+    ((8192 / stride) & !(BYTE_GROUP_SIZE - 1)).min(256)
+}
+
+fn unzigzag8(v: u8) -> u8 {
+    0u8.wrapping_sub(v & 1) ^ (v >> 1)
+}
+
+/// Decodes one group of `BYTE_GROUP_SIZE` bytes stored with `bits` bits
+/// each, returning the position after the group.
+fn decode_bytes_group(data: &[u8], pos: usize, bits: u8, out: &mut [u8]) -> usize {
+    match bits {
+        0 => {
+            out.iter_mut().for_each(|x| *x = 0);
+            pos
+        }
+        1 | 2 => {
+            let bits = 1 << bits;
+            let per_byte = 8 / bits;
+            let sentinel = (1u8 << bits) - 1;
+            let mut var = pos + BYTE_GROUP_SIZE / per_byte;
+            for (i, x) in out.iter_mut().enumerate() {
+                let byte = data[pos + i / per_byte];
+                let enc = (byte >> (8 - bits * (i % per_byte + 1))) & sentinel;
+                *x = if enc == sentinel {
+                    var += 1;
+                    data[var - 1]
+                } else {
+                    enc
+                };
+            }
+            var
+        }
+        _ => {
+            out.copy_from_slice(&data[pos..pos + BYTE_GROUP_SIZE]);
+            pos + BYTE_GROUP_SIZE
+        }
+    }
+}
+
+/// Decodes the byte groups of one byte of every vertex in a block.
+fn decode_bytes(data: &[u8], mut pos: usize, out: &mut [u8]) -> Result<usize, Error> {
+    let groups = out.len() / BYTE_GROUP_SIZE;
+    let header_size = groups.div_ceil(4);
+    if data.len() - pos < header_size {
+        return Err(Error::Truncated);
+    }
+    let header = pos;
+    pos += header_size;
+    for (i, group) in out.chunks_exact_mut(BYTE_GROUP_SIZE).enumerate() {
+        // The tail guarantees that a whole group can be read.
+        if data.len() - pos < TAIL_MAX_SIZE {
+            return Err(Error::Truncated);
+        }
+        let bits = (data[header + i / 4] >> ((i % 4) * 2)) & 3;
+        pos = decode_bytes_group(data, pos, bits, group);
+    }
+    Ok(pos)
+}
+
+/// Decodes a vertex buffer stream of `count` elements of `stride` bytes.
+///
+/// `stride` must be a multiple of four no greater than 256.
+pub fn decode_vertex_buffer(count: usize, stride: usize, data: &[u8]) -> Result<Vec<u8>, Error> {
+    if stride == 0 || stride > 256 || !stride.is_multiple_of(4) {
+        return Err(Error::Invalid);
+    }
+    if data.len() < 1 + stride {
+        return Err(Error::Truncated);
+    }
+    if data[0] != VERTEX_HEADER {
+        return Err(Error::Header(data[0]));
+    }
+
+    let mut out = vec![0; count * stride];
+    let mut last = data[data.len() - stride..].to_vec();
+    let mut buffer = [0; 256];
+    let block_size = vertex_block_size(stride);
+    let mut pos = 1;
+    let mut offset = 0;
+    while offset < count {
+        let size = block_size.min(count - offset);
+        let aligned = (size + BYTE_GROUP_SIZE - 1) & !(BYTE_GROUP_SIZE - 1);
+        let block = &mut out[offset * stride..(offset + size) * stride];
+        for (k, last) in last.iter_mut().enumerate() {
+            pos = decode_bytes(data, pos, &mut buffer[..aligned])?;
+            for (i, delta) in buffer[..size].iter().enumerate() {
+                *last = unzigzag8(*delta).wrapping_add(*last);
+                block[i * stride + k] = *last;
+            }
+        }
+        offset += size;
+    }
+
+    if data.len() - pos != stride.max(TAIL_MAX_SIZE) {
+        return Err(Error::Trailing);
+    }
+    Ok(out)
+}
+
+fn decode_vbyte(data: &[u8], pos: &mut usize) -> u32 {
+    let lead = data[*pos];
+    *pos += 1;
+    if lead < 128 {
+        return lead as u32;
+    }
+    let mut result = (lead & 127) as u32;
+    let mut shift = 7;
+    for _ in 0..4 {
+        let group = data[*pos];
+        *pos += 1;
+        result |= ((group & 127) as u32) << shift;
+        shift += 7;
+        if group < 128 {
+            break;
+        }
+    }
+    result
+}
+
+fn decode_index(data: &[u8], pos: &mut usize, last: u32) -> u32 {
+    let v = decode_vbyte(data, pos);
+    let d = (v >> 1) ^ 0u32.wrapping_sub(v & 1);
+    last.wrapping_add(d)
+}
+
+fn write_index(out: &mut Vec<u8>, stride: usize, index: u32) {
+    if stride == 2 {
+        out.extend_from_slice(&(index as u16).to_le_bytes());
+    } else {
+        out.extend_from_slice(&index.to_le_bytes());
+    }
+}
+
+/// The edge and vertex FIFOs shared by the index buffer encoder and decoder.
+pub(super) struct Fifos {
+    edges: [[u32; 2]; 16],
+    edge_offset: usize,
+    vertices: [u32; 16],
+    vertex_offset: usize,
+}
+
+impl Fifos {
+    pub(super) fn new() -> Self {
+        Self {
+            edges: [[u32::MAX; 2]; 16],
+            edge_offset: 0,
+            vertices: [u32::MAX; 16],
+            vertex_offset: 0,
+        }
+    }
+
+    /// Returns the edge `i` places before the most recent one.
+    pub(super) fn edge(&self, i: usize) -> [u32; 2] {
+        self.edges[self.edge_offset.wrapping_sub(1 + i) & 15]
+    }
+
+    /// Returns the vertex `i` places before the next one to be pushed.
+    pub(super) fn vertex(&self, i: usize) -> u32 {
+        self.vertices[self.vertex_offset.wrapping_sub(i) & 15]
+    }
+
+    pub(super) fn push_edge(&mut self, a: u32, b: u32) {
+        self.edges[self.edge_offset] = [a, b];
+        self.edge_offset = (self.edge_offset + 1) & 15;
+    }
+
+    /// Pushes a vertex; the slot is overwritten again unless `advance` is
+    /// set.
+    pub(super) fn push_vertex(&mut self, v: u32, advance: bool) {
+        self.vertices[self.vertex_offset] = v;
+        self.vertex_offset = (self.vertex_offset + advance as usize) & 15;
+    }
+}
+
+/// Decodes a triangle index buffer stream of `count` indices of `stride`
+/// bytes.
+///
+/// `count` must be a multiple of three and `stride` must be 2 or 4.
+pub fn decode_index_buffer(count: usize, stride: usize, data: &[u8]) -> Result<Vec<u8>, Error> {
+    if !count.is_multiple_of(3) || (stride != 2 && stride != 4) {
+        return Err(Error::Invalid);
+    }
+    if data.len() < 1 + count / 3 + 16 {
+        return Err(Error::Truncated);
+    }
+    let version = data[0] & 0x0f;
+    if data[0] & 0xf0 != INDEX_HEADER || version > 1 {
+        return Err(Error::Header(data[0]));
+    }
+    // Version 1 reserves two edge codes for the last free index +/- 1.
+    let fecmax = if version >= 1 { 13 } else { 15 };
+
+    let mut out = Vec::with_capacity(count * stride);
+    let mut fifos = Fifos::new();
+    let mut next = 0u32;
+    let mut last = 0u32;
+    let mut pos = 1 + count / 3;
+    // The codeaux table at the end also serves as padding, so that every
+    // triangle can be read without further bounds checks.
+    let end = data.len() - 16;
+    let table = &data[end..];
+
+    for code in 1..1 + count / 3 {
+        if pos > end {
+            return Err(Error::Truncated);
+        }
+        let codetri = data[code];
+
+        let [a, b, c] = if codetri < 0xf0 {
+            let [a, b] = fifos.edge((codetri >> 4) as usize);
+            let fec = codetri & 15;
+            let c = if fec < fecmax {
+                let c = if fec == 0 {
+                    next
+                } else {
+                    fifos.vertex(1 + fec as usize)
+                };
+                next += (fec == 0) as u32;
+                fifos.push_vertex(c, fec == 0);
+                c
+            } else {
+                last = match fec {
+                    13 => last.wrapping_sub(1),
+                    14 => last.wrapping_add(1),
+                    _ => decode_index(data, &mut pos, last),
+                };
+                fifos.push_vertex(last, true);
+                last
+            };
+            fifos.push_edge(c, b);
+            fifos.push_edge(a, c);
+            [a, b, c]
+        } else if codetri < 0xfe {
+            let codeaux = table[(codetri & 15) as usize];
+            let feb = (codeaux >> 4) as usize;
+            let fec = (codeaux & 15) as usize;
+            let a = next;
+            next += 1;
+            let b = if feb == 0 { next } else { fifos.vertex(feb) };
+            next += (feb == 0) as u32;
+            let c = if fec == 0 { next } else { fifos.vertex(fec) };
+            next += (fec == 0) as u32;
+            fifos.push_vertex(a, true);
+            fifos.push_vertex(b, feb == 0);
+            fifos.push_vertex(c, fec == 0);
+            fifos.push_edge(b, a);
+            fifos.push_edge(c, b);
+            fifos.push_edge(a, c);
+            [a, b, c]
+        } else {
+            let codeaux = data[pos];
+            pos += 1;
+            let fea = if codetri == 0xfe { 0 } else { 15 };
+            let feb = (codeaux >> 4) as usize;
+            let fec = (codeaux & 15) as usize;
+            let mut vertex = |fe: usize| {
+                if fe == 0 {
+                    next += 1;
+                    next - 1
+                } else if fe < 15 {
+                    fifos.vertex(fe)
+                } else {
+                    0
+                }
+            };
+            let (mut a, mut b, mut c) = (vertex(fea), vertex(feb), vertex(fec));
+            for (fe, v) in [(fea, &mut a), (feb, &mut b), (fec, &mut c)] {
+                if fe == 15 {
+                    last = decode_index(data, &mut pos, last);
+                    *v = last;
+                }
+            }
+            fifos.push_vertex(a, true);
+            fifos.push_vertex(b, feb == 0 || feb == 15);
+            fifos.push_vertex(c, fec == 0 || fec == 15);
+            fifos.push_edge(b, a);
+            fifos.push_edge(c, b);
+            fifos.push_edge(a, c);
+            [a, b, c]
+        };
+        for index in [a, b, c] {
+            write_index(&mut out, stride, index);
+        }
+    }
+
+    if pos != end {
+        return Err(Error::Trailing);
+    }
+    Ok(out)
+}
+
+/// Decodes an index sequence stream of `count` indices of `stride` bytes.
+///
+/// `stride` must be 2 or 4.
+pub fn decode_index_sequence(count: usize, stride: usize, data: &[u8]) -> Result<Vec<u8>, Error> {
+    if stride != 2 && stride != 4 {
+        return Err(Error::Invalid);
+    }
+    if data.len() < 1 + count + 4 {
+        return Err(Error::Truncated);
+    }
+    if data[0] & 0xf0 != SEQUENCE_HEADER || data[0] & 0x0f > 1 {
+        return Err(Error::Header(data[0]));
+    }
+
+    let mut out = Vec::with_capacity(count * stride);
+    let mut last = [0u32; 2];
+    let mut pos = 1;
+    let end = data.len() - 4;
+    for _ in 0..count {
+        if pos >= end {
+            return Err(Error::Truncated);
+        }
+        let v = decode_vbyte(data, &mut pos);
+        // The low bit selects which of the two baselines the delta is from.
+        let baseline = (v & 1) as usize;
+        let v = v >> 1;
+        let d = (v >> 1) ^ 0u32.wrapping_sub(v & 1);
+        last[baseline] = last[baseline].wrapping_add(d);
+        write_index(&mut out, stride, last[baseline]);
+    }
+
+    if pos != end {
+        return Err(Error::Trailing);
+    }
+    Ok(out)
+}
+
+/// Rounds to the nearest integer, away from zero on ties.
+fn round(x: f32) -> i32 {
+    (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32
+}
+
+fn decode_octahedral(x: f32, y: f32, one: f32, max: f32) -> [i32; 3] {
+    let z = one - x.abs() - y.abs();
+    // Fold the negative hemisphere back.
+    let t = z.min(0.0);
+    let x = x + if x >= 0.0 { t } else { -t };
+    let y = y + if y >= 0.0 { t } else { -t };
+    let s = max / (x * x + y * y + z * z).sqrt();
+    [round(x * s), round(y * s), round(z * s)]
+}
+
+/// Reverses `filter` in place on `data`, which holds elements of `stride`
+/// bytes.
+///
+/// The octahedral filter requires a stride of 4 or 8 bytes, the quaternion
+/// filter a stride of 8 bytes, and the exponential filter a multiple of 4.
+pub fn decode_filter(filter: Filter, stride: usize, data: &mut [u8]) -> Result<(), Error> {
+    match filter {
+        Filter::None => {}
+        Filter::Octahedral if stride == 4 => {
+            for element in data.chunks_exact_mut(4) {
+                let [x, y, one, _] = [0, 1, 2, 3].map(|i| element[i] as i8 as f32);
+                let xyz = decode_octahedral(x, y, one, 127.0);
+                for (byte, v) in element.iter_mut().zip(xyz) {
+                    *byte = v as i8 as u8;
+                }
+            }
+        }
+        Filter::Octahedral if stride == 8 => {
+            for element in data.chunks_exact_mut(8) {
+                let [x, y, one] = [0, 1, 2]
+                    .map(|i| i16::from_le_bytes([element[2 * i], element[2 * i + 1]]) as f32);
+                let xyz = decode_octahedral(x, y, one, 32767.0);
+                for (bytes, v) in element.chunks_exact_mut(2).zip(xyz) {
+                    bytes.copy_from_slice(&(v as i16).to_le_bytes());
+                }
+            }
+        }
+        Filter::Quaternion if stride == 8 => {
+            let scale = 1.0 / 2f32.sqrt();
+            for element in data.chunks_exact_mut(8) {
+                let q =
+                    [0, 1, 2, 3].map(|i| i16::from_le_bytes([element[2 * i], element[2 * i + 1]]));
+                // The largest component is dropped; its index is stored in
+                // the low bits of the fourth value, and the scale above them.
+                let s = scale / (q[3] | 3) as f32;
+                let [x, y, z] = [0, 1, 2].map(|i| q[i] as f32 * s);
+                let w = (1.0 - x * x - y * y - z * z).max(0.0).sqrt();
+                let qc = (q[3] & 3) as usize;
+                for (i, v) in [w, x, y, z].iter().enumerate() {
+                    let offset = 2 * ((qc + i) & 3);
+                    element[offset..offset + 2]
+                        .copy_from_slice(&(round(v * 32767.0) as i16).to_le_bytes());
+                }
+            }
+        }
+        Filter::Exponential if stride.is_multiple_of(4) => {
+            for bytes in data.chunks_exact_mut(4) {
+                let v = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                let mantissa = ((v << 8) as i32) >> 8;
+                let exponent = (v as i32) >> 24;
+                let scale = f32::from_bits(((exponent + 127) as u32) << 23);
+                bytes.copy_from_slice(&(scale * mantissa as f32).to_le_bytes());
+            }
+        }
+        _ => return Err(Error::Invalid),
+    }
+    Ok(())
+}
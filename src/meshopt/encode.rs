@@ -0,0 +1,394 @@
+use super::decode::{
+    vertex_block_size, Fifos, BYTE_GROUP_SIZE, INDEX_HEADER, SEQUENCE_HEADER, TAIL_MAX_SIZE,
+    VERTEX_HEADER,
+};
+
+/// The `codeaux` values written to the table at the end of index buffer
+/// streams, chosen by symbol frequency on a set of training meshes.
+const CODE_AUX_TABLE: [u8; 16] = [
+    0x00, 0x76, 0x87, 0x56, 0x67, 0x78, 0xa9, 0x86, 0x65, 0x89, 0x68, 0x98, 0x01, 0x69, 0, 0,
+];
+
+/// The version of index streams written by the encoder.
+const INDEX_VERSION: u8 = 1;
+
+fn zigzag8(v: u8) -> u8 {
+    (((v as i8) >> 7) as u8) ^ (v << 1)
+}
+
+/// Returns the encoded size of a group of bytes stored with `bits` bits each.
+fn measure_bytes_group(group: &[u8], bits: usize) -> usize {
+    match bits {
+        1 if group.iter().all(|x| *x == 0) => 0,
+        1 => usize::MAX,
+        8 => BYTE_GROUP_SIZE,
+        _ => {
+            let sentinel = (1 << bits) - 1;
+            BYTE_GROUP_SIZE * bits / 8 + group.iter().filter(|x| **x >= sentinel).count()
+        }
+    }
+}
+
+fn encode_bytes_group(out: &mut Vec<u8>, group: &[u8], bits: usize) {
+    match bits {
+        1 => {}
+        8 => out.extend_from_slice(group),
+        _ => {
+            let sentinel = (1 << bits) - 1;
+            for chunk in group.chunks_exact(8 / bits) {
+                let byte = chunk
+                    .iter()
+                    .fold(0u8, |byte, x| (byte << bits) | (*x).min(sentinel));
+                out.push(byte);
+            }
+            out.extend(group.iter().filter(|x| **x >= sentinel));
+        }
+    }
+}
+
+/// Encodes one byte of every vertex in a block, padded to whole groups.
+fn encode_bytes(out: &mut Vec<u8>, buffer: &[u8]) {
+    let groups = buffer.len() / BYTE_GROUP_SIZE;
+    let header = out.len();
+    out.resize(header + groups.div_ceil(4), 0);
+    for (i, group) in buffer.chunks_exact(BYTE_GROUP_SIZE).enumerate() {
+        let mut best = (8, measure_bytes_group(group, 8));
+        for bits in [1, 2, 4] {
+            let size = measure_bytes_group(group, bits);
+            if size < best.1 {
+                best = (bits, size);
+            }
+        }
+        let bits_log2 = best.0.trailing_zeros() as u8;
+        out[header + i / 4] |= bits_log2 << ((i % 4) * 2);
+        encode_bytes_group(out, group, best.0);
+    }
+}
+
+/// Encodes `data`, holding elements of `stride` bytes, as a vertex buffer
+/// stream.
+///
+/// # Panics
+///
+/// Panics if `stride` is not a multiple of four between 4 and 256, or if the
+/// length of `data` is not a multiple of `stride`.
+pub fn encode_vertex_buffer(data: &[u8], stride: usize) -> Vec<u8> {
+    assert!(stride > 0 && stride <= 256 && stride.is_multiple_of(4));
+    assert_eq!(data.len() % stride, 0);
+    let count = data.len() / stride;
+
+    let mut out = vec![VERTEX_HEADER];
+    let mut last = vec![0; stride];
+    if count > 0 {
+        last.copy_from_slice(&data[..stride]);
+    }
+    let first = last.clone();
+    let mut buffer = [0; 256];
+    for block in data.chunks(vertex_block_size(stride) * stride) {
+        let size = block.len() / stride;
+        let aligned = (size + BYTE_GROUP_SIZE - 1) & !(BYTE_GROUP_SIZE - 1);
+        for (k, last) in last.iter_mut().enumerate() {
+            buffer[..aligned].iter_mut().for_each(|x| *x = 0);
+            for (i, delta) in buffer[..size].iter_mut().enumerate() {
+                let v = block[i * stride + k];
+                *delta = zigzag8(v.wrapping_sub(*last));
+                *last = v;
+            }
+            encode_bytes(&mut out, &buffer[..aligned]);
+        }
+    }
+
+    // The first vertex is stored at the end, padded to the tail size.
+    out.resize(out.len() + TAIL_MAX_SIZE.saturating_sub(stride), 0);
+    out.extend_from_slice(&first);
+    out
+}
+
+fn encode_vbyte(out: &mut Vec<u8>, mut v: u32) {
+    loop {
+        out.push((v & 127) as u8 | if v > 127 { 128 } else { 0 });
+        v >>= 7;
+        if v == 0 {
+            break;
+        }
+    }
+}
+
+fn encode_index(out: &mut Vec<u8>, index: u32, last: u32) {
+    let d = index.wrapping_sub(last);
+    encode_vbyte(out, (d << 1) ^ (((d as i32) >> 31) as u32));
+}
+
+/// Returns the position of edge `ab`, `bc` or `ca` in the edge FIFO, plus
+/// which of them matched.
+fn find_edge(fifos: &Fifos, [a, b, c]: [u32; 3]) -> Option<(usize, usize)> {
+    (0..16).find_map(|i| {
+        let edge = fifos.edge(i);
+        [[a, b], [b, c], [c, a]]
+            .iter()
+            .position(|x| *x == edge)
+            .map(|rotation| (i, rotation))
+    })
+}
+
+/// Returns how many places before the next slot `v` is in the vertex FIFO,
+/// minus one.
+fn find_vertex(fifos: &Fifos, v: u32) -> Option<usize> {
+    (0..16).find(|i| fifos.vertex(1 + i) == v)
+}
+
+/// Encodes triangle list indices as an index buffer stream.
+///
+/// The decoded triangles keep their winding but may start at a different
+/// vertex.
+///
+/// # Panics
+///
+/// Panics if the number of indices is not a multiple of three.
+pub fn encode_index_buffer(indices: &[u32]) -> Vec<u8> {
+    assert_eq!(indices.len() % 3, 0);
+    let mut out = vec![INDEX_HEADER | INDEX_VERSION];
+    out.resize(1 + indices.len() / 3, 0);
+    let mut code = 1;
+    let mut fifos = Fifos::new();
+    let mut next = 0u32;
+    let mut last = 0u32;
+    // Version 1 reserves two edge codes for the last free index +/- 1.
+    let fecmax = 13;
+
+    for triangle in indices.chunks_exact(3) {
+        let triangle = [triangle[0], triangle[1], triangle[2]];
+        match find_edge(&fifos, triangle).filter(|(fe, _)| *fe < 15) {
+            Some((fe, rotation)) => {
+                let [a, b, c] = [0, 1, 2].map(|i| triangle[(rotation + i) % 3]);
+                let mut fec = match find_vertex(&fifos, c) {
+                    Some(fc) if fc >= 1 && fc < fecmax => fc,
+                    _ if c == next => {
+                        next += 1;
+                        0
+                    }
+                    _ => 15,
+                };
+                if fec == 15 {
+                    if c.wrapping_add(1) == last {
+                        fec = 13;
+                        last = c;
+                    } else if c == last.wrapping_add(1) {
+                        fec = 14;
+                        last = c;
+                    }
+                }
+                out[code] = ((fe << 4) | fec) as u8;
+                code += 1;
+                if fec == 15 {
+                    encode_index(&mut out, c, last);
+                    last = c;
+                }
+                if fec == 0 || fec >= fecmax {
+                    fifos.push_vertex(c, true);
+                }
+                fifos.push_edge(c, b);
+                fifos.push_edge(a, c);
+            }
+            None => {
+                // Start at `next` if possible, as it is cheapest to encode.
+                let rotation = triangle[1..]
+                    .iter()
+                    .position(|x| *x == next)
+                    .map_or(0, |x| x + 1);
+                let [a, b, c] = [0, 1, 2].map(|i| triangle[(rotation + i) % 3]);
+                let fb = find_vertex(&fifos, b);
+                let fc = find_vertex(&fifos, c);
+
+                let mut fe = |v: u32, f: Option<usize>| match f {
+                    Some(f) if f < 14 => f + 1,
+                    _ if v == next => {
+                        next += 1;
+                        0
+                    }
+                    _ => 15,
+                };
+                let fea = fe(a, None);
+                let feb = fe(b, fb);
+                let fec = fe(c, fc);
+
+                let codeaux = ((feb << 4) | fec) as u8;
+                match CODE_AUX_TABLE.iter().position(|x| *x == codeaux) {
+                    Some(index) if fea == 0 && index < 14 => out[code] = 0xf0 | index as u8,
+                    _ => {
+                        out[code] = 0xfe | (fea == 15) as u8;
+                        out.push(codeaux);
+                    }
+                }
+                code += 1;
+
+                for (fe, v) in [(fea, a), (feb, b), (fec, c)] {
+                    if fe == 15 {
+                        encode_index(&mut out, v, last);
+                        last = v;
+                    }
+                }
+                for (fe, v) in [(fea, a), (feb, b), (fec, c)] {
+                    if fe == 0 || fe == 15 {
+                        fifos.push_vertex(v, true);
+                    }
+                }
+                fifos.push_edge(b, a);
+                fifos.push_edge(c, b);
+                fifos.push_edge(a, c);
+            }
+        }
+    }
+
+    out.extend_from_slice(&CODE_AUX_TABLE);
+    out
+}
+
+/// Encodes arbitrary indices as an index sequence stream.
+pub fn encode_index_sequence(indices: &[u32]) -> Vec<u8> {
+    let mut out = vec![SEQUENCE_HEADER | INDEX_VERSION];
+    let mut last = [0u32; 2];
+    let mut current = 0;
+    for index in indices {
+        // Switch baselines when the delta grows too large for one byte.
+        let cd = index.wrapping_sub(last[current]) as i32;
+        if cd.unsigned_abs() >= 30 {
+            current ^= 1;
+        }
+        let d = index.wrapping_sub(last[current]);
+        let v = (d << 1) ^ (((d as i32) >> 31) as u32);
+        encode_vbyte(&mut out, (v << 1) | current as u32);
+        last[current] = *index;
+    }
+    out.extend_from_slice(&[0; 4]);
+    out
+}
+
+/// Quantizes `v` in `[-1, 1]` to a signed integer of `bits` bits.
+fn quantize_snorm(v: f32, bits: u32) -> i32 {
+    let scale = ((1 << (bits - 1)) - 1) as f32;
+    let round = if v >= 0.0 { 0.5 } else { -0.5 };
+    (v.clamp(-1.0, 1.0) * scale + round) as i32
+}
+
+/// Applies the octahedral filter to unit vectors with an optional fourth
+/// component, such as tangent handedness, producing elements of `stride`
+/// bytes.
+///
+/// The octahedral coordinates use `bits` bits, up to the component size.
+///
+/// # Panics
+///
+/// Panics if `stride` is not 4 or 8, or `bits` does not fit the component
+/// size.
+pub fn encode_filter_octahedral(values: &[[f32; 4]], stride: usize, bits: u32) -> Vec<u8> {
+    assert!(stride == 4 || stride == 8);
+    assert!(bits >= 2 && bits as usize <= stride * 2);
+    let mut out = Vec::with_capacity(values.len() * stride);
+    for [x, y, z, w] in values {
+        let l = x.abs() + y.abs() + z.abs();
+        let s = if l == 0.0 { 0.0 } else { 1.0 / l };
+        let (x, y) = (x * s, y * s);
+        let sign = |v: f32| if v >= 0.0 { 1.0 } else { -1.0 };
+        let (u, v) = if *z >= 0.0 {
+            (x, y)
+        } else {
+            ((1.0 - y.abs()) * sign(x), (1.0 - x.abs()) * sign(y))
+        };
+        let encoded = [
+            quantize_snorm(u, bits),
+            quantize_snorm(v, bits),
+            quantize_snorm(1.0, bits),
+            quantize_snorm(*w, stride as u32 * 2),
+        ];
+        for v in encoded {
+            if stride == 4 {
+                out.push(v as i8 as u8);
+            } else {
+                out.extend_from_slice(&(v as i16).to_le_bytes());
+            }
+        }
+    }
+    out
+}
+
+/// Applies the quaternion filter to unit quaternions, producing elements of
+/// 8 bytes.
+///
+/// The three stored components use `bits` bits.
+///
+/// # Panics
+///
+/// Panics if `bits` is not between 4 and 16.
+pub fn encode_filter_quaternion(values: &[[f32; 4]], bits: u32) -> Vec<u8> {
+    assert!((4..=16).contains(&bits));
+    let scale = 2f32.sqrt();
+    let mut out = Vec::with_capacity(values.len() * 8);
+    for q in values {
+        // Drop the largest component; it is recovered from the others.
+        let mut qc = 0;
+        for i in 1..4 {
+            if q[i].abs() > q[qc].abs() {
+                qc = i;
+            }
+        }
+        // q and -q are the same rotation, so the sign can be chosen freely.
+        let sign = if q[qc] < 0.0 { -1.0 } else { 1.0 };
+        let encoded = [
+            quantize_snorm(q[(qc + 1) & 3] * scale * sign, bits),
+            quantize_snorm(q[(qc + 2) & 3] * scale * sign, bits),
+            quantize_snorm(q[(qc + 3) & 3] * scale * sign, bits),
+            (quantize_snorm(1.0, bits) & !3) | qc as i32,
+        ];
+        for v in encoded {
+            out.extend_from_slice(&(v as i16).to_le_bytes());
+        }
+    }
+    out
+}
+
+/// Returns the exponent of `v` as defined by C's `frexp`.
+fn frexp_exponent(v: f32) -> i32 {
+    if v == 0.0 || !v.is_finite() {
+        return 0;
+    }
+    let bits = v.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    if exponent == 0 {
+        // Subnormal: normalize first.
+        frexp_exponent(v * 2f32.powi(64)) - 64
+    } else {
+        exponent - 126
+    }
+}
+
+/// Applies the exponential filter to `values`, grouped into elements of
+/// `stride` bytes that share an exponent.
+///
+/// The mantissas use `bits` bits.
+///
+/// # Panics
+///
+/// Panics if `stride` is not a positive multiple of four dividing the data
+/// size, or `bits` is not between 1 and 24.
+pub fn encode_filter_exponential(values: &[f32], stride: usize, bits: u32) -> Vec<u8> {
+    assert!(stride > 0 && stride.is_multiple_of(4) && (values.len() * 4).is_multiple_of(stride));
+    assert!((1..=24).contains(&bits));
+    let mut out = Vec::with_capacity(values.len() * 4);
+    for element in values.chunks_exact(stride / 4) {
+        // The largest exponent keeps every mantissa within `bits` bits.
+        let exponent = element
+            .iter()
+            .map(|x| frexp_exponent(*x))
+            .fold(-100, i32::max)
+            - (bits as i32 - 1);
+        let scale = 2f64.powi(-exponent);
+        for v in element {
+            let round = if *v >= 0.0 { 0.5 } else { -0.5 };
+            let mantissa = (*v as f64 * scale) as f32 + round;
+            let encoded = (mantissa as i32 as u32 & 0xff_ffff) | ((exponent as u32) << 24);
+            out.extend_from_slice(&encoded.to_le_bytes());
+        }
+    }
+    out
+}
@@ -0,0 +1,259 @@
+//! Buffer views compressed with `EXT_meshopt_compression` store their data
+//! in a different buffer than the one they refer to. The view itself points
+//! into a *fallback* buffer that receives the decoded data, which may have no
+//! data source of its own.
+//!
+//! The importer decodes every compressed view into its fallback buffer, so the
+//! usual reader APIs work on compressed assets without further steps. The
+//! functions of this module can be used to decode streams directly.
+//!
+//! ```
+//! # use gltf_transform_rs as gltf;
+//! # fn run() -> Result<(), gltf::meshopt::Error> {
+//! use gltf::meshopt::{decode, Filter, Mode};
+//!
+//! // Three 4-byte vertices, all zero.
+//! let mut stream = vec![0xa0, 0, 0, 0, 0];
+//! stream.resize(1 + 4 + 32, 0);
+//! let vertices = decode(Mode::Attributes, Filter::None, 3, 4, &stream)?;
+//! assert_eq!(vertices, vec![0; 12]);
+//! # Ok(())
+//! # }
+//! # fn main() {
+//! #    let _ = run().expect("runtime error");
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::buffer;
+
+mod decode;
+mod encode;
+
+pub use self::decode::{
+    decode_filter, decode_index_buffer, decode_index_sequence, decode_vertex_buffer,
+};
+pub use self::encode::{
+    encode_filter_exponential, encode_filter_octahedral, encode_filter_quaternion,
+    encode_index_buffer, encode_index_sequence, encode_vertex_buffer,
+};
+
+/// The name of the extension.
+pub const EXTENSION: &str = "EXT_meshopt_compression";
+
+/// Error decoding a compressed stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The stream has an unknown header byte or version.
+    Header(u8),
+
+    /// The element count, byte stride, or extension object is invalid.
+    Invalid,
+
+    /// The stream ended before all elements were decoded.
+    Truncated,
+
+    /// The stream has data left over after all elements were decoded.
+    Trailing,
+
+    /// Unknown compression mode.
+    Mode(String),
+
+    /// Unknown filter.
+    Filter(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Header(header) => write!(f, "unsupported stream header 0x{:02x}", header),
+            Error::Invalid => write!(f, "invalid compression parameters"),
+            Error::Truncated => write!(f, "compressed stream is truncated"),
+            Error::Trailing => write!(f, "unexpected data after compressed stream"),
+            Error::Mode(mode) => write!(f, "unknown compression mode {:?}", mode),
+            Error::Filter(filter) => write!(f, "unknown compression filter {:?}", filter),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The compression mode of a buffer view.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Mode {
+    /// Vertex attributes, or other data with a byte stride that is a multiple
+    /// of four.
+    Attributes,
+
+    /// Triangle list indices.
+    Triangles,
+
+    /// Any other indices.
+    Indices,
+}
+
+impl Mode {
+    /// Returns the mode with the given JSON name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ATTRIBUTES" => Some(Mode::Attributes),
+            "TRIANGLES" => Some(Mode::Triangles),
+            "INDICES" => Some(Mode::Indices),
+            _ => None,
+        }
+    }
+
+    /// Returns the JSON name of the mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Attributes => "ATTRIBUTES",
+            Mode::Triangles => "TRIANGLES",
+            Mode::Indices => "INDICES",
+        }
+    }
+}
+
+/// The filter applied to vertex data before compression.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Filter {
+    /// No filter.
+    #[default]
+    None,
+
+    /// Unit vectors stored as octahedral coordinates.
+    Octahedral,
+
+    /// Unit quaternions stored as three components.
+    Quaternion,
+
+    /// Floating point values stored with a shared exponent.
+    Exponential,
+}
+
+impl Filter {
+    /// Returns the filter with the given JSON name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "NONE" => Some(Filter::None),
+            "OCTAHEDRAL" => Some(Filter::Octahedral),
+            "QUATERNION" => Some(Filter::Quaternion),
+            "EXPONENTIAL" => Some(Filter::Exponential),
+            _ => None,
+        }
+    }
+
+    /// Returns the JSON name of the filter.
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::None => "NONE",
+            Filter::Octahedral => "OCTAHEDRAL",
+            Filter::Quaternion => "QUATERNION",
+            Filter::Exponential => "EXPONENTIAL",
+        }
+    }
+}
+
+/// Decodes a stream of `count` elements of `stride` bytes.
+pub fn decode(
+    mode: Mode,
+    filter: Filter,
+    count: usize,
+    stride: usize,
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut decoded = match mode {
+        Mode::Attributes => decode_vertex_buffer(count, stride, data)?,
+        Mode::Triangles => decode_index_buffer(count, stride, data)?,
+        Mode::Indices => decode_index_sequence(count, stride, data)?,
+    };
+    if mode == Mode::Attributes {
+        decode_filter(filter, stride, &mut decoded)?;
+    } else if filter != Filter::None {
+        return Err(Error::Invalid);
+    }
+    Ok(decoded)
+}
+
+/// The `EXT_meshopt_compression` extension object of a buffer view.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Compression {
+    /// The index of the buffer holding the compressed stream.
+    pub buffer: usize,
+
+    /// The offset of the compressed stream in bytes.
+    pub byte_offset: usize,
+
+    /// The length of the compressed stream in bytes.
+    pub byte_length: usize,
+
+    /// The size of one decoded element in bytes.
+    pub byte_stride: usize,
+
+    /// The number of decoded elements.
+    pub count: usize,
+
+    /// The compression mode.
+    pub mode: Mode,
+
+    /// The filter to reverse after decoding.
+    pub filter: Filter,
+}
+
+impl Compression {
+    /// Returns the compression parameters of a buffer view, or `None` if the
+    /// view is not compressed.
+    pub fn from_view(view: &buffer::View) -> Result<Option<Self>, Error> {
+        view.extension_value(EXTENSION)
+            .map(Self::from_value)
+            .transpose()
+    }
+
+    /// Parses an extension object.
+    fn from_value(value: &json::Value) -> Result<Self, Error> {
+        let integer = |key: &str| -> Result<usize, Error> {
+            value
+                .get(key)
+                .and_then(json::Value::as_u64)
+                .map(|x| x as usize)
+                .ok_or(Error::Invalid)
+        };
+        let name = |key: &str| value.get(key).and_then(json::Value::as_str);
+        let mode = name("mode").ok_or(Error::Invalid)?;
+        let filter = name("filter").unwrap_or("NONE");
+        Ok(Self {
+            buffer: integer("buffer")?,
+            byte_offset: value
+                .get("byteOffset")
+                .map_or(Ok(0), |_| integer("byteOffset"))?,
+            byte_length: integer("byteLength")?,
+            byte_stride: integer("byteStride")?,
+            count: integer("count")?,
+            mode: Mode::from_name(mode).ok_or_else(|| Error::Mode(mode.to_string()))?,
+            filter: Filter::from_name(filter).ok_or_else(|| Error::Filter(filter.to_string()))?,
+        })
+    }
+
+    /// Decodes the compressed stream from the buffer data of a document.
+    pub fn decode(&self, buffers: &[buffer::Data]) -> Result<Vec<u8>, Error> {
+        let end = self
+            .byte_offset
+            .checked_add(self.byte_length)
+            .ok_or(Error::Invalid)?;
+        let data = buffers
+            .get(self.buffer)
+            .and_then(|x| x.get(self.byte_offset..end))
+            .ok_or(Error::Truncated)?;
+        decode(self.mode, self.filter, self.count, self.byte_stride, data)
+    }
+}
+
+/// Returns whether a buffer is a fallback buffer whose data is only provided
+/// by decoding compressed buffer views.
+pub fn is_fallback(buffer: &buffer::Buffer) -> bool {
+    buffer
+        .extension_value(EXTENSION)
+        .and_then(|x| x.get("fallback"))
+        .and_then(json::Value::as_bool)
+        .unwrap_or(false)
+}
@@ -0,0 +1,233 @@
+use crate::math::Matrix4;
+use crate::{Document, Node};
+
+/// A precomputed index of the node hierarchy of a document.
+///
+/// The index records the parent and world matrix of every node, along with
+/// the nodes that break the rule that the node hierarchy is a set of disjoint
+/// trees: nodes with more than one parent, and nodes on a cycle. Child
+/// indices that are out of range are ignored.
+#[derive(Clone, Debug)]
+pub struct Hierarchy<'a> {
+    /// The parent `Document` struct.
+    document: &'a Document,
+
+    /// The first parent of each node.
+    parents: Vec<Option<usize>>,
+
+    /// The nodes that are a child more than once, in ascending order.
+    multiple_parents: Vec<usize>,
+
+    /// Whether each node is on a cycle.
+    cyclic: Vec<bool>,
+
+    /// The world matrix of each node, or `None` for nodes on or below a
+    /// cycle.
+    worlds: Vec<Option<[[f32; 4]; 4]>>,
+}
+
+/// Marks the nodes that are on a cycle, using Tarjan's strongly connected
+/// components algorithm.
+fn find_cycles(children: &[Vec<usize>]) -> Vec<bool> {
+    const UNVISITED: usize = usize::MAX;
+    let count = children.len();
+    let mut index = vec![UNVISITED; count];
+    let mut low = vec![0; count];
+    let mut on_stack = vec![false; count];
+    let mut stack = Vec::new();
+    let mut cyclic = vec![false; count];
+    let mut next = 0;
+    for start in 0..count {
+        if index[start] != UNVISITED {
+            continue;
+        }
+        index[start] = next;
+        low[start] = next;
+        next += 1;
+        stack.push(start);
+        on_stack[start] = true;
+        let mut calls = vec![(start, 0)];
+        while let Some((node, i)) = calls.last().copied() {
+            if let Some(&child) = children[node].get(i) {
+                calls.last_mut().unwrap().1 += 1;
+                if index[child] == UNVISITED {
+                    index[child] = next;
+                    low[child] = next;
+                    next += 1;
+                    stack.push(child);
+                    on_stack[child] = true;
+                    calls.push((child, 0));
+                } else if on_stack[child] {
+                    low[node] = low[node].min(index[child]);
+                }
+                continue;
+            }
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if low[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                if component.len() > 1 || children[node].contains(&node) {
+                    for member in component {
+                        cyclic[member] = true;
+                    }
+                }
+            }
+        }
+    }
+    cyclic
+}
+
+/// Multiplies the local matrices of each node and its ancestors, leaving
+/// nodes on or below a cycle as `None`.
+fn world_matrices(
+    parents: &[Option<usize>],
+    cyclic: &[bool],
+    locals: &[[[f32; 4]; 4]],
+) -> Vec<Option<[[f32; 4]; 4]>> {
+    let mut worlds: Vec<Option<Option<Matrix4>>> = cyclic
+        .iter()
+        .map(|x| if *x { Some(None) } else { None })
+        .collect();
+    for node in 0..parents.len() {
+        let mut chain = Vec::new();
+        let mut current = Some(node);
+        let mut world = Some(Matrix4::identity());
+        while let Some(index) = current {
+            if let Some(known) = worlds[index] {
+                world = known;
+                break;
+            }
+            chain.push(index);
+            current = parents[index];
+        }
+        for index in chain.into_iter().rev() {
+            world = world.map(|x| x * Matrix4::from_array(locals[index]));
+            worlds[index] = Some(world);
+        }
+    }
+    worlds
+        .into_iter()
+        .map(|x| x.flatten().map(|x| x.as_array()))
+        .collect()
+}
+
+impl<'a> Hierarchy<'a> {
+    /// Indexes the node hierarchy of `document`.
+    pub fn new(document: &'a Document) -> Self {
+        let nodes = &document.0.nodes;
+        let count = nodes.len();
+        let children: Vec<Vec<usize>> = nodes
+            .iter()
+            .map(|node| {
+                node.children
+                    .iter()
+                    .flatten()
+                    .map(|x| x.value())
+                    .filter(|x| *x < count)
+                    .collect()
+            })
+            .collect();
+
+        let mut parents = vec![None; count];
+        let mut multiple_parents = Vec::new();
+        for (parent, list) in children.iter().enumerate() {
+            for child in list {
+                match parents[*child] {
+                    None => parents[*child] = Some(parent),
+                    Some(_) => multiple_parents.push(*child),
+                }
+            }
+        }
+        multiple_parents.sort_unstable();
+        multiple_parents.dedup();
+
+        let cyclic = find_cycles(&children);
+        let locals: Vec<_> = document
+            .nodes()
+            .map(|node| node.transform().matrix())
+            .collect();
+        let worlds = world_matrices(&parents, &cyclic, &locals);
+
+        Self {
+            document,
+            parents,
+            multiple_parents,
+            cyclic,
+            worlds,
+        }
+    }
+
+    /// Returns the parent of `node`, or `None` for root nodes.
+    ///
+    /// For nodes with more than one parent, this is the parent with the
+    /// lowest index.
+    pub fn parent(&self, node: &Node) -> Option<Node<'a>> {
+        let parent = (*self.parents.get(node.index())?)?;
+        self.document.nodes().nth(parent)
+    }
+
+    /// Returns the matrix that transforms `node` into world space, the
+    /// product of the local transforms of the node and its ancestors.
+    ///
+    /// Returns `None` if the node or one of its ancestors is on a cycle.
+    pub fn world_matrix(&self, node: &Node) -> Option<[[f32; 4]; 4]> {
+        *self.worlds.get(node.index())?
+    }
+
+    /// Returns the world matrix of every node, by index, computed from the
+    /// given local matrices instead of the node transforms.
+    pub(crate) fn world_matrices(&self, locals: &[[[f32; 4]; 4]]) -> Vec<Option<[[f32; 4]; 4]>> {
+        world_matrices(&self.parents, &self.cyclic, locals)
+    }
+
+    /// Returns the nodes from the root of the tree that contains `node` down
+    /// to `node` itself.
+    ///
+    /// Returns `None` if the node or one of its ancestors is on a cycle.
+    pub fn path_from_root(&self, node: &Node) -> Option<Vec<Node<'a>>> {
+        self.world_matrix(node)?;
+        let mut path = Vec::new();
+        let mut current = Some(node.index());
+        while let Some(index) = current {
+            path.push(self.document.nodes().nth(index)?);
+            current = self.parents[index];
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Returns `true` if `node` is its own descendant.
+    pub fn is_cyclic(&self, node: &Node) -> bool {
+        self.cyclic.get(node.index()).copied().unwrap_or(false)
+    }
+
+    /// Returns an `Iterator` that visits the nodes that are on a cycle.
+    pub fn cyclic_nodes(&self) -> impl Iterator<Item = Node<'a>> + '_ {
+        self.document
+            .nodes()
+            .filter(move |node| self.cyclic[node.index()])
+    }
+
+    /// Returns an `Iterator` that visits the nodes that appear more than once
+    /// among the children of nodes.
+    pub fn multiple_parents(&self) -> impl Iterator<Item = Node<'a>> + '_ {
+        self.multiple_parents
+            .iter()
+            .filter_map(move |x| self.document.nodes().nth(*x))
+    }
+
+    /// Returns `true` if the node hierarchy is a set of disjoint trees, with
+    /// no cycles and no node that has more than one parent.
+    pub fn is_valid(&self) -> bool {
+        self.multiple_parents.is_empty() && !self.cyclic.contains(&true)
+    }
+}
@@ -1,9 +1,26 @@
 use crate::math::*;
-use crate::{Camera, Document, Mesh, Skin};
+use crate::{Accessor, Camera, Document, Mesh, Skin};
+
+#[cfg(feature = "utils")]
+use crate::Buffer;
 
 /// Iterators.
 pub mod iter;
 
+/// Parent lookup and world transforms.
+mod hierarchy;
+
+/// Utility functions.
+#[cfg(feature = "utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+pub mod util;
+
+#[doc(inline)]
+pub use self::hierarchy::Hierarchy;
+#[cfg(feature = "utils")]
+#[doc(inline)]
+pub use self::util::Reader;
+
 /// The transform for a `Node`.
 #[derive(Clone, Debug)]
 pub enum Transform {
@@ -97,11 +114,34 @@ pub struct Node<'a> {
     json: &'a json::scene::Node,
 }
 
+/// A level of detail of a node, as defined by the `MSFT_lod` extension.
+#[derive(Clone, Debug)]
+pub struct Lod<'a> {
+    /// The node to render at this level of detail.
+    node: Node<'a>,
+
+    /// The minimum screen coverage at which this level is used.
+    screen_coverage: Option<f32>,
+}
+
+/// The per-instance attributes of a node, as defined by the
+/// `EXT_mesh_gpu_instancing` extension.
+///
+/// The node's mesh is drawn once per instance, with the instance transform
+/// applied before the node's own transform.
+#[derive(Clone, Debug)]
+pub struct Instancing<'a> {
+    /// The node the instances belong to.
+    node: Node<'a>,
+
+    /// The `attributes` object of the extension.
+    attributes: &'a json::Value,
+}
+
 /// The root nodes of a scene.
 #[derive(Clone, Debug)]
 pub struct Scene<'a> {
     /// The parent `Document` struct.
-    #[allow(dead_code)]
     document: &'a Document,
 
     /// The corresponding JSON index.
@@ -147,6 +187,68 @@ impl<'a> Node<'a> {
         &self.json.extras
     }
 
+    /// Returns the raw JSON of the extension called `name`, if present.
+    ///
+    /// See [`Buffer::extension_value`](../buffer/struct.Buffer.html#method.extension_value).
+    pub fn extension_value(&self, name: &str) -> Option<&'a json::Value> {
+        self.document.1.get("nodes", self.index, name)
+    }
+
+    /// Returns the levels of detail of this node, as defined by the
+    /// `MSFT_lod` extension, from highest to lowest detail.
+    ///
+    /// The first level is this node itself. Screen coverage hints are read
+    /// from the `MSFT_screencoverage` entry of the node's extras, one per
+    /// level. LOD node indices that are out of range are skipped. Returns an
+    /// empty `Vec` when the node does not use the extension.
+    pub fn lods(&self) -> Vec<Lod<'a>> {
+        let ids = match self
+            .extension_value("MSFT_lod")
+            .and_then(|x| x.get("ids"))
+            .and_then(|x| x.as_array())
+        {
+            Some(ids) => ids,
+            None => return Vec::new(),
+        };
+        let coverage = self
+            .document
+            .1
+            .extra("nodes", self.index, "MSFT_screencoverage")
+            .and_then(|x| x.as_array());
+        // Levels are numbered before invalid ids are skipped so that the
+        // coverage hints of the remaining levels stay aligned.
+        let nodes = ids.iter().map(|x| {
+            x.as_u64()
+                .and_then(|x| self.document.nodes().nth(x as usize))
+        });
+        std::iter::once(Some(self.clone()))
+            .chain(nodes)
+            .enumerate()
+            .filter_map(|(level, node)| Some((level, node?)))
+            .map(|(level, node)| Lod {
+                node,
+                screen_coverage: coverage
+                    .and_then(|x| x.get(level))
+                    .and_then(|x| x.as_f64())
+                    .map(|x| x as f32),
+            })
+            .collect()
+    }
+
+    /// Returns the per-instance attributes of this node, as defined by the
+    /// `EXT_mesh_gpu_instancing` extension, or `None` when the node does not
+    /// use the extension.
+    pub fn instancing(&self) -> Option<Instancing<'a>> {
+        let attributes = self
+            .extension_value("EXT_mesh_gpu_instancing")?
+            .get("attributes")
+            .filter(|x| x.is_object())?;
+        Some(Instancing {
+            node: self.clone(),
+            attributes,
+        })
+    }
+
     /// Returns the light at this node as defined by the `KHR_lights_punctual` extension.
     #[cfg(feature = "KHR_lights_punctual")]
     #[cfg_attr(docsrs, doc(cfg(feature = "KHR_lights_punctual")))]
@@ -211,6 +313,88 @@ impl<'a> Node<'a> {
     }
 }
 
+impl<'a> Lod<'a> {
+    /// Returns the node to render at this level of detail.
+    pub fn node(&self) -> &Node<'a> {
+        &self.node
+    }
+
+    /// Returns the minimum fraction of the screen the node should cover for
+    /// this level of detail to be used, if specified.
+    pub fn screen_coverage(&self) -> Option<f32> {
+        self.screen_coverage
+    }
+}
+
+impl<'a> Instancing<'a> {
+    /// Returns the node the instances belong to.
+    pub fn node(&self) -> &Node<'a> {
+        &self.node
+    }
+
+    /// Returns the accessor of the attribute called `name`, such as
+    /// `"TRANSLATION"` or a custom attribute starting with an underscore.
+    /// Accessor indices that are out of range are treated as missing.
+    pub fn get(&self, name: &str) -> Option<Accessor<'a>> {
+        let index = self.attributes.get(name)?.as_u64()?;
+        self.node.document.accessors().nth(index as usize)
+    }
+
+    /// Returns every attribute with its accessor, in the order they appear in
+    /// the JSON.
+    pub fn attributes(&self) -> Vec<(&'a str, Accessor<'a>)> {
+        let attributes = match self.attributes.as_object() {
+            Some(attributes) => attributes,
+            None => return Vec::new(),
+        };
+        attributes
+            .iter()
+            .filter_map(|(name, index)| {
+                let index = index.as_u64()?;
+                let accessor = self.node.document.accessors().nth(index as usize)?;
+                Some((name.as_str(), accessor))
+            })
+            .collect()
+    }
+
+    /// Returns the accessor containing the `[x, y, z]` instance translations.
+    pub fn translation(&self) -> Option<Accessor<'a>> {
+        self.get("TRANSLATION")
+    }
+
+    /// Returns the accessor containing the `[x, y, z, w]` instance rotation
+    /// quaternions.
+    pub fn rotation(&self) -> Option<Accessor<'a>> {
+        self.get("ROTATION")
+    }
+
+    /// Returns the accessor containing the `[x, y, z]` instance scales.
+    pub fn scale(&self) -> Option<Accessor<'a>> {
+        self.get("SCALE")
+    }
+
+    /// Returns the number of instances, which is the count of the first
+    /// attribute's accessor. All attributes must have the same count.
+    pub fn count(&self) -> usize {
+        self.attributes()
+            .first()
+            .map_or(0, |(_, accessor)| accessor.count())
+    }
+
+    /// Constructs an instancing reader.
+    #[cfg(feature = "utils")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "utils")))]
+    pub fn reader<'s, F>(&'a self, get_buffer_data: F) -> Reader<'a, 's, F>
+    where
+        F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+    {
+        Reader {
+            instancing: self.clone(),
+            get_buffer_data,
+        }
+    }
+}
+
 impl<'a> Scene<'a> {
     /// Constructs a `Scene`.
     pub(crate) fn new(document: &'a Document, index: usize, json: &'a json::scene::Scene) -> Self {
@@ -237,6 +421,11 @@ impl<'a> Scene<'a> {
         self.json.name.as_deref()
     }
 
+    /// Returns the parent `Document` struct.
+    pub(crate) fn document(&self) -> &'a Document {
+        self.document
+    }
+
     /// Returns an `Iterator` that visits each root node of the scene.
     pub fn nodes(&self) -> iter::Nodes<'a> {
         iter::Nodes {
@@ -0,0 +1,219 @@
+use crate::accessor;
+
+use crate::animation::util::{rotations, Rotations};
+use crate::scene::{Instancing, Transform};
+use crate::{Buffer, Normalize};
+
+/// Instance translations of type `[f32; 3]`.
+pub type ReadTranslations<'a> = CastingIter<'a>;
+
+/// Instance scales of type `[f32; 3]`.
+pub type ReadScales<'a> = CastingIter<'a>;
+
+/// XYZ instance attributes of any component type allowed by
+/// `KHR_mesh_quantization`.
+#[derive(Clone, Debug)]
+pub enum Vectors<'a> {
+    /// Vectors of type `[i8; 3]`.
+    I8(accessor::Iter<'a, [i8; 3]>),
+    /// Vectors of type `[u8; 3]`.
+    U8(accessor::Iter<'a, [u8; 3]>),
+    /// Vectors of type `[i16; 3]`.
+    I16(accessor::Iter<'a, [i16; 3]>),
+    /// Vectors of type `[u16; 3]`.
+    U16(accessor::Iter<'a, [u16; 3]>),
+    /// Vectors of type `[f32; 3]`.
+    F32(accessor::Iter<'a, [f32; 3]>),
+}
+
+/// Casting iterator that visits `Vectors` as `[f32; 3]`.
+///
+/// Integer components are normalized when the accessor is normalized, and
+/// converted as is otherwise.
+#[derive(Clone, Debug)]
+pub struct CastingIter<'a> {
+    vectors: Vectors<'a>,
+    normalized: bool,
+}
+
+impl<'a> CastingIter<'a> {
+    /// Unwrap underlying `Vectors` object.
+    pub fn unwrap(self) -> Vectors<'a> {
+        self.vectors
+    }
+
+    fn cast<T>(x: [T; 3], normalized: bool) -> [f32; 3]
+    where
+        T: Normalize<f32> + Into<f64> + Copy,
+    {
+        if normalized {
+            x.normalize()
+        } else {
+            x.map(|x| x.into() as f32)
+        }
+    }
+}
+
+impl<'a> ExactSizeIterator for CastingIter<'a> {}
+impl<'a> Iterator for CastingIter<'a> {
+    type Item = [f32; 3];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nth(0)
+    }
+
+    #[inline]
+    fn nth(&mut self, x: usize) -> Option<Self::Item> {
+        let normalized = self.normalized;
+        match self.vectors {
+            Vectors::I8(ref mut i) => i.nth(x).map(|x| Self::cast(x, normalized)),
+            Vectors::U8(ref mut i) => i.nth(x).map(|x| Self::cast(x, normalized)),
+            Vectors::I16(ref mut i) => i.nth(x).map(|x| Self::cast(x, normalized)),
+            Vectors::U16(ref mut i) => i.nth(x).map(|x| Self::cast(x, normalized)),
+            Vectors::F32(ref mut i) => i.nth(x),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.vectors {
+            Vectors::I8(ref i) => i.size_hint(),
+            Vectors::U8(ref i) => i.size_hint(),
+            Vectors::I16(ref i) => i.size_hint(),
+            Vectors::U16(ref i) => i.size_hint(),
+            Vectors::F32(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// Instancing reader.
+#[derive(Clone, Debug)]
+pub struct Reader<'a, 's, F>
+where
+    F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+{
+    pub(crate) instancing: Instancing<'a>,
+    pub(crate) get_buffer_data: F,
+}
+
+/// An `Iterator` that visits the transform of every instance.
+#[derive(Clone, Debug)]
+pub struct ReadTransforms<'a> {
+    translations: Option<ReadTranslations<'a>>,
+    rotations: Option<rotations::CastingIter<'a, rotations::F32>>,
+    scales: Option<ReadScales<'a>>,
+    remaining: usize,
+}
+
+impl<'a, 's, F> Reader<'a, 's, F>
+where
+    F: Clone + Fn(Buffer<'a>) -> Option<&'s [u8]>,
+{
+    /// Reads the `[x, y, z]` elements of `accessor`, whatever their
+    /// component type.
+    fn read_vectors(&self, accessor: crate::Accessor<'a>) -> Option<CastingIter<'s>> {
+        use accessor::{DataType, Dimensions, Iter};
+        if accessor.dimensions() != Dimensions::Vec3 {
+            return None;
+        }
+        let normalized = accessor.normalized();
+        let get = self.get_buffer_data.clone();
+        let vectors = match accessor.data_type() {
+            DataType::I8 => Iter::new(accessor, get).map(Vectors::I8),
+            DataType::U8 => Iter::new(accessor, get).map(Vectors::U8),
+            DataType::I16 => Iter::new(accessor, get).map(Vectors::I16),
+            DataType::U16 => Iter::new(accessor, get).map(Vectors::U16),
+            DataType::F32 => Iter::new(accessor, get).map(Vectors::F32),
+            _ => None,
+        }?;
+        Some(CastingIter {
+            vectors,
+            normalized,
+        })
+    }
+
+    /// Visits the instance translations.
+    pub fn read_translations(&self) -> Option<ReadTranslations<'s>> {
+        self.read_vectors(self.instancing.translation()?)
+    }
+
+    /// Visits the instance rotations.
+    pub fn read_rotations(&self) -> Option<Rotations<'s>> {
+        use accessor::{DataType, Dimensions, Iter};
+        let accessor = self.instancing.rotation()?;
+        if accessor.dimensions() != Dimensions::Vec4 {
+            return None;
+        }
+        match accessor.data_type() {
+            DataType::I8 => Iter::new(accessor, self.get_buffer_data.clone()).map(Rotations::I8),
+            DataType::I16 => Iter::new(accessor, self.get_buffer_data.clone()).map(Rotations::I16),
+            DataType::F32 => Iter::new(accessor, self.get_buffer_data.clone()).map(Rotations::F32),
+            _ => None,
+        }
+    }
+
+    /// Visits the instance scales.
+    pub fn read_scales(&self) -> Option<ReadScales<'s>> {
+        self.read_vectors(self.instancing.scale()?)
+    }
+
+    /// Visits the transform of every instance, relative to the node.
+    ///
+    /// Missing attributes default to no translation, the identity rotation,
+    /// and a unit scale. Returns `None` if an attribute that is present cannot
+    /// be read.
+    pub fn read_transforms(&self) -> Option<ReadTransforms<'s>> {
+        let instancing = &self.instancing;
+        let translations = match instancing.translation() {
+            Some(_) => Some(self.read_translations()?),
+            None => None,
+        };
+        let rotations = match instancing.rotation() {
+            Some(_) => Some(self.read_rotations()?.into_f32()),
+            None => None,
+        };
+        let scales = match instancing.scale() {
+            Some(_) => Some(self.read_scales()?),
+            None => None,
+        };
+        Some(ReadTransforms {
+            translations,
+            rotations,
+            scales,
+            remaining: instancing.count(),
+        })
+    }
+}
+
+impl<'a> Iterator for ReadTransforms<'a> {
+    type Item = Transform;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let translation = match self.translations.as_mut() {
+            Some(iter) => iter.next()?,
+            None => [0.0; 3],
+        };
+        let rotation = match self.rotations.as_mut() {
+            Some(iter) => iter.next()?,
+            None => [0.0, 0.0, 0.0, 1.0],
+        };
+        let scale = match self.scales.as_mut() {
+            Some(iter) => iter.next()?,
+            None => [1.0; 3],
+        };
+        Some(Transform::Decomposed {
+            translation,
+            rotation,
+            scale,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::graph::{
+    Accessor, Animation, Entity, ExtensionMap, Graph, Handle, Image, Instancing, Material, Mesh,
+    Node, Primitive, Sampler, Skin, Texture, TextureInfo,
+};
+
+/// The number of objects of each kind merged by [`dedup`].
+///
+/// [`dedup`]: fn.dedup.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DedupReport {
+    /// The number of accessors removed.
+    pub accessors: usize,
+
+    /// The number of meshes removed.
+    pub meshes: usize,
+
+    /// The number of materials removed.
+    pub materials: usize,
+
+    /// The number of samplers removed.
+    pub samplers: usize,
+
+    /// The number of textures removed.
+    pub textures: usize,
+
+    /// The number of images removed.
+    pub images: usize,
+}
+
+/// Maps duplicate handles to their canonical copy.
+type Remap<T> = HashMap<Handle<T>, Handle<T>>;
+
+/// Groups objects by `key`, mapping every object but the first of each group
+/// to the first one.
+fn find_duplicates<'a, T, K, F>(graph: &'a Graph, key: F) -> Remap<T>
+where
+    T: Entity + 'a,
+    K: Hash + Eq,
+    F: Fn(&'a T) -> K,
+{
+    let mut canonical = HashMap::new();
+    let mut remap = HashMap::new();
+    for (handle, item) in graph.iter::<T>() {
+        match canonical.entry(key(item)) {
+            Entry::Occupied(entry) => {
+                remap.insert(handle, *entry.get());
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(handle);
+            }
+        }
+    }
+    remap
+}
+
+/// Removes every duplicate object and returns how many were removed.
+fn remove_duplicates<T: Entity>(graph: &mut Graph, remap: &Remap<T>) -> usize {
+    for handle in remap.keys() {
+        graph.remove(*handle);
+    }
+    remap.len()
+}
+
+/// Replaces `handle` with its canonical copy.
+fn apply<T>(remap: &Remap<T>, handle: &mut Handle<T>) {
+    if let Some(canonical) = remap.get(handle) {
+        *handle = *canonical;
+    }
+}
+
+fn bits<const N: usize>(values: [f32; N]) -> [u32; N] {
+    values.map(f32::to_bits)
+}
+
+/// Returns `extensions` as a JSON string, which orders its keys.
+fn extensions_key(extensions: &ExtensionMap) -> String {
+    json::serialize::to_string(extensions).unwrap_or_default()
+}
+
+fn texture_info_key(info: &Option<TextureInfo>) -> Option<(Handle<Texture>, u32, String)> {
+    info.as_ref()
+        .map(|x| (x.texture, x.tex_coord, extensions_key(&x.extensions)))
+}
+
+fn accessor_key(accessor: &Accessor) -> impl Hash + Eq {
+    let mut bytes = Vec::with_capacity(accessor.values.len() * accessor.data_type().size());
+    for i in 0..accessor.values.len() {
+        accessor.values.write_component(i, &mut bytes);
+    }
+    (
+        accessor.buffer,
+        accessor.dimensions as u32,
+        accessor.normalized,
+        accessor.data_type() as u32,
+        bytes,
+    )
+}
+
+fn material_key(material: &Material) -> impl Hash + Eq {
+    (
+        (
+            bits(material.base_color_factor),
+            material.metallic_factor.to_bits(),
+            material.roughness_factor.to_bits(),
+            material.normal_scale.to_bits(),
+            material.occlusion_strength.to_bits(),
+            bits(material.emissive_factor),
+        ),
+        material.textures().map(texture_info_key),
+        material.alpha_mode as u32,
+        material.alpha_cutoff.map(f32::to_bits),
+        material.double_sided,
+        extensions_key(&material.extensions),
+    )
+}
+
+fn primitive_key(primitive: &Primitive) -> impl Hash + Eq + '_ {
+    (
+        primitive.mode.as_gl_enum(),
+        &primitive.attributes,
+        primitive.indices,
+        primitive.material,
+        primitive
+            .targets
+            .iter()
+            .map(|target| target.accessors())
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Merges objects with identical contents and rewrites every reference to
+/// point to a single canonical copy.
+///
+/// Images are compared by their encoded bytes (or URI when they have no
+/// data), accessors by their component data, and materials, samplers,
+/// textures and meshes by their properties, including their extension
+/// objects. Names and extras are ignored; the canonical copy is the first one
+/// in the graph and keeps its own.
+///
+/// Removed objects leave holes in the graph that are skipped when it is
+/// written.
+pub fn dedup(graph: &mut Graph) -> DedupReport {
+    let mut report = DedupReport::default();
+
+    let images = find_duplicates(graph, |image: &Image| {
+        (
+            &image.data,
+            &image.mime_type,
+            &image.uri,
+            extensions_key(&image.extensions),
+        )
+    });
+    let samplers = find_duplicates(graph, |sampler: &Sampler| {
+        (
+            sampler.mag_filter.map(|x| x.as_gl_enum()),
+            sampler.min_filter.map(|x| x.as_gl_enum()),
+            sampler.wrap_s.as_gl_enum(),
+            sampler.wrap_t.as_gl_enum(),
+            extensions_key(&sampler.extensions),
+        )
+    });
+    report.images = remove_duplicates(graph, &images);
+    report.samplers = remove_duplicates(graph, &samplers);
+    for (_, texture) in graph.iter_mut::<Texture>() {
+        apply(&images, &mut texture.source);
+        texture.map_extension_images(|mut x| {
+            apply(&images, &mut x);
+            Some(x)
+        });
+        if let Some(sampler) = texture.sampler.as_mut() {
+            apply(&samplers, sampler);
+        }
+    }
+
+    let textures = find_duplicates(graph, |texture: &Texture| {
+        (
+            texture.source,
+            texture.sampler,
+            extensions_key(&texture.extensions),
+        )
+    });
+    report.textures = remove_duplicates(graph, &textures);
+    for (_, material) in graph.iter_mut::<Material>() {
+        for info in material
+            .textures_mut()
+            .iter_mut()
+            .filter_map(|x| x.as_mut())
+        {
+            apply(&textures, &mut info.texture);
+        }
+        material.map_extension_textures(|mut x| {
+            apply(&textures, &mut x);
+            Some(x)
+        });
+    }
+
+    let materials = find_duplicates(graph, material_key);
+    let accessors = find_duplicates(graph, accessor_key);
+    report.materials = remove_duplicates(graph, &materials);
+    report.accessors = remove_duplicates(graph, &accessors);
+    for (_, mesh) in graph.iter_mut::<Mesh>() {
+        for primitive in &mut mesh.primitives {
+            for accessor in primitive.accessors_mut() {
+                apply(&accessors, accessor);
+            }
+            if let Some(material) = primitive.material.as_mut() {
+                apply(&materials, material);
+            }
+        }
+    }
+    for (_, skin) in graph.iter_mut::<Skin>() {
+        if let Some(matrices) = skin.inverse_bind_matrices.as_mut() {
+            apply(&accessors, matrices);
+        }
+    }
+    for (_, node) in graph.iter_mut::<Node>() {
+        for accessor in node
+            .instancing
+            .iter_mut()
+            .flat_map(Instancing::accessors_mut)
+        {
+            apply(&accessors, accessor);
+        }
+    }
+    for (_, animation) in graph.iter_mut::<Animation>() {
+        for channel in &mut animation.channels {
+            apply(&accessors, &mut channel.input);
+            apply(&accessors, &mut channel.output);
+        }
+    }
+
+    let meshes = find_duplicates(graph, |mesh: &Mesh| {
+        (
+            mesh.primitives
+                .iter()
+                .map(primitive_key)
+                .collect::<Vec<_>>(),
+            mesh.weights
+                .as_ref()
+                .map(|x| x.iter().map(|w| w.to_bits()).collect::<Vec<_>>()),
+        )
+    });
+    report.meshes = remove_duplicates(graph, &meshes);
+    for (_, node) in graph.iter_mut::<Node>() {
+        if let Some(mesh) = node.mesh.as_mut() {
+            apply(&meshes, mesh);
+        }
+    }
+
+    report
+}
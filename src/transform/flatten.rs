@@ -0,0 +1,403 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::animation::Property;
+use crate::graph::{Accessor, Animation, Graph, Handle, Mesh, Node, Primitive, Scene, Skin};
+use crate::math::Matrix4;
+use crate::mesh::Mode;
+use crate::scene::Transform;
+use crate::Semantic;
+
+use super::{cross, dot, normalize, remove_unused_accessors, vertex_count};
+
+/// How the elements of an accessor are transformed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Kind {
+    /// Points, transformed by the full matrix.
+    Position,
+
+    /// Unit normals, transformed by the inverse transpose and renormalized.
+    Normal,
+
+    /// Unit tangents with their handedness in `w`.
+    Tangent,
+
+    /// Position or tangent displacements of morph targets, transformed by the
+    /// linear part of the matrix only.
+    Displacement,
+
+    /// Normal displacements of morph targets, transformed by the inverse
+    /// transpose without renormalizing.
+    NormalDisplacement,
+}
+
+/// The world matrix of a node and whether the node is static, i.e. may be
+/// moved to the scene root.
+#[derive(Clone, Copy, Debug)]
+struct State {
+    world: Matrix4,
+    is_static: bool,
+}
+
+/// A world matrix prepared for transforming vertex data.
+pub(super) struct Bake {
+    /// The columns of the matrix.
+    columns: [[f64; 4]; 4],
+
+    /// The columns of the inverse transpose of the upper 3x3 part.
+    inverse_transpose: [[f64; 3]; 3],
+
+    /// The determinant of the upper 3x3 part.
+    determinant: f64,
+
+    /// The bits of the matrix, identifying it in caches.
+    key: [u32; 16],
+}
+
+impl Bake {
+    /// Returns `None` if the matrix is singular, so that normals cannot be
+    /// transformed.
+    pub(super) fn new(matrix: [[f32; 4]; 4]) -> Option<Self> {
+        let columns = matrix.map(|column| column.map(f64::from));
+        let [a0, a1, a2] = [0, 1, 2].map(|i| [columns[i][0], columns[i][1], columns[i][2]]);
+        let determinant = dot(a0, cross(a1, a2));
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let inverse_transpose =
+            [cross(a1, a2), cross(a2, a0), cross(a0, a1)].map(|x| x.map(|x| x / determinant));
+        let mut key = [0; 16];
+        for (i, x) in matrix.iter().flatten().enumerate() {
+            key[i] = x.to_bits();
+        }
+        Some(Self {
+            columns,
+            inverse_transpose,
+            determinant,
+            key,
+        })
+    }
+
+    fn linear(&self, v: [f64; 3]) -> [f64; 3] {
+        let c = &self.columns;
+        [0, 1, 2].map(|i| c[0][i] * v[0] + c[1][i] * v[1] + c[2][i] * v[2])
+    }
+
+    fn point(&self, v: [f64; 3]) -> [f64; 3] {
+        let linear = self.linear(v);
+        [0, 1, 2].map(|i| linear[i] + self.columns[3][i])
+    }
+
+    fn normal(&self, v: [f64; 3]) -> [f64; 3] {
+        let c = &self.inverse_transpose;
+        [0, 1, 2].map(|i| c[0][i] * v[0] + c[1][i] * v[1] + c[2][i] * v[2])
+    }
+}
+
+/// Caches of the meshes and accessors created for each world matrix.
+#[derive(Default)]
+pub(super) struct Baked {
+    meshes: HashMap<(Handle<Mesh>, [u32; 16]), Handle<Mesh>>,
+    accessors: HashMap<(Handle<Accessor>, Kind, [u32; 16]), Handle<Accessor>>,
+}
+
+/// Returns the local transform matrix of a node.
+fn local_matrix(node: &Node) -> Matrix4 {
+    Matrix4::from_array(node.transform.clone().matrix())
+}
+
+/// Returns the identity transform.
+fn identity() -> Transform {
+    Transform::Decomposed {
+        translation: [0.0, 0.0, 0.0],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+        scale: [1.0, 1.0, 1.0],
+    }
+}
+
+/// Records the world matrix of `handle` and its descendants, returning
+/// whether the node is static.
+///
+/// Nodes are static unless they or an ancestor are animated, they are
+/// skinned, joints or skeleton roots, or one of their descendants or levels
+/// of detail is not static. Levels of detail with children are not static.
+fn classify(
+    graph: &Graph,
+    handle: Handle<Node>,
+    parent: Matrix4,
+    animated: bool,
+    (targets, pinned): (&HashSet<Handle<Node>>, &HashSet<Handle<Node>>),
+    states: &mut HashMap<Handle<Node>, State>,
+) -> bool {
+    if let Some(state) = states.get(&handle) {
+        return state.is_static;
+    }
+    let node = match graph.get(handle) {
+        Some(node) => node,
+        None => return true,
+    };
+    let world = parent * local_matrix(node);
+    let animated = animated || targets.contains(&handle);
+    // Marks the node while its descendants are visited, which guards against
+    // cycles.
+    let mut state = State {
+        world,
+        is_static: false,
+    };
+    states.insert(handle, state);
+    let mut is_static = !animated && !pinned.contains(&handle);
+    for child in &node.children {
+        is_static &= classify(graph, *child, world, animated, (targets, pinned), states);
+    }
+    for lod in &node.lods {
+        // Levels of detail are not part of the hierarchy, so their children
+        // cannot be moved to the scene root.
+        let leaf = graph.get(*lod).is_none_or(|x| x.children.is_empty());
+        is_static &= classify(graph, *lod, parent, animated, (targets, pinned), states) && leaf;
+    }
+    state.is_static = is_static;
+    states.insert(handle, state);
+    is_static
+}
+
+/// Appends the new root nodes of a scene in depth-first order: the existing
+/// roots that are not static, and every static node in the hierarchy.
+fn collect_roots(
+    graph: &Graph,
+    handle: Handle<Node>,
+    is_root: bool,
+    states: &HashMap<Handle<Node>, State>,
+    roots: &mut Vec<Handle<Node>>,
+) {
+    if roots.contains(&handle) {
+        return;
+    }
+    let node = match (graph.get(handle), states.get(&handle)) {
+        (Some(node), Some(state)) => {
+            if state.is_static || is_root {
+                roots.push(handle);
+            }
+            node
+        }
+        _ => return,
+    };
+    for child in &node.children {
+        collect_roots(graph, *child, false, states, roots);
+    }
+}
+
+/// Returns a copy of the accessor with its elements transformed.
+fn bake_accessor(
+    graph: &mut Graph,
+    baked: &mut Baked,
+    handle: Handle<Accessor>,
+    kind: Kind,
+    bake: &Bake,
+) -> Handle<Accessor> {
+    if let Some(result) = baked.accessors.get(&(handle, kind, bake.key)) {
+        return *result;
+    }
+    let accessor = match graph.get(handle) {
+        Some(accessor) => accessor,
+        None => return handle,
+    };
+    let narrow = |x: [f64; 3]| x.map(|x| x as f32);
+    let widen = |x: &[f32]| [x[0], x[1], x[2]].map(f64::from);
+    let transformed = if kind == Kind::Tangent {
+        // Mirroring flips the handedness of the tangent basis.
+        let sign = bake.determinant.signum() as f32;
+        let tangents: Vec<_> = accessor
+            .to_f32::<4>()
+            .iter()
+            .map(|t| {
+                let [x, y, z] = narrow(normalize(bake.linear(widen(t))).0);
+                [x, y, z, t[3] * sign]
+            })
+            .collect();
+        Accessor::from_f32(&tangents)
+    } else {
+        let elements: Vec<_> = accessor
+            .to_f32::<3>()
+            .iter()
+            .map(|x| {
+                let x = widen(x);
+                narrow(match kind {
+                    Kind::Position => bake.point(x),
+                    Kind::Normal => normalize(bake.normal(x)).0,
+                    Kind::NormalDisplacement => bake.normal(x),
+                    Kind::Tangent | Kind::Displacement => bake.linear(x),
+                })
+            })
+            .collect();
+        Accessor::from_f32(&elements)
+    };
+    let transformed = Accessor {
+        name: accessor.name.clone(),
+        buffer: accessor.buffer,
+        extras: accessor.extras.clone(),
+        ..transformed
+    };
+    let result = graph.add(transformed);
+    baked.accessors.insert((handle, kind, bake.key), result);
+    result
+}
+
+/// Returns a copy of the primitive with its vertex data transformed, and its
+/// triangles flipped if the transform mirrors them.
+pub(super) fn bake_primitive(
+    graph: &mut Graph,
+    baked: &mut Baked,
+    primitive: &Primitive,
+    bake: &Bake,
+) -> Primitive {
+    let mut primitive = primitive.clone();
+    let vertex_count = vertex_count(graph, &primitive);
+    for (semantic, accessor) in &mut primitive.attributes {
+        let kind = match semantic {
+            Semantic::Positions => Kind::Position,
+            Semantic::Normals => Kind::Normal,
+            Semantic::Tangents => Kind::Tangent,
+            _ => continue,
+        };
+        *accessor = bake_accessor(graph, baked, *accessor, kind, bake);
+    }
+    for target in &mut primitive.targets {
+        let accessors = [
+            (&mut target.positions, Kind::Displacement),
+            (&mut target.normals, Kind::NormalDisplacement),
+            (&mut target.tangents, Kind::Displacement),
+        ];
+        for (accessor, kind) in accessors {
+            if let Some(accessor) = accessor {
+                *accessor = bake_accessor(graph, baked, *accessor, kind, bake);
+            }
+        }
+    }
+
+    let triangles = matches!(
+        primitive.mode,
+        Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+    );
+    if let (true, true, Some(vertex_count)) = (bake.determinant < 0.0, triangles, vertex_count) {
+        let indices: Vec<u32> = primitive
+            .triangles(graph)
+            .flat_map(|[a, b, c]| [a, c, b])
+            .collect();
+        if !indices.is_empty() {
+            primitive.mode = Mode::Triangles;
+            primitive.indices = Some(graph.add(Accessor::from_indices(&indices, vertex_count)));
+        }
+    }
+    primitive
+}
+
+/// Returns a copy of the mesh with its vertex data transformed.
+fn bake_mesh(
+    graph: &mut Graph,
+    baked: &mut Baked,
+    handle: Handle<Mesh>,
+    bake: &Bake,
+) -> Handle<Mesh> {
+    if let Some(result) = baked.meshes.get(&(handle, bake.key)) {
+        return *result;
+    }
+    let mut mesh = graph[handle].clone();
+    mesh.primitives = mesh
+        .primitives
+        .iter()
+        .map(|primitive| bake_primitive(graph, baked, primitive, bake))
+        .collect();
+    let result = graph.add(mesh);
+    baked.meshes.insert((handle, bake.key), result);
+    result
+}
+
+/// Collapses the node hierarchy of every scene, baking node transforms into
+/// vertex data.
+///
+/// Static nodes are moved to the root of their scene. Those with a mesh get
+/// an identity transform, with their world matrix applied to the positions,
+/// normals, and tangents of a copy of the mesh, including its morph targets;
+/// triangles are flipped where the matrix mirrors them so that they keep
+/// facing outwards. Other static nodes, and mesh nodes whose world matrix is
+/// singular or that also have a camera or instances, keep their world matrix
+/// as their transform instead.
+///
+/// Nodes are not static, and keep their place in the hierarchy and their
+/// transform, if they or any of their ancestors are targeted by translation,
+/// rotation, or scale animations, if they are skinned, joints, or skeleton
+/// roots, or if any of their descendants or levels of detail is not static.
+/// Meshes and accessors that are no longer referenced afterwards are removed.
+pub fn flatten(graph: &mut Graph) {
+    let targets: HashSet<Handle<Node>> = graph
+        .iter::<Animation>()
+        .flat_map(|(_, animation)| &animation.channels)
+        .filter(|channel| channel.property != Property::MorphTargetWeights)
+        .map(|channel| channel.node)
+        .collect();
+    let mut pinned: HashSet<Handle<Node>> = graph
+        .iter::<Node>()
+        .filter(|(_, node)| node.skin.is_some())
+        .map(|(handle, _)| handle)
+        .collect();
+    for (_, skin) in graph.iter::<Skin>() {
+        pinned.extend(skin.joints.iter().copied());
+        pinned.extend(skin.skeleton);
+    }
+
+    let mut states = HashMap::new();
+    let mut roots = Vec::new();
+    for scene in graph.handles::<Scene>() {
+        for root in &graph[scene].nodes {
+            let marks = (&targets, &pinned);
+            classify(graph, *root, Matrix4::identity(), false, marks, &mut states);
+        }
+        let mut nodes = Vec::new();
+        for root in &graph[scene].nodes {
+            collect_roots(graph, *root, true, &states, &mut nodes);
+        }
+        roots.push((scene, nodes));
+    }
+
+    let mut baked = Baked::default();
+    for (handle, state) in &states {
+        let is_static = |x: &Handle<Node>| states.get(x).is_some_and(|x| x.is_static);
+        if !state.is_static {
+            graph[*handle].children.retain(|x| !is_static(x));
+            continue;
+        }
+        let world = state.world.as_array();
+        let node = &graph[*handle];
+        let mesh = node
+            .mesh
+            .filter(|_| node.camera.is_none() && node.instancing.is_none());
+        graph[*handle].children.clear();
+        if state.world == Matrix4::identity() {
+            graph[*handle].transform = identity();
+            continue;
+        }
+        match (mesh, Bake::new(world)) {
+            (Some(mesh), Some(bake)) => {
+                let mesh = bake_mesh(graph, &mut baked, mesh, &bake);
+                graph[*handle].mesh = Some(mesh);
+                graph[*handle].transform = identity();
+            }
+            _ => graph[*handle].transform = Transform::Matrix { matrix: world },
+        }
+    }
+    for (scene, nodes) in roots {
+        graph[scene].nodes = nodes;
+    }
+
+    // Remove the original meshes that are no longer used.
+    let used: HashSet<Handle<Mesh>> = graph.iter::<Node>().filter_map(|(_, x)| x.mesh).collect();
+    let mut candidates = HashSet::new();
+    for (mesh, _) in baked.meshes.keys() {
+        if !used.contains(mesh) {
+            if let Some(mesh) = graph.remove(*mesh) {
+                candidates.extend(mesh.primitives.iter().flat_map(Primitive::accessors));
+            }
+        }
+    }
+    remove_unused_accessors(graph, candidates);
+    graph.update_extensions();
+}
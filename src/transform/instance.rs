@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+
+use crate::graph::{Accessor, Graph, Handle, Instancing, Mesh, Node};
+use crate::scene::Transform;
+
+use super::join::{pinned_nodes, Siblings};
+
+/// Options for [`instance`].
+///
+/// [`instance`]: fn.instance.html
+#[derive(Clone, Debug)]
+pub struct InstanceOptions {
+    /// The smallest number of sibling nodes sharing a mesh that are replaced
+    /// by an instanced node.
+    pub min_instances: usize,
+}
+
+impl Default for InstanceOptions {
+    fn default() -> Self {
+        Self { min_instances: 2 }
+    }
+}
+
+/// Returns `true` if `node` may be replaced by an instance.
+fn is_instanceable(node: &Node) -> bool {
+    node.mesh.is_some()
+        && node.children.is_empty()
+        && node.camera.is_none()
+        && has_trs(&node.transform)
+}
+
+/// Returns `true` if `transform` has a scale without zero components and,
+/// if it is a matrix, is rebuilt by its decomposition, i.e. has no shear.
+fn has_trs(transform: &Transform) -> bool {
+    const EPSILON: f32 = 1e-5;
+    let (translation, rotation, scale) = transform.clone().decomposed();
+    if scale.iter().any(|x| *x == 0.0 || !x.is_finite()) {
+        return false;
+    }
+    let matrix = match transform {
+        Transform::Matrix { matrix } => matrix,
+        Transform::Decomposed { .. } => return true,
+    };
+    let composed = Transform::Decomposed {
+        translation,
+        rotation,
+        scale,
+    }
+    .matrix();
+    matrix
+        .iter()
+        .flatten()
+        .zip(composed.iter().flatten())
+        .all(|(a, b)| (a - b).abs() <= EPSILON * a.abs().max(1.0))
+}
+
+/// Adds the instance attributes for the transforms of `nodes`, leaving out
+/// attributes that hold only default values.
+fn instance_attributes(graph: &mut Graph, nodes: &[Handle<Node>]) -> Instancing {
+    let mut translations = Vec::with_capacity(nodes.len());
+    let mut rotations = Vec::with_capacity(nodes.len());
+    let mut scales = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let (translation, rotation, scale) = graph[*node].transform.clone().decomposed();
+        translations.push(translation);
+        rotations.push(rotation);
+        scales.push(scale);
+    }
+    let mut instancing = Instancing::default();
+    let rotated = rotations.iter().any(|x| *x != [0.0, 0.0, 0.0, 1.0]);
+    let scaled = scales.iter().any(|x| *x != [1.0; 3]);
+    // At least one attribute is required.
+    if translations.iter().any(|x| *x != [0.0; 3]) || !(rotated || scaled) {
+        let accessor = graph.add(Accessor::from_f32(&translations));
+        instancing.set("TRANSLATION", accessor);
+    }
+    if rotated {
+        let accessor = graph.add(Accessor::from_f32(&rotations));
+        instancing.set("ROTATION", accessor);
+    }
+    if scaled {
+        let accessor = graph.add(Accessor::from_f32(&scales));
+        instancing.set("SCALE", accessor);
+    }
+    instancing
+}
+
+/// Replaces groups of `siblings` sharing a mesh with instanced nodes,
+/// returning the new list of siblings.
+fn instance_siblings(
+    graph: &mut Graph,
+    siblings: &[Handle<Node>],
+    excluded: &HashSet<Handle<Node>>,
+    options: &InstanceOptions,
+) -> Vec<Handle<Node>> {
+    let mut groups: Vec<(Handle<Mesh>, Vec<Handle<Node>>)> = Vec::new();
+    for handle in siblings {
+        let node = &graph[*handle];
+        if excluded.contains(handle) || !is_instanceable(node) {
+            continue;
+        }
+        let mesh = node.mesh.unwrap();
+        match groups.iter_mut().find(|(x, _)| *x == mesh) {
+            Some((_, nodes)) => nodes.push(*handle),
+            None => groups.push((mesh, vec![*handle])),
+        }
+    }
+    groups.retain(|(_, nodes)| nodes.len() >= options.min_instances.max(1));
+    if groups.is_empty() {
+        return siblings.to_vec();
+    }
+
+    // Each group is replaced by a node at the position of its first node.
+    let mut replacements = Vec::new();
+    for (mesh, nodes) in &groups {
+        let instancing = instance_attributes(graph, nodes);
+        let node = graph.add(Node {
+            mesh: Some(*mesh),
+            instancing: Some(instancing),
+            ..Default::default()
+        });
+        replacements.push((nodes[0], node));
+    }
+    let mut result = Vec::new();
+    for handle in siblings {
+        if let Some((_, node)) = replacements.iter().find(|(x, _)| x == handle) {
+            result.push(*node);
+        }
+        if groups.iter().any(|(_, nodes)| nodes.contains(handle)) {
+            graph.remove(*handle);
+        } else {
+            result.push(*handle);
+        }
+    }
+    result
+}
+
+/// Replaces sibling nodes that share a mesh with a single node drawing the
+/// mesh once per instance, as defined by the `EXT_mesh_gpu_instancing`
+/// extension.
+///
+/// Among the root nodes of each scene and the children of each node, nodes
+/// with a mesh but no children or camera are grouped by mesh. Each group of
+/// at least `min_instances` nodes is replaced by one node at the position of
+/// the first, with an identity transform and the decomposed transforms of
+/// the group as its `TRANSLATION`, `ROTATION`, and `SCALE` attributes.
+/// Attributes that hold only default values are left out, except that at
+/// least one is always written. Names and extras of the replaced nodes are
+/// not kept.
+///
+/// Nodes that are skinned, joints, animated, or in more than one list of
+/// siblings, or that have or are levels of detail or have morph target
+/// weights or instances, are left unchanged, as are nodes with a zero scale
+/// or a sheared matrix, which cannot be written as instance attributes. Use [`flatten`] first to
+/// instance nodes from different levels of the hierarchy.
+///
+/// [`flatten`]: fn.flatten.html
+pub fn instance(graph: &mut Graph, options: &InstanceOptions) {
+    let mut excluded = pinned_nodes(graph);
+    let (lists, shared) = Siblings::collect(graph);
+    excluded.extend(shared);
+
+    for list in lists {
+        let siblings = match list.nodes(graph) {
+            Some(siblings) => siblings,
+            None => continue,
+        };
+        let siblings = instance_siblings(graph, &siblings, &excluded, options);
+        list.set(graph, siblings);
+    }
+    graph.update_extensions();
+}
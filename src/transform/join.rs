@@ -0,0 +1,384 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::accessor::{DataType, Dimensions};
+use crate::graph::{
+    Accessor, Animation, Graph, Handle, Material, Mesh, Node, Primitive, Scene, Skin, Values,
+};
+use crate::math::Matrix4;
+use crate::mesh::Mode;
+
+use super::flatten::{bake_primitive, Bake, Baked};
+use super::{remove_unused_accessors, vertex_count};
+
+/// Options for [`join`].
+///
+/// [`join`]: fn.join.html
+#[derive(Clone, Debug)]
+pub struct JoinOptions {
+    /// The largest number of vertices of a joined primitive. Primitives that
+    /// would exceed it start a new joined primitive. The default keeps the
+    /// indices of joined primitives within 16 bits.
+    pub max_vertices: usize,
+}
+
+impl Default for JoinOptions {
+    fn default() -> Self {
+        Self {
+            max_vertices: u16::MAX as usize,
+        }
+    }
+}
+
+/// The properties primitives must share to be joined.
+#[derive(Clone, Debug, PartialEq)]
+struct Key {
+    material: Option<Handle<Material>>,
+    mode: Mode,
+    attributes: Vec<(String, Dimensions, DataType, bool)>,
+}
+
+/// A list of sibling nodes.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum Siblings {
+    /// The root nodes of a scene.
+    Roots(Handle<Scene>),
+
+    /// The children of a node.
+    Children(Handle<Node>),
+}
+
+/// A primitive that may be joined with others.
+struct Part {
+    /// The node and the index of the primitive in its mesh.
+    source: (Handle<Node>, usize),
+
+    /// The primitive with the node transform applied.
+    primitive: Primitive,
+
+    vertex_count: usize,
+}
+
+impl Siblings {
+    /// Returns every list of siblings in the graph, and the nodes that are in
+    /// more than one list.
+    pub(super) fn collect(graph: &Graph) -> (Vec<Siblings>, HashSet<Handle<Node>>) {
+        let mut lists = Vec::new();
+        let mut seen = HashMap::new();
+        for (handle, scene) in graph.iter::<Scene>() {
+            lists.push(Siblings::Roots(handle));
+            for node in &scene.nodes {
+                *seen.entry(*node).or_insert(0) += 1;
+            }
+        }
+        for (handle, node) in graph.iter::<Node>() {
+            lists.push(Siblings::Children(handle));
+            for child in &node.children {
+                *seen.entry(*child).or_insert(0) += 1;
+            }
+        }
+        let shared = seen
+            .into_iter()
+            .filter(|(_, x)| *x > 1)
+            .map(|(x, _)| x)
+            .collect();
+        (lists, shared)
+    }
+
+    /// Returns the nodes of the list, or `None` if its parent was removed.
+    pub(super) fn nodes(self, graph: &Graph) -> Option<Vec<Handle<Node>>> {
+        match self {
+            Siblings::Roots(scene) => graph.get(scene).map(|x| x.nodes.clone()),
+            Siblings::Children(node) => graph.get(node).map(|x| x.children.clone()),
+        }
+    }
+
+    /// Replaces the nodes of the list.
+    pub(super) fn set(self, graph: &mut Graph, nodes: Vec<Handle<Node>>) {
+        match self {
+            Siblings::Roots(scene) => graph[scene].nodes = nodes,
+            Siblings::Children(node) => graph[node].children = nodes,
+        }
+    }
+}
+
+/// Returns the nodes whose mesh must stay on the node: nodes that are
+/// skinned, have morph target weights, levels of detail, or instances, and
+/// their levels of detail, as well as joints, skeleton roots, and animation
+/// targets.
+pub(super) fn pinned_nodes(graph: &Graph) -> HashSet<Handle<Node>> {
+    let mut pinned: HashSet<Handle<Node>> = graph
+        .iter::<Node>()
+        .filter(|(_, node)| {
+            node.skin.is_some()
+                || node.weights.is_some()
+                || !node.lods.is_empty()
+                || node.instancing.is_some()
+        })
+        .flat_map(|(handle, node)| node.lods.iter().copied().chain(Some(handle)))
+        .collect();
+    for (_, skin) in graph.iter::<Skin>() {
+        pinned.extend(skin.joints.iter().copied());
+        pinned.extend(skin.skeleton);
+    }
+    for (_, animation) in graph.iter::<Animation>() {
+        pinned.extend(animation.channels.iter().map(|x| x.node));
+    }
+    pinned
+}
+
+/// Returns the key of a primitive, or `None` if it cannot be joined.
+fn join_key(graph: &Graph, primitive: &Primitive) -> Option<Key> {
+    if !primitive.targets.is_empty() {
+        return None;
+    }
+    match primitive.mode {
+        Mode::Points | Mode::Lines | Mode::Triangles => {}
+        _ => return None,
+    }
+    let mut attributes = primitive
+        .attributes
+        .iter()
+        .map(|(semantic, handle)| {
+            let accessor = graph.get(*handle)?;
+            Some((
+                semantic.to_string(),
+                accessor.dimensions,
+                accessor.data_type(),
+                accessor.normalized,
+            ))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+    Some(Key {
+        material: primitive.material,
+        mode: primitive.mode,
+        attributes,
+    })
+}
+
+/// Concatenates the vertices of `parts` into one primitive, offsetting the
+/// indices of each part by the vertices before it.
+fn join_primitives(graph: &mut Graph, parts: &[&Part]) -> Primitive {
+    let first = &parts[0].primitive;
+    let mut joined = Primitive {
+        mode: first.mode,
+        material: first.material,
+        extras: first.extras.clone(),
+        ..Default::default()
+    };
+    for (semantic, handle) in &first.attributes {
+        let template = &graph[*handle];
+        let mut values = Values::new(template.data_type());
+        for part in parts {
+            let accessor = &graph[part.primitive.get(semantic).unwrap()];
+            values.extend_from(&accessor.values, 0, accessor.values.len());
+        }
+        let accessor = Accessor {
+            buffer: template.buffer,
+            normalized: template.normalized,
+            ..Accessor::new(template.dimensions, values)
+        };
+        joined.set(semantic.clone(), graph.add(accessor));
+    }
+    if parts.iter().any(|x| x.primitive.indices.is_some()) {
+        let mut indices = Vec::new();
+        let mut base = 0;
+        for part in parts {
+            match part.primitive.indices {
+                Some(handle) => {
+                    indices.extend(graph[handle].to_u32().iter().map(|x| x + base));
+                }
+                None => indices.extend(base..base + part.vertex_count as u32),
+            }
+            base += part.vertex_count as u32;
+        }
+        joined.indices = Some(graph.add(Accessor::from_indices(&indices, base as usize)));
+    }
+    joined
+}
+
+/// Splits the parts of a group into runs that stay within the vertex limit.
+fn chunks(parts: &[Part], max_vertices: usize) -> Vec<Vec<&Part>> {
+    let mut chunks: Vec<Vec<&Part>> = Vec::new();
+    let mut count = 0;
+    for part in parts {
+        match chunks.last_mut() {
+            Some(chunk) if count + part.vertex_count <= max_vertices => {
+                chunk.push(part);
+                count += part.vertex_count;
+            }
+            _ => {
+                chunks.push(vec![part]);
+                count = part.vertex_count;
+            }
+        }
+    }
+    chunks
+}
+
+/// Returns the primitives of the joinable nodes among `siblings`, with their
+/// local transforms applied, grouped by key.
+fn collect_parts(
+    graph: &mut Graph,
+    siblings: &[Handle<Node>],
+    excluded: &HashSet<Handle<Node>>,
+    baked: &mut Baked,
+) -> Vec<(Key, Vec<Part>)> {
+    let mut groups: Vec<(Key, Vec<Part>)> = Vec::new();
+    for handle in siblings {
+        if excluded.contains(handle) {
+            continue;
+        }
+        let node = &graph[*handle];
+        let mesh = match node.mesh.and_then(|x| graph.get(x)) {
+            Some(mesh) => mesh.clone(),
+            None => continue,
+        };
+        let matrix = node.transform.clone().matrix();
+        let bake = match Bake::new(matrix) {
+            _ if matrix == Matrix4::identity().as_array() => None,
+            Some(bake) => Some(bake),
+            None => continue,
+        };
+        for (index, primitive) in mesh.primitives.iter().enumerate() {
+            let (key, vertex_count) =
+                match (join_key(graph, primitive), vertex_count(graph, primitive)) {
+                    (Some(key), Some(count)) => (key, count),
+                    _ => continue,
+                };
+            let primitive = match &bake {
+                Some(bake) => bake_primitive(graph, baked, primitive, bake),
+                None => primitive.clone(),
+            };
+            let part = Part {
+                source: (*handle, index),
+                primitive,
+                vertex_count,
+            };
+            match groups.iter_mut().find(|(x, _)| *x == key) {
+                Some((_, parts)) => parts.push(part),
+                None => groups.push((key, vec![part])),
+            }
+        }
+    }
+    groups
+}
+
+/// Joins the primitives of `siblings`, returning the new list of siblings.
+fn join_siblings(
+    graph: &mut Graph,
+    siblings: &[Handle<Node>],
+    excluded: &HashSet<Handle<Node>>,
+    options: &JoinOptions,
+    (touched, candidates): (&mut HashSet<Handle<Mesh>>, &mut HashSet<Handle<Accessor>>),
+) -> Vec<Handle<Node>> {
+    let mut baked = Baked::default();
+    let groups = collect_parts(graph, siblings, excluded, &mut baked);
+
+    let mut joined = Vec::new();
+    let mut sources = HashSet::new();
+    for (_, parts) in &groups {
+        for chunk in chunks(parts, options.max_vertices) {
+            if chunk.len() > 1 {
+                joined.push(join_primitives(graph, &chunk));
+                sources.extend(chunk.iter().map(|x| x.source));
+            }
+        }
+        candidates.extend(parts.iter().flat_map(|x| x.primitive.accessors()));
+    }
+    if joined.is_empty() {
+        return siblings.to_vec();
+    }
+
+    // Remove the joined primitives from their nodes, and nodes left empty.
+    let mut result = Vec::new();
+    let mut position = None;
+    for handle in siblings {
+        let mesh = graph[*handle].mesh.and_then(|x| graph.get(x)).cloned();
+        let mesh = match mesh {
+            Some(mesh) if sources.iter().any(|(x, _)| x == handle) => mesh,
+            _ => {
+                result.push(*handle);
+                continue;
+            }
+        };
+        position.get_or_insert(result.len());
+        let primitives: Vec<_> = mesh
+            .primitives
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !sources.contains(&(*handle, *index)))
+            .map(|(_, primitive)| primitive.clone())
+            .collect();
+        touched.extend(graph[*handle].mesh);
+        let node = &graph[*handle];
+        if !primitives.is_empty() {
+            let mesh = graph.add(Mesh { primitives, ..mesh });
+            graph[*handle].mesh = Some(mesh);
+            result.push(*handle);
+        } else if !node.children.is_empty() || node.camera.is_some() {
+            graph[*handle].mesh = None;
+            result.push(*handle);
+        } else {
+            graph.remove(*handle);
+        }
+    }
+    let mesh = graph.add(Mesh {
+        primitives: joined,
+        ..Default::default()
+    });
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    result.insert(position.unwrap_or(0), node);
+    result
+}
+
+/// Joins compatible primitives of sibling nodes to reduce draw calls.
+///
+/// Among the root nodes of each scene and the children of each node, the
+/// primitives of nodes' meshes that share a material, a `POINTS`, `LINES`,
+/// or `TRIANGLES` mode, and the same attributes with the same types are
+/// concatenated into one primitive, with each node's transform applied to
+/// its vertices. Joined primitives hold at most `max_vertices` vertices;
+/// they are added to a new node at the position of the first node they were
+/// taken from. Nodes left without a mesh, children, or camera are removed.
+///
+/// Primitives with morph targets are not joined, nor are nodes that are
+/// skinned, joints, animated, or in more than one list of siblings, or that
+/// have or are levels of detail or have morph target weights or instances.
+/// Use [`flatten`] first to join nodes from different levels of the
+/// hierarchy, and [`unify_topology`] to join strips and fans. Meshes and
+/// accessors that are no longer referenced afterwards are removed.
+///
+/// [`flatten`]: fn.flatten.html
+/// [`unify_topology`]: fn.unify_topology.html
+pub fn join(graph: &mut Graph, options: &JoinOptions) {
+    let mut excluded = pinned_nodes(graph);
+    let (lists, shared) = Siblings::collect(graph);
+    excluded.extend(shared);
+
+    let mut touched = HashSet::new();
+    let mut candidates = HashSet::new();
+    for list in lists {
+        let siblings = match list.nodes(graph) {
+            Some(siblings) => siblings,
+            None => continue,
+        };
+        let removed = (&mut touched, &mut candidates);
+        let siblings = join_siblings(graph, &siblings, &excluded, options, removed);
+        list.set(graph, siblings);
+    }
+
+    // Remove the original meshes that are no longer used.
+    let used: HashSet<Handle<Mesh>> = graph.iter::<Node>().filter_map(|(_, x)| x.mesh).collect();
+    for mesh in touched {
+        if !used.contains(&mesh) {
+            if let Some(mesh) = graph.remove(mesh) {
+                candidates.extend(mesh.primitives.iter().flat_map(Primitive::accessors));
+            }
+        }
+    }
+    remove_unused_accessors(graph, candidates);
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use crate::animation::util::Keyframes;
+use crate::animation::Interpolation;
+use crate::graph::{Animation, Graph};
+
+use super::resample::{error, mix, simplify};
+use super::{remove_unused_accessors, write_keyframes};
+
+/// The largest number of times an interval between samples is halved.
+const MAX_DEPTH: u32 = 8;
+
+/// Options for [`linearize`].
+///
+/// [`linearize`]: fn.linearize.html
+#[derive(Clone, Debug)]
+pub struct LinearizeOptions {
+    /// The number of samples per second taken from cubic spline channels.
+    pub sample_rate: f32,
+
+    /// The largest error allowed between the linear keyframes and the
+    /// original channel: the largest component difference for translations,
+    /// scales and morph target weights, and the angle in radians for
+    /// rotations.
+    pub tolerance: f32,
+}
+
+impl Default for LinearizeOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 30.0,
+            tolerance: 1e-4,
+        }
+    }
+}
+
+/// Returns the sample times of a cubic spline channel: every keyframe, and
+/// evenly spaced times between keyframes at `rate` samples per second.
+fn sample_times(keyframes: &Keyframes, rate: f32) -> Vec<f32> {
+    let inputs = keyframes.inputs();
+    let mut times = vec![inputs[0]];
+    for pair in inputs.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let steps = ((b - a) * rate).ceil().clamp(1.0, 1e6) as usize;
+        times.extend((1..steps).map(|i| a + (b - a) * i as f32 / steps as f32));
+        times.push(b);
+    }
+    times
+}
+
+/// Adds samples between `a` and `b` until linear interpolation reproduces
+/// the keyframes at the middle of each interval within `tolerance`.
+fn subdivide(
+    keyframes: &Keyframes,
+    a: (f32, &[f32]),
+    b: (f32, &[f32]),
+    tolerance: f32,
+    depth: u32,
+    samples: &mut Vec<(f32, Vec<f32>)>,
+) {
+    let t = 0.5 * (a.0 + b.0);
+    if depth == 0 || t <= a.0 || t >= b.0 {
+        return;
+    }
+    let value = keyframes.sample(t);
+    let rotation = keyframes.is_rotation();
+    if error(&mix(a.1, b.1, 0.5, rotation), &value, rotation) <= tolerance {
+        return;
+    }
+    subdivide(keyframes, a, (t, &value), tolerance, depth - 1, samples);
+    samples.push((t, value.clone()));
+    subdivide(keyframes, (t, &value), b, tolerance, depth - 1, samples);
+}
+
+/// Returns linear keyframes that reproduce cubic spline `keyframes`.
+fn sample_spline(keyframes: &Keyframes, options: &LinearizeOptions) -> Vec<(f32, Vec<f32>)> {
+    let mut samples: Vec<(f32, Vec<f32>)> = Vec::new();
+    for t in sample_times(keyframes, options.sample_rate) {
+        let value = keyframes.sample(t);
+        if let Some((last, previous)) = samples.last().cloned() {
+            let (a, b) = ((last, &previous[..]), (t, &value[..]));
+            subdivide(keyframes, a, b, options.tolerance, MAX_DEPTH, &mut samples);
+        }
+        samples.push((t, value));
+    }
+    samples
+}
+
+/// Returns linear keyframes that reproduce step `keyframes`, holding each
+/// value until just before the next keyframe.
+fn sample_steps(keyframes: &Keyframes) -> Vec<(f32, Vec<f32>)> {
+    let mut samples: Vec<(f32, Vec<f32>)> = Vec::with_capacity(2 * keyframes.len());
+    for (key, t) in keyframes.inputs().iter().enumerate() {
+        if key > 0 {
+            let hold = t.next_down();
+            if samples.last().is_some_and(|(last, _)| hold > *last) {
+                samples.push((hold, keyframes.value(key - 1).to_vec()));
+            }
+        }
+        samples.push((*t, keyframes.value(key).to_vec()));
+    }
+    samples
+}
+
+/// Converts `STEP` and `CUBICSPLINE` animation channels to `LINEAR`.
+///
+/// Cubic splines are sampled at `options.sample_rate`, with extra samples
+/// where linear interpolation between samples strays from the spline by more
+/// than `options.tolerance`. Steps hold each value until just before the next
+/// keyframe. Samples that the remaining ones reproduce within the tolerance
+/// are then removed. Morph target weight channels are sampled for every
+/// target at once.
+///
+/// Outputs keep their component type, and accessors no longer used are
+/// removed. Channels whose accessors cannot be read are left untouched.
+pub fn linearize(graph: &mut Graph, options: &LinearizeOptions) {
+    let mut replaced = HashSet::new();
+    for handle in graph.handles::<Animation>() {
+        let mut channels = std::mem::take(&mut graph[handle].channels);
+        for channel in &mut channels {
+            if channel.interpolation == Interpolation::Linear {
+                continue;
+            }
+            let keyframes = match channel.keyframes(graph) {
+                Some(keyframes) => keyframes,
+                None => continue,
+            };
+            let samples = match keyframes.interpolation() {
+                Interpolation::CubicSpline => sample_spline(&keyframes, options),
+                _ => sample_steps(&keyframes),
+            };
+            let (inputs, outputs): (Vec<f32>, Vec<Vec<f32>>) = samples.into_iter().unzip();
+            let rotation = keyframes.is_rotation();
+            let outputs = outputs.concat();
+            let linear = match Keyframes::new(Interpolation::Linear, inputs, outputs, rotation) {
+                Some(linear) => linear,
+                None => continue,
+            };
+            let keep = simplify(&linear, options.tolerance);
+            let inputs = keep.iter().map(|x| linear.inputs()[*x]).collect();
+            let outputs = keep
+                .iter()
+                .flat_map(|x| linear.value(*x).to_vec())
+                .collect();
+            let linear = Keyframes::new(Interpolation::Linear, inputs, outputs, rotation).unwrap();
+            replaced.insert(channel.input);
+            replaced.insert(channel.output);
+            write_keyframes(graph, channel, &linear);
+        }
+        graph[handle].channels = channels;
+    }
+    remove_unused_accessors(graph, replaced);
+}
@@ -0,0 +1,316 @@
+//! Transforms that optimize or restructure a [`Graph`].
+//!
+//! Each transform edits a graph in place. Convert a `Document` into a graph
+//! with [`Graph::from_document`], apply transforms, and write it back with
+//! [`Graph::to_document`] or [`Graph::export`].
+//!
+//! ```
+//! # use gltf_transform_rs as gltf;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use gltf::graph::Graph;
+//! let (document, buffers, _) = gltf::import("examples/Box.gltf")?;
+//! let mut graph = Graph::from_document(&document, &buffers)?;
+//! let report = gltf::transform::dedup(&mut graph);
+//! assert_eq!(report.accessors, 0);
+//! # Ok(())
+//! # }
+//! # fn main() {
+//! #    let _ = run().expect("runtime error");
+//! # }
+//! ```
+//!
+//! [`Graph`]: ../graph/struct.Graph.html
+//! [`Graph::from_document`]: ../graph/struct.Graph.html#method.from_document
+//! [`Graph::to_document`]: ../graph/struct.Graph.html#method.to_document
+//! [`Graph::export`]: ../graph/struct.Graph.html#method.export
+
+use std::collections::{HashMap, HashSet};
+
+use crate::animation::util::Keyframes;
+use crate::graph::{
+    Accessor, Animation, Channel, Graph, Handle, Instancing, Mesh, Node, Primitive, Skin, Values,
+};
+use crate::mesh::Mode;
+
+mod dedup;
+mod flatten;
+mod instance;
+mod join;
+mod linearize;
+mod normals;
+mod prune;
+mod quantize;
+mod reorder;
+mod resample;
+mod retime;
+mod simplify;
+mod tangents;
+mod unify;
+mod weld;
+
+#[doc(inline)]
+pub use self::dedup::{dedup, DedupReport};
+#[doc(inline)]
+pub use self::flatten::flatten;
+#[doc(inline)]
+pub use self::instance::{instance, InstanceOptions};
+#[doc(inline)]
+pub use self::join::{join, JoinOptions};
+#[doc(inline)]
+pub use self::linearize::{linearize, LinearizeOptions};
+#[doc(inline)]
+pub use self::normals::{normals, NormalWeighting, NormalsOptions};
+#[doc(inline)]
+pub use self::prune::{prune, PruneOptions};
+#[doc(inline)]
+pub use self::quantize::{quantize, QuantizeOptions};
+#[doc(inline)]
+pub use self::reorder::{reorder, ReorderOptions};
+#[doc(inline)]
+pub use self::resample::{resample, ResampleOptions, ResampleReport};
+#[doc(inline)]
+pub use self::retime::{merge_animations, retime_animation, split_animation, trim_animation, Clip};
+#[doc(inline)]
+pub use self::simplify::{simplify, simplify_primitive, Simplified, SimplifyOptions};
+#[doc(inline)]
+pub use self::tangents::{tangents, TangentsOptions};
+#[doc(inline)]
+pub use self::unify::unify_topology;
+#[doc(inline)]
+pub use self::weld::{weld, WeldOptions};
+
+/// Removes the `candidates` that are no longer referenced by any mesh, skin,
+/// node, or animation.
+pub(crate) fn remove_unused_accessors(
+    graph: &mut Graph,
+    mut candidates: HashSet<Handle<Accessor>>,
+) {
+    for (_, mesh) in graph.iter::<Mesh>() {
+        for primitive in &mesh.primitives {
+            for accessor in primitive.accessors() {
+                candidates.remove(&accessor);
+            }
+        }
+    }
+    for (_, skin) in graph.iter::<Skin>() {
+        if let Some(accessor) = skin.inverse_bind_matrices {
+            candidates.remove(&accessor);
+        }
+    }
+    for (_, node) in graph.iter::<Node>() {
+        for accessor in node.instancing.iter().flat_map(Instancing::accessors) {
+            candidates.remove(&accessor);
+        }
+    }
+    for (_, animation) in graph.iter::<Animation>() {
+        for channel in &animation.channels {
+            candidates.remove(&channel.input);
+            candidates.remove(&channel.output);
+        }
+    }
+    for accessor in candidates {
+        graph.remove(accessor);
+    }
+}
+
+/// Replaces the accessors and interpolation of `channel` with `keyframes`,
+/// keeping the component type of the outputs.
+pub(crate) fn write_keyframes(graph: &mut Graph, channel: &mut Channel, keyframes: &Keyframes) {
+    let output = &graph[channel.output];
+    let mut values = Values::with_capacity(output.data_type(), keyframes.outputs().len());
+    for x in keyframes.outputs() {
+        values.push_f32(*x, output.normalized);
+    }
+    let output = Accessor {
+        normalized: output.normalized,
+        ..Accessor::new(output.dimensions, values)
+    };
+    let inputs: Vec<[f32; 1]> = keyframes.inputs().iter().map(|x| [*x]).collect();
+    channel.input = graph.add(Accessor::from_f32(&inputs));
+    channel.output = graph.add(output);
+    channel.interpolation = keyframes.interpolation();
+}
+
+/// Returns a copy of `template` holding `indices`, with the same component
+/// type.
+pub(crate) fn index_accessor(template: &Accessor, indices: &[u32]) -> Accessor {
+    let mut values = Values::with_capacity(template.data_type(), indices.len());
+    for index in indices {
+        values.push_u32(*index);
+    }
+    Accessor {
+        values,
+        ..template.gather(&[])
+    }
+}
+
+/// Returns the vertex count shared by every attribute and morph target of
+/// the primitive, or `None` if they disagree or are missing.
+pub(crate) fn vertex_count(graph: &Graph, primitive: &Primitive) -> Option<usize> {
+    let mut counts = primitive
+        .accessors()
+        .filter(|x| Some(*x) != primitive.indices)
+        .map(|x| graph.get(x).map(Accessor::count));
+    let count = counts.next()??;
+    if counts.all(|x| x == Some(count)) {
+        Some(count)
+    } else {
+        None
+    }
+}
+
+/// Returns the vertex count and triangle list indices of a `TRIANGLES`
+/// primitive, or `None` if its indices are out of range or its vertex
+/// attributes differ in length.
+pub(crate) fn triangle_list(graph: &Graph, primitive: &Primitive) -> Option<(usize, Vec<u32>)> {
+    if primitive.mode != Mode::Triangles {
+        return None;
+    }
+    let vertex_count = vertex_count(graph, primitive)?;
+    let indices = match primitive.indices {
+        Some(indices) => graph.get(indices)?.to_u32(),
+        None => (0..vertex_count as u32).collect(),
+    };
+    if indices.len() % 3 != 0 || indices.iter().any(|x| *x as usize >= vertex_count) {
+        return None;
+    }
+    Some((vertex_count, indices))
+}
+
+/// Gives every vertex the value of the corners using it, splitting vertices
+/// whose corners disagree.
+///
+/// `indices` is updated to refer to the new vertices. Returns the value of
+/// every vertex, `fallback` for unused ones, and the source vertex of each
+/// new vertex, in the order they follow the `vertex_count` existing ones.
+pub(crate) fn split_vertices<const N: usize>(
+    indices: &mut [u32],
+    corners: &[[f32; N]],
+    vertex_count: usize,
+    fallback: [f32; N],
+) -> (Vec<[f32; N]>, Vec<u32>) {
+    let mut values: Vec<Option<[f32; N]>> = vec![None; vertex_count];
+    let mut splits = HashMap::new();
+    let mut extra = Vec::new();
+    for (index, value) in indices.iter_mut().zip(corners) {
+        let bits = value.map(f32::to_bits);
+        match values[*index as usize] {
+            None => values[*index as usize] = Some(*value),
+            Some(x) if x.map(f32::to_bits) == bits => {}
+            Some(_) => {
+                let source = *index;
+                *index = *splits.entry((source, bits)).or_insert_with(|| {
+                    extra.push(source);
+                    values.push(Some(*value));
+                    (values.len() - 1) as u32
+                });
+            }
+        }
+    }
+    let values = values.iter().map(|x| x.unwrap_or(fallback)).collect();
+    (values, extra)
+}
+
+/// Appends copies of the `extra` vertices to the vertex attributes of
+/// `primitive` and makes it use `indices`, as returned by [`split_vertices`].
+///
+/// The primitive gets new attribute and index accessors unless `extra` is
+/// empty. Returns the accessors it no longer references.
+///
+/// [`split_vertices`]: fn.split_vertices.html
+pub(crate) fn append_vertices(
+    graph: &mut Graph,
+    primitive: &mut Primitive,
+    vertex_count: usize,
+    extra: &[u32],
+    indices: &[u32],
+) -> Vec<Handle<Accessor>> {
+    if extra.is_empty() {
+        return Vec::new();
+    }
+    let vertices: Vec<u32> = (0..vertex_count as u32)
+        .chain(extra.iter().copied())
+        .collect();
+    let mut replaced: Vec<_> = primitive
+        .accessors()
+        .filter(|x| Some(*x) != primitive.indices)
+        .collect();
+    let mut replacements = HashMap::new();
+    for handle in &replaced {
+        if let Some(accessor) = graph.get(*handle) {
+            let accessor = accessor.gather(&vertices);
+            replacements
+                .entry(*handle)
+                .or_insert_with(|| graph.add(accessor));
+        }
+    }
+    for accessor in primitive.accessors_mut() {
+        if let Some(replacement) = replacements.get(accessor) {
+            *accessor = *replacement;
+        }
+    }
+    replaced.extend(primitive.indices);
+    let indices = Accessor::from_indices(indices, vertices.len());
+    primitive.indices = Some(graph.add(indices));
+    replaced
+}
+
+/// Per-vertex lists of the triangles using each vertex.
+pub(crate) struct Adjacency {
+    offsets: Vec<usize>,
+    triangles: Vec<usize>,
+}
+
+impl Adjacency {
+    pub(crate) fn new(indices: &[u32], vertex_count: usize) -> Self {
+        let mut offsets = vec![0; vertex_count + 1];
+        for index in indices {
+            offsets[*index as usize + 1] += 1;
+        }
+        for i in 0..vertex_count {
+            offsets[i + 1] += offsets[i];
+        }
+        let mut fill = offsets.clone();
+        let mut triangles = vec![0; indices.len()];
+        for (i, index) in indices.iter().enumerate() {
+            triangles[fill[*index as usize]] = i / 3;
+            fill[*index as usize] += 1;
+        }
+        Self { offsets, triangles }
+    }
+
+    pub(crate) fn get(&self, vertex: u32) -> &[usize] {
+        &self.triangles[self.offsets[vertex as usize]..self.offsets[vertex as usize + 1]]
+    }
+}
+
+/// Returns `a - b`.
+pub(crate) fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// Returns the cross product of `a` and `b`.
+pub(crate) fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Returns the dot product of `a` and `b`.
+pub(crate) fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Returns `a` scaled to unit length, and its original length.
+///
+/// Zero vectors are returned unchanged.
+pub(crate) fn normalize(a: [f64; 3]) -> ([f64; 3], f64) {
+    let length = dot(a, a).sqrt();
+    if length > 0.0 {
+        (a.map(|x| x / length), length)
+    } else {
+        (a, 0.0)
+    }
+}
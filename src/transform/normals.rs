@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+
+use crate::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use crate::Semantic;
+
+use super::{
+    append_vertices, cross, dot, normalize, remove_unused_accessors, split_vertices, sub,
+    triangle_list, Adjacency,
+};
+
+/// How face normals are weighted when they are averaged into smooth vertex
+/// normals.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum NormalWeighting {
+    /// Each face is weighted by its area.
+    Area,
+
+    /// Each face is weighted by the angle of its corner at the vertex, which
+    /// does not depend on how the surface is tessellated.
+    #[default]
+    Angle,
+}
+
+/// Options for [`normals`].
+///
+/// [`normals`]: fn.normals.html
+#[derive(Clone, Debug)]
+pub struct NormalsOptions {
+    /// Whether every triangle gets its own face normal instead of smooth
+    /// vertex normals.
+    pub flat: bool,
+
+    /// The largest angle in radians between two faces sharing a vertex
+    /// position for which their normals are averaged. Edges between faces at
+    /// a larger angle become hard creases. Ignored when `flat` is set.
+    pub crease_angle: f32,
+
+    /// How face normals are weighted. Ignored when `flat` is set.
+    pub weighting: NormalWeighting,
+
+    /// Whether existing `NORMAL` attributes are replaced. When unset, only
+    /// primitives without normals are changed.
+    pub overwrite: bool,
+}
+
+impl Default for NormalsOptions {
+    fn default() -> Self {
+        Self {
+            flat: false,
+            crease_angle: PI,
+            weighting: NormalWeighting::Angle,
+            overwrite: false,
+        }
+    }
+}
+
+/// The normal used where no face defines one, e.g. for degenerate triangles.
+const FALLBACK: [f32; 3] = [0.0, 0.0, 1.0];
+
+/// Returns the normal of every corner of a triangle list.
+fn corner_normals(
+    positions: &[[f64; 3]],
+    indices: &[u32],
+    options: &NormalsOptions,
+) -> Vec<[f32; 3]> {
+    let faces: Vec<_> = indices
+        .chunks(3)
+        .map(|t| {
+            let [p0, p1, p2] = [0, 1, 2].map(|i| positions[t[i] as usize]);
+            normalize(cross(sub(p1, p0), sub(p2, p0)))
+        })
+        .collect();
+    let finish = |n: [f64; 3]| match normalize(n) {
+        (n, length) if length > 0.0 => n.map(|x| x as f32),
+        _ => FALLBACK,
+    };
+    if options.flat {
+        return faces.iter().flat_map(|(n, _)| [finish(*n); 3]).collect();
+    }
+
+    // Group vertices by position so that faces are smoothed across attribute
+    // seams.
+    let mut unique = HashMap::new();
+    let ids: Vec<u32> = positions
+        .iter()
+        .map(|p| {
+            let next = unique.len() as u32;
+            *unique.entry(p.map(f64::to_bits)).or_insert(next)
+        })
+        .collect();
+    let shared: Vec<u32> = indices.iter().map(|x| ids[*x as usize]).collect();
+    let adjacency = Adjacency::new(&shared, unique.len());
+
+    // Returns the weight of `triangle` at its corner on `position`.
+    let weight = |triangle: usize, position: u32| -> f64 {
+        let (_, area) = faces[triangle];
+        match options.weighting {
+            NormalWeighting::Area => area,
+            NormalWeighting::Angle => {
+                let t = &indices[triangle * 3..triangle * 3 + 3];
+                let k = (0..3)
+                    .find(|k| shared[triangle * 3 + k] == position)
+                    .unwrap();
+                let p = |i: usize| positions[t[(k + i) % 3] as usize];
+                let (a, _) = normalize(sub(p(1), p(0)));
+                let (b, _) = normalize(sub(p(2), p(0)));
+                dot(a, b).clamp(-1.0, 1.0).acos()
+            }
+        }
+    };
+
+    let threshold = f64::from(options.crease_angle)
+        .min(std::f64::consts::PI)
+        .cos();
+    let mut normals = Vec::with_capacity(indices.len());
+    for (corner, position) in shared.iter().enumerate() {
+        let (own, own_area) = faces[corner / 3];
+        let mut sum = [0.0; 3];
+        for triangle in adjacency.get(*position) {
+            // Triangles listed twice have two corners on the same position,
+            // and thus no area.
+            let (normal, area) = faces[*triangle];
+            if area == 0.0 || (own_area > 0.0 && dot(own, normal) < threshold) {
+                continue;
+            }
+            let w = weight(*triangle, *position);
+            for i in 0..3 {
+                sum[i] += normal[i] * w;
+            }
+        }
+        normals.push(finish(sum));
+    }
+    normals
+}
+
+/// Generates normals for one primitive, returning the accessors it no longer
+/// references.
+fn primitive_normals(
+    graph: &mut Graph,
+    primitive: &mut Primitive,
+    options: &NormalsOptions,
+) -> Vec<Handle<Accessor>> {
+    if primitive.get(&Semantic::Normals).is_some() && !options.overwrite {
+        return Vec::new();
+    }
+    let positions = match primitive
+        .get(&Semantic::Positions)
+        .and_then(|x| graph.get(x))
+    {
+        Some(positions) => positions,
+        None => return Vec::new(),
+    };
+    let (vertex_count, mut indices) = match triangle_list(graph, primitive) {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    let positions: Vec<_> = positions
+        .to_f32::<3>()
+        .iter()
+        .map(|p| p.map(f64::from))
+        .collect();
+
+    let corners = corner_normals(&positions, &indices, options);
+    let (normals, extra) = split_vertices(&mut indices, &corners, vertex_count, FALLBACK);
+    let mut replaced: Vec<_> = primitive.remove(&Semantic::Normals).into_iter().collect();
+    replaced.extend(append_vertices(
+        graph,
+        primitive,
+        vertex_count,
+        &extra,
+        &indices,
+    ));
+    primitive.set(Semantic::Normals, graph.add(Accessor::from_f32(&normals)));
+    replaced
+}
+
+/// Generates vertex normals for triangle primitives.
+///
+/// With `flat` set, every triangle gets its face normal. Otherwise the
+/// normals of the faces around each vertex position are averaged, weighted
+/// as set in `options`, leaving out faces at more than the crease angle from
+/// the face the vertex belongs to. Faces sharing a position are smoothed
+/// even when the vertex is duplicated for other attributes. Vertices used by
+/// corners that need different normals are split, which gives the primitive
+/// new attribute accessors and a new index accessor. Degenerate triangles
+/// take the normals of their neighbors, or `+Z` if they have none.
+///
+/// Only primitives with mode `TRIANGLES` are changed, and only those without
+/// a `NORMAL` attribute unless `overwrite` is set. Accessors that are no
+/// longer referenced afterwards are removed.
+pub fn normals(graph: &mut Graph, options: &NormalsOptions) {
+    let mut replaced = HashSet::new();
+    for handle in graph.handles::<Mesh>() {
+        let mut primitives = std::mem::take(&mut graph[handle].primitives);
+        for primitive in &mut primitives {
+            replaced.extend(primitive_normals(graph, primitive, options));
+        }
+        graph[handle].primitives = primitives;
+    }
+    remove_unused_accessors(graph, replaced);
+}
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+
+use crate::graph::{
+    Accessor, Animation, Buffer, Camera, Entity, Graph, Handle, Image, Instancing, Material, Mesh,
+    Node, Sampler, Scene, Skin, Texture,
+};
+
+/// Options for [`prune`].
+///
+/// [`prune`]: fn.prune.html
+#[derive(Clone, Debug, Default)]
+pub struct PruneOptions {
+    /// Keep empty leaf nodes that have a name or extras, e.g. because they
+    /// mark attachment points.
+    pub keep_named_leaves: bool,
+}
+
+/// Removes every object of one kind that is not in `keep`.
+fn retain<T: Entity>(graph: &mut Graph, keep: &HashSet<Handle<T>>) {
+    for handle in graph.handles::<T>() {
+        if !keep.contains(&handle) {
+            graph.remove(handle);
+        }
+    }
+}
+
+/// Returns `true` if the node has a name or extras.
+fn is_named(node: &Node) -> bool {
+    #[cfg(feature = "extras")]
+    let has_extras = node.extras.is_some();
+    #[cfg(not(feature = "extras"))]
+    let has_extras = false;
+    node.name.is_some() || has_extras
+}
+
+/// Returns the nodes reachable from any scene.
+fn reachable_nodes(graph: &Graph) -> HashSet<Handle<Node>> {
+    let mut reachable = HashSet::new();
+    let mut stack: Vec<_> = graph
+        .iter::<Scene>()
+        .flat_map(|(_, scene)| scene.nodes.iter().copied())
+        .collect();
+    while let Some(handle) = stack.pop() {
+        if let Some(node) = graph.get(handle) {
+            if reachable.insert(handle) {
+                stack.extend(node.children.iter().copied());
+                stack.extend(node.lods.iter().copied());
+            }
+        }
+    }
+    reachable
+}
+
+/// Removes every object that cannot be reached from a scene.
+///
+/// Nodes are kept when they are reachable from a scene through `children`
+/// or `lods`, or are joints or skeleton roots of a skin used by such a node.
+/// Reachable nodes without children, levels of detail, mesh, camera, or skin
+/// are removed as well unless they are joints or animation targets, or
+/// `options` asks to keep them. Meshes, cameras, skins, materials, textures,
+/// samplers, images, accessors, and buffers are kept only when referenced by
+/// a kept object; animation channels targeting removed nodes are dropped, as
+/// are animations left without channels. Scenes are never removed.
+///
+/// References to removed objects are cleared, and declarations of extensions
+/// that are no longer used are removed; see [`Graph::update_extensions`].
+/// Indices are compacted when the graph is written.
+///
+/// [`Graph::update_extensions`]: ../graph/struct.Graph.html#method.update_extensions
+pub fn prune(graph: &mut Graph, options: &PruneOptions) {
+    let mut nodes = reachable_nodes(graph);
+    let skins: HashSet<Handle<Skin>> = nodes
+        .iter()
+        .filter_map(|x| graph[*x].skin)
+        .filter(|x| graph.contains(*x))
+        .collect();
+    let mut joints = HashSet::new();
+    for skin in &skins {
+        joints.extend(graph[*skin].joints.iter().copied());
+        joints.extend(graph[*skin].skeleton);
+    }
+    joints.retain(|x| graph.contains(*x));
+    nodes.extend(joints.iter().copied());
+    let targets: HashSet<Handle<Node>> = graph
+        .iter::<Animation>()
+        .flat_map(|(_, animation)| animation.channels.iter().map(|x| x.node))
+        .collect();
+
+    // Remove empty leaves until none are left, since removing a leaf may turn
+    // its parent into one.
+    loop {
+        let leaves: Vec<_> = nodes
+            .iter()
+            .copied()
+            .filter(|handle| {
+                let node = &graph[*handle];
+                node.children.iter().all(|x| !nodes.contains(x))
+                    && node.lods.iter().all(|x| !nodes.contains(x))
+                    && node.mesh.is_none()
+                    && node.camera.is_none()
+                    && node.skin.is_none()
+                    && !joints.contains(handle)
+                    && !targets.contains(handle)
+                    && !(options.keep_named_leaves && is_named(node))
+            })
+            .collect();
+        if leaves.is_empty() {
+            break;
+        }
+        for leaf in leaves {
+            nodes.remove(&leaf);
+        }
+    }
+    retain(graph, &nodes);
+    for (_, node) in graph.iter_mut::<Node>() {
+        node.children.retain(|x| nodes.contains(x));
+        node.lods.retain(|x| nodes.contains(x));
+    }
+    for (_, scene) in graph.iter_mut::<Scene>() {
+        scene.nodes.retain(|x| nodes.contains(x));
+    }
+    for (_, animation) in graph.iter_mut::<Animation>() {
+        animation.channels.retain(|x| nodes.contains(&x.node));
+    }
+    let animations: HashSet<Handle<Animation>> = graph
+        .iter::<Animation>()
+        .filter(|(_, animation)| !animation.channels.is_empty())
+        .map(|(handle, _)| handle)
+        .collect();
+    retain(graph, &animations);
+
+    let mut meshes = HashSet::new();
+    let mut cameras = HashSet::new();
+    for (_, node) in graph.iter::<Node>() {
+        meshes.extend(node.mesh);
+        cameras.extend(node.camera);
+    }
+    retain::<Mesh>(graph, &meshes);
+    retain::<Camera>(graph, &cameras);
+    retain(graph, &skins);
+
+    let mut accessors = HashSet::new();
+    let mut materials = HashSet::new();
+    for (_, mesh) in graph.iter::<Mesh>() {
+        for primitive in &mesh.primitives {
+            accessors.extend(primitive.accessors());
+            materials.extend(primitive.material);
+        }
+    }
+    for (_, skin) in graph.iter::<Skin>() {
+        accessors.extend(skin.inverse_bind_matrices);
+    }
+    for (_, node) in graph.iter::<Node>() {
+        accessors.extend(node.instancing.iter().flat_map(Instancing::accessors));
+    }
+    for (_, animation) in graph.iter::<Animation>() {
+        for channel in &animation.channels {
+            accessors.insert(channel.input);
+            accessors.insert(channel.output);
+        }
+    }
+    retain::<Accessor>(graph, &accessors);
+    retain::<Material>(graph, &materials);
+
+    let mut textures = HashSet::new();
+    for (_, material) in graph.iter::<Material>() {
+        textures.extend(
+            material
+                .textures()
+                .iter()
+                .filter_map(|x| x.as_ref())
+                .map(|x| x.texture),
+        );
+        textures.extend(material.extension_textures());
+    }
+    retain::<Texture>(graph, &textures);
+    let mut images = HashSet::new();
+    let mut samplers = HashSet::new();
+    for (_, texture) in graph.iter::<Texture>() {
+        images.insert(texture.source);
+        images.extend(texture.extension_images());
+        samplers.extend(texture.sampler);
+    }
+    retain::<Image>(graph, &images);
+    retain::<Sampler>(graph, &samplers);
+
+    // Accessors without a buffer and embedded images are written to the first
+    // buffer.
+    let mut buffers: HashSet<Handle<Buffer>> = graph
+        .iter::<Accessor>()
+        .filter_map(|(_, x)| x.buffer)
+        .collect();
+    let needs_first = graph.iter::<Accessor>().any(|(_, x)| x.buffer.is_none())
+        || graph.iter::<Image>().any(|(_, x)| !x.data.is_empty());
+    if needs_first {
+        buffers.extend(graph.handles::<Buffer>().first());
+    }
+    retain(graph, &buffers);
+    graph.update_extensions();
+}
@@ -0,0 +1,462 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::accessor::{DataType, Dimensions};
+use crate::animation::Property;
+use crate::graph::{Accessor, Animation, Graph, Handle, Instancing, Mesh, Node, Values};
+use crate::scene::Transform;
+use crate::Semantic;
+
+use super::remove_unused_accessors;
+
+/// Options for [`quantize`].
+///
+/// Each field is the number of bits kept per component for one kind of
+/// vertex attribute. Up to 8 bits are stored in 8-bit integers and up to 16
+/// bits in 16-bit integers. A value of zero leaves that kind of attribute
+/// unchanged.
+///
+/// [`quantize`]: fn.quantize.html
+#[derive(Clone, Debug)]
+pub struct QuantizeOptions {
+    /// Bits per `POSITION` component, including morph target displacements.
+    pub position_bits: u8,
+
+    /// Bits per `NORMAL` component.
+    pub normal_bits: u8,
+
+    /// Bits per `TANGENT` component.
+    pub tangent_bits: u8,
+
+    /// Bits per `TEXCOORD_n` component.
+    pub tex_coord_bits: u8,
+
+    /// Bits per `COLOR_n` component.
+    pub color_bits: u8,
+
+    /// Bits per `WEIGHTS_n` component.
+    ///
+    /// Weights always use the full precision of their storage type so that
+    /// they can be renormalized to sum to exactly one.
+    pub weight_bits: u8,
+}
+
+impl Default for QuantizeOptions {
+    fn default() -> Self {
+        Self {
+            position_bits: 14,
+            normal_bits: 10,
+            tangent_bits: 10,
+            tex_coord_bits: 12,
+            color_bits: 8,
+            weight_bits: 8,
+        }
+    }
+}
+
+/// Returns the storage type for `bits` bits per component.
+fn storage_type(bits: u8, signed: bool) -> DataType {
+    match (bits <= 8, signed) {
+        (true, true) => DataType::I8,
+        (true, false) => DataType::U8,
+        (false, true) => DataType::I16,
+        (false, false) => DataType::U16,
+    }
+}
+
+/// Returns a normalized integer copy of a float accessor, with each component
+/// mapped through `map` and rounded to `bits` bits.
+///
+/// Returns `None` if the accessor is not a float accessor or a mapped
+/// component falls outside `[-1, 1]`, or `[0, 1]` when `signed` is `false`.
+fn quantize_accessor<F>(accessor: &Accessor, bits: u8, signed: bool, map: F) -> Option<Accessor>
+where
+    F: Fn(usize, f32) -> f32,
+{
+    const EPSILON: f32 = 1e-5;
+    if accessor.data_type() != DataType::F32 || bits == 0 {
+        return None;
+    }
+    let bits = bits.min(16);
+    let (low, steps) = if signed {
+        (-1.0, ((1u32 << (bits - 1)) - 1) as f32)
+    } else {
+        (0.0, ((1u32 << bits) - 1) as f32)
+    };
+    let n = accessor.dimensions.multiplicity();
+    let mut values = Values::with_capacity(storage_type(bits, signed), accessor.values.len());
+    for i in 0..accessor.values.len() {
+        let x = map(i % n, accessor.values.get_f32(i, false));
+        if !(low - EPSILON..=1.0 + EPSILON).contains(&x) {
+            return None;
+        }
+        values.push_f32((x.clamp(low, 1.0) * steps).round() / steps, true);
+    }
+    Some(Accessor {
+        normalized: true,
+        values,
+        ..accessor.gather(&[])
+    })
+}
+
+/// Returns a normalized integer copy of a float weights accessor whose
+/// elements still sum to one.
+fn quantize_weights(accessor: &Accessor, bits: u8) -> Option<Accessor> {
+    if accessor.data_type() != DataType::F32 || bits == 0 {
+        return None;
+    }
+    let max = if bits <= 8 {
+        u8::MAX as u32
+    } else {
+        u16::MAX as u32
+    };
+    let n = accessor.dimensions.multiplicity();
+    let mut values = Values::with_capacity(storage_type(bits, false), accessor.values.len());
+    let mut element = vec![0u32; n];
+    for i in 0..accessor.count() {
+        for (j, x) in element.iter_mut().enumerate() {
+            let w = accessor.values.get_f32(i * n + j, false).clamp(0.0, 1.0);
+            *x = (w * max as f32).round() as u32;
+        }
+        let sum: u32 = element.iter().sum();
+        if sum > 0 && sum != max {
+            // Correct the rounding error on the largest weight.
+            let (largest, _) = element.iter().enumerate().max_by_key(|(_, x)| **x).unwrap();
+            element[largest] = (element[largest] + max).saturating_sub(sum);
+        }
+        for x in &element {
+            values.push_u32(*x);
+        }
+    }
+    Some(Accessor {
+        normalized: true,
+        values,
+        ..accessor.gather(&[])
+    })
+}
+
+/// The dequantization transform of a mesh: a translation followed by a
+/// uniform scale.
+#[derive(Clone, Copy, Debug)]
+struct Dequantize {
+    offset: [f32; 3],
+    scale: f32,
+}
+
+/// Computes the dequantization transform for the positions of a mesh, or
+/// `None` if they cannot be quantized.
+fn position_transform(graph: &Graph, mesh: &Mesh) -> Option<Dequantize> {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    let mut displacement = 0.0f32;
+    for primitive in &mesh.primitives {
+        let positions = graph.get(primitive.get(&Semantic::Positions)?)?;
+        if positions.data_type() != DataType::F32 {
+            return None;
+        }
+        for p in positions.to_f32::<3>() {
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+        }
+        for target in &primitive.targets {
+            if let Some(positions) = target.positions.and_then(|x| graph.get(x)) {
+                for d in positions.to_f32::<3>() {
+                    displacement = d.iter().fold(displacement, |a, b| a.max(b.abs()));
+                }
+            }
+        }
+    }
+    if min[0] > max[0] {
+        return None;
+    }
+    let offset = [0, 1, 2].map(|i| (min[i] + max[i]) / 2.0);
+    let extent = [0, 1, 2]
+        .iter()
+        .fold(displacement, |a, i| a.max((max[*i] - min[*i]) / 2.0));
+    let scale = if extent > 0.0 { extent } else { 1.0 };
+    Some(Dequantize { offset, scale })
+}
+
+/// Applies `dequantize` after a node's local transform.
+fn fold(transform: &mut Transform, dequantize: Dequantize) {
+    let matrix = transform.clone().matrix();
+    let mut offset = [0.0; 3];
+    for (i, x) in offset.iter_mut().enumerate() {
+        *x = (0..3)
+            .map(|j| matrix[j][i] * dequantize.offset[j])
+            .sum::<f32>();
+    }
+    match transform {
+        Transform::Matrix { matrix } => {
+            for column in matrix.iter_mut().take(3) {
+                for x in column.iter_mut().take(3) {
+                    *x *= dequantize.scale;
+                }
+            }
+            for i in 0..3 {
+                matrix[3][i] += offset[i];
+            }
+        }
+        Transform::Decomposed {
+            translation, scale, ..
+        } => {
+            for i in 0..3 {
+                translation[i] += offset[i];
+                scale[i] *= dequantize.scale;
+            }
+        }
+    }
+}
+
+/// Per-instance translations and scales.
+type Instances = (Vec<[f32; 3]>, Vec<[f32; 3]>);
+
+/// Returns the per-instance translations and scales of `instancing` with
+/// `dequantize` applied after each instance transform, or `None` if its
+/// attributes cannot be read.
+fn fold_instances(
+    graph: &Graph,
+    instancing: &Instancing,
+    dequantize: Dequantize,
+) -> Option<Instances> {
+    let count = graph.get(instancing.accessors().next()?)?.count();
+    let read = |name: &str, dimensions: Dimensions| match instancing.get(name) {
+        Some(handle) => {
+            let accessor = graph.get(handle)?;
+            let valid = accessor.dimensions == dimensions && accessor.count() == count;
+            valid.then_some(Some(accessor))
+        }
+        None => Some(None),
+    };
+    let translations = read("TRANSLATION", Dimensions::Vec3)?;
+    let rotations = read("ROTATION", Dimensions::Vec4)?;
+    let scales = read("SCALE", Dimensions::Vec3)?;
+    let mut folded = (Vec::with_capacity(count), Vec::with_capacity(count));
+    for i in 0..count {
+        let translation = translations.map_or([0.0; 3], |x| x.get(i));
+        let rotation = rotations.map_or([0.0, 0.0, 0.0, 1.0], |x| x.get(i));
+        let scale = scales.map_or([1.0; 3], |x| x.get(i));
+        let matrix = Transform::Decomposed {
+            translation,
+            rotation,
+            scale,
+        }
+        .matrix();
+        let mut offset = [0.0; 3];
+        for (j, x) in offset.iter_mut().enumerate() {
+            *x = matrix[3][j]
+                + (0..3)
+                    .map(|k| matrix[k][j] * dequantize.offset[k])
+                    .sum::<f32>();
+        }
+        folded.0.push(offset);
+        folded.1.push(scale.map(|x| x * dequantize.scale));
+    }
+    Some(folded)
+}
+
+/// Returns whether the dequantization transform cannot be folded into the
+/// transform of `node` without affecting its children or being overwritten by
+/// animation.
+fn needs_child(handle: Handle<Node>, node: &Node, animated: &HashSet<Handle<Node>>) -> bool {
+    !node.children.is_empty() || animated.contains(&handle)
+}
+
+/// Quantizes the positions of `mesh` and folds the dequantization transform
+/// into the nodes that instantiate it.
+fn quantize_positions(
+    graph: &mut Graph,
+    mesh: Handle<Mesh>,
+    bits: u8,
+    animated: &HashSet<Handle<Node>>,
+    replaced: &mut HashSet<Handle<Accessor>>,
+) {
+    let nodes: Vec<_> = graph
+        .iter::<Node>()
+        .filter(|(_, node)| node.mesh == Some(mesh))
+        .map(|(handle, _)| handle)
+        .collect();
+    // The levels of detail of a node replace the node itself, so they would
+    // stay behind if its mesh moved to a child node.
+    let unsupported = |handle: &Handle<Node>| {
+        let node = &graph[*handle];
+        node.skin.is_some()
+            || (!node.lods.is_empty()
+                && node.instancing.is_none()
+                && needs_child(*handle, node, animated))
+    };
+    if nodes.is_empty() || nodes.iter().any(unsupported) {
+        return;
+    }
+    let dequantize = match position_transform(graph, &graph[mesh]) {
+        Some(dequantize) => dequantize,
+        None => return,
+    };
+    // Instance transforms apply before the node transform, so the
+    // dequantization transform is folded into them instead.
+    let mut instances = HashMap::new();
+    for handle in &nodes {
+        if let Some(instancing) = &graph[*handle].instancing {
+            match fold_instances(graph, instancing, dequantize) {
+                Some(folded) => instances.insert(*handle, folded),
+                None => return,
+            };
+        }
+    }
+
+    let mut quantized = HashMap::new();
+    let mut handles = Vec::new();
+    for primitive in &graph[mesh].primitives {
+        handles.extend(primitive.get(&Semantic::Positions));
+        handles.extend(primitive.targets.iter().filter_map(|x| x.positions));
+    }
+    let offset = dequantize.offset;
+    let scale = dequantize.scale;
+    for handle in handles {
+        let is_target = graph[mesh].primitives.iter().any(|primitive| {
+            primitive
+                .targets
+                .iter()
+                .any(|x| x.positions == Some(handle))
+        });
+        let accessor = if is_target {
+            quantize_accessor(&graph[handle], bits, true, |_, x| x / scale)
+        } else {
+            quantize_accessor(&graph[handle], bits, true, |i, x| (x - offset[i]) / scale)
+        };
+        match accessor {
+            Some(accessor) => {
+                quantized.insert(handle, accessor);
+            }
+            // Leave the whole mesh alone rather than mix quantized and float
+            // positions under one transform.
+            None => return,
+        }
+    }
+    let quantized: HashMap<_, _> = quantized
+        .into_iter()
+        .map(|(handle, accessor)| (handle, graph.add(accessor)))
+        .collect();
+    for primitive in &mut graph[mesh].primitives {
+        for accessor in primitive.accessors_mut() {
+            if let Some(replacement) = quantized.get(accessor) {
+                replaced.insert(*accessor);
+                *accessor = *replacement;
+            }
+        }
+    }
+
+    for handle in nodes {
+        if let Some((translations, scales)) = instances.remove(&handle) {
+            let translations = graph.add(Accessor::from_f32(&translations));
+            let scales = graph.add(Accessor::from_f32(&scales));
+            let instancing = graph[handle].instancing.as_mut().unwrap();
+            replaced.extend(instancing.set("TRANSLATION", translations));
+            replaced.extend(instancing.set("SCALE", scales));
+            continue;
+        }
+        let node = &mut graph[handle];
+        if !needs_child(handle, node, animated) {
+            fold(&mut node.transform, dequantize);
+            continue;
+        }
+        let mut child = Node {
+            mesh: node.mesh.take(),
+            weights: node.weights.take(),
+            ..Default::default()
+        };
+        fold(&mut child.transform, dequantize);
+        let child = graph.add(child);
+        graph[handle].children.push(child);
+        for (_, animation) in graph.iter_mut::<Animation>() {
+            for channel in &mut animation.channels {
+                if channel.node == handle && channel.property == Property::MorphTargetWeights {
+                    channel.node = child;
+                }
+            }
+        }
+    }
+}
+
+/// Converts float vertex attributes to normalized 8- or 16-bit integers and
+/// declares `KHR_mesh_quantization` when required.
+///
+/// Positions are quantized per mesh relative to the mesh's bounding box. The
+/// resulting offset and uniform scale are folded into the transform of every
+/// node that instantiates the mesh; when such a node has children or is
+/// animated, the mesh is moved to a new child node instead, along with its
+/// morph weights and their animation channels. For nodes that use
+/// `EXT_mesh_gpu_instancing`, the transform is folded into float
+/// `TRANSLATION` and `SCALE` instance attributes instead. Meshes that are
+/// skinned, not instantiated by any node, instantiated with unreadable
+/// instance attributes, or instantiated by a node with levels of detail that
+/// has children or is animated keep float positions.
+///
+/// Normals, tangents, and morph target normal and tangent displacements
+/// become signed normalized integers. Texture coordinates and colors become
+/// unsigned normalized integers when they lie within `[0, 1]`; other sets are
+/// left unchanged. Weights are renormalized after rounding so each vertex's
+/// weights still sum to one.
+///
+/// Accessor `min` and `max` are recomputed when the graph is written, and
+/// accessors that are no longer referenced are removed.
+pub fn quantize(graph: &mut Graph, options: &QuantizeOptions) {
+    let mut replaced = HashSet::new();
+
+    if options.position_bits > 0 {
+        let animated: HashSet<_> = graph
+            .iter::<Animation>()
+            .flat_map(|(_, animation)| animation.channels.iter().map(|x| x.node))
+            .collect();
+        for mesh in graph.handles::<Mesh>() {
+            quantize_positions(graph, mesh, options.position_bits, &animated, &mut replaced);
+        }
+    }
+
+    let mut quantized: HashMap<Handle<Accessor>, Option<Handle<Accessor>>> = HashMap::new();
+    for mesh in graph.handles::<Mesh>() {
+        let mut primitives = std::mem::take(&mut graph[mesh].primitives);
+        for primitive in &mut primitives {
+            let mut slots: Vec<(Semantic, &mut Handle<Accessor>)> = primitive
+                .attributes
+                .iter_mut()
+                .map(|(semantic, handle)| (semantic.clone(), handle))
+                .collect();
+            for target in &mut primitive.targets {
+                slots.extend(target.normals.as_mut().map(|x| (Semantic::Normals, x)));
+                slots.extend(target.tangents.as_mut().map(|x| (Semantic::Tangents, x)));
+            }
+            for (semantic, handle) in slots {
+                let replacement = *quantized.entry(*handle).or_insert_with(|| {
+                    let accessor = graph.get(*handle)?;
+                    let accessor = match semantic {
+                        Semantic::Normals => {
+                            quantize_accessor(accessor, options.normal_bits, true, |_, x| x)
+                        }
+                        Semantic::Tangents => {
+                            quantize_accessor(accessor, options.tangent_bits, true, |_, x| x)
+                        }
+                        Semantic::TexCoords(_) => {
+                            quantize_accessor(accessor, options.tex_coord_bits, false, |_, x| x)
+                        }
+                        Semantic::Colors(_) => {
+                            quantize_accessor(accessor, options.color_bits, false, |_, x| x)
+                        }
+                        Semantic::Weights(_) => quantize_weights(accessor, options.weight_bits),
+                        _ => None,
+                    }?;
+                    Some(graph.add(accessor))
+                });
+                if let Some(replacement) = replacement {
+                    replaced.insert(*handle);
+                    *handle = replacement;
+                }
+            }
+        }
+        graph[mesh].primitives = primitives;
+    }
+
+    remove_unused_accessors(graph, replaced);
+    graph.update_extensions();
+}
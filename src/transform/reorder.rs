@@ -0,0 +1,441 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use crate::mesh::Mode;
+use crate::Semantic;
+
+use super::{index_accessor, remove_unused_accessors, Adjacency};
+
+/// Options for [`reorder`].
+///
+/// [`reorder`]: fn.reorder.html
+#[derive(Clone, Debug)]
+pub struct ReorderOptions {
+    /// The size of the simulated post-transform vertex cache.
+    pub cache_size: usize,
+
+    /// How much worse the vertex cache efficiency of a cluster of triangles
+    /// may get to reduce overdraw, e.g. `1.05` for 5%, or 0 to skip overdraw
+    /// optimization.
+    pub overdraw_threshold: f32,
+}
+
+impl Default for ReorderOptions {
+    fn default() -> Self {
+        Self {
+            cache_size: 16,
+            overdraw_threshold: 1.05,
+        }
+    }
+}
+
+/// Reorders triangles for vertex cache efficiency with the Tipsify
+/// algorithm.
+///
+/// Returns the reordered indices plus the first triangle of every cluster
+/// started from a dead end, which are the places the order can be broken up
+/// without further loss of cache efficiency.
+fn optimize_vertex_cache(
+    indices: &[u32],
+    vertex_count: usize,
+    cache_size: usize,
+) -> (Vec<u32>, Vec<usize>) {
+    let adjacency = Adjacency::new(indices, vertex_count);
+    let mut live: Vec<usize> = (0..vertex_count as u32)
+        .map(|x| adjacency.get(x).len())
+        .collect();
+    let mut cache_time = vec![0; vertex_count];
+    let mut timestamp = cache_size + 1;
+    let mut emitted = vec![false; indices.len() / 3];
+    let mut dead_end = Vec::new();
+    let mut cursor = 0;
+    let mut output = Vec::with_capacity(indices.len());
+    let mut clusters = Vec::new();
+
+    let mut next = (0..vertex_count).find(|x| live[*x] > 0).map(|x| x as u32);
+    if next.is_some() {
+        clusters.push(0);
+    }
+    while let Some(vertex) = next {
+        let mut candidates = Vec::new();
+        for triangle in adjacency.get(vertex) {
+            if emitted[*triangle] {
+                continue;
+            }
+            emitted[*triangle] = true;
+            for v in &indices[triangle * 3..triangle * 3 + 3] {
+                output.push(*v);
+                dead_end.push(*v);
+                if !candidates.contains(v) {
+                    candidates.push(*v);
+                }
+                let v = *v as usize;
+                live[v] -= 1;
+                if timestamp - cache_time[v] > cache_size {
+                    cache_time[v] = timestamp;
+                    timestamp += 1;
+                }
+            }
+        }
+
+        // Prefer the candidate that stays in the cache the longest while its
+        // remaining triangles are emitted.
+        let mut best = None;
+        let mut priority = 0;
+        for v in candidates {
+            let age = timestamp - cache_time[v as usize];
+            let remaining = live[v as usize];
+            if remaining == 0 {
+                continue;
+            }
+            let p = if age + 2 * remaining <= cache_size {
+                age
+            } else {
+                0
+            };
+            if best.is_none() || p > priority {
+                best = Some(v);
+                priority = p;
+            }
+        }
+        next = best.or_else(|| {
+            let restart = std::iter::from_fn(|| dead_end.pop())
+                .find(|x| live[*x as usize] > 0)
+                .or_else(|| {
+                    while cursor < vertex_count && live[cursor] == 0 {
+                        cursor += 1;
+                    }
+                    Some(cursor as u32).filter(|_| cursor < vertex_count)
+                });
+            if restart.is_some() {
+                clusters.push(output.len() / 3);
+            }
+            restart
+        });
+    }
+    (output, clusters)
+}
+
+/// Simulates a FIFO vertex cache for one triangle, advancing `timestamp`
+/// once per cache miss.
+fn update_cache(triangle: &[u32], cache_time: &mut [usize], timestamp: &mut usize, size: usize) {
+    for v in triangle {
+        let v = *v as usize;
+        if *timestamp - cache_time[v] > size {
+            cache_time[v] = *timestamp;
+            *timestamp += 1;
+        }
+    }
+}
+
+/// Splits the clusters produced by [`optimize_vertex_cache`] at points where
+/// the vertex cache efficiency so far is within `threshold` of the cluster
+/// as a whole.
+fn soft_boundaries(
+    indices: &[u32],
+    clusters: &[usize],
+    vertex_count: usize,
+    cache_size: usize,
+    threshold: f32,
+) -> Vec<usize> {
+    let triangle_count = indices.len() / 3;
+    let mut cache_time = vec![0; vertex_count];
+    let mut timestamp = cache_size + 1;
+    let mut misses = |triangle: usize, timestamp: &mut usize| {
+        let before = *timestamp;
+        let triangle = &indices[triangle * 3..triangle * 3 + 3];
+        update_cache(triangle, &mut cache_time, timestamp, cache_size);
+        *timestamp - before
+    };
+
+    let mut boundaries = Vec::new();
+    for (i, start) in clusters.iter().enumerate() {
+        let start = *start;
+        let end = clusters.get(i + 1).copied().unwrap_or(triangle_count);
+        timestamp += cache_size + 1;
+        let total: usize = (start..end).map(|x| misses(x, &mut timestamp)).sum();
+        let cluster_threshold = threshold * total as f32 / (end - start) as f32;
+
+        boundaries.push(start);
+        timestamp += cache_size + 1;
+        let (mut running_misses, mut running_faces) = (0, 0);
+        for triangle in start..end {
+            running_misses += misses(triangle, &mut timestamp);
+            running_faces += 1;
+            if running_misses as f32 / running_faces as f32 <= cluster_threshold {
+                boundaries.push(triangle + 1);
+                timestamp += cache_size + 1;
+                running_misses = 0;
+                running_faces = 0;
+            }
+        }
+
+        // The remainder after the last split is usually too small to be
+        // efficient on its own, so it is merged into the previous cluster.
+        if boundaries.last() != Some(&start) {
+            boundaries.pop();
+        }
+    }
+    boundaries
+}
+
+/// Sorts clusters of triangles so that those facing away from the center of
+/// the mesh are drawn first, which occludes the rest.
+fn optimize_overdraw(
+    indices: &[u32],
+    clusters: &[usize],
+    positions: &[[f32; 3]],
+    cache_size: usize,
+    threshold: f32,
+) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    let clusters = soft_boundaries(indices, clusters, positions.len(), cache_size, threshold);
+
+    let mut center = [0.0f64; 3];
+    for index in indices {
+        for (c, p) in center.iter_mut().zip(&positions[*index as usize]) {
+            *c += *p as f64;
+        }
+    }
+    let center = center.map(|x| (x / indices.len().max(1) as f64) as f32);
+
+    let mut keys: Vec<(usize, f32)> = Vec::with_capacity(clusters.len());
+    for (i, start) in clusters.iter().enumerate() {
+        let end = clusters.get(i + 1).copied().unwrap_or(triangle_count);
+        let mut centroid = [0.0f32; 3];
+        let mut normal = [0.0f32; 3];
+        let mut area = 0.0;
+        for triangle in indices[start * 3..end * 3].chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            let e1 = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let e2 = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let n = [
+                e1[1] * e2[2] - e1[2] * e2[1],
+                e1[2] * e2[0] - e1[0] * e2[2],
+                e1[0] * e2[1] - e1[1] * e2[0],
+            ];
+            let weight = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            for k in 0..3 {
+                centroid[k] += (a[k] + b[k] + c[k]) / 3.0 * weight;
+                normal[k] += n[k];
+            }
+            area += weight;
+        }
+        let inverse_area = if area == 0.0 { 0.0 } else { 1.0 / area };
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        let inverse_length = if length == 0.0 { 0.0 } else { 1.0 / length };
+        let key = (0..3)
+            .map(|k| (centroid[k] * inverse_area - center[k]) * normal[k] * inverse_length)
+            .sum();
+        keys.push((i, key));
+    }
+    // The sort is stable, so ties keep their cache-optimized order.
+    keys.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut output = Vec::with_capacity(indices.len());
+    for (i, _) in keys {
+        let start = clusters[i];
+        let end = clusters.get(i + 1).copied().unwrap_or(triangle_count);
+        output.extend_from_slice(&indices[start * 3..end * 3]);
+    }
+    output
+}
+
+/// Primitives that share the same set of vertex accessors.
+struct Group {
+    accessors: Vec<Handle<Accessor>>,
+    primitives: Vec<(Handle<Mesh>, usize)>,
+}
+
+/// Returns the vertex accessors of a primitive, sorted, and their common
+/// vertex count.
+fn vertex_accessors(
+    graph: &Graph,
+    primitive: &Primitive,
+) -> Option<(Vec<Handle<Accessor>>, usize)> {
+    let mut handles: Vec<_> = primitive
+        .accessors()
+        .filter(|x| Some(*x) != primitive.indices)
+        .collect();
+    handles.sort();
+    handles.dedup();
+    let mut counts = handles.iter().map(|x| graph.get(*x).map(Accessor::count));
+    let count = counts.next()??;
+    if counts.all(|x| x == Some(count)) {
+        Some((handles, count))
+    } else {
+        None
+    }
+}
+
+/// Returns the indices of a primitive if they are all in range.
+fn indices(graph: &Graph, primitive: &Primitive, vertex_count: usize) -> Option<Vec<u32>> {
+    let indices = graph.get(primitive.indices?)?.to_u32();
+    if indices.iter().all(|x| (*x as usize) < vertex_count) {
+        Some(indices)
+    } else {
+        None
+    }
+}
+
+/// Reorders the triangles of a primitive, returning the new indices.
+fn reorder_triangles(
+    graph: &Graph,
+    primitive: &Primitive,
+    vertex_count: usize,
+    options: &ReorderOptions,
+) -> Option<Vec<u32>> {
+    let indices = indices(graph, primitive, vertex_count)?;
+    if primitive.mode != Mode::Triangles || indices.len() % 3 != 0 {
+        return None;
+    }
+    let cache_size = options.cache_size.max(3);
+    let (indices, clusters) = optimize_vertex_cache(&indices, vertex_count, cache_size);
+    let positions = primitive
+        .get(&Semantic::Positions)
+        .and_then(|x| graph.get(x))
+        .map(|x| x.to_f32::<3>());
+    match positions {
+        Some(positions) if options.overdraw_threshold > 0.0 => Some(optimize_overdraw(
+            &indices,
+            &clusters,
+            &positions,
+            cache_size,
+            options.overdraw_threshold,
+        )),
+        _ => Some(indices),
+    }
+}
+
+/// Reorders indexed triangle primitives for rendering efficiency.
+///
+/// Triangles are first reordered for post-transform vertex cache efficiency
+/// with the Tipsify algorithm, then clusters of triangles are sorted to reduce
+/// overdraw, see [`ReorderOptions`]. Finally vertices are reordered in the
+/// order they are first referenced, and unreferenced vertices are dropped.
+///
+/// The vertices of a primitive are only reordered if its vertex accessors
+/// are not shared with primitives that use a different set of vertex
+/// accessors; primitives using the same set are reordered together.
+/// Non-indexed primitives are left untouched, see [`weld`]. The result only
+/// depends on the contents of the graph.
+///
+/// Accessors that are no longer referenced afterwards are removed.
+///
+/// [`ReorderOptions`]: struct.ReorderOptions.html
+/// [`weld`]: fn.weld.html
+pub fn reorder(graph: &mut Graph, options: &ReorderOptions) {
+    let meshes = graph.handles::<Mesh>();
+    let mut replaced = HashSet::new();
+
+    // Reorder triangles.
+    let mut reordered = HashMap::new();
+    for mesh in &meshes {
+        let mut primitives = std::mem::take(&mut graph[*mesh].primitives);
+        for primitive in &mut primitives {
+            let (handles, vertex_count) = match vertex_accessors(graph, primitive) {
+                Some(x) => x,
+                None => continue,
+            };
+            let key = (primitive.indices, handles);
+            let handle = match reordered.get(&key) {
+                Some(handle) => *handle,
+                None => match reorder_triangles(graph, primitive, vertex_count, options) {
+                    Some(indices) => {
+                        let template = &graph[primitive.indices.unwrap()];
+                        let handle = graph.add(index_accessor(template, &indices));
+                        reordered.insert(key, handle);
+                        handle
+                    }
+                    None => continue,
+                },
+            };
+            replaced.extend(primitive.indices.replace(handle));
+        }
+        graph[*mesh].primitives = primitives;
+    }
+
+    // Group primitives by their vertex accessors.
+    let mut groups: Vec<Group> = Vec::new();
+    let mut group_of = HashMap::new();
+    let mut owners: HashMap<Handle<Accessor>, usize> = HashMap::new();
+    for mesh in &meshes {
+        for (i, primitive) in graph[*mesh].primitives.iter().enumerate() {
+            let handles = match vertex_accessors(graph, primitive) {
+                Some((handles, _)) => handles,
+                None => primitive
+                    .accessors()
+                    .filter(|x| Some(*x) != primitive.indices)
+                    .collect(),
+            };
+            let group = *group_of.entry(handles.clone()).or_insert_with(|| {
+                groups.push(Group {
+                    accessors: handles.clone(),
+                    primitives: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            groups[group].primitives.push((*mesh, i));
+            for handle in handles {
+                let owner = owners.entry(handle).or_insert(group);
+                if *owner != group {
+                    *owner = usize::MAX;
+                }
+            }
+        }
+    }
+
+    // Reorder the vertices of groups that own their accessors.
+    for (index, group) in groups.iter().enumerate() {
+        let Group {
+            accessors,
+            primitives,
+        } = group;
+        if accessors.is_empty() || accessors.iter().any(|x| owners[x] != index) {
+            continue;
+        }
+        let (mesh, i) = primitives[0];
+        let vertex_count = match vertex_accessors(graph, &graph[mesh].primitives[i]) {
+            Some((_, count)) => count,
+            None => continue,
+        };
+        let lists: Option<Vec<_>> = primitives
+            .iter()
+            .map(|(mesh, i)| indices(graph, &graph[*mesh].primitives[*i], vertex_count))
+            .collect();
+        let lists = match lists {
+            Some(lists) => lists,
+            None => continue,
+        };
+
+        let mut remap = vec![u32::MAX; vertex_count];
+        let mut order = Vec::new();
+        for index in lists.iter().flatten() {
+            if remap[*index as usize] == u32::MAX {
+                remap[*index as usize] = order.len() as u32;
+                order.push(*index);
+            }
+        }
+        if order.iter().enumerate().all(|(i, x)| i as u32 == *x) && order.len() == vertex_count {
+            continue;
+        }
+
+        for handle in accessors {
+            let accessor = graph[*handle].gather(&order);
+            graph[*handle] = accessor;
+        }
+        let mut remapped = HashMap::new();
+        for ((mesh, i), indices) in primitives.iter().zip(lists) {
+            let old = graph[*mesh].primitives[*i].indices.unwrap();
+            let handle = *remapped.entry(old).or_insert_with(|| {
+                let indices: Vec<_> = indices.iter().map(|x| remap[*x as usize]).collect();
+                let accessor = index_accessor(&graph[old], &indices);
+                graph.add(accessor)
+            });
+            graph[*mesh].primitives[*i].indices = Some(handle);
+            replaced.insert(old);
+        }
+    }
+
+    remove_unused_accessors(graph, replaced);
+}
@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use crate::animation::util::{slerp, Keyframes};
+use crate::animation::{Interpolation, Property};
+use crate::graph::{Animation, Channel, Graph};
+
+use super::remove_unused_accessors;
+
+/// Options for [`resample`].
+///
+/// [`resample`]: fn.resample.html
+#[derive(Clone, Debug)]
+pub struct ResampleOptions {
+    /// The largest error allowed where a keyframe is removed: the largest
+    /// component difference for translations, scales and morph target
+    /// weights, and the angle in radians for rotations.
+    pub tolerance: f32,
+}
+
+impl Default for ResampleOptions {
+    fn default() -> Self {
+        Self { tolerance: 1e-4 }
+    }
+}
+
+/// The number of objects removed by [`resample`].
+///
+/// [`resample`]: fn.resample.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ResampleReport {
+    /// The number of keyframes removed from the remaining channels.
+    pub keyframes: usize,
+
+    /// The number of channels removed because they hold the rest pose.
+    pub channels: usize,
+
+    /// The number of animations removed because no channel remained.
+    pub animations: usize,
+}
+
+/// Returns the difference between two output values: the rotation angle
+/// between two quaternions, or the largest component difference.
+pub(super) fn error(a: &[f32], b: &[f32], rotation: bool) -> f32 {
+    if !rotation {
+        return a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+    }
+    let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+    let (mut difference, mut sum) = (0.0f32, 0.0f32);
+    for (a, b) in a.iter().zip(b) {
+        difference += (a - sign * b).powi(2);
+        sum += (a + sign * b).powi(2);
+    }
+    // Stable for nearly equal rotations, unlike the arccosine of `dot`.
+    4.0 * difference.sqrt().atan2(sum.sqrt())
+}
+
+/// Interpolates linearly from `a` to `b`, or spherically for rotations.
+pub(super) fn mix(a: &[f32], b: &[f32], s: f32, rotation: bool) -> Vec<f32> {
+    if rotation {
+        slerp(a, b, s).to_vec()
+    } else {
+        a.iter().zip(b).map(|(a, b)| a + (b - a) * s).collect()
+    }
+}
+
+/// Evaluates the segment from keyframe `a` to keyframe `b` at time `t`, as
+/// if there were no keyframes between them.
+fn interpolate(keyframes: &Keyframes, a: usize, b: usize, t: f32) -> Vec<f32> {
+    let (ta, tb) = (keyframes.inputs()[a], keyframes.inputs()[b]);
+    let s = if tb > ta { (t - ta) / (tb - ta) } else { 0.0 };
+    let (a, b) = (keyframes.value(a), keyframes.value(b));
+    match keyframes.interpolation() {
+        Interpolation::Linear => mix(a, b, s, keyframes.is_rotation()),
+        _ => a.to_vec(),
+    }
+}
+
+/// Returns whether every keyframe holds the value of the first one, with
+/// flat tangents.
+fn is_constant(keyframes: &Keyframes, tolerance: f32) -> bool {
+    let rotation = keyframes.is_rotation();
+    let first = keyframes.value(0);
+    (1..keyframes.len()).all(|i| error(keyframes.value(i), first, rotation) <= tolerance)
+        && (0..keyframes.len()).all(|i| {
+            keyframes
+                .tangents(i)
+                .is_none_or(|(a, b)| a.iter().chain(b).all(|x| x.abs() <= tolerance))
+        })
+}
+
+/// Returns the keyframes to keep, in ascending order.
+///
+/// Constant channels keep their first and last keyframes only, so that the
+/// time range of the channel is preserved. Otherwise a keyframe is removed
+/// if every keyframe removed since the last one kept is reproduced by the
+/// segment from that keyframe to the next one. Cubic spline keyframes are
+/// only removed from constant channels.
+pub(super) fn simplify(keyframes: &Keyframes, tolerance: f32) -> Vec<usize> {
+    let count = keyframes.len();
+    let rotation = keyframes.is_rotation();
+    if is_constant(keyframes, tolerance) {
+        return if count > 1 {
+            vec![0, count - 1]
+        } else {
+            vec![0]
+        };
+    }
+    if keyframes.interpolation() == Interpolation::CubicSpline {
+        return (0..count).collect();
+    }
+    let inputs = keyframes.inputs();
+    let mut keep = vec![0];
+    for i in 1..count - 1 {
+        let last = keep[keep.len() - 1];
+        let redundant = (last + 1..=i).all(|j| {
+            let value = interpolate(keyframes, last, i + 1, inputs[j]);
+            error(&value, keyframes.value(j), rotation) <= tolerance
+        });
+        if !redundant {
+            keep.push(i);
+        }
+    }
+    keep.push(count - 1);
+    keep
+}
+
+/// Returns the value the target of `channel` has when it is not animated, or
+/// `None` if the node has no morph target weights.
+fn rest_value(graph: &Graph, channel: &Channel) -> Option<Vec<f32>> {
+    let node = graph.get(channel.node)?;
+    let (translation, rotation, scale) = node.transform.clone().decomposed();
+    Some(match channel.property {
+        Property::Translation => translation.to_vec(),
+        Property::Rotation => rotation.to_vec(),
+        Property::Scale => scale.to_vec(),
+        Property::MorphTargetWeights => {
+            let mesh = node.mesh.and_then(|x| graph.get(x));
+            match (&node.weights, mesh) {
+                (Some(weights), _) => weights.clone(),
+                (None, Some(mesh)) => match &mesh.weights {
+                    Some(weights) => weights.clone(),
+                    None => vec![0.0; mesh.primitives.first()?.targets.len()],
+                },
+                (None, None) => return None,
+            }
+        }
+    })
+}
+
+/// Replaces the accessors of `channel` with copies holding only the `keep`
+/// keyframes.
+fn gather_keyframes(
+    graph: &mut Graph,
+    channel: &mut Channel,
+    keyframes: &Keyframes,
+    keep: &[usize],
+) {
+    let per_key = match keyframes.interpolation() {
+        Interpolation::CubicSpline => 3,
+        _ => 1,
+    };
+    let output = &graph[channel.output];
+    // Weight channels have one output element per morph target.
+    let elements = output.count() / (keyframes.len() * per_key);
+    let outputs: Vec<u32> = keep
+        .iter()
+        .flat_map(|key| {
+            let start = key * per_key * elements;
+            (start..start + per_key * elements).map(|x| x as u32)
+        })
+        .collect();
+    let output = output.gather(&outputs);
+    let keys: Vec<u32> = keep.iter().map(|x| *x as u32).collect();
+    let input = graph[channel.input].gather(&keys);
+    channel.input = graph.add(input);
+    channel.output = graph.add(output);
+}
+
+/// Removes redundant animation keyframes.
+///
+/// Keyframes that the remaining keyframes reproduce within
+/// `options.tolerance` are removed, and constant channels are reduced to their
+/// first and last keyframes. Constant channels that hold the rest value of
+/// their target are removed, unless they extend the time range of the
+/// remaining channels, along with animations left without channels. Channels
+/// whose accessors cannot be read are left untouched.
+///
+/// Changed channels get new accessors with the same component types, and
+/// accessors no longer used are removed.
+pub fn resample(graph: &mut Graph, options: &ResampleOptions) -> ResampleReport {
+    let mut report = ResampleReport::default();
+    let mut replaced = HashSet::new();
+    for handle in graph.handles::<Animation>() {
+        let channels = std::mem::take(&mut graph[handle].channels);
+        let count = channels.len();
+        let mut planned = Vec::with_capacity(count);
+        // The time range of the channels that are not at rest.
+        let (mut start, mut end) = (f32::INFINITY, f32::NEG_INFINITY);
+        for channel in channels {
+            let keyframes = match channel.keyframes(graph) {
+                Some(keyframes) => keyframes,
+                None => {
+                    planned.push((channel, None, false));
+                    continue;
+                }
+            };
+            let at_rest = is_constant(&keyframes, options.tolerance)
+                && rest_value(graph, &channel).is_some_and(|rest| {
+                    let value = keyframes.value(0);
+                    rest.len() == value.len()
+                        && error(&rest, value, keyframes.is_rotation()) <= options.tolerance
+                });
+            if !at_rest {
+                start = start.min(keyframes.inputs()[0]);
+                end = end.max(keyframes.inputs()[keyframes.len() - 1]);
+            }
+            planned.push((channel, Some(keyframes), at_rest));
+        }
+
+        let mut kept = Vec::with_capacity(count);
+        for (mut channel, keyframes, at_rest) in planned {
+            let keyframes = match keyframes {
+                Some(keyframes) => keyframes,
+                None => {
+                    kept.push(channel);
+                    continue;
+                }
+            };
+            let inputs = keyframes.inputs();
+            let (first, last) = (inputs[0], inputs[inputs.len() - 1]);
+            // Channels at rest are dropped when there is no other readable
+            // channel, or when the others cover their time range.
+            if at_rest && (start > end || first >= start && last <= end) {
+                replaced.insert(channel.input);
+                replaced.insert(channel.output);
+                report.channels += 1;
+                continue;
+            }
+            start = start.min(first);
+            end = end.max(last);
+            let keep = simplify(&keyframes, options.tolerance);
+            if keep.len() < keyframes.len() {
+                replaced.insert(channel.input);
+                replaced.insert(channel.output);
+                gather_keyframes(graph, &mut channel, &keyframes, &keep);
+                report.keyframes += keyframes.len() - keep.len();
+            }
+            kept.push(channel);
+        }
+        let emptied = kept.is_empty() && count > 0;
+        graph[handle].channels = kept;
+        if emptied {
+            graph.remove(handle);
+            report.animations += 1;
+        }
+    }
+    remove_unused_accessors(graph, replaced);
+    report
+}
@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+
+use crate::animation::util::Keyframes;
+use crate::animation::Interpolation;
+use crate::graph::{Animation, Graph, Handle};
+
+use super::{remove_unused_accessors, write_keyframes};
+
+/// A named time range of an animation, for [`split_animation`].
+///
+/// [`split_animation`]: fn.split_animation.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip {
+    /// The name of the animation made from the range.
+    pub name: String,
+
+    /// The start of the range in seconds.
+    pub start: f32,
+
+    /// The end of the range in seconds.
+    pub end: f32,
+}
+
+/// Appends a keyframe to flattened cubic spline or other outputs.
+fn push_key(outputs: &mut Vec<f32>, value: &[f32], tangents: Option<(&[f32], &[f32])>) {
+    match tangents {
+        Some((in_tangent, out_tangent)) => {
+            outputs.extend_from_slice(in_tangent);
+            outputs.extend_from_slice(value);
+            outputs.extend_from_slice(out_tangent);
+        }
+        None => outputs.extend_from_slice(value),
+    }
+}
+
+/// Returns the keyframes between `start` and `end`, with new keyframes at
+/// either end where the range cuts between two keyframes, so that the
+/// channel is unchanged within the range.
+fn cut(keyframes: &Keyframes, start: f32, end: f32) -> Keyframes {
+    let inputs = keyframes.inputs();
+    let cubic = keyframes.interpolation() == Interpolation::CubicSpline;
+    let (first, last) = keyframes.time_range();
+    let mut times = Vec::new();
+    let mut outputs = Vec::new();
+    let boundary = |t: f32, times: &mut Vec<f32>, outputs: &mut Vec<f32>| {
+        if t <= first || t >= last || inputs.contains(&t) {
+            return;
+        }
+        times.push(t);
+        if cubic {
+            let (value, slope) = keyframes.spline_point(t);
+            push_key(outputs, &value, Some((&slope, &slope)));
+        } else {
+            push_key(outputs, &keyframes.sample(t), None);
+        }
+    };
+    boundary(start, &mut times, &mut outputs);
+    for (key, t) in inputs.iter().enumerate() {
+        if *t >= start && *t <= end {
+            times.push(*t);
+            push_key(&mut outputs, keyframes.value(key), keyframes.tangents(key));
+        }
+    }
+    if end > start {
+        boundary(end, &mut times, &mut outputs);
+    }
+    if times.is_empty() {
+        // The range lies before the first keyframe or after the last.
+        let value = keyframes.sample(start);
+        let zero = vec![0.0; value.len()];
+        times.push(start);
+        push_key(
+            &mut outputs,
+            &value,
+            Some((&zero[..], &zero[..])).filter(|_| cubic),
+        );
+    }
+    let rotation = keyframes.is_rotation();
+    Keyframes::new(keyframes.interpolation(), times, outputs, rotation).unwrap()
+}
+
+/// Maps every keyframe time `t` to `t * scale + offset`, scaling cubic
+/// spline tangents to match.
+fn shift(keyframes: &Keyframes, offset: f32, scale: f32) -> Keyframes {
+    let times = keyframes
+        .inputs()
+        .iter()
+        .map(|t| t * scale + offset)
+        .collect();
+    let mut outputs = Vec::with_capacity(keyframes.outputs().len());
+    for key in 0..keyframes.len() {
+        match keyframes.tangents(key) {
+            Some((in_tangent, out_tangent)) => {
+                let in_tangent: Vec<f32> = in_tangent.iter().map(|x| x / scale).collect();
+                let out_tangent: Vec<f32> = out_tangent.iter().map(|x| x / scale).collect();
+                push_key(
+                    &mut outputs,
+                    keyframes.value(key),
+                    Some((&in_tangent, &out_tangent)),
+                );
+            }
+            None => push_key(&mut outputs, keyframes.value(key), None),
+        }
+    }
+    let (interpolation, rotation) = (keyframes.interpolation(), keyframes.is_rotation());
+    Keyframes::new(interpolation, times, outputs, rotation).unwrap()
+}
+
+/// Rewrites the keyframes of every readable channel of `animation` with
+/// `edit`, removing the accessors no longer used.
+fn edit_channels<F>(graph: &mut Graph, animation: Handle<Animation>, edit: F)
+where
+    F: Fn(&Keyframes) -> Keyframes,
+{
+    let mut channels = match graph.get_mut(animation) {
+        Some(animation) => std::mem::take(&mut animation.channels),
+        None => return,
+    };
+    let mut replaced = HashSet::new();
+    for channel in &mut channels {
+        if let Some(keyframes) = channel.keyframes(graph) {
+            replaced.insert(channel.input);
+            replaced.insert(channel.output);
+            write_keyframes(graph, channel, &edit(&keyframes));
+        }
+    }
+    graph[animation].channels = channels;
+    remove_unused_accessors(graph, replaced);
+}
+
+/// Removes the keyframes of `animation` outside the range from `start` to
+/// `end` seconds, without changing the remaining keyframe times.
+///
+/// Channels get new keyframes where the range cuts between two keyframes, so
+/// they are unchanged within the range. Channels whose accessors cannot be
+/// read are left untouched.
+///
+/// # Panics
+///
+/// Panics if `start` is greater than `end`.
+pub fn trim_animation(graph: &mut Graph, animation: Handle<Animation>, start: f32, end: f32) {
+    assert!(start <= end, "the start of the range is after its end");
+    edit_channels(graph, animation, |x| cut(x, start, end));
+}
+
+/// Maps every keyframe time `t` of `animation` to `t * scale + offset`.
+///
+/// A `scale` of `2.0` plays the animation at half speed. Cubic spline
+/// tangents are scaled to match. Channels whose accessors cannot be read are
+/// left untouched.
+///
+/// # Panics
+///
+/// Panics if `scale` is not positive.
+pub fn retime_animation(graph: &mut Graph, animation: Handle<Animation>, offset: f32, scale: f32) {
+    assert!(scale > 0.0, "the time scale is not positive");
+    edit_channels(graph, animation, |x| shift(x, offset, scale));
+}
+
+/// Replaces `animation` with one animation per clip, holding the keyframes
+/// of the clip's time range moved to start at zero.
+///
+/// Returns the new animations in the order of `clips`, or nothing if
+/// `animation` is not part of the graph. Without clips, `animation` is left
+/// untouched. Channels are cut as by
+/// [`trim_animation`]; channels whose accessors cannot be read are left out.
+///
+/// [`trim_animation`]: fn.trim_animation.html
+///
+/// # Panics
+///
+/// Panics if the start of a clip is after its end.
+pub fn split_animation(
+    graph: &mut Graph,
+    animation: Handle<Animation>,
+    clips: &[Clip],
+) -> Vec<Handle<Animation>> {
+    for clip in clips {
+        assert!(
+            clip.start <= clip.end,
+            "the start of a clip is after its end"
+        );
+    }
+    if clips.is_empty() {
+        return Vec::new();
+    }
+    let source = match graph.remove(animation) {
+        Some(source) => source,
+        None => return Vec::new(),
+    };
+    let keyframes: Vec<_> = source.channels.iter().map(|x| x.keyframes(graph)).collect();
+    let mut animations = Vec::with_capacity(clips.len());
+    for clip in clips {
+        let mut channels = Vec::with_capacity(source.channels.len());
+        for (channel, keyframes) in source.channels.iter().zip(&keyframes) {
+            if let Some(keyframes) = keyframes {
+                let mut channel = channel.clone();
+                let keyframes = shift(&cut(keyframes, clip.start, clip.end), -clip.start, 1.0);
+                write_keyframes(graph, &mut channel, &keyframes);
+                channels.push(channel);
+            }
+        }
+        animations.push(graph.add(Animation {
+            name: Some(clip.name.clone()),
+            channels,
+            extras: source.extras.clone(),
+        }));
+    }
+    let replaced = source
+        .channels
+        .iter()
+        .flat_map(|x| [x.input, x.output])
+        .collect();
+    remove_unused_accessors(graph, replaced);
+    animations
+}
+
+/// Moves the channels of every animation in `animations` into the first one,
+/// removing the others.
+///
+/// Returns the merged animation, or `None` without changing the graph if an
+/// animation is not part of the graph or two channels target the same
+/// property of the same node.
+pub fn merge_animations(
+    graph: &mut Graph,
+    animations: &[Handle<Animation>],
+) -> Option<Handle<Animation>> {
+    let mut unique = Vec::with_capacity(animations.len());
+    let mut targets = Vec::new();
+    for handle in animations {
+        if unique.contains(handle) {
+            continue;
+        }
+        for channel in &graph.get(*handle)?.channels {
+            let target = (channel.node, channel.property);
+            if targets.contains(&target) {
+                return None;
+            }
+            targets.push(target);
+        }
+        unique.push(*handle);
+    }
+    let (first, rest) = unique.split_first()?;
+    for handle in rest {
+        let animation = graph.remove(*handle).unwrap();
+        graph[*first].channels.extend(animation.channels);
+    }
+    Some(*first)
+}
@@ -0,0 +1,542 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{Graph, Mesh, Primitive};
+use crate::mesh::Mode;
+use crate::Semantic;
+
+use super::{cross, dot, index_accessor, normalize, remove_unused_accessors, sub, Adjacency};
+
+/// Options for [`simplify`] and [`simplify_primitive`].
+///
+/// Simplification stops at whichever limit is reached first.
+///
+/// [`simplify`]: fn.simplify.html
+/// [`simplify_primitive`]: fn.simplify_primitive.html
+#[derive(Clone, Debug)]
+pub struct SimplifyOptions {
+    /// The fraction of triangles to keep.
+    pub ratio: f32,
+
+    /// The largest deviation from the original surface, relative to the
+    /// largest extent of the primitive's bounding box.
+    pub error: f32,
+
+    /// Whether vertices on open borders of the mesh are kept in place.
+    pub lock_border: bool,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        Self {
+            ratio: 0.5,
+            error: 0.01,
+            lock_border: false,
+        }
+    }
+}
+
+/// The result of [`simplify_primitive`].
+///
+/// [`simplify_primitive`]: fn.simplify_primitive.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Simplified {
+    /// The new triangle list indices, referring to the original vertices.
+    pub indices: Vec<u32>,
+
+    /// The largest deviation from the original surface introduced, relative
+    /// to the largest extent of the primitive's bounding box.
+    pub error: f32,
+}
+
+/// How a vertex may be collapsed, depending on its neighborhood.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    /// An interior vertex, which can be collapsed onto any neighbor.
+    Manifold,
+
+    /// A vertex on an open border, which can only move along the border.
+    Border,
+
+    /// One of two vertices on an attribute seam, which move together along
+    /// the seam.
+    Seam,
+
+    /// A vertex that must stay in place.
+    Locked,
+}
+
+/// A symmetric 4x4 matrix measuring the squared distance to a set of planes,
+/// weighted by area.
+#[derive(Clone, Copy, Debug, Default)]
+struct Quadric {
+    a: [f64; 10],
+    weight: f64,
+}
+
+impl Quadric {
+    /// Returns the quadric of the plane `n.x + d = 0` with the given weight.
+    fn plane(n: [f64; 3], d: f64, weight: f64) -> Self {
+        let [x, y, z] = n;
+        let a = [
+            x * x,
+            x * y,
+            x * z,
+            x * d,
+            y * y,
+            y * z,
+            y * d,
+            z * z,
+            z * d,
+            d * d,
+        ];
+        Self {
+            a: a.map(|v| v * weight),
+            weight,
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.a.iter_mut().zip(&other.a) {
+            *a += b;
+        }
+        self.weight += other.weight;
+    }
+
+    /// Returns the mean squared distance of `p` to the planes.
+    fn error(&self, p: [f64; 3]) -> f64 {
+        let [x, y, z] = p;
+        let a = &self.a;
+        let e = a[0] * x * x
+            + a[4] * y * y
+            + a[7] * z * z
+            + 2.0 * (a[1] * x * y + a[2] * x * z + a[5] * y * z)
+            + 2.0 * (a[3] * x + a[6] * y + a[8] * z)
+            + a[9];
+        if self.weight > 0.0 {
+            e.abs() / self.weight
+        } else {
+            e.abs()
+        }
+    }
+}
+
+/// The weight of the planes keeping border and seam edges in place.
+const EDGE_WEIGHT: f64 = 10.0;
+
+/// The mesh being simplified.
+struct Simplifier<'a> {
+    positions: &'a [[f64; 3]],
+
+    /// The first vertex with the same position as each vertex.
+    remap: Vec<u32>,
+
+    /// The vertices sharing the position of each vertex, including itself.
+    wedges: HashMap<u32, Vec<u32>>,
+
+    /// The quadric of each position, indexed by `remap`.
+    quadrics: Vec<Quadric>,
+
+    lock_border: bool,
+}
+
+/// The classification of the current mesh.
+struct Topology {
+    kinds: Vec<Kind>,
+
+    /// The next vertex along the open edge leaving each vertex.
+    next: Vec<u32>,
+
+    /// The previous vertex along the open edge entering each vertex.
+    previous: Vec<u32>,
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(positions: &'a [[f64; 3]], indices: &[u32], lock_border: bool) -> Self {
+        let mut first = HashMap::new();
+        let mut remap = Vec::with_capacity(positions.len());
+        let mut wedges: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (i, p) in positions.iter().enumerate() {
+            let key = p.map(f64::to_bits);
+            let rep = *first.entry(key).or_insert(i as u32);
+            remap.push(rep);
+            wedges.entry(rep).or_default().push(i as u32);
+        }
+        let mut simplifier = Self {
+            positions,
+            remap,
+            wedges,
+            quadrics: vec![Quadric::default(); positions.len()],
+            lock_border,
+        };
+        simplifier.fill_quadrics(indices);
+        simplifier
+    }
+
+    fn position(&self, v: u32) -> [f64; 3] {
+        self.positions[v as usize]
+    }
+
+    fn wedge(&self, v: u32) -> &[u32] {
+        &self.wedges[&self.remap[v as usize]]
+    }
+
+    /// Accumulates the plane of every triangle, plus planes through open
+    /// edges that keep borders and seams from drifting.
+    fn fill_quadrics(&mut self, indices: &[u32]) {
+        let edges: HashSet<(u32, u32)> = indices
+            .chunks_exact(3)
+            .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+            .collect();
+        for t in indices.chunks_exact(3) {
+            let [p0, p1, p2] = [0, 1, 2].map(|i| self.position(t[i]));
+            let (normal, area) = normalize(cross(sub(p1, p0), sub(p2, p0)));
+            if area == 0.0 {
+                continue;
+            }
+            let quadric = Quadric::plane(normal, -dot(normal, p0), area * 0.5);
+            for v in t {
+                self.quadrics[self.remap[*v as usize] as usize].add(&quadric);
+            }
+            for k in 0..3 {
+                let (a, b) = (t[k], t[(k + 1) % 3]);
+                if edges.contains(&(b, a)) {
+                    continue;
+                }
+                let edge = sub(self.position(b), self.position(a));
+                let (n, length) = normalize(cross(edge, normal));
+                let quadric =
+                    Quadric::plane(n, -dot(n, self.position(a)), length * length * EDGE_WEIGHT);
+                for v in [a, b] {
+                    self.quadrics[self.remap[v as usize] as usize].add(&quadric);
+                }
+            }
+        }
+    }
+
+    /// Classifies the vertices of the current mesh.
+    fn classify(&self, indices: &[u32]) -> Topology {
+        let count = self.positions.len();
+        let edges: HashSet<(u32, u32)> = indices
+            .chunks_exact(3)
+            .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+            .collect();
+        let position_edges: HashSet<(u32, u32)> = edges
+            .iter()
+            .map(|(a, b)| (self.remap[*a as usize], self.remap[*b as usize]))
+            .collect();
+
+        let mut open_out = vec![0; count];
+        let mut open_in = vec![0; count];
+        let mut next = vec![u32::MAX; count];
+        let mut previous = vec![u32::MAX; count];
+        // Whether an open edge at the vertex is also open between positions.
+        let mut border = vec![false; count];
+        let mut seam = vec![false; count];
+        let mut sorted: Vec<_> = edges.iter().copied().collect();
+        sorted.sort_unstable();
+        for (a, b) in sorted {
+            if edges.contains(&(b, a)) {
+                continue;
+            }
+            open_out[a as usize] += 1;
+            open_in[b as usize] += 1;
+            next[a as usize] = b;
+            previous[b as usize] = a;
+            let reverse = (self.remap[b as usize], self.remap[a as usize]);
+            let flag = if position_edges.contains(&reverse) {
+                &mut seam
+            } else {
+                &mut border
+            };
+            flag[a as usize] = true;
+            flag[b as usize] = true;
+        }
+
+        let mut kinds = vec![Kind::Locked; count];
+        for v in 0..count as u32 {
+            let i = v as usize;
+            let wedge = self.wedge(v);
+            let single = open_in[i] == 1 && open_out[i] == 1;
+            kinds[i] = match wedge.len() {
+                1 if open_in[i] == 0 && open_out[i] == 0 => Kind::Manifold,
+                1 if single && !seam[i] && !self.lock_border => Kind::Border,
+                2 => {
+                    let u = wedge[(wedge[0] == v) as usize];
+                    let j = u as usize;
+                    let mirrored = open_in[j] == 1
+                        && open_out[j] == 1
+                        && self.remap[next[i] as usize] == self.remap[previous[j] as usize]
+                        && self.remap[previous[i] as usize] == self.remap[next[j] as usize];
+                    if single && mirrored && !border[i] && !border[j] {
+                        Kind::Seam
+                    } else {
+                        Kind::Locked
+                    }
+                }
+                _ => Kind::Locked,
+            };
+        }
+        Topology {
+            kinds,
+            next,
+            previous,
+        }
+    }
+
+    /// Returns the vertex pairs to collapse for collapsing `from` onto `to`,
+    /// or `None` if the collapse is not allowed.
+    fn collapse(&self, topology: &Topology, from: u32, to: u32) -> Option<Vec<(u32, u32)>> {
+        let kind = |v: u32| topology.kinds[v as usize];
+        let along = |v: u32| topology.next[v as usize] == to || topology.previous[v as usize] == to;
+        match kind(from) {
+            Kind::Manifold => Some(vec![(from, to)]),
+            Kind::Border if along(from) && matches!(kind(to), Kind::Border | Kind::Locked) => {
+                Some(vec![(from, to)])
+            }
+            Kind::Seam if along(from) && matches!(kind(to), Kind::Seam | Kind::Locked) => {
+                let wedge = self.wedge(from);
+                let partner = wedge[(wedge[0] == from) as usize];
+                let target = if topology.next[from as usize] == to {
+                    topology.previous[partner as usize]
+                } else {
+                    topology.next[partner as usize]
+                };
+                if self.remap[target as usize] == self.remap[to as usize] {
+                    Some(vec![(from, to), (partner, target)])
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the error of moving the position of `from` onto `to`.
+    fn error(&self, from: u32, to: u32) -> f64 {
+        self.quadrics[self.remap[from as usize] as usize].error(self.position(to))
+    }
+
+    /// Returns whether moving `from` onto `to` flips a triangle around
+    /// `from`.
+    fn flips(&self, indices: &[u32], adjacency: &Adjacency, from: u32, to: u32) -> bool {
+        let target = self.remap[to as usize];
+        adjacency.get(from).iter().any(|triangle| {
+            let t = &indices[triangle * 3..triangle * 3 + 3];
+            if t.iter().any(|v| self.remap[*v as usize] == target) {
+                return false;
+            }
+            let k = t.iter().position(|v| *v == from).unwrap_or(0);
+            let (b, c) = (self.position(t[(k + 1) % 3]), self.position(t[(k + 2) % 3]));
+            let before = cross(sub(b, self.position(from)), sub(c, self.position(from)));
+            let after = cross(sub(b, self.position(to)), sub(c, self.position(to)));
+            dot(before, before) > 0.0 && dot(before, after) <= 0.0
+        })
+    }
+}
+
+/// Simplifies a triangle list, returning the new indices and the squared
+/// error of positions normalized to the unit cube.
+fn simplify_indices(
+    positions: &[[f64; 3]],
+    mut indices: Vec<u32>,
+    target: usize,
+    error_limit: f64,
+    lock_border: bool,
+) -> (Vec<u32>, f64) {
+    let mut simplifier = Simplifier::new(positions, &indices, lock_border);
+    let mut result_error = 0.0f64;
+
+    while indices.len() / 3 > target {
+        let topology = simplifier.classify(&indices);
+        let adjacency = Adjacency::new(&indices, positions.len());
+
+        // Pick the cheaper direction of every edge that can be collapsed.
+        let mut edges: Vec<(u32, u32)> = indices
+            .chunks_exact(3)
+            .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        let mut candidates = Vec::new();
+        for (a, b) in edges {
+            let best = [(a, b), (b, a)]
+                .iter()
+                .filter_map(|(from, to)| {
+                    let pairs = simplifier.collapse(&topology, *from, *to)?;
+                    let error = pairs
+                        .iter()
+                        .map(|(from, to)| simplifier.error(*from, *to))
+                        .fold(0.0, f64::max);
+                    Some((error, pairs))
+                })
+                .min_by(|x, y| x.0.total_cmp(&y.0));
+            if let Some((error, pairs)) = best {
+                if error <= error_limit {
+                    candidates.push((error, pairs));
+                }
+            }
+        }
+        candidates.sort_by(|x, y| x.0.total_cmp(&y.0).then_with(|| x.1.cmp(&y.1)));
+
+        // Collapse the cheapest edges whose neighborhoods are untouched.
+        let goal = indices.len() / 3 - target;
+        let mut removed = 0;
+        let mut locked = vec![false; positions.len()];
+        let mut remap: Vec<u32> = (0..positions.len() as u32).collect();
+        for (error, pairs) in candidates {
+            if removed >= goal {
+                break;
+            }
+            let touched = pairs.iter().any(|(from, to)| {
+                locked[simplifier.remap[*from as usize] as usize]
+                    || locked[simplifier.remap[*to as usize] as usize]
+            });
+            if touched
+                || pairs
+                    .iter()
+                    .any(|(from, to)| simplifier.flips(&indices, &adjacency, *from, *to))
+            {
+                continue;
+            }
+            for (from, to) in &pairs {
+                remap[*from as usize] = *to;
+                locked[simplifier.remap[*from as usize] as usize] = true;
+                locked[simplifier.remap[*to as usize] as usize] = true;
+                let shared = adjacency
+                    .get(*from)
+                    .iter()
+                    .filter(|t| indices[*t * 3..*t * 3 + 3].contains(to))
+                    .count();
+                removed += shared;
+            }
+            let (from, to) = pairs[0];
+            let quadric = simplifier.quadrics[simplifier.remap[from as usize] as usize];
+            simplifier.quadrics[simplifier.remap[to as usize] as usize].add(&quadric);
+            result_error = result_error.max(error);
+        }
+        if removed == 0 {
+            break;
+        }
+
+        let remapped: Vec<u32> = indices.iter().map(|x| remap[*x as usize]).collect();
+        indices = remapped
+            .chunks_exact(3)
+            .filter(|t| {
+                let [a, b, c] = [0, 1, 2].map(|i| simplifier.remap[t[i] as usize]);
+                a != b && b != c && c != a
+            })
+            .flatten()
+            .copied()
+            .collect();
+    }
+    (indices, result_error)
+}
+
+/// Simplifies a triangle list primitive with quadric error metrics.
+///
+/// Edges are collapsed onto existing vertices in order of increasing error
+/// until the number of triangles drops to [`SimplifyOptions::ratio`] or no
+/// collapse stays within [`SimplifyOptions::error`]. The vertex data is not
+/// changed, so the result refers to the original vertices of the primitive
+/// and keeps all of their attributes.
+///
+/// Vertices sharing a position with a different set of attributes form
+/// seams, e.g. along UV or normal discontinuities. Seam vertices only move
+/// along the seam together with their counterpart on the other side, and
+/// vertices where seams meet are kept in place, as are vertices on open
+/// borders with [`SimplifyOptions::lock_border`].
+///
+/// Returns `None` if the primitive does not consist of triangles, has no
+/// positions, or has indices out of range. Primitives are expected to be
+/// indexed with duplicate vertices merged, see [`weld`]; otherwise every
+/// vertex is treated as a seam and nothing can be simplified.
+///
+/// [`SimplifyOptions::ratio`]: struct.SimplifyOptions.html#structfield.ratio
+/// [`SimplifyOptions::error`]: struct.SimplifyOptions.html#structfield.error
+/// [`SimplifyOptions::lock_border`]: struct.SimplifyOptions.html#structfield.lock_border
+/// [`weld`]: fn.weld.html
+pub fn simplify_primitive(
+    graph: &Graph,
+    primitive: &Primitive,
+    options: &SimplifyOptions,
+) -> Option<Simplified> {
+    if primitive.mode != Mode::Triangles {
+        return None;
+    }
+    let positions = graph.get(primitive.get(&Semantic::Positions)?)?;
+    let vertex_count = positions.count();
+    let indices = match primitive.indices {
+        Some(handle) => graph.get(handle)?.to_u32(),
+        None => (0..vertex_count as u32).collect(),
+    };
+    if indices.len() % 3 != 0 || indices.iter().any(|x| *x as usize >= vertex_count) {
+        return None;
+    }
+
+    // Normalize positions so that errors are relative to the extent.
+    let positions: Vec<[f32; 3]> = positions.to_f32();
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for p in &positions {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k] as f64);
+            max[k] = max[k].max(p[k] as f64);
+        }
+    }
+    let extent = (0..3).map(|k| max[k] - min[k]).fold(0.0, f64::max);
+    let scale = if extent > 0.0 { 1.0 / extent } else { 0.0 };
+    let normalized: Vec<[f64; 3]> = positions
+        .iter()
+        .map(|p| [0, 1, 2].map(|k| (p[k] as f64 - min[k]) * scale))
+        .collect();
+
+    let target = (indices.len() / 3) as f64 * options.ratio.clamp(0.0, 1.0) as f64;
+    let error_limit = (options.error.max(0.0) as f64).powi(2);
+    let (indices, error) = simplify_indices(
+        &normalized,
+        indices,
+        target as usize,
+        error_limit,
+        options.lock_border,
+    );
+    Some(Simplified {
+        indices,
+        error: error.sqrt() as f32,
+    })
+}
+
+/// Simplifies every triangle list primitive, see [`simplify_primitive`].
+///
+/// Each simplified primitive gets a new index accessor of the same component
+/// type. The vertex data is left as is; use [`reorder`] to drop vertices that
+/// are no longer referenced. Accessors that are no longer referenced
+/// afterwards are removed.
+///
+/// [`simplify_primitive`]: fn.simplify_primitive.html
+/// [`reorder`]: fn.reorder.html
+pub fn simplify(graph: &mut Graph, options: &SimplifyOptions) {
+    let mut replaced = HashSet::new();
+    for handle in graph.handles::<Mesh>() {
+        let mut primitives = std::mem::take(&mut graph[handle].primitives);
+        for primitive in &mut primitives {
+            let simplified = match simplify_primitive(graph, primitive, options) {
+                Some(simplified) => simplified,
+                None => continue,
+            };
+            let accessor = match primitive.indices.and_then(|x| graph.get(x)) {
+                Some(template) => index_accessor(template, &simplified.indices),
+                None => {
+                    let vertex_count = primitive
+                        .get(&Semantic::Positions)
+                        .and_then(|x| graph.get(x))
+                        .map_or(0, |x| x.count());
+                    crate::graph::Accessor::from_indices(&simplified.indices, vertex_count)
+                }
+            };
+            replaced.extend(primitive.indices.replace(graph.add(accessor)));
+        }
+        graph[handle].primitives = primitives;
+    }
+    remove_unused_accessors(graph, replaced);
+}
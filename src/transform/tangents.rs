@@ -0,0 +1,318 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use crate::Semantic;
+
+use super::{
+    append_vertices, dot, normalize, remove_unused_accessors, split_vertices, sub, triangle_list,
+    Adjacency,
+};
+
+/// Options for [`tangents`].
+///
+/// [`tangents`]: fn.tangents.html
+#[derive(Clone, Debug, Default)]
+pub struct TangentsOptions {
+    /// Whether existing `TANGENT` attributes are replaced. When unset, only
+    /// primitives without tangents are changed.
+    pub overwrite: bool,
+}
+
+/// The tangent space of one triangle in texture space.
+#[derive(Clone, Copy, Debug)]
+struct Face {
+    /// The direction of increasing `u`, of unit length unless the texture
+    /// coordinates are degenerate.
+    tangent: [f64; 3],
+
+    /// Whether the texture mapping preserves orientation.
+    preserving: bool,
+
+    /// Whether the texture coordinates have no area, so that the triangle
+    /// can join the tangent space of any neighbor.
+    degenerate: bool,
+}
+
+/// The vertex data MikkTSpace reads.
+struct Input<'a> {
+    positions: &'a [[f64; 3]],
+    normals: &'a [[f64; 3]],
+    uvs: &'a [[f64; 2]],
+}
+
+impl Input<'_> {
+    /// Returns the tangent space of the triangle with corners `t`.
+    fn face(&self, t: &[u32]) -> Face {
+        let [p0, p1, p2] = [0, 1, 2].map(|i| self.positions[t[i] as usize]);
+        let [t0, t1, t2] = [0, 1, 2].map(|i| self.uvs[t[i] as usize]);
+        let (d1, d2) = (sub(p1, p0), sub(p2, p0));
+        let (t21, t31) = (
+            [t1[0] - t0[0], t1[1] - t0[1]],
+            [t2[0] - t0[0], t2[1] - t0[1]],
+        );
+        let area = t21[0] * t31[1] - t21[1] * t31[0];
+        let preserving = area > 0.0;
+        let os = [0, 1, 2].map(|i| t31[1] * d1[i] - t21[1] * d2[i]);
+        let (os, _) = normalize(os);
+        let sign = if preserving { 1.0 } else { -1.0 };
+        Face {
+            tangent: os.map(|x| x * sign),
+            preserving,
+            degenerate: area == 0.0,
+        }
+    }
+
+    /// Returns `v` projected onto the plane of `normal` and normalized.
+    fn project(normal: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+        let d = dot(normal, v);
+        normalize([0, 1, 2].map(|i| v[i] - normal[i] * d)).0
+    }
+
+    /// Returns the angle of corner `k` of the triangle `t`, measured in the
+    /// plane of the vertex normal.
+    fn angle(&self, t: &[u32], k: usize) -> f64 {
+        let p = |i: usize| self.positions[t[(k + i) % 3] as usize];
+        let normal = self.normals[t[k] as usize];
+        let a = Self::project(normal, sub(p(2), p(0)));
+        let b = Self::project(normal, sub(p(1), p(0)));
+        dot(a, b).clamp(-1.0, 1.0).acos()
+    }
+}
+
+/// Returns any unit vector perpendicular to `normal`.
+fn perpendicular(normal: [f64; 3]) -> [f64; 3] {
+    let axis = if normal[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    Input::project(normal, axis)
+}
+
+/// Finds the representative of `i`, compressing the path.
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Returns the tangent of every corner of a triangle list, following the
+/// MikkTSpace algorithm with its default settings.
+fn corner_tangents(input: &Input, indices: &[u32]) -> Vec<[f32; 4]> {
+    // MikkTSpace identifies vertices by their position, normal and texture
+    // coordinates rather than by index.
+    let mut unique = HashMap::new();
+    let ids: Vec<u32> = (0..input.positions.len())
+        .map(|i| {
+            let key = (
+                input.positions[i].map(f64::to_bits),
+                input.normals[i].map(f64::to_bits),
+                input.uvs[i].map(f64::to_bits),
+            );
+            let next = unique.len() as u32;
+            *unique.entry(key).or_insert(next)
+        })
+        .collect();
+    let welded: Vec<u32> = indices.iter().map(|x| ids[*x as usize]).collect();
+    let faces: Vec<Face> = indices.chunks(3).map(|t| input.face(t)).collect();
+    let adjacency = Adjacency::new(&welded, unique.len());
+
+    let mut tangents = vec![None; indices.len()];
+    for vertex in 0..unique.len() as u32 {
+        // The corners on this vertex, skipping triangles that collapse to an
+        // edge or a point.
+        let corners: Vec<usize> = adjacency
+            .get(vertex)
+            .iter()
+            .filter(|t| {
+                let w = &welded[*t * 3..*t * 3 + 3];
+                w[0] != w[1] && w[1] != w[2] && w[2] != w[0]
+            })
+            .map(|t| {
+                (0..3)
+                    .map(|k| t * 3 + k)
+                    .find(|c| welded[*c] == vertex)
+                    .unwrap()
+            })
+            .collect();
+        if corners.is_empty() {
+            continue;
+        }
+        let next = |c: usize| welded[c - c % 3 + (c + 1) % 3];
+        let previous = |c: usize| welded[c - c % 3 + (c + 2) % 3];
+
+        // Group the corners whose triangles share an edge through the vertex
+        // and agree on orientation. Triangles with degenerate texture
+        // coordinates join the first group they share an edge with.
+        let mut parents: Vec<usize> = (0..corners.len()).collect();
+        let connected = |a: usize, b: usize| {
+            next(corners[a]) == previous(corners[b]) || previous(corners[a]) == next(corners[b])
+        };
+        for a in 0..corners.len() {
+            for b in a + 1..corners.len() {
+                let (fa, fb) = (faces[corners[a] / 3], faces[corners[b] / 3]);
+                if !fa.degenerate
+                    && !fb.degenerate
+                    && fa.preserving == fb.preserving
+                    && connected(a, b)
+                {
+                    let (ra, rb) = (find(&mut parents, a), find(&mut parents, b));
+                    parents[rb] = ra;
+                }
+            }
+        }
+        for a in 0..corners.len() {
+            if faces[corners[a] / 3].degenerate {
+                let group = (0..corners.len())
+                    .find(|b| !faces[corners[*b] / 3].degenerate && connected(a, *b));
+                if let Some(b) = group {
+                    parents[a] = find(&mut parents, b);
+                }
+            }
+        }
+
+        // Average the projected face tangents of each group, weighted by the
+        // corner angles.
+        let normal = input.normals[indices[corners[0]] as usize];
+        let mut sums: HashMap<usize, ([f64; 3], bool)> = HashMap::new();
+        for a in 0..corners.len() {
+            let face = faces[corners[a] / 3];
+            let root = find(&mut parents, a);
+            let entry = sums.entry(root).or_insert(([0.0; 3], true));
+            if !face.degenerate {
+                let t = &indices[corners[a] - corners[a] % 3..][..3];
+                let weight = input.angle(t, corners[a] % 3);
+                let tangent = Input::project(normal, face.tangent);
+                for (sum, x) in entry.0.iter_mut().zip(tangent) {
+                    *sum += x * weight;
+                }
+                entry.1 = face.preserving;
+            }
+        }
+        for a in 0..corners.len() {
+            let (sum, preserving) = sums[&find(&mut parents, a)];
+            let (tangent, length) = normalize(sum);
+            let tangent = if length > 0.0 {
+                tangent
+            } else {
+                perpendicular(normal)
+            };
+            let sign = if preserving { 1.0 } else { -1.0 };
+            let [x, y, z] = tangent.map(|x| x as f32);
+            tangents[corners[a]] = Some([x, y, z, sign]);
+        }
+    }
+
+    // Corners of collapsed triangles take the tangent of another corner on
+    // the same vertex.
+    let mut fallback: HashMap<u32, [f32; 4]> = HashMap::new();
+    for (corner, tangent) in tangents.iter().enumerate() {
+        if let Some(tangent) = tangent {
+            fallback.entry(welded[corner]).or_insert(*tangent);
+        }
+    }
+    tangents
+        .iter()
+        .enumerate()
+        .map(|(corner, tangent)| {
+            tangent
+                .or_else(|| fallback.get(&welded[corner]).copied())
+                .unwrap_or_else(|| {
+                    let normal = input.normals[indices[corner] as usize];
+                    let [x, y, z] = perpendicular(normal).map(|x| x as f32);
+                    [x, y, z, 1.0]
+                })
+        })
+        .collect()
+}
+
+/// Generates tangents for one primitive, returning the accessors it no
+/// longer references.
+fn primitive_tangents(
+    graph: &mut Graph,
+    primitive: &mut Primitive,
+    options: &TangentsOptions,
+) -> Vec<Handle<Accessor>> {
+    if primitive.get(&Semantic::Tangents).is_some() && !options.overwrite {
+        return Vec::new();
+    }
+    let set = match primitive
+        .material
+        .and_then(|x| graph.get(x))
+        .and_then(|x| x.normal_texture.as_ref())
+    {
+        Some(info) => info.tex_coord,
+        None => return Vec::new(),
+    };
+    let (vertex_count, mut indices) = match triangle_list(graph, primitive) {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    let read = |semantic: Semantic| {
+        primitive
+            .get(&semantic)
+            .and_then(|x| graph.get(x))
+            .map(|x| x.to_f32::<3>())
+    };
+    let (positions, normals, uvs) = match (
+        read(Semantic::Positions),
+        read(Semantic::Normals),
+        read(Semantic::TexCoords(set)),
+    ) {
+        (Some(positions), Some(normals), Some(uvs)) => (positions, normals, uvs),
+        _ => return Vec::new(),
+    };
+    let widen = |x: &[f32; 3]| x.map(f64::from);
+    let positions: Vec<_> = positions.iter().map(widen).collect();
+    let normals: Vec<_> = normals.iter().map(|x| normalize(widen(x)).0).collect();
+    let uvs: Vec<_> = uvs.iter().map(|x| [x[0].into(), x[1].into()]).collect();
+    let input = Input {
+        positions: &positions,
+        normals: &normals,
+        uvs: &uvs,
+    };
+
+    let corners = corner_tangents(&input, &indices);
+    let fallback = [1.0, 0.0, 0.0, 1.0];
+    let (tangents, extra) = split_vertices(&mut indices, &corners, vertex_count, fallback);
+    let mut replaced: Vec<_> = primitive.remove(&Semantic::Tangents).into_iter().collect();
+    replaced.extend(append_vertices(
+        graph,
+        primitive,
+        vertex_count,
+        &extra,
+        &indices,
+    ));
+    primitive.set(Semantic::Tangents, graph.add(Accessor::from_f32(&tangents)));
+    replaced
+}
+
+/// Generates MikkTSpace tangents for normal mapped triangle primitives.
+///
+/// Primitives are changed if their material has a normal texture and they
+/// have positions, normals, and the texture coordinates the normal texture
+/// uses; see [`normals`] for generating missing normals. Tangents follow the
+/// MikkTSpace algorithm with its default settings, so they match those of
+/// common normal map bakers, and their `w` component holds the handedness
+/// of the tangent basis. Vertices used by corners that need different
+/// tangents, e.g. on texture mirroring seams, are split, which gives the
+/// primitive new attribute accessors and a new index accessor.
+///
+/// Only primitives with mode `TRIANGLES` are changed, and only those without
+/// a `TANGENT` attribute unless `overwrite` is set. Accessors that are no
+/// longer referenced afterwards are removed.
+///
+/// [`normals`]: fn.normals.html
+pub fn tangents(graph: &mut Graph, options: &TangentsOptions) {
+    let mut replaced = HashSet::new();
+    for handle in graph.handles::<Mesh>() {
+        let mut primitives = std::mem::take(&mut graph[handle].primitives);
+        for primitive in &mut primitives {
+            replaced.extend(primitive_tangents(graph, primitive, options));
+        }
+        graph[handle].primitives = primitives;
+    }
+    remove_unused_accessors(graph, replaced);
+}
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use crate::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use crate::mesh::Mode;
+
+use super::{remove_unused_accessors, vertex_count};
+
+/// Returns the index list of the primitive in its unified mode, or `None` if
+/// it is left unchanged.
+fn unified_indices(graph: &Graph, primitive: &Primitive) -> Option<(Mode, Vec<u32>)> {
+    let indexed = primitive.indices.is_some();
+    match primitive.mode {
+        Mode::Triangles if indexed => None,
+        Mode::Triangles => Some((
+            Mode::Triangles,
+            primitive.triangles(graph).flatten().collect(),
+        )),
+        Mode::TriangleStrip | Mode::TriangleFan => {
+            // Strips are commonly stitched together with repeated indices,
+            // which leave degenerate triangles that draw nothing.
+            let indices = primitive
+                .triangles(graph)
+                .filter(|[a, b, c]| a != b && b != c && c != a)
+                .flatten()
+                .collect();
+            Some((Mode::Triangles, indices))
+        }
+        Mode::Lines if indexed => None,
+        Mode::Lines | Mode::LineStrip | Mode::LineLoop => {
+            let count = primitive.draw_count(graph)?;
+            let vertices = match primitive.indices {
+                Some(indices) => graph.get(indices)?.to_u32(),
+                None => (0..count as u32).collect(),
+            };
+            let indices = match primitive.mode {
+                Mode::Lines => vertices[..count - count % 2].to_vec(),
+                mode => {
+                    let mut indices: Vec<u32> = vertices.windows(2).flatten().copied().collect();
+                    if mode == Mode::LineLoop && vertices.len() > 2 {
+                        indices.extend_from_slice(&[vertices[vertices.len() - 1], vertices[0]]);
+                    }
+                    indices
+                }
+            };
+            Some((Mode::Lines, indices))
+        }
+        Mode::Points => None,
+    }
+}
+
+/// Unifies the topology of one primitive, returning the accessors it no
+/// longer references.
+fn unify_primitive(graph: &mut Graph, primitive: &mut Primitive) -> Vec<Handle<Accessor>> {
+    let vertex_count = match vertex_count(graph, primitive) {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    let (mode, indices) = match unified_indices(graph, primitive) {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    if indices.is_empty() || indices.iter().any(|x| *x as usize >= vertex_count) {
+        return Vec::new();
+    }
+    primitive.mode = mode;
+    let indices = graph.add(Accessor::from_indices(&indices, vertex_count));
+    primitive.indices.replace(indices).into_iter().collect()
+}
+
+/// Converts every primitive into an indexed list of triangles or lines.
+///
+/// Triangle strips and fans become `TRIANGLES` with the winding order of
+/// every triangle kept; the degenerate triangles commonly used to stitch
+/// strips together are dropped. Line strips and loops become `LINES`. Non
+/// indexed triangle and line lists get sequential indices; use [`weld`] to
+/// also merge their duplicate vertices. Vertex attributes are not changed.
+///
+/// Points, and primitives whose indices reference missing vertices or that
+/// would be left without any triangle or line, are left unchanged. Index
+/// accessors that are no longer referenced afterwards are removed.
+///
+/// [`weld`]: fn.weld.html
+pub fn unify_topology(graph: &mut Graph) {
+    let mut replaced = HashSet::new();
+    for handle in graph.handles::<Mesh>() {
+        let mut primitives = std::mem::take(&mut graph[handle].primitives);
+        for primitive in &mut primitives {
+            replaced.extend(unify_primitive(graph, primitive));
+        }
+        graph[handle].primitives = primitives;
+    }
+    remove_unused_accessors(graph, replaced);
+}
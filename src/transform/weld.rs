@@ -0,0 +1,127 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+
+use super::remove_unused_accessors;
+
+/// Options for [`weld`].
+///
+/// [`weld`]: fn.weld.html
+#[derive(Clone, Debug, Default)]
+pub struct WeldOptions {
+    /// The per-component tolerance used to compare vertices.
+    ///
+    /// When zero, only vertices with bitwise identical attributes are merged.
+    /// Otherwise every component, normalized if its accessor is normalized,
+    /// is rounded to a multiple of `tolerance` and vertices that round to the
+    /// same values are merged; merged vertices therefore differ by less than
+    /// `tolerance` in every component.
+    pub tolerance: f32,
+}
+
+/// Returns the key used to compare vertex `index` of a primitive.
+fn vertex_key(accessors: &[&Accessor], index: usize, tolerance: f32, key: &mut Vec<u8>) {
+    key.clear();
+    for accessor in accessors {
+        let n = accessor.dimensions.multiplicity();
+        for i in index * n..(index + 1) * n {
+            if tolerance > 0.0 {
+                let x = accessor.values.get_f32(i, accessor.normalized);
+                let cell = (x / tolerance).round() as i64;
+                key.extend_from_slice(&cell.to_le_bytes());
+            } else {
+                accessor.values.write_component(i, key);
+            }
+        }
+    }
+}
+
+/// Welds one primitive, returning the accessors it no longer references.
+fn weld_primitive(
+    graph: &mut Graph,
+    primitive: &mut Primitive,
+    options: &WeldOptions,
+) -> Vec<Handle<Accessor>> {
+    let handles: Vec<_> = primitive
+        .accessors()
+        .filter(|x| Some(*x) != primitive.indices)
+        .collect();
+    let accessors: Option<Vec<&Accessor>> = handles.iter().map(|x| graph.get(*x)).collect();
+    let accessors = match accessors {
+        Some(accessors) if !accessors.is_empty() => accessors,
+        _ => return Vec::new(),
+    };
+    let vertex_count = accessors[0].count();
+    if accessors.iter().any(|x| x.count() != vertex_count) {
+        return Vec::new();
+    }
+    let indices = match primitive.indices.and_then(|x| graph.get(x)) {
+        Some(indices) => indices.to_u32(),
+        None if primitive.indices.is_some() => return Vec::new(),
+        None => (0..vertex_count as u32).collect(),
+    };
+    if indices.iter().any(|x| *x as usize >= vertex_count) {
+        return Vec::new();
+    }
+
+    // Map every vertex to the first vertex with the same key.
+    let mut unique = HashMap::new();
+    let mut remap = vec![0; vertex_count];
+    let mut vertices = Vec::new();
+    let mut key = Vec::new();
+    for (vertex, slot) in remap.iter_mut().enumerate() {
+        vertex_key(&accessors, vertex, options.tolerance, &mut key);
+        *slot = match unique.entry(key.clone()) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                vertices.push(vertex as u32);
+                *entry.insert(vertices.len() as u32 - 1)
+            }
+        };
+    }
+    if vertices.len() == vertex_count && primitive.indices.is_some() {
+        return Vec::new();
+    }
+
+    let welded: Vec<_> = accessors.iter().map(|x| x.gather(&vertices)).collect();
+    let indices: Vec<_> = indices.iter().map(|x| remap[*x as usize]).collect();
+    let mut replaced = handles.clone();
+    replaced.extend(primitive.indices);
+    let mut replacements: HashMap<_, _> = HashMap::new();
+    for (handle, accessor) in handles.into_iter().zip(welded) {
+        replacements
+            .entry(handle)
+            .or_insert_with(|| graph.add(accessor));
+    }
+    for accessor in primitive.accessors_mut() {
+        if let Some(replacement) = replacements.get(accessor) {
+            *accessor = *replacement;
+        }
+    }
+    primitive.indices = Some(graph.add(Accessor::from_indices(&indices, vertices.len())));
+    replaced
+}
+
+/// Merges duplicate vertices of every primitive and indexes it.
+///
+/// Vertices are compared by their full attribute set, including morph target
+/// displacements; see [`WeldOptions`] for how tolerance is applied. Each
+/// welded primitive gets new attribute accessors and a new index accessor of
+/// the smallest component type that fits. Primitives that are already
+/// indexed and have no duplicate vertices are left untouched.
+///
+/// Accessors that are no longer referenced afterwards are removed.
+///
+/// [`WeldOptions`]: struct.WeldOptions.html
+pub fn weld(graph: &mut Graph, options: &WeldOptions) {
+    let mut replaced = HashSet::new();
+    for handle in graph.handles::<Mesh>() {
+        let mut primitives = std::mem::take(&mut graph[handle].primitives);
+        for primitive in &mut primitives {
+            replaced.extend(weld_primitive(graph, primitive, options));
+        }
+        graph[handle].primitives = primitives;
+    }
+    remove_unused_accessors(graph, replaced);
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use common::{assert_close, channel};
+use gltf::animation::{Interpolation, Player, Property, Track, Wrap};
+use gltf::graph::{Accessor, Animation, Graph, Handle, Mesh, MorphTarget, Node, Primitive, Scene};
+use gltf::scene::Transform;
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+/// Builds a scene with a parent at `[1, 0, 0]` and an animated child, plus an
+/// animated node outside the scene.
+fn build() -> (Graph, Handle<Node>, Handle<Node>) {
+    let mut graph = Graph::new();
+    let child = graph.add(Node::default());
+    let parent = graph.add(Node {
+        children: vec![child],
+        transform: Transform::Decomposed {
+            translation: [1.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0; 3],
+        },
+        ..Default::default()
+    });
+    let outside = graph.add(Node::default());
+    graph.add(Scene {
+        nodes: vec![parent],
+        ..Default::default()
+    });
+
+    let slide = channel(
+        &mut graph,
+        child,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0, 2.0],
+        Accessor::from_f32(&[[0.0; 3], [2.0, 0.0, 0.0]]),
+    );
+    let ignored = channel(
+        &mut graph,
+        outside,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0, 10.0],
+        Accessor::from_f32(&[[0.0; 3], [1.0; 3]]),
+    );
+    graph.add(Animation {
+        channels: vec![slide, ignored],
+        ..Default::default()
+    });
+    let (sin, cos) = std::f32::consts::FRAC_PI_4.sin_cos();
+    let turn = channel(
+        &mut graph,
+        child,
+        Property::Rotation,
+        Interpolation::Linear,
+        &[0.0],
+        Accessor::from_f32(&[[0.0, 0.0, sin, cos]]),
+    );
+    let hold = channel(
+        &mut graph,
+        child,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0],
+        Accessor::from_f32(&[[-2.0, 0.0, 0.0]]),
+    );
+    graph.add(Animation {
+        channels: vec![turn, hold],
+        ..Default::default()
+    });
+    (graph, child, outside)
+}
+
+#[test]
+fn test_wrap() {
+    let (graph, child, outside) = build();
+    let (document, buffers) = graph.to_document().unwrap();
+    let get = |buffer: gltf::Buffer| Some(&buffers[buffer.index()][..]);
+    let scene = document.scenes().next().unwrap();
+    let player = Player::new(&scene, get);
+    let animation = document.animations().next().unwrap();
+    // The channel on the node outside the scene does not count.
+    assert_eq!(player.duration(&animation), Some(2.0));
+
+    let child = document.nodes().nth(child.index()).unwrap();
+    let mut track = Track::new(&animation, 1.0);
+    let pose = player.pose(&[track.clone()]);
+    assert_eq!(pose.local_matrix(&child).unwrap()[3], [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(pose.world_matrix(&child).unwrap()[3], [2.0, 0.0, 0.0, 1.0]);
+
+    let mut translation = |time: f32, wrap: Wrap| {
+        track.time = time;
+        track.wrap = wrap;
+        player.pose(&[track.clone()]).local_matrix(&child).unwrap()[3][0]
+    };
+    assert_eq!(translation(2.5, Wrap::Clamp), 2.0);
+    assert_eq!(translation(2.5, Wrap::Loop), 0.5);
+    assert_eq!(translation(2.5, Wrap::PingPong), 1.5);
+    assert_eq!(translation(-0.5, Wrap::PingPong), 0.5);
+
+    let outside = document.nodes().nth(outside.index()).unwrap();
+    assert!(pose.local_matrix(&outside).is_none());
+    assert!(pose.world_matrix(&outside).is_none());
+}
+
+#[test]
+fn test_blend() {
+    let (graph, child, _) = build();
+    let (document, buffers) = graph.to_document().unwrap();
+    let get = |buffer: gltf::Buffer| Some(&buffers[buffer.index()][..]);
+    let scene = document.scenes().next().unwrap();
+    let player = Player::new(&scene, get);
+    let animations: Vec<_> = document.animations().collect();
+    let child = document.nodes().nth(child.index()).unwrap();
+
+    // Without tracks every node keeps its rest pose.
+    let pose = player.pose(&[]);
+    assert_eq!(pose.world_matrix(&child).unwrap()[3], [1.0, 0.0, 0.0, 1.0]);
+
+    // Equal weights average the translations to zero.
+    let slide = Track::new(&animations[0], 2.0);
+    let turn = Track::new(&animations[1], 0.0);
+    let pose = player.pose(&[slide.clone(), turn.clone()]);
+    let local = pose.local_matrix(&child).unwrap();
+    assert_close(&local[3], &[0.0, 0.0, 0.0, 1.0]);
+
+    // A partial weight mixes with the rest pose: half of a quarter turn.
+    let half = Track {
+        weight: 0.5,
+        ..turn
+    };
+    let local = player.pose(&[half]).local_matrix(&child).unwrap();
+    let (sin, cos) = std::f32::consts::FRAC_PI_4.sin_cos();
+    assert_close(&local[0], &[cos, sin, 0.0, 0.0]);
+    assert_close(&local[3], &[-1.0, 0.0, 0.0, 1.0]);
+
+    let quarter = Track {
+        weight: 0.25,
+        ..slide
+    };
+    let local = player.pose(&[quarter]).local_matrix(&child).unwrap();
+    assert_close(&local[3], &[0.5, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_morph_target_weights() {
+    let mut graph = Graph::new();
+    let mut primitive = Primitive::default();
+    let positions = graph.add(Accessor::from_f32(&[[0.0; 3]]));
+    primitive.set(Semantic::Positions, positions);
+    for _ in 0..2 {
+        primitive.targets.push(MorphTarget {
+            positions: Some(positions),
+            ..Default::default()
+        });
+    }
+    let mesh = graph.add(Mesh {
+        primitives: vec![primitive],
+        weights: Some(vec![0.5, 0.5]),
+        ..Default::default()
+    });
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    let other = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![node, other],
+        ..Default::default()
+    });
+    let weights = channel(
+        &mut graph,
+        node,
+        Property::MorphTargetWeights,
+        Interpolation::Linear,
+        &[0.0, 1.0],
+        Accessor::from_f32(&[[0.0], [1.0], [1.0], [0.0]]),
+    );
+    graph.add(Animation {
+        channels: vec![weights],
+        ..Default::default()
+    });
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let get = |buffer: gltf::Buffer| Some(&buffers[buffer.index()][..]);
+    let scene = document.scenes().next().unwrap();
+    let player = Player::new(&scene, get);
+    let animation = document.animations().next().unwrap();
+    let pose = player.pose(&[Track::new(&animation, 0.25)]);
+    let node = document.nodes().nth(node.index()).unwrap();
+    let other = document.nodes().nth(other.index()).unwrap();
+    assert_eq!(pose.weights(&node), Some(&[0.25, 0.75][..]));
+    // Unanimated nodes take the weights of their mesh.
+    assert_eq!(pose.weights(&other), Some(&[0.5, 0.5][..]));
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::{assert_close, channel};
+use gltf::accessor::Dimensions;
+use gltf::animation::util::{Keyframes, Value};
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Graph, Node, Values};
+use gltf_transform_rs as gltf;
+
+#[test]
+fn test_sample_channels() {
+    let mut graph = Graph::new();
+    let node = graph.add(Node::default());
+    let h = std::f32::consts::FRAC_1_SQRT_2;
+    let translation = channel(
+        &mut graph,
+        node,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0, 1.0, 3.0],
+        Accessor::from_f32(&[[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 4.0, 0.0]]),
+    );
+    let scale = channel(
+        &mut graph,
+        node,
+        Property::Scale,
+        Interpolation::Step,
+        &[0.0, 1.0],
+        Accessor::from_f32(&[[1.0; 3], [2.0; 3]]),
+    );
+    // A quarter turn about Z, quantized to normalized shorts.
+    let q = (h * 32767.0).round() as i16;
+    let rotations = Accessor {
+        normalized: true,
+        ..Accessor::new(
+            Dimensions::Vec4,
+            Values::I16(vec![0, 0, 0, 32767, 0, 0, q, q]),
+        )
+    };
+    let rotation = channel(
+        &mut graph,
+        node,
+        Property::Rotation,
+        Interpolation::Linear,
+        &[0.0, 1.0],
+        rotations,
+    );
+    graph.add(Animation {
+        channels: vec![translation, scale, rotation],
+        ..Default::default()
+    });
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let get = |buffer: gltf::Buffer| Some(&buffers[buffer.index()][..]);
+    let animation = document.animations().next().unwrap();
+    let channels: Vec<_> = animation.channels().collect();
+
+    let sampler = channels[0].sampler();
+    assert_eq!(sampler.evaluate(0.5, get), Some(vec![1.0, 0.0, 0.0]));
+    assert_eq!(sampler.evaluate(2.0, get), Some(vec![2.0, 2.0, 0.0]));
+    // Times outside the keyframe range are clamped.
+    assert_eq!(sampler.evaluate(-1.0, get), Some(vec![0.0, 0.0, 0.0]));
+    assert_eq!(sampler.evaluate(10.0, get), Some(vec![2.0, 4.0, 0.0]));
+    assert_eq!(
+        channels[0].sample(1.0, get),
+        Some(Value::Translation([2.0, 0.0, 0.0]))
+    );
+
+    assert_eq!(channels[1].sample(0.99, get), Some(Value::Scale([1.0; 3])));
+    assert_eq!(channels[1].sample(1.0, get), Some(Value::Scale([2.0; 3])));
+
+    // Halfway along the quarter turn is an eighth turn, not a linear blend.
+    let (sin, cos) = std::f32::consts::FRAC_PI_8.sin_cos();
+    match channels[2].sample(0.5, get) {
+        Some(Value::Rotation(value)) => assert_close(&value, &[0.0, 0.0, sin, cos]),
+        other => panic!("unexpected value {:?}", other),
+    }
+    let keyframes = channels[2].sampler().keyframes(get).unwrap();
+    assert!(keyframes.is_rotation());
+    assert_eq!(keyframes.len(), 2);
+    assert_eq!(keyframes.value(0), &[0.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_sample_morph_target_weights() {
+    let mut graph = Graph::new();
+    let node = graph.add(Node::default());
+    let weights = Accessor {
+        normalized: true,
+        ..Accessor::new(Dimensions::Scalar, Values::U8(vec![0, 255, 255, 0]))
+    };
+    let channel = channel(
+        &mut graph,
+        node,
+        Property::MorphTargetWeights,
+        Interpolation::Linear,
+        &[0.0, 2.0],
+        weights,
+    );
+    graph.add(Animation {
+        channels: vec![channel],
+        ..Default::default()
+    });
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let get = |buffer: gltf::Buffer| Some(&buffers[buffer.index()][..]);
+    let channel = document
+        .animations()
+        .next()
+        .unwrap()
+        .channels()
+        .next()
+        .unwrap();
+    assert_eq!(channel.sampler().keyframes(get).unwrap().width(), 2);
+    assert_eq!(
+        channel.sample(0.5, get),
+        Some(Value::MorphTargetWeights(vec![0.25, 0.75]))
+    );
+}
+
+#[test]
+fn test_cubic_spline() {
+    // Each keyframe stores an in-tangent, a value and an out-tangent.
+    let outputs = vec![0.0, 0.0, 3.0, 0.0, 1.0, 0.0];
+    let keyframes = Keyframes::new(Interpolation::CubicSpline, vec![0.0, 1.0], outputs, false);
+    let keyframes = keyframes.unwrap();
+    assert_eq!(keyframes.width(), 1);
+    assert_eq!(keyframes.value(1), &[1.0]);
+    assert_eq!(keyframes.tangents(0), Some((&[0.0][..], &[3.0][..])));
+    // h00 * 0 + h10 * 3 + h01 * 1 + h11 * 0 at s = 0.5.
+    assert_close(&keyframes.sample(0.5), &[0.375 + 0.5]);
+    assert_eq!(keyframes.sample(1.0), vec![1.0]);
+
+    assert!(Keyframes::new(Interpolation::Linear, vec![], vec![], false).is_none());
+    assert!(Keyframes::new(Interpolation::Linear, vec![1.0, 0.0], vec![0.0; 2], false).is_none());
+    assert!(Keyframes::new(Interpolation::Linear, vec![0.0, 1.0], vec![0.0; 3], false).is_none());
+    assert!(Keyframes::new(Interpolation::Linear, vec![0.0], vec![0.0; 3], true).is_none());
+}
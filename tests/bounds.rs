@@ -0,0 +1,228 @@
+mod common;
+
+use common::{add_mesh, primitive};
+use gltf::accessor::Dimensions;
+use gltf::bounds::{self, Sphere};
+use gltf::graph::{Accessor, Buffer, Graph, Instancing, MorphTarget, Node, Scene, Values};
+use gltf::scene::{Hierarchy, Transform};
+use gltf_transform_rs as gltf;
+
+fn translation(x: f32, y: f32, z: f32) -> Transform {
+    Transform::Decomposed {
+        translation: [x, y, z],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+        scale: [1.0; 3],
+    }
+}
+
+#[test]
+fn test_quantized_and_morphed() {
+    let mut graph = Graph::new();
+    let values = Values::I16(vec![0, 0, 0, 32767, 0, 0, 0, -32767, 0]);
+    let positions = Accessor {
+        normalized: true,
+        ..Accessor::new(Dimensions::Vec3, values)
+    };
+    let primitive = primitive(&mut graph, positions);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let displacements = [[0.0, 0.0, 0.0], [0.0, 0.0, 2.0], [0.0, 0.0, -1.0]];
+    let target = MorphTarget {
+        positions: Some(graph.add(Accessor::from_f32(&displacements))),
+        ..Default::default()
+    };
+    graph[mesh].primitives[0].targets.push(target);
+    graph[mesh].weights = Some(vec![0.0]);
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let mesh = document.meshes().next().unwrap();
+    let primitive = mesh.primitives().next().unwrap();
+    let aabb = bounds::primitive_aabb(&primitive, &buffers)
+        .unwrap()
+        .unwrap();
+    assert_eq!(aabb.min, [0.0, -1.0, -1.0]);
+    assert_eq!(aabb.max, [1.0, 0.0, 2.0]);
+    assert_eq!(bounds::mesh_aabb(&mesh, &buffers).unwrap(), Some(aabb));
+
+    let sphere = bounds::primitive_sphere(&primitive, &buffers)
+        .unwrap()
+        .unwrap();
+    assert_eq!(sphere.center, [0.5, -0.5, 0.5]);
+    // The farthest corner reachable by the second vertex.
+    let expected = (0.25f32 + 0.25 + 2.25).sqrt();
+    assert!((sphere.radius - expected).abs() < 1e-6);
+}
+
+#[test]
+fn test_sparse_positions() {
+    let mut data = Vec::new();
+    for x in [0.0f32, 0.0, 0.0, 1.0, 1.0, 1.0, 2.0, 0.0, 0.0] {
+        data.extend_from_slice(&x.to_le_bytes());
+    }
+    data.extend_from_slice(&[1, 0, 0, 0]);
+    for x in [-5.0f32, 4.0, 3.0] {
+        data.extend_from_slice(&x.to_le_bytes());
+    }
+    let json = format!(
+        r#"{{
+            "asset": {{"version": "2.0"}},
+            "buffers": [{{"byteLength": {}}}],
+            "bufferViews": [
+                {{"buffer": 0, "byteLength": 36}},
+                {{"buffer": 0, "byteOffset": 36, "byteLength": 1}},
+                {{"buffer": 0, "byteOffset": 40, "byteLength": 12}}
+            ],
+            "accessors": [{{
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC3",
+                "sparse": {{
+                    "count": 1,
+                    "indices": {{"bufferView": 1, "componentType": 5121}},
+                    "values": {{"bufferView": 2}}
+                }}
+            }}],
+            "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}}}]}}]
+        }}"#,
+        data.len()
+    );
+    let gltf = gltf::Gltf::from_slice_without_validation(json.as_bytes()).unwrap();
+    let buffers = vec![gltf::buffer::Data(data)];
+    let primitive = gltf.meshes().next().unwrap().primitives().next().unwrap();
+
+    // The accessor declares no `min` and `max`.
+    assert!(primitive.try_bounding_box().is_none());
+    let aabb = bounds::primitive_aabb(&primitive, &buffers)
+        .unwrap()
+        .unwrap();
+    assert_eq!(aabb.min, [-5.0, 0.0, 0.0]);
+    assert_eq!(aabb.max, [2.0, 4.0, 3.0]);
+}
+
+#[test]
+fn test_scene_aabb() {
+    let mut graph = Graph::new();
+    let unit = [[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]];
+    let primitive = primitive(&mut graph, Accessor::from_f32(&unit));
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let child = graph.add(Node {
+        mesh: Some(mesh),
+        transform: Transform::Decomposed {
+            translation: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [2.0; 3],
+        },
+        ..Default::default()
+    });
+    let parent = graph.add(Node {
+        children: vec![child],
+        transform: translation(10.0, 0.0, 0.0),
+        ..Default::default()
+    });
+    let offsets = graph.add(Accessor::from_f32(&[[0.0, 0.0, 0.0], [0.0, -5.0, 0.0]]));
+    let mut instancing = Instancing::default();
+    instancing.set("TRANSLATION", offsets);
+    let instanced = graph.add(Node {
+        mesh: Some(mesh),
+        instancing: Some(instancing),
+        ..Default::default()
+    });
+    // Not part of the scene.
+    let other = graph.add(Node {
+        mesh: Some(mesh),
+        transform: translation(100.0, 0.0, 0.0),
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![parent, instanced],
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![other],
+        ..Default::default()
+    });
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let scene = document.scenes().next().unwrap();
+    let aabb = bounds::scene_aabb(&scene, &buffers).unwrap().unwrap();
+    assert_eq!(aabb.min, [0.0, -5.0, 0.0]);
+    assert_eq!(aabb.max, [12.0, 2.0, 2.0]);
+
+    let hierarchy = Hierarchy::new(&document);
+    let child = document.nodes().nth(child.index()).unwrap();
+    let aabb = bounds::node_aabb(&child, &hierarchy, &buffers).unwrap();
+    assert_eq!(aabb.unwrap().min, [10.0, 0.0, 0.0]);
+    let mesh = document.meshes().next().unwrap();
+    let sphere = bounds::mesh_sphere(&mesh, &buffers).unwrap();
+    assert_eq!(
+        sphere,
+        Some(Sphere {
+            center: [0.5; 3],
+            radius: 0.75f32.sqrt(),
+        })
+    );
+}
+
+#[test]
+fn test_unreadable_instances() {
+    let mut graph = Graph::new();
+    let primitive = primitive(&mut graph, Accessor::from_f32(&[[0.0; 3], [1.0; 3]]));
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    graph.add(Buffer::default());
+    let separate = graph.add(Buffer {
+        uri: Some("instances.bin".to_string()),
+        ..Default::default()
+    });
+    let offsets = graph.add(Accessor {
+        buffer: Some(separate),
+        ..Accessor::from_f32(&[[0.0, 5.0, 0.0]])
+    });
+    let mut instancing = Instancing::default();
+    instancing.set("TRANSLATION", offsets);
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        instancing: Some(instancing),
+        ..Default::default()
+    });
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let hierarchy = Hierarchy::new(&document);
+    let node = document.nodes().nth(node.index()).unwrap();
+    let aabb = bounds::node_aabb(&node, &hierarchy, &buffers).unwrap();
+    assert_eq!(aabb.unwrap().min, [0.0, 5.0, 0.0]);
+    let aabb = bounds::node_aabb(&node, &hierarchy, &buffers[..1]).unwrap();
+    assert_eq!(aabb, None);
+}
+
+#[test]
+fn test_quantized_instances() {
+    let mut graph = Graph::new();
+    let primitive = primitive(&mut graph, Accessor::from_f32(&[[0.0; 3], [1.0; 3]]));
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let offsets = graph.add(Accessor {
+        normalized: true,
+        ..Accessor::new(Dimensions::Vec3, Values::I8(vec![0, 0, 0, 0, -127, 127]))
+    });
+    let scales = graph.add(Accessor::new(
+        Dimensions::Vec3,
+        Values::U8(vec![1, 1, 1, 2, 2, 2]),
+    ));
+    let mut instancing = Instancing::default();
+    instancing.set("TRANSLATION", offsets);
+    instancing.set("SCALE", scales);
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        instancing: Some(instancing),
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![node],
+        ..Default::default()
+    });
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let scene = document.scenes().next().unwrap();
+    let aabb = bounds::scene_aabb(&scene, &buffers).unwrap().unwrap();
+    assert_eq!(aabb.min, [0.0, -1.0, 0.0]);
+    assert_eq!(aabb.max, [2.0, 1.0, 3.0]);
+}
@@ -0,0 +1,85 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Channel, Graph, Handle, Mesh, Node, Primitive, Scene};
+use gltf::scene::Transform;
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+/// Returns a channel animating `property` of `node` with keyframes at
+/// `times` and the values in `output`.
+pub fn channel(
+    graph: &mut Graph,
+    node: Handle<Node>,
+    property: Property,
+    interpolation: Interpolation,
+    times: &[f32],
+    output: Accessor,
+) -> Channel {
+    let input: Vec<[f32; 1]> = times.iter().map(|x| [*x]).collect();
+    Channel {
+        node,
+        property,
+        input: graph.add(Accessor::from_f32(&input)),
+        output: graph.add(output),
+        interpolation,
+        extras: Default::default(),
+    }
+}
+
+/// Asserts that `a` and `b` have the same length and differ by less than
+/// `1e-5` in every component.
+pub fn assert_close(a: &[f32], b: &[f32]) {
+    assert_eq!(a.len(), b.len(), "{:?} != {:?}", a, b);
+    for (x, y) in a.iter().zip(b) {
+        assert!((x - y).abs() < 1e-5, "{:?} != {:?}", a, b);
+    }
+}
+
+/// Returns a primitive with `positions` and no indices.
+pub fn primitive(graph: &mut Graph, positions: Accessor) -> Primitive {
+    let mut primitive = Primitive::default();
+    primitive.set(Semantic::Positions, graph.add(positions));
+    primitive
+}
+
+/// Returns a primitive with one counter-clockwise triangle in the XY plane,
+/// facing `+Z`.
+pub fn triangle(graph: &mut Graph) -> Primitive {
+    let positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+    let mut primitive = primitive(graph, Accessor::from_f32(&positions));
+    primitive.indices = Some(graph.add(Accessor::from_indices(&[0, 1, 2], 3)));
+    primitive
+}
+
+/// Adds a mesh made of `primitives`.
+pub fn add_mesh(graph: &mut Graph, primitives: Vec<Primitive>) -> Handle<Mesh> {
+    graph.add(Mesh {
+        primitives,
+        ..Default::default()
+    })
+}
+
+/// Adds `count` nodes along the X axis, `spacing` apart, that all use
+/// `mesh`, as the roots of a new scene.
+pub fn props(graph: &mut Graph, mesh: Handle<Mesh>, count: usize, spacing: f32) -> Handle<Scene> {
+    let nodes = (0..count)
+        .map(|i| {
+            graph.add(Node {
+                mesh: Some(mesh),
+                transform: Transform::Decomposed {
+                    translation: [i as f32 * spacing, 0.0, 0.0],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    scale: [1.0; 3],
+                },
+                ..Default::default()
+            })
+        })
+        .collect();
+    graph.add(Scene {
+        nodes,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{add_mesh, triangle};
+use gltf::graph::{Accessor, Graph, Image, Material, Node, Texture, TextureInfo};
+use gltf::transform::{dedup, DedupReport};
+use gltf_transform_rs as gltf;
+
+fn textured_material(graph: &mut Graph, data: &[u8]) -> Material {
+    let source = graph.add(Image {
+        data: data.to_vec(),
+        mime_type: Some("image/png".to_string()),
+        ..Default::default()
+    });
+    let texture = graph.add(Texture {
+        name: None,
+        source,
+        sampler: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+    Material {
+        base_color_texture: Some(TextureInfo {
+            texture,
+            tex_coord: 0,
+            extensions: Default::default(),
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_dedup_merges_identical_objects() {
+    let mut graph = Graph::new();
+    let mut nodes = Vec::new();
+    for _ in 0..3 {
+        let material = textured_material(&mut graph, b"\x89PNG pixels");
+        let mut primitive = triangle(&mut graph);
+        primitive.material = Some(graph.add(material));
+        let mesh = add_mesh(&mut graph, vec![primitive]);
+        nodes.push(graph.add(Node {
+            mesh: Some(mesh),
+            ..Default::default()
+        }));
+    }
+    let material = textured_material(&mut graph, b"\x89PNG other pixels");
+    let mut primitive = triangle(&mut graph);
+    primitive.material = Some(graph.add(material));
+    let unique = add_mesh(&mut graph, vec![primitive]);
+
+    let report = dedup(&mut graph);
+    assert_eq!(
+        report,
+        DedupReport {
+            accessors: 6,
+            meshes: 2,
+            materials: 2,
+            samplers: 0,
+            textures: 2,
+            images: 2,
+        }
+    );
+
+    let mesh = graph[nodes[0]].mesh.unwrap();
+    assert!(nodes.iter().all(|node| graph[*node].mesh == Some(mesh)));
+    assert_ne!(mesh, unique);
+    assert_eq!(graph.count::<Accessor>(), 2);
+    assert_eq!(graph.count::<Image>(), 2);
+
+    let (document, _) = graph.to_document().unwrap();
+    assert_eq!(document.meshes().count(), 2);
+    assert_eq!(document.accessors().count(), 2);
+    assert_eq!(document.materials().count(), 2);
+    assert_eq!(document.textures().count(), 2);
+    assert_eq!(document.images().count(), 2);
+}
@@ -0,0 +1,47 @@
+use gltf_transform_rs as gltf;
+use std::{env, fs};
+
+fn positions(document: &gltf::Document, buffers: &[gltf::buffer::Data]) -> Vec<[f32; 3]> {
+    let mesh = document.meshes().next().unwrap();
+    let primitive = mesh.primitives().next().unwrap();
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    reader.read_positions().unwrap().collect()
+}
+
+#[test]
+fn test_export_glb() {
+    let (document, buffers, _) = gltf::import("examples/Box.gltf").unwrap();
+    let bytes = gltf::export_glb(&document, &buffers).unwrap();
+    assert_eq!(bytes.len() % 4, 0);
+
+    let (output, output_buffers, _) = gltf::import_slice(&bytes).unwrap();
+    assert_eq!(output_buffers.len(), 1);
+    assert_eq!(output_buffers[0].len() % 4, 0);
+    assert_eq!(
+        positions(&output, &output_buffers),
+        positions(&document, &buffers)
+    );
+}
+
+#[test]
+fn test_export_sidecar_files() {
+    let directory = env::temp_dir().join("gltf_export_sidecar_files");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let (document, buffers, _) = gltf::import("tests/box_sparse.gltf").unwrap();
+    let path = directory.join("box sparse.gltf");
+    gltf::export(&path, &document, &buffers).unwrap();
+    assert!(directory.join("box sparse.bin").exists());
+
+    let (output, output_buffers, _) = gltf::import(&path).unwrap();
+    match output.buffers().next().unwrap().source() {
+        gltf::buffer::Source::Uri(uri) => assert_eq!(uri, "box%20sparse.bin"),
+        gltf::buffer::Source::Bin => panic!("expected a sidecar buffer"),
+    }
+    assert_eq!(
+        positions(&output, &output_buffers),
+        positions(&document, &buffers)
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
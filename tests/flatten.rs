@@ -0,0 +1,261 @@
+mod common;
+
+use common::{add_mesh, assert_close, triangle};
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Channel, Graph, Handle, Mesh, Node, Scene, Skin};
+use gltf::scene::Transform;
+use gltf::transform::flatten;
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Adds a mesh with one counter-clockwise triangle in the XY plane, facing
+/// `+Z`, with normals and tangents.
+fn mesh(graph: &mut Graph) -> Handle<Mesh> {
+    let mut primitive = triangle(graph);
+    primitive.set(
+        Semantic::Normals,
+        graph.add(Accessor::from_f32(&[[0.0, 0.0, 1.0]; 3])),
+    );
+    primitive.set(
+        Semantic::Tangents,
+        graph.add(Accessor::from_f32(&[[1.0, 0.0, 0.0, 1.0]; 3])),
+    );
+    add_mesh(graph, vec![primitive])
+}
+
+fn decomposed(translation: [f32; 3], rotation: [f32; 4], scale: [f32; 3]) -> Transform {
+    Transform::Decomposed {
+        translation,
+        rotation,
+        scale,
+    }
+}
+
+fn translation(x: f32, y: f32, z: f32) -> Transform {
+    decomposed([x, y, z], [0.0, 0.0, 0.0, 1.0], [1.0; 3])
+}
+
+fn attribute<const N: usize>(
+    graph: &Graph,
+    mesh: Handle<Mesh>,
+    semantic: Semantic,
+) -> Vec<[f32; N]> {
+    graph[graph[mesh].primitives[0].get(&semantic).unwrap()].to_f32()
+}
+
+fn is_identity(node: &Node) -> bool {
+    node.transform.clone().matrix() == IDENTITY
+}
+
+#[test]
+fn test_flatten_hierarchy() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let child = graph.add(Node {
+        mesh: Some(mesh),
+        transform: decomposed([0.0, 0.0, 1.0], [0.0, 0.0, 0.0, 1.0], [2.0; 3]),
+        ..Default::default()
+    });
+    let parent = graph.add(Node {
+        children: vec![child],
+        transform: translation(1.0, 0.0, 0.0),
+        ..Default::default()
+    });
+    let scene = graph.add(Scene {
+        nodes: vec![parent],
+        ..Default::default()
+    });
+
+    flatten(&mut graph);
+    assert_eq!(graph[scene].nodes, vec![parent, child]);
+    assert!(graph[parent].children.is_empty());
+    assert_eq!(
+        graph[parent].transform.clone().matrix()[3],
+        [1.0, 0.0, 0.0, 1.0]
+    );
+    assert!(is_identity(&graph[child]));
+
+    let baked = graph[child].mesh.unwrap();
+    assert!(!graph.contains(mesh));
+    let positions = attribute::<3>(&graph, baked, Semantic::Positions);
+    assert_eq!(
+        positions,
+        vec![[1.0, 0.0, 1.0], [3.0, 0.0, 1.0], [1.0, 2.0, 1.0]]
+    );
+    for normal in attribute::<3>(&graph, baked, Semantic::Normals) {
+        assert_close(&normal, &[0.0, 0.0, 1.0]);
+    }
+    assert_eq!(graph.count::<Mesh>(), 1);
+    assert_eq!(graph.count::<Accessor>(), 4);
+}
+
+#[test]
+fn test_rotation() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    // A quarter turn around the Y axis.
+    let s = 0.5f32.sqrt();
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        transform: decomposed([0.0; 3], [0.0, s, 0.0, s], [1.0, 1.0, 3.0]),
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![node],
+        ..Default::default()
+    });
+
+    flatten(&mut graph);
+    let baked = graph[node].mesh.unwrap();
+    let positions = attribute::<3>(&graph, baked, Semantic::Positions);
+    assert_close(&positions[1], &[0.0, 0.0, -1.0]);
+    for normal in attribute::<3>(&graph, baked, Semantic::Normals) {
+        assert_close(&normal, &[1.0, 0.0, 0.0]);
+    }
+    for tangent in attribute::<4>(&graph, baked, Semantic::Tangents) {
+        assert_close(&tangent, &[0.0, 0.0, -1.0, 1.0]);
+    }
+}
+
+#[test]
+fn test_mirrored_winding() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        transform: decomposed([0.0; 3], [0.0, 0.0, 0.0, 1.0], [1.0, 1.0, -1.0]),
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![node],
+        ..Default::default()
+    });
+
+    flatten(&mut graph);
+    let baked = graph[node].mesh.unwrap();
+    let primitive = &graph[baked].primitives[0];
+    let triangles: Vec<_> = primitive.triangles(&graph).collect();
+    assert_eq!(triangles, vec![[0, 2, 1]]);
+    // The flipped triangle faces `-Z`, as does its normal.
+    for normal in attribute::<3>(&graph, baked, Semantic::Normals) {
+        assert_close(&normal, &[0.0, 0.0, -1.0]);
+    }
+    for tangent in attribute::<4>(&graph, baked, Semantic::Tangents) {
+        assert_close(&tangent, &[1.0, 0.0, 0.0, -1.0]);
+    }
+}
+
+#[test]
+fn test_shared_mesh() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let nodes: Vec<_> = [0.0, 0.0, 5.0]
+        .iter()
+        .map(|x| {
+            graph.add(Node {
+                mesh: Some(mesh),
+                transform: translation(*x, 0.0, 0.0),
+                ..Default::default()
+            })
+        })
+        .collect();
+    let skinned = graph.add(Node {
+        mesh: Some(mesh),
+        transform: translation(7.0, 0.0, 0.0),
+        ..Default::default()
+    });
+    let joint = graph.add(Node {
+        transform: translation(0.0, 7.0, 0.0),
+        ..Default::default()
+    });
+    let skin = graph.add(Skin {
+        joints: vec![joint],
+        ..Default::default()
+    });
+    graph[skinned].skin = Some(skin);
+    let mut roots = nodes.clone();
+    roots.extend_from_slice(&[skinned, joint]);
+    graph.add(Scene {
+        nodes: roots,
+        ..Default::default()
+    });
+
+    flatten(&mut graph);
+    // Identity transforms need no baking, and equal transforms share a mesh.
+    assert_eq!(graph[nodes[0]].mesh, Some(mesh));
+    assert_eq!(graph[nodes[1]].mesh, Some(mesh));
+    assert_ne!(graph[nodes[2]].mesh, Some(mesh));
+    assert_eq!(graph[skinned].mesh, Some(mesh));
+    assert_eq!(
+        graph[skinned].transform.clone().matrix()[3],
+        [7.0, 0.0, 0.0, 1.0]
+    );
+    assert_eq!(
+        graph[joint].transform.clone().matrix()[3],
+        [0.0, 7.0, 0.0, 1.0]
+    );
+    assert_eq!(graph.count::<Mesh>(), 2);
+}
+
+#[test]
+fn test_animated_nodes() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let leaf = graph.add(Node {
+        mesh: Some(mesh),
+        transform: translation(0.0, 1.0, 0.0),
+        ..Default::default()
+    });
+    let animated = graph.add(Node {
+        children: vec![leaf],
+        transform: translation(0.0, 2.0, 0.0),
+        ..Default::default()
+    });
+    let sibling = graph.add(Node {
+        mesh: Some(mesh),
+        transform: translation(0.0, 0.0, 3.0),
+        ..Default::default()
+    });
+    let root = graph.add(Node {
+        children: vec![animated, sibling],
+        transform: translation(4.0, 0.0, 0.0),
+        ..Default::default()
+    });
+    let scene = graph.add(Scene {
+        nodes: vec![root],
+        ..Default::default()
+    });
+    let input = graph.add(Accessor::from_f32(&[[0.0], [1.0]]));
+    let output = graph.add(Accessor::from_f32(&[[0.0, 2.0, 0.0], [0.0, 3.0, 0.0]]));
+    graph.add(Animation {
+        channels: vec![Channel {
+            node: animated,
+            property: Property::Translation,
+            input,
+            output,
+            interpolation: Interpolation::Linear,
+            extras: Default::default(),
+        }],
+        ..Default::default()
+    });
+
+    flatten(&mut graph);
+    // The ancestors of the animated node keep their place and transform.
+    assert_eq!(graph[scene].nodes, vec![root, sibling]);
+    assert_eq!(graph[root].children, vec![animated]);
+    assert_eq!(graph[animated].children, vec![leaf]);
+    assert!(!is_identity(&graph[root]));
+    assert_eq!(graph[leaf].mesh, Some(mesh));
+
+    assert!(is_identity(&graph[sibling]));
+    let baked = graph[sibling].mesh.unwrap();
+    let positions = attribute::<3>(&graph, baked, Semantic::Positions);
+    assert_eq!(positions[0], [4.0, 0.0, 3.0]);
+}
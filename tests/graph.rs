@@ -0,0 +1,207 @@
+use gltf::graph::{Accessor, Animation, Buffer, Graph, Mesh, Node, Primitive, Texture};
+use gltf_transform_rs as gltf;
+
+fn positions(document: &gltf::Document, buffers: &[gltf::buffer::Data]) -> Vec<[f32; 3]> {
+    let mesh = document.meshes().next().unwrap();
+    let primitive = mesh.primitives().next().unwrap();
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    reader.read_positions().unwrap().collect()
+}
+
+#[test]
+fn test_graph_roundtrip() {
+    let (document, buffers, _) = gltf::import("examples/Box.gltf").unwrap();
+    let graph = Graph::from_document(&document, &buffers).unwrap();
+    assert_eq!(graph.count::<Node>(), document.nodes().count());
+    assert_eq!(graph.count::<Accessor>(), document.accessors().count());
+
+    let (output, output_buffers) = graph.to_document().unwrap();
+    assert_eq!(output.nodes().count(), document.nodes().count());
+    assert_eq!(output.accessors().count(), document.accessors().count());
+    assert_eq!(
+        positions(&output, &output_buffers),
+        positions(&document, &buffers)
+    );
+    for view in output.views() {
+        assert_eq!(view.offset() % 4, 0);
+    }
+}
+
+#[test]
+fn test_graph_sparse_accessor() {
+    let (document, buffers, _) = gltf::import("tests/box_sparse.gltf").unwrap();
+    let graph = Graph::from_document(&document, &buffers).unwrap();
+    let (output, output_buffers) = graph.to_document().unwrap();
+    for accessor in output.accessors() {
+        assert!(accessor.sparse().is_none());
+    }
+    assert_eq!(
+        positions(&output, &output_buffers),
+        positions(&document, &buffers)
+    );
+}
+
+#[test]
+fn test_graph_remove_drops_references() {
+    let (document, buffers, _) = gltf::import("examples/Box.gltf").unwrap();
+    let mut graph = Graph::from_document(&document, &buffers).unwrap();
+    let mesh = graph.handles::<Mesh>()[0];
+    graph.remove(mesh);
+    assert!(!graph.contains(mesh));
+
+    let (output, _) = graph.to_document().unwrap();
+    assert_eq!(output.meshes().count(), 0);
+    assert!(output.nodes().all(|node| node.mesh().is_none()));
+}
+
+#[test]
+fn test_graph_skips_empty_objects() {
+    let (document, buffers, _) = gltf::import("examples/Box.gltf").unwrap();
+    let mut graph = Graph::from_document(&document, &buffers).unwrap();
+    graph.add(Buffer {
+        uri: Some("empty.bin".to_string()),
+        ..Default::default()
+    });
+    graph.add(Animation::default());
+    let mut primitive = Primitive::default();
+    let empty: &[[f32; 3]] = &[];
+    primitive.set(
+        gltf::Semantic::Positions,
+        graph.add(Accessor::from_f32(empty)),
+    );
+    let mesh = graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    });
+    graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+
+    let (output, data) = graph.to_document().unwrap();
+    let json = output.into_json_value().unwrap();
+    let bytes = gltf::json::serialize::to_vec(&json).unwrap();
+    let reloaded = gltf::Gltf::from_slice(&bytes).unwrap();
+    assert_eq!(reloaded.buffers().count(), data.len());
+    for buffer in reloaded.buffers() {
+        assert!(buffer.length() > 0);
+        assert_eq!(buffer.length(), data[buffer.index()].len());
+    }
+    for accessor in reloaded.accessors() {
+        assert!(accessor.count() > 0);
+    }
+    assert_eq!(reloaded.animations().count(), 0);
+    assert_eq!(reloaded.meshes().count(), document.meshes().count());
+    assert!(reloaded.meshes().all(|x| x.primitives().count() > 0));
+    assert_eq!(reloaded.nodes().count(), document.nodes().count() + 1);
+}
+
+const EXTENSIONS: &str = r#"{
+    "asset": {"version": "2.0"},
+    "extensionsUsed": [
+        "KHR_lights_punctual",
+        "KHR_materials_clearcoat",
+        "KHR_materials_variants",
+        "KHR_texture_basisu",
+        "KHR_texture_transform"
+    ],
+    "extensionsRequired": ["KHR_texture_basisu"],
+    "extensions": {"KHR_lights_punctual": {"lights": [{"type": "point"}]}},
+    "scene": 0,
+    "scenes": [{"nodes": [0]}],
+    "nodes": [{"mesh": 0, "extensions": {"KHR_lights_punctual": {"light": 0}}}],
+    "meshes": [{
+        "primitives": [{
+            "attributes": {"POSITION": 0},
+            "material": 0,
+            "extensions": {"KHR_materials_variants": {"mappings": []}}
+        }]
+    }],
+    "accessors": [{
+        "componentType": 5126,
+        "count": 3,
+        "type": "VEC3",
+        "min": [0, 0, 0],
+        "max": [0, 0, 0]
+    }],
+    "materials": [{
+        "pbrMetallicRoughness": {
+            "baseColorTexture": {
+                "index": 1,
+                "extensions": {"KHR_texture_transform": {"scale": [2, 2]}}
+            }
+        },
+        "extensions": {
+            "KHR_materials_clearcoat": {"clearcoatFactor": 1, "clearcoatTexture": {"index": 2}}
+        }
+    }],
+    "textures": [
+        {"source": 0},
+        {"source": 1},
+        {"source": 1, "extensions": {"KHR_texture_basisu": {"source": 2}}}
+    ],
+    "images": [{"uri": "unused.png"}, {"uri": "a.png"}, {"uri": "b.ktx2"}]
+}"#;
+
+#[test]
+fn test_graph_extensions_roundtrip() {
+    let gltf = gltf::Gltf::from_slice_without_validation(EXTENSIONS.as_bytes()).unwrap();
+    let mut graph = Graph::from_document(&gltf.document, &[]).unwrap();
+    gltf::transform::prune(&mut graph, &Default::default());
+    assert_eq!(graph.count::<Texture>(), 2);
+
+    let (document, _) = graph.to_document().unwrap();
+    let json = document.into_json_value().unwrap();
+    let names = |key: &str| -> Vec<String> {
+        let names = json[key].as_array().unwrap().iter();
+        names.map(|x| x.as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(
+        names("extensionsUsed"),
+        [
+            "KHR_lights_punctual",
+            "KHR_materials_clearcoat",
+            "KHR_texture_basisu",
+            "KHR_texture_transform"
+        ]
+    );
+    assert_eq!(names("extensionsRequired"), ["KHR_texture_basisu"]);
+    assert_eq!(
+        json["extensions"]["KHR_lights_punctual"]["lights"][0]["type"],
+        "point"
+    );
+    assert_eq!(
+        json["nodes"][0]["extensions"]["KHR_lights_punctual"]["light"],
+        0
+    );
+    let material = &json["materials"][0];
+    let base_color = &material["pbrMetallicRoughness"]["baseColorTexture"];
+    assert_eq!(base_color["index"], 0);
+    let transform = &base_color["extensions"]["KHR_texture_transform"];
+    assert_eq!(transform["scale"][0], 2);
+    let clearcoat = &material["extensions"]["KHR_materials_clearcoat"];
+    assert_eq!(clearcoat["clearcoatTexture"]["index"], 1);
+    assert_eq!(
+        json["textures"][1]["extensions"]["KHR_texture_basisu"]["source"],
+        1
+    );
+    assert_eq!(json["images"][1]["uri"], "b.ktx2");
+    assert!(json["meshes"][0]["primitives"][0]
+        .get("extensions")
+        .is_none());
+}
+
+#[test]
+fn test_graph_unsupported_required_extension() {
+    let json = EXTENSIONS.replace(
+        r#""extensionsRequired": ["KHR_texture_basisu"]"#,
+        r#""extensionsRequired": ["KHR_materials_variants"]"#,
+    );
+    let gltf = gltf::Gltf::from_slice_without_validation(json.as_bytes()).unwrap();
+    match Graph::from_document(&gltf.document, &[]) {
+        Err(gltf::Error::UnsupportedExtension(name)) => {
+            assert_eq!(name, "KHR_materials_variants");
+        }
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
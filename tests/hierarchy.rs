@@ -0,0 +1,77 @@
+use gltf::scene::Hierarchy;
+use gltf_transform_rs as gltf;
+
+fn document(json: &str) -> gltf::Document {
+    gltf::Gltf::from_slice_without_validation(json.as_bytes())
+        .unwrap()
+        .document
+}
+
+const TREE: &str = r#"{
+    "asset": {"version": "2.0"},
+    "scenes": [{"nodes": [0]}],
+    "nodes": [
+        {"children": [1, 2], "translation": [1, 0, 0]},
+        {"children": [3], "scale": [2, 2, 2]},
+        {},
+        {"translation": [0, 1, 0]}
+    ]
+}"#;
+
+#[test]
+fn test_parents_and_paths() {
+    let document = document(TREE);
+    let hierarchy = Hierarchy::new(&document);
+    let nodes: Vec<_> = document.nodes().collect();
+    assert!(hierarchy.is_valid());
+    assert!(hierarchy.parent(&nodes[0]).is_none());
+    assert_eq!(hierarchy.parent(&nodes[2]).unwrap().index(), 0);
+    assert_eq!(hierarchy.parent(&nodes[3]).unwrap().index(), 1);
+
+    let path = hierarchy.path_from_root(&nodes[3]).unwrap();
+    let path: Vec<_> = path.iter().map(|x| x.index()).collect();
+    assert_eq!(path, vec![0, 1, 3]);
+    assert_eq!(hierarchy.path_from_root(&nodes[0]).unwrap().len(), 1);
+}
+
+#[test]
+fn test_world_matrix() {
+    let document = document(TREE);
+    let hierarchy = Hierarchy::new(&document);
+    let nodes: Vec<_> = document.nodes().collect();
+    let world = hierarchy.world_matrix(&nodes[3]).unwrap();
+    assert_eq!(world[0], [2.0, 0.0, 0.0, 0.0]);
+    assert_eq!(world[3], [1.0, 2.0, 0.0, 1.0]);
+    let world = hierarchy.world_matrix(&nodes[2]).unwrap();
+    assert_eq!(world[3], [1.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_invalid_hierarchy() {
+    let document = document(
+        r#"{
+            "asset": {"version": "2.0"},
+            "nodes": [
+                {"children": [1]},
+                {"children": [2]},
+                {"children": [1, 3]},
+                {},
+                {"children": [3, 4, 9]}
+            ]
+        }"#,
+    );
+    let hierarchy = Hierarchy::new(&document);
+    let nodes: Vec<_> = document.nodes().collect();
+    assert!(!hierarchy.is_valid());
+
+    let cyclic: Vec<_> = hierarchy.cyclic_nodes().map(|x| x.index()).collect();
+    assert_eq!(cyclic, vec![1, 2, 4]);
+    assert!(!hierarchy.is_cyclic(&nodes[0]));
+    let shared: Vec<_> = hierarchy.multiple_parents().map(|x| x.index()).collect();
+    assert_eq!(shared, vec![1, 3]);
+
+    assert!(hierarchy.world_matrix(&nodes[0]).is_some());
+    assert!(hierarchy.world_matrix(&nodes[1]).is_none());
+    assert!(hierarchy.world_matrix(&nodes[3]).is_none());
+    assert!(hierarchy.path_from_root(&nodes[3]).is_none());
+}
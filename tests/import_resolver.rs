@@ -0,0 +1,31 @@
+use gltf_transform_rs as gltf;
+use std::cell::RefCell;
+use std::fs;
+
+#[test]
+fn test_import_with_resolver() {
+    let requested = RefCell::new(Vec::new());
+    let resolver = |uri: &str| {
+        requested.borrow_mut().push(uri.to_string());
+        fs::read("examples/Box0.bin").map_err(gltf::Error::Io)
+    };
+    let (document, buffers, _) =
+        gltf::import_with_resolver("examples/Box.gltf", &resolver).unwrap();
+    assert_eq!(*requested.borrow(), vec!["Box0.bin".to_string()]);
+    assert_eq!(buffers.len(), document.buffers().count());
+}
+
+#[test]
+fn test_import_slice_with_resolver() {
+    let json = fs::read("examples/Box.gltf").unwrap();
+    match gltf::import_slice(&json) {
+        Err(gltf::Error::ExternalReferenceInSliceImport) => {}
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    let resolver = gltf::FileSystemResolver::new("examples");
+    let (_, buffers, _) = gltf::import_slice_with_resolver(&json, &resolver).unwrap();
+    let (_, expected, _) = gltf::import("examples/Box.gltf").unwrap();
+    assert_eq!(buffers.len(), expected.len());
+    assert_eq!(*buffers[0], *expected[0]);
+}
@@ -0,0 +1,346 @@
+mod common;
+
+use common::{add_mesh, props, triangle};
+use gltf::accessor::Dimensions;
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Channel, Graph, Handle, Instancing, Node, Scene, Values};
+use gltf::scene::Transform;
+use gltf::transform::{
+    flatten, instance, join, quantize, InstanceOptions, JoinOptions, QuantizeOptions,
+};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+fn decomposed(translation: [f32; 3], rotation: [f32; 4], scale: [f32; 3]) -> Transform {
+    Transform::Decomposed {
+        translation,
+        rotation,
+        scale,
+    }
+}
+
+fn instanced(graph: &Graph, scene: Handle<Scene>) -> Vec<Handle<Node>> {
+    graph[scene]
+        .nodes
+        .iter()
+        .copied()
+        .filter(|x| graph[*x].instancing.is_some())
+        .collect()
+}
+
+#[test]
+fn test_instance_props() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 4, 1.0);
+    let primitive = triangle(&mut graph);
+    let other = add_mesh(&mut graph, vec![primitive]);
+    let single = graph.add(Node {
+        mesh: Some(other),
+        ..Default::default()
+    });
+    graph[scene].nodes.insert(1, single);
+
+    instance(&mut graph, &InstanceOptions::default());
+    let nodes = &graph[scene].nodes;
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1], single);
+    assert!(graph[single].instancing.is_none());
+    assert_eq!(graph.count::<Node>(), 2);
+
+    let node = &graph[nodes[0]];
+    assert_eq!(node.mesh, Some(mesh));
+    let instancing = node.instancing.as_ref().unwrap();
+    assert_eq!(instancing.attributes.len(), 1);
+    let translations: Vec<[f32; 3]> = graph[instancing.get("TRANSLATION").unwrap()].to_f32();
+    assert_eq!(translations.len(), 4);
+    assert_eq!(translations[3], [3.0, 0.0, 0.0]);
+    assert!(graph
+        .extensions_used
+        .iter()
+        .any(|x| x == "EXT_mesh_gpu_instancing"));
+}
+
+#[test]
+fn test_rotation_and_scale() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 3, 1.0);
+    let s = 0.5f32.sqrt();
+    let nodes = graph[scene].nodes.clone();
+    for (i, node) in nodes.iter().enumerate() {
+        graph[*node].transform = decomposed([0.0; 3], [0.0, 0.0, s, s], [i as f32 + 1.0; 3]);
+    }
+
+    instance(&mut graph, &InstanceOptions { min_instances: 3 });
+    let node = &graph[graph[scene].nodes[0]];
+    let instancing = node.instancing.as_ref().unwrap();
+    assert!(instancing.get("TRANSLATION").is_none());
+    let rotations: Vec<[f32; 4]> = graph[instancing.get("ROTATION").unwrap()].to_f32();
+    assert_eq!(rotations, vec![[0.0, 0.0, s, s]; 3]);
+    let scales: Vec<[f32; 3]> = graph[instancing.get("SCALE").unwrap()].to_f32();
+    assert_eq!(scales[2], [3.0; 3]);
+}
+
+/// Returns the world-space positions of every instance of the mesh of the
+/// root node `node`.
+fn instance_positions(graph: &Graph, node: Handle<Node>) -> Vec<[f32; 3]> {
+    let apply = |m: [[f32; 4]; 4], p: [f32; 3]| {
+        let mut out = [0.0; 3];
+        for (i, x) in out.iter_mut().enumerate() {
+            *x = m[3][i] + (0..3).map(|j| m[j][i] * p[j]).sum::<f32>();
+        }
+        out
+    };
+    let node = &graph[node];
+    let instancing = node.instancing.as_ref().unwrap();
+    let attribute = |name, default: [f32; 4]| match instancing.get(name) {
+        Some(x) if name == "ROTATION" => graph[x].to_f32::<4>(),
+        Some(x) => graph[x]
+            .to_f32::<3>()
+            .into_iter()
+            .map(|[a, b, c]| [a, b, c, 0.0])
+            .collect(),
+        None => vec![default; 3],
+    };
+    let translations = attribute("TRANSLATION", [0.0; 4]);
+    let rotations = attribute("ROTATION", [0.0, 0.0, 0.0, 1.0]);
+    let scales = attribute("SCALE", [1.0; 4]);
+    let primitive = &graph[node.mesh.unwrap()].primitives[0];
+    let positions = graph[primitive.get(&Semantic::Positions).unwrap()].to_f32::<3>();
+    let mut out = Vec::new();
+    for i in 0..3 {
+        let [x, y, z, _] = translations[i];
+        let [a, b, c, _] = scales[i];
+        let instance = decomposed([x, y, z], rotations[i], [a, b, c]).matrix();
+        for p in &positions {
+            out.push(apply(node.transform.clone().matrix(), apply(instance, *p)));
+        }
+    }
+    out
+}
+
+#[test]
+fn test_instance_then_quantize() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 3, 1.0);
+    let s = 0.5f32.sqrt();
+    let nodes = graph[scene].nodes.clone();
+    for (i, node) in nodes.iter().enumerate() {
+        let t = i as f32;
+        graph[*node].transform = decomposed([t, 2.0, 0.0], [0.0, 0.0, s, s], [t + 1.0; 3]);
+    }
+    instance(&mut graph, &InstanceOptions::default());
+    let node = graph[scene].nodes[0];
+    graph[node].transform = decomposed([0.0, 0.0, 5.0], [s, 0.0, 0.0, s], [2.0; 3]);
+    let expected = instance_positions(&graph, node);
+
+    quantize(&mut graph, &QuantizeOptions::default());
+    let primitive = &graph[mesh].primitives[0];
+    assert!(graph[primitive.get(&Semantic::Positions).unwrap()].normalized);
+    assert_eq!(graph[scene].nodes, vec![node]);
+    let actual = instance_positions(&graph, node);
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(&expected) {
+        for i in 0..3 {
+            assert!((actual[i] - expected[i]).abs() < 1e-3);
+        }
+    }
+}
+
+#[test]
+fn test_excluded_nodes() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 4, 1.0);
+    let nodes = graph[scene].nodes.clone();
+    let input = graph.add(Accessor::from_f32(&[[0.0], [1.0]]));
+    let output = graph.add(Accessor::from_f32(&[[0.0; 3], [1.0; 3]]));
+    graph.add(Animation {
+        channels: vec![Channel {
+            node: nodes[0],
+            property: Property::Translation,
+            input,
+            output,
+            interpolation: Interpolation::Linear,
+            extras: Default::default(),
+        }],
+        ..Default::default()
+    });
+    let child = graph.add(Node::default());
+    graph[nodes[1]].children.push(child);
+
+    instance(&mut graph, &InstanceOptions { min_instances: 3 });
+    assert_eq!(graph[scene].nodes, nodes);
+    instance(&mut graph, &InstanceOptions::default());
+    assert_eq!(graph[scene].nodes.len(), 3);
+    assert_eq!(&graph[scene].nodes[..2], &nodes[..2]);
+    assert_eq!(instanced(&graph, scene).len(), 1);
+}
+
+#[test]
+fn test_sheared_and_zero_scale_nodes() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 4, 1.0);
+    let nodes = graph[scene].nodes.clone();
+    graph[nodes[0]].transform = Transform::Matrix {
+        matrix: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.5, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+    graph[nodes[1]].transform = Transform::Matrix {
+        matrix: [
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0, 1.0],
+        ],
+    };
+    graph[nodes[2]].transform = Transform::Matrix {
+        matrix: [
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [2.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    instance(&mut graph, &InstanceOptions::default());
+    assert_eq!(graph[scene].nodes.len(), 3);
+    assert_eq!(&graph[scene].nodes[..2], &nodes[..2]);
+    let node = &graph[instanced(&graph, scene)[0]];
+    let instancing = node.instancing.as_ref().unwrap();
+    let translations: Vec<[f32; 3]> = graph[instancing.get("TRANSLATION").unwrap()].to_f32();
+    assert_eq!(translations, vec![[2.0, 0.0, 0.0], [3.0, 0.0, 0.0]]);
+    let scales: Vec<[f32; 3]> = graph[instancing.get("SCALE").unwrap()].to_f32();
+    assert_eq!(scales, vec![[2.0; 3], [1.0; 3]]);
+    assert!(instancing.get("ROTATION").is_none());
+}
+
+#[test]
+fn test_join_and_flatten_keep_instances() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 2, 1.0);
+    instance(&mut graph, &InstanceOptions::default());
+    let node = graph[scene].nodes[0];
+    let parent = graph.add(Node {
+        children: vec![node],
+        transform: decomposed([0.0, 5.0, 0.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3]),
+        ..Default::default()
+    });
+    graph[scene].nodes = vec![parent];
+
+    join(&mut graph, &JoinOptions::default());
+    assert_eq!(graph[parent].children, vec![node]);
+    flatten(&mut graph);
+    assert_eq!(graph[scene].nodes, vec![parent, node]);
+    assert_eq!(graph[node].mesh, Some(mesh));
+    let matrix = graph[node].transform.clone().matrix();
+    assert_eq!(matrix[3], [0.0, 5.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_instancing_document() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    props(&mut graph, mesh, 3, 1.0);
+    instance(&mut graph, &InstanceOptions::default());
+
+    let (document, buffers) = graph.to_document().unwrap();
+    assert!(document
+        .extensions_used()
+        .any(|x| x == "EXT_mesh_gpu_instancing"));
+    let node = document.nodes().find(|x| x.mesh().is_some()).unwrap();
+    let instancing = node.instancing().unwrap();
+    assert_eq!(instancing.count(), 3);
+    assert!(instancing.rotation().is_none());
+    assert_eq!(instancing.attributes()[0].0, "TRANSLATION");
+    let reader = instancing.reader(|buffer| Some(&buffers[buffer.index()]));
+    let transforms: Vec<_> = reader.read_transforms().unwrap().collect();
+    assert_eq!(transforms.len(), 3);
+    let (translation, rotation, scale) = transforms[2].clone().decomposed();
+    assert_eq!(translation, [2.0, 0.0, 0.0]);
+    assert_eq!(rotation, [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(scale, [1.0; 3]);
+
+    let graph = Graph::from_document(&document, &buffers).unwrap();
+    let (_, node) = graph
+        .iter::<Node>()
+        .find(|(_, x)| x.instancing.is_some())
+        .unwrap();
+    let accessor = node.instancing.as_ref().unwrap().get("TRANSLATION");
+    assert_eq!(graph[accessor.unwrap()].count(), 3);
+}
+
+#[test]
+fn test_instance_keeps_required_declaration() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    props(&mut graph, mesh, 3, 1.0);
+    let name = "EXT_mesh_gpu_instancing".to_string();
+    graph.extensions_required.push(name.clone());
+    instance(&mut graph, &InstanceOptions::default());
+    assert_eq!(graph.extensions_used, vec![name.clone()]);
+    assert_eq!(graph.extensions_required, vec![name.clone()]);
+
+    let node = graph.handles::<Node>()[0];
+    graph[node].instancing = None;
+    graph.update_extensions();
+    assert!(graph.extensions_used.is_empty());
+    assert!(graph.extensions_required.is_empty());
+}
+
+#[test]
+fn test_read_quantized_instances() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let translations = graph.add(Accessor {
+        normalized: true,
+        ..Accessor::new(
+            Dimensions::Vec3,
+            Values::I16(vec![32767, 0, -32767, 0, 16384, 0]),
+        )
+    });
+    let scales = graph.add(Accessor::new(
+        Dimensions::Vec3,
+        Values::U8(vec![1, 2, 3, 4, 5, 6]),
+    ));
+    let mut instancing = Instancing::default();
+    instancing.set("TRANSLATION", translations);
+    instancing.set("SCALE", scales);
+    graph.add(Node {
+        mesh: Some(mesh),
+        instancing: Some(instancing),
+        ..Default::default()
+    });
+
+    let (document, buffers) = graph.to_document().unwrap();
+    let node = document.nodes().next().unwrap();
+    let instancing = node.instancing().unwrap();
+    let reader = instancing.reader(|buffer| Some(&buffers[buffer.index()]));
+    let translations: Vec<_> = reader.read_translations().unwrap().collect();
+    assert_eq!(translations[0], [1.0, 0.0, -1.0]);
+    assert!((translations[1][1] - 0.5).abs() < 1e-4);
+    let scales: Vec<_> = reader.read_scales().unwrap().collect();
+    assert_eq!(scales, vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let transforms: Vec<_> = reader.read_transforms().unwrap().collect();
+    assert_eq!(transforms.len(), 2);
+    let (translation, _, scale) = transforms[0].clone().decomposed();
+    assert_eq!(translation, [1.0, 0.0, -1.0]);
+    assert_eq!(scale, [1.0, 2.0, 3.0]);
+}
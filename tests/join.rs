@@ -0,0 +1,176 @@
+mod common;
+
+use common::{add_mesh, props, triangle};
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Channel, Graph, Material, Mesh, Node, Primitive, Scene};
+use gltf::transform::{join, JoinOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+fn positions(graph: &Graph, primitive: &Primitive) -> Vec<[f32; 3]> {
+    graph[primitive.get(&Semantic::Positions).unwrap()].to_f32()
+}
+
+#[test]
+fn test_join_props() {
+    let mut graph = Graph::new();
+    let material = graph.add(Material::default());
+    let mut primitive = triangle(&mut graph);
+    primitive.material = Some(material);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 10, 2.0);
+
+    join(&mut graph, &JoinOptions::default());
+    assert_eq!(graph[scene].nodes.len(), 1);
+    assert_eq!(graph.count::<Node>(), 1);
+    assert_eq!(graph.count::<Mesh>(), 1);
+    assert!(!graph.contains(mesh));
+    let node = &graph[graph[scene].nodes[0]];
+    let primitives = &graph[node.mesh.unwrap()].primitives;
+    assert_eq!(primitives.len(), 1);
+    assert_eq!(primitives[0].material, Some(material));
+
+    let positions = positions(&graph, &primitives[0]);
+    assert_eq!(positions.len(), 30);
+    assert_eq!(positions[3], [2.0, 0.0, 0.0]);
+    assert_eq!(positions[29], [18.0, 1.0, 0.0]);
+    let triangles: Vec<_> = primitives[0].triangles(&graph).collect();
+    assert_eq!(triangles.len(), 10);
+    assert_eq!(triangles[9], [27, 28, 29]);
+    assert_eq!(graph.count::<Accessor>(), 2);
+}
+
+#[test]
+fn test_materials_and_limit() {
+    let mut graph = Graph::new();
+    let materials = [
+        graph.add(Material::default()),
+        graph.add(Material::default()),
+    ];
+    let mut nodes = Vec::new();
+    for i in 0..10 {
+        let mut primitive = triangle(&mut graph);
+        primitive.material = Some(materials[i % 2]);
+        let mesh = add_mesh(&mut graph, vec![primitive]);
+        nodes.push(graph.add(Node {
+            mesh: Some(mesh),
+            ..Default::default()
+        }));
+    }
+    let parent = graph.add(Node {
+        children: nodes,
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![parent],
+        ..Default::default()
+    });
+
+    let options = JoinOptions { max_vertices: 6 };
+    join(&mut graph, &options);
+    // Five triangles of each material, joined in pairs, leave one triangle
+    // of each material on its node.
+    let children = &graph[parent].children;
+    assert_eq!(children.len(), 3);
+    let node = &graph[children[0]];
+    let primitives = &graph[node.mesh.unwrap()].primitives;
+    assert_eq!(primitives.len(), 4);
+    for (primitive, material) in primitives.iter().zip([0, 0, 1, 1]) {
+        assert_eq!(primitive.material, Some(materials[material]));
+        assert_eq!(positions(&graph, primitive).len(), 6);
+    }
+    for child in &children[1..] {
+        let mesh = graph[*child].mesh.unwrap();
+        assert_eq!(positions(&graph, &graph[mesh].primitives[0]).len(), 3);
+    }
+    assert_eq!(graph.count::<Node>(), 4);
+}
+
+#[test]
+fn test_keeps_remaining_primitives() {
+    let mut graph = Graph::new();
+    let material = graph.add(Material::default());
+    let shared = triangle(&mut graph);
+    let mut unique = triangle(&mut graph);
+    unique.material = Some(material);
+    // A different attribute layout prevents joining.
+    let normals = graph.add(Accessor::from_f32(&[[0.0, 0.0, 1.0]; 3]));
+    unique.set(Semantic::Normals, normals);
+    let mixed = add_mesh(&mut graph, vec![shared.clone(), unique]);
+    let plain = add_mesh(&mut graph, vec![shared]);
+    let a = graph.add(Node {
+        mesh: Some(mixed),
+        ..Default::default()
+    });
+    let b = graph.add(Node {
+        mesh: Some(plain),
+        ..Default::default()
+    });
+    let scene = graph.add(Scene {
+        nodes: vec![a, b],
+        ..Default::default()
+    });
+
+    join(&mut graph, &JoinOptions::default());
+    let nodes = &graph[scene].nodes;
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1], a);
+    let remaining = &graph[graph[a].mesh.unwrap()].primitives;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].get(&Semantic::Normals), Some(normals));
+    assert!(!graph.contains(b));
+    assert!(!graph.contains(mixed));
+    assert!(!graph.contains(plain));
+}
+
+#[test]
+fn test_excluded_nodes() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    let scene = props(&mut graph, mesh, 3, 2.0);
+    let animated = graph[scene].nodes[0];
+    let input = graph.add(Accessor::from_f32(&[[0.0], [1.0]]));
+    let output = graph.add(Accessor::from_f32(&[[0.0; 4], [0.0; 4]]));
+    graph.add(Animation {
+        channels: vec![Channel {
+            node: animated,
+            property: Property::Rotation,
+            input,
+            output,
+            interpolation: Interpolation::Step,
+            extras: Default::default(),
+        }],
+        ..Default::default()
+    });
+    let weighted = graph[scene].nodes[1];
+    graph[weighted].weights = Some(vec![0.5]);
+
+    join(&mut graph, &JoinOptions::default());
+    assert_eq!(graph[scene].nodes.len(), 3);
+    assert_eq!(graph[animated].mesh, Some(mesh));
+    assert_eq!(graph[weighted].mesh, Some(mesh));
+    assert_eq!(graph.count::<Mesh>(), 1);
+}
+
+#[test]
+fn test_joined_document() {
+    let mut graph = Graph::new();
+    let primitive = triangle(&mut graph);
+    let mesh = add_mesh(&mut graph, vec![primitive]);
+    props(&mut graph, mesh, 4, 2.0);
+
+    join(&mut graph, &JoinOptions::default());
+    let (document, _) = graph.to_document().unwrap();
+    assert_eq!(document.meshes().count(), 1);
+    let primitive = document
+        .meshes()
+        .next()
+        .unwrap()
+        .primitives()
+        .next()
+        .unwrap();
+    assert_eq!(primitive.indices().unwrap().count(), 12);
+    let bounds = primitive.bounding_box();
+    assert_eq!(bounds.max, [7.0, 1.0, 0.0]);
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::channel;
+use gltf::accessor::{DataType, Dimensions};
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Graph, Node, Values};
+use gltf::transform::{linearize, LinearizeOptions};
+use gltf_transform_rs as gltf;
+
+#[test]
+fn test_step() {
+    let mut graph = Graph::new();
+    let node = graph.add(Node::default());
+    let scales = Accessor {
+        normalized: true,
+        ..Accessor::new(Dimensions::Vec3, Values::U8(vec![0, 0, 0, 255, 255, 255]))
+    };
+    let scale = channel(
+        &mut graph,
+        node,
+        Property::Scale,
+        Interpolation::Step,
+        &[0.0, 1.0],
+        scales,
+    );
+    let animation = graph.add(Animation {
+        channels: vec![scale],
+        ..Default::default()
+    });
+    let before = graph[animation].channels[0].keyframes(&graph).unwrap();
+
+    linearize(&mut graph, &LinearizeOptions::default());
+    let channel = &graph[animation].channels[0];
+    assert_eq!(channel.interpolation, Interpolation::Linear);
+    let output = &graph[channel.output];
+    assert_eq!(output.data_type(), DataType::U8);
+    assert!(output.normalized);
+    let after = channel.keyframes(&graph).unwrap();
+    assert_eq!(after.len(), 3);
+    for t in [0.0, 0.5, 0.999, 1.0, 2.0] {
+        assert_eq!(after.sample(t), before.sample(t));
+    }
+    assert_eq!(graph.count::<Accessor>(), 2);
+}
+
+#[test]
+fn test_cubic_spline() {
+    let mut graph = Graph::new();
+    let node = graph.add(Node::default());
+    // In-tangent, value and out-tangent of each keyframe.
+    let (sin, cos) = std::f32::consts::FRAC_PI_4.sin_cos();
+    let rotations = Accessor::from_f32(&[
+        [0.0; 4],
+        [0.0, 0.0, 0.0, 1.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, sin, cos],
+        [0.0; 4],
+    ]);
+    let rotation = channel(
+        &mut graph,
+        node,
+        Property::Rotation,
+        Interpolation::CubicSpline,
+        &[0.0, 1.0],
+        rotations,
+    );
+    // Two morph targets that ease in opposite directions.
+    let weights = Accessor::from_f32(&[
+        [0.0],
+        [0.0],
+        [0.0],
+        [1.0],
+        [0.0],
+        [0.0],
+        [0.0],
+        [0.0],
+        [1.0],
+        [0.0],
+        [0.0],
+        [0.0],
+    ]);
+    let weights = channel(
+        &mut graph,
+        node,
+        Property::MorphTargetWeights,
+        Interpolation::CubicSpline,
+        &[0.0, 2.0],
+        weights,
+    );
+    let animation = graph.add(Animation {
+        channels: vec![rotation, weights],
+        ..Default::default()
+    });
+    let before: Vec<_> = graph[animation]
+        .channels
+        .iter()
+        .map(|x| x.keyframes(&graph).unwrap())
+        .collect();
+
+    let options = LinearizeOptions {
+        sample_rate: 10.0,
+        tolerance: 1e-3,
+    };
+    linearize(&mut graph, &options);
+    let after: Vec<_> = graph[animation]
+        .channels
+        .iter()
+        .map(|x| x.keyframes(&graph).unwrap())
+        .collect();
+    assert_eq!(after[0].interpolation(), Interpolation::Linear);
+    assert_eq!(after[1].width(), 2);
+    assert!(after[0].len() > 2);
+    for i in 0..=200 {
+        let t = i as f32 / 100.0;
+        let (a, b) = (before[0].sample(t), after[0].sample(t));
+        let dot: f32 = a.iter().zip(&b).map(|(a, b)| a * b).sum();
+        assert!(dot.abs() > (2e-3f32).cos(), "rotation at {}", t);
+        let (a, b) = (before[1].sample(t), after[1].sample(t));
+        for (a, b) in a.iter().zip(&b) {
+            assert!((a - b).abs() < 2e-3, "weights at {}", t);
+        }
+    }
+}
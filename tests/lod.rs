@@ -0,0 +1,205 @@
+use gltf::graph::{Accessor, Graph, Handle, Mesh, Node, Primitive, Scene};
+use gltf::transform::{prune, simplify_primitive, PruneOptions, SimplifyOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+const LODS: &str = r#"{
+    "asset": {"version": "2.0"},
+    "nodes": [
+        {
+            "extensions": {"MSFT_lod": {"ids": [1, 2]}},
+            "extras": {"MSFT_screencoverage": [0.5, 0.2, 0.01], "tag": 1}
+        },
+        {},
+        {}
+    ],
+    "scenes": [{"nodes": [0]}],
+    "extensionsUsed": ["MSFT_lod"]
+}"#;
+
+/// Builds a mesh with a grid of quads over the unit square.
+fn grid(graph: &mut Graph) -> Handle<Mesh> {
+    let mut positions = Vec::new();
+    for y in 0..=8 {
+        for x in 0..=8 {
+            positions.push([x as f32 / 8.0, y as f32 / 8.0, 0.0]);
+        }
+    }
+    let mut indices = Vec::new();
+    for y in 0..8 {
+        for x in 0..8 {
+            let a = y * 9 + x;
+            indices.extend_from_slice(&[a, a + 1, a + 9, a + 9, a + 1, a + 10]);
+        }
+    }
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    primitive.indices = Some(graph.add(Accessor::from_indices(&indices, positions.len())));
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_read_lods() {
+    let gltf = gltf::Gltf::from_slice(LODS.as_bytes()).unwrap();
+    let node = gltf.nodes().next().unwrap();
+    let lods = node.lods();
+    let nodes: Vec<_> = lods.iter().map(|x| x.node().index()).collect();
+    assert_eq!(nodes, vec![0, 1, 2]);
+    let coverage: Vec<_> = lods.iter().map(|x| x.screen_coverage()).collect();
+    assert_eq!(coverage, vec![Some(0.5), Some(0.2), Some(0.01)]);
+    assert!(gltf.nodes().nth(1).unwrap().lods().is_empty());
+}
+
+#[test]
+fn test_lods_skip_invalid_ids() {
+    let json = LODS.replace("[1, 2]", "[7, 2]");
+    let gltf = gltf::Gltf::from_slice_without_validation(json.as_bytes()).unwrap();
+    let lods = gltf.nodes().next().unwrap().lods();
+    let nodes: Vec<_> = lods.iter().map(|x| x.node().index()).collect();
+    assert_eq!(nodes, vec![0, 2]);
+    let coverage: Vec<_> = lods.iter().map(|x| x.screen_coverage()).collect();
+    assert_eq!(coverage, vec![Some(0.5), Some(0.01)]);
+
+    let graph = Graph::from_document(&gltf.document, &[]).unwrap();
+    let handles = graph.handles::<Node>();
+    let base = handles[0];
+    assert_eq!(graph[base].lods, vec![handles[2]]);
+    assert_eq!(graph[base].screen_coverage, Some(vec![0.5, 0.01]));
+}
+
+#[test]
+fn test_lods_removed_level() {
+    let gltf = gltf::Gltf::from_slice(LODS.as_bytes()).unwrap();
+    let mut graph = Graph::from_document(&gltf.document, &[]).unwrap();
+    let handles = graph.handles::<Node>();
+    graph.remove(handles[1]);
+    let (document, _) = graph.to_document().unwrap();
+    let lods = document.nodes().next().unwrap().lods();
+    let coverage: Vec<_> = lods.iter().map(|x| x.screen_coverage()).collect();
+    assert_eq!(coverage, vec![Some(0.5), Some(0.01)]);
+}
+
+#[test]
+fn test_lods_roundtrip() {
+    let gltf = gltf::Gltf::from_slice(LODS.as_bytes()).unwrap();
+    let mut graph = Graph::from_document(&gltf.document, &[]).unwrap();
+    let handles = graph.handles::<Node>();
+    let base = handles[0];
+    assert_eq!(graph[base].lods, handles[1..].to_vec());
+    assert_eq!(graph[base].screen_coverage, Some(vec![0.5, 0.2, 0.01]));
+
+    graph[base].screen_coverage = None;
+    let (document, _) = graph.to_document().unwrap();
+    let node = document.nodes().next().unwrap();
+    let lods = node.lods();
+    assert_eq!(lods.len(), 3);
+    assert!(lods.iter().all(|x| x.screen_coverage().is_none()));
+
+    let value = document.into_json_value().unwrap();
+    let ids = &value["nodes"][0]["extensions"]["MSFT_lod"]["ids"];
+    assert_eq!(ids.as_array().unwrap().len(), 2);
+    assert!(value["nodes"][0]["extras"]
+        .get("MSFT_screencoverage")
+        .is_none());
+}
+
+#[test]
+fn test_add_lod() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph);
+    let node = graph.add(Node {
+        name: Some("grid".to_string()),
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    let scene = graph.add(Scene {
+        nodes: vec![node],
+        ..Default::default()
+    });
+    graph.default_scene = Some(scene);
+
+    let options = SimplifyOptions {
+        ratio: 0.25,
+        ..Default::default()
+    };
+    let mut primitive = graph[mesh].primitives[0].clone();
+    let simplified = simplify_primitive(&graph, &primitive, &options).unwrap();
+    let count = graph[primitive.get(&Semantic::Positions).unwrap()].count();
+    primitive.indices = Some(graph.add(Accessor::from_indices(&simplified.indices, count)));
+    let coarse = graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    });
+    let lod = graph.add_lod(node, coarse);
+    graph[node].screen_coverage = Some(vec![0.4, 0.1]);
+    assert_eq!(graph[lod].name.as_deref(), Some("grid_LOD1"));
+    assert_eq!(graph.extensions_used, vec!["MSFT_lod".to_string()]);
+
+    prune(&mut graph, &PruneOptions::default());
+    assert!(graph.contains(lod));
+    assert_eq!(graph.extensions_used, vec!["MSFT_lod".to_string()]);
+
+    let glb = graph.to_glb().unwrap();
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    let node = gltf.scenes().next().unwrap().nodes().next().unwrap();
+    let lods = node.lods();
+    assert_eq!(lods.len(), 2);
+    assert_eq!(lods[0].screen_coverage(), Some(0.4));
+    assert_eq!(lods[1].screen_coverage(), Some(0.1));
+    let triangles = |node: &gltf::Node| {
+        let primitive = node.mesh().unwrap().primitives().next().unwrap();
+        primitive.indices().unwrap().count() / 3
+    };
+    assert_eq!(triangles(lods[0].node()), 128);
+    assert!(triangles(lods[1].node()) <= 32);
+}
+
+#[test]
+fn test_prune_unused_lods() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph);
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    graph.add(Scene {
+        nodes: vec![node],
+        ..Default::default()
+    });
+    let orphan = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    let lod = graph.add_lod(orphan, mesh);
+
+    prune(&mut graph, &PruneOptions::default());
+    assert!(!graph.contains(orphan));
+    assert!(!graph.contains(lod));
+    assert!(graph.extensions_used.is_empty());
+}
+
+#[test]
+fn test_prune_keeps_required_lods() {
+    let json = LODS.replace(
+        r#""extensionsUsed": ["MSFT_lod"]"#,
+        r#""extensionsUsed": ["MSFT_lod"], "extensionsRequired": ["MSFT_lod"]"#,
+    );
+    let gltf = gltf::Gltf::from_slice(json.as_bytes()).unwrap();
+    let mut graph = Graph::from_document(&gltf.document, &[]).unwrap();
+    let mesh = grid(&mut graph);
+    for node in graph.handles::<Node>() {
+        graph[node].mesh = Some(mesh);
+    }
+    prune(&mut graph, &PruneOptions::default());
+    assert_eq!(graph.extensions_used, vec!["MSFT_lod"]);
+    assert_eq!(graph.extensions_required, vec!["MSFT_lod"]);
+
+    let (document, _) = graph.to_document().unwrap();
+    assert!(document.extensions_required().any(|x| x == "MSFT_lod"));
+}
@@ -0,0 +1,346 @@
+use std::borrow::Cow;
+use std::{env, fs};
+
+use gltf::accessor::Dimensions;
+use gltf::binary::{Glb, Header};
+use gltf::graph::{
+    Accessor, Buffer, Graph, Mesh, MeshoptOptions, Node, Primitive, Values, WriteOptions,
+};
+use gltf::meshopt::{decode_filter, Compression, Error, Filter, Mode};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+const POSITIONS: [[f32; 3]; 6] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.5, 2.0, 0.25],
+    [-1.0, 0.5, 3.0],
+];
+
+const INDICES: [u32; 18] = [0, 1, 2, 2, 1, 3, 2, 3, 4, 5, 0, 2, 4, 3, 5, 1, 0, 5];
+
+/// `POSITIONS` encoded with the meshoptimizer vertex codec.
+const VERTEX_STREAM: [u8; 85] = [
+    160, 0, 0, 1, 63, 240, 0, 0, 255, 255, 255, 255, 255, 1, 63, 48, 0, 0, 126, 125, 126, 255, 0,
+    0, 1, 12, 192, 0, 0, 255, 255, 1, 12, 144, 0, 0, 126, 0, 0, 1, 0, 240, 0, 0, 255, 127, 1, 0,
+    240, 0, 0, 124, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0,
+];
+
+/// `INDICES` encoded with the meshoptimizer index codec.
+const INDEX_STREAM: [u8; 24] = [
+    224, 240, 16, 0, 64, 63, 37, 10, 0, 118, 135, 86, 103, 120, 169, 134, 101, 137, 104, 152, 1,
+    105, 0, 0,
+];
+
+const JSON: &str = r#"{
+    "asset": { "version": "2.0" },
+    "extensionsUsed": ["EXT_meshopt_compression"],
+    "extensionsRequired": ["EXT_meshopt_compression"],
+    "buffers": [
+        { "byteLength": 112 },
+        {
+            "byteLength": 108,
+            "extensions": { "EXT_meshopt_compression": { "fallback": true } }
+        }
+    ],
+    "bufferViews": [
+        {
+            "buffer": 1, "byteLength": 72, "byteStride": 12, "target": 34962,
+            "extensions": {
+                "EXT_meshopt_compression": {
+                    "buffer": 0, "byteLength": 85, "byteStride": 12, "count": 6,
+                    "mode": "ATTRIBUTES"
+                }
+            }
+        },
+        {
+            "buffer": 1, "byteOffset": 72, "byteLength": 36, "target": 34963,
+            "extensions": {
+                "EXT_meshopt_compression": {
+                    "buffer": 0, "byteOffset": 88, "byteLength": 24, "byteStride": 2,
+                    "count": 18, "mode": "TRIANGLES"
+                }
+            }
+        }
+    ],
+    "accessors": [
+        {
+            "bufferView": 0, "componentType": 5126, "count": 6, "type": "VEC3",
+            "min": [-1, 0, 0], "max": [1, 2, 3]
+        },
+        { "bufferView": 1, "componentType": 5123, "count": 18, "type": "SCALAR" }
+    ],
+    "meshes": [
+        { "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1 }] }
+    ]
+}"#;
+
+fn glb() -> Vec<u8> {
+    let mut bin = VERTEX_STREAM.to_vec();
+    bin.resize(88, 0);
+    bin.extend_from_slice(&INDEX_STREAM);
+    Glb {
+        header: Header {
+            magic: *b"glTF",
+            version: 2,
+            length: 0,
+        },
+        json: Cow::Borrowed(JSON.as_bytes()),
+        bin: Some(Cow::Owned(bin)),
+    }
+    .to_vec()
+    .unwrap()
+}
+
+/// Rotates each triangle to start at its smallest index, since the index
+/// codec does not preserve the winding start.
+fn triangles(indices: &[u32]) -> Vec<[u32; 3]> {
+    indices
+        .chunks(3)
+        .map(|t| {
+            let i = (0..3).min_by_key(|i| t[*i]).unwrap();
+            [t[i], t[(i + 1) % 3], t[(i + 2) % 3]]
+        })
+        .collect()
+}
+
+#[test]
+fn test_import_decodes_compressed_views() {
+    let (document, buffers, _) = gltf::import_slice(glb()).unwrap();
+    let primitive = document
+        .meshes()
+        .next()
+        .unwrap()
+        .primitives()
+        .next()
+        .unwrap();
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    let positions: Vec<_> = reader.read_positions().unwrap().collect();
+    assert_eq!(positions, POSITIONS.to_vec());
+    let indices: Vec<_> = reader.read_indices().unwrap().into_u32().collect();
+    assert_eq!(triangles(&indices), triangles(&INDICES));
+
+    let graph = Graph::from_document(&document, &buffers).unwrap();
+    assert_eq!(graph.count::<Buffer>(), 1);
+    assert!(graph.extensions_used.is_empty());
+    assert!(graph.extensions_required.is_empty());
+}
+
+#[test]
+fn test_import_rejects_corrupt_stream() {
+    let mut glb = glb();
+    let header = glb.len() - 112 + 88;
+    glb[header] = 0xc0;
+    match gltf::import_slice(glb) {
+        Err(gltf::Error::Meshopt { view: 1, error }) => {
+            assert_eq!(error, gltf::meshopt::Error::Header(0xc0));
+        }
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_decode_rejects_overflowing_range() {
+    let compression = Compression {
+        buffer: 0,
+        byte_offset: usize::MAX,
+        byte_length: 2,
+        byte_stride: 4,
+        count: 1,
+        mode: Mode::Attributes,
+        filter: Filter::None,
+    };
+    let buffers = [gltf::buffer::Data(vec![0; 16])];
+    assert_eq!(compression.decode(&buffers), Err(Error::Invalid));
+}
+
+#[test]
+fn test_decode_filters() {
+    let mut normals = [0, 0, 127, 0, 127, 0, 127, 0, 127, 127, 127, 0];
+    decode_filter(Filter::Octahedral, 4, &mut normals).unwrap();
+    let normals: Vec<_> = normals.iter().map(|x| *x as i8).collect();
+    assert_eq!(normals, vec![0, 0, 127, 0, 127, 0, 0, 0, 0, 0, -127, 0]);
+
+    // A quarter turn about Y, stored without its largest component (Y).
+    let mut rotation = Vec::new();
+    for x in [0i16, 32767, 0, 32765] {
+        rotation.extend_from_slice(&x.to_le_bytes());
+    }
+    decode_filter(Filter::Quaternion, 8, &mut rotation).unwrap();
+    let rotation: Vec<_> = rotation
+        .chunks(2)
+        .map(|x| i16::from_le_bytes([x[0], x[1]]))
+        .collect();
+    assert_eq!(rotation, vec![0, 23170, 0, 23170]);
+
+    let mut values = Vec::new();
+    for x in [0xff00_0003u32, 0x01ff_ffff] {
+        values.extend_from_slice(&x.to_le_bytes());
+    }
+    decode_filter(Filter::Exponential, 8, &mut values).unwrap();
+    let values: Vec<_> = values
+        .chunks(4)
+        .map(|x| f32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect();
+    assert_eq!(values, vec![1.5, -2.0]);
+
+    assert!(decode_filter(Filter::Quaternion, 4, &mut [0; 4]).is_err());
+}
+
+/// Builds a graph holding the test mesh, with normalized `i8` normals.
+fn graph() -> Graph {
+    let mut graph = Graph::new();
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&POSITIONS)),
+    );
+    let normals = [
+        0, 0, 127, 0, 127, 0, 127, 0, 0, 0, 0, -127, 90, 90, 0, -90, 0, 90,
+    ];
+    let mut normals = Accessor::new(Dimensions::Vec3, Values::I8(normals.to_vec()));
+    normals.normalized = true;
+    primitive.set(Semantic::Normals, graph.add(normals));
+    primitive.indices = Some(graph.add(Accessor::from_u32(INDICES.to_vec())));
+    let mesh = graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    });
+    graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    graph
+}
+
+fn options(required: bool) -> WriteOptions {
+    WriteOptions {
+        meshopt: Some(MeshoptOptions {
+            required,
+            octahedral_bits: 8,
+            ..Default::default()
+        }),
+    }
+}
+
+fn normals(graph: &Graph) -> Vec<[f32; 3]> {
+    let (_, mesh) = graph.iter::<Mesh>().next().unwrap();
+    graph[mesh.primitives[0].get(&Semantic::Normals).unwrap()].to_f32()
+}
+
+#[test]
+fn test_encode_round_trip() {
+    let mut vertices = Vec::new();
+    for x in POSITIONS.iter().flatten() {
+        vertices.extend_from_slice(&x.to_le_bytes());
+    }
+    let stream = gltf::meshopt::encode_vertex_buffer(&vertices, 12);
+    assert_eq!(stream, VERTEX_STREAM.to_vec());
+
+    let stream = gltf::meshopt::encode_index_buffer(&INDICES);
+    let decoded = gltf::meshopt::decode_index_buffer(18, 4, &stream).unwrap();
+    let decoded: Vec<_> = decoded
+        .chunks(4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect();
+    assert_eq!(triangles(&decoded), triangles(&INDICES));
+
+    let sequence = [7, 0, 1000, 1001, 2, 70000, 3];
+    let stream = gltf::meshopt::encode_index_sequence(&sequence);
+    let decoded = gltf::meshopt::decode_index_sequence(7, 4, &stream).unwrap();
+    let decoded: Vec<_> = decoded
+        .chunks(4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect();
+    assert_eq!(decoded, sequence.to_vec());
+}
+
+#[test]
+fn test_export_compressed_glb() {
+    let original = graph();
+    let glb = original.to_glb_with(&options(true)).unwrap();
+
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    let required: Vec<_> = gltf.extensions_required().collect();
+    assert_eq!(
+        required,
+        vec![gltf::meshopt::EXTENSION, "KHR_mesh_quantization"]
+    );
+    assert_eq!(gltf.buffers().count(), 2);
+    assert!(gltf::meshopt::is_fallback(&gltf.buffers().nth(1).unwrap()));
+    for view in gltf.views() {
+        let compression = gltf::meshopt::Compression::from_view(&view).unwrap();
+        assert_eq!(compression.unwrap().buffer, 0);
+    }
+
+    let (document, buffers, _) = gltf::import_slice(&glb).unwrap();
+    let graph = Graph::from_document(&document, &buffers).unwrap();
+    assert_eq!(graph.count::<Buffer>(), 1);
+    assert_eq!(graph.extensions_required, vec!["KHR_mesh_quantization"]);
+    let (_, mesh) = graph.iter::<Mesh>().next().unwrap();
+    let primitive = &mesh.primitives[0];
+    let positions = graph[primitive.get(&Semantic::Positions).unwrap()].to_f32::<3>();
+    assert_eq!(positions, POSITIONS.to_vec());
+    let indices = graph[primitive.indices.unwrap()].to_u32();
+    assert_eq!(triangles(&indices), triangles(&INDICES));
+    for (actual, expected) in normals(&graph).iter().zip(normals(&original)) {
+        for i in 0..3 {
+            assert!((actual[i] - expected[i]).abs() < 0.02);
+        }
+    }
+}
+
+#[test]
+fn test_export_uncompressed_fallback() {
+    let glb = graph().to_glb_with(&options(false)).unwrap();
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    // The quantized normals require `KHR_mesh_quantization`.
+    let required: Vec<_> = gltf.extensions_required().collect();
+    assert_eq!(required, vec!["KHR_mesh_quantization"]);
+    assert!(gltf
+        .extensions_used()
+        .any(|x| x == gltf::meshopt::EXTENSION));
+    assert_eq!(gltf.buffers().count(), 1);
+
+    // Loaders without support for the extension read the views directly.
+    let blob = gltf.blob.as_ref().unwrap();
+    let accessor = gltf.meshes().next().unwrap().primitives().next().unwrap();
+    let accessor = accessor.get(&Semantic::Positions).unwrap();
+    let view = accessor.view().unwrap();
+    let bytes = &blob[view.offset()..view.offset() + view.length()];
+    let positions: Vec<_> = bytes
+        .chunks(4)
+        .map(|x| f32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect();
+    assert_eq!(
+        positions,
+        POSITIONS.iter().flatten().copied().collect::<Vec<_>>()
+    );
+
+    let (document, buffers, _) = gltf::import_slice(&glb).unwrap();
+    let graph = Graph::from_document(&document, &buffers).unwrap();
+    assert_eq!(graph.count::<Buffer>(), 1);
+}
+
+#[test]
+fn test_export_compressed_gltf() {
+    let directory = env::temp_dir().join("gltf_export_meshopt");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let path = directory.join("mesh.gltf");
+    graph().export_with(&path, &options(true)).unwrap();
+    let files = fs::read_dir(&directory).unwrap().count();
+    assert_eq!(files, 2);
+
+    let (document, buffers, _) = gltf::import(&path).unwrap();
+    let graph = Graph::from_document(&document, &buffers).unwrap();
+    let (_, mesh) = graph.iter::<Mesh>().next().unwrap();
+    let positions = graph[mesh.primitives[0].get(&Semantic::Positions).unwrap()].to_f32::<3>();
+    assert_eq!(positions, POSITIONS.to_vec());
+    fs::remove_dir_all(&directory).unwrap();
+}
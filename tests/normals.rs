@@ -0,0 +1,238 @@
+mod common;
+
+use common::assert_close;
+use gltf::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use gltf::transform::{normals, NormalWeighting, NormalsOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+/// Corners of the unit cube centered on the origin.
+const CORNERS: [[f32; 3]; 8] = [
+    [-0.5, -0.5, -0.5],
+    [0.5, -0.5, -0.5],
+    [-0.5, 0.5, -0.5],
+    [0.5, 0.5, -0.5],
+    [-0.5, -0.5, 0.5],
+    [0.5, -0.5, 0.5],
+    [-0.5, 0.5, 0.5],
+    [0.5, 0.5, 0.5],
+];
+
+/// Counter-clockwise quads of the cube, split along their first diagonal.
+const QUADS: [[u32; 4]; 6] = [
+    [0, 2, 3, 1],
+    [4, 5, 7, 6],
+    [0, 1, 5, 4],
+    [2, 6, 7, 3],
+    [0, 4, 6, 2],
+    [1, 3, 7, 5],
+];
+
+fn cube_indices() -> Vec<u32> {
+    QUADS
+        .iter()
+        .flat_map(|[a, b, c, d]| [*a, *b, *c, *a, *c, *d])
+        .collect()
+}
+
+fn add_mesh(graph: &mut Graph, primitive: Primitive) -> Handle<Mesh> {
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+fn cube(graph: &mut Graph) -> Handle<Mesh> {
+    let mut primitive = Primitive::default();
+    primitive.set(Semantic::Positions, graph.add(Accessor::from_f32(&CORNERS)));
+    primitive.indices = Some(graph.add(Accessor::from_indices(&cube_indices(), 8)));
+    add_mesh(graph, primitive)
+}
+
+fn attribute<const N: usize>(
+    graph: &Graph,
+    mesh: Handle<Mesh>,
+    semantic: Semantic,
+) -> Vec<[f32; N]> {
+    graph[graph[mesh].primitives[0].get(&semantic).unwrap()].to_f32()
+}
+
+#[test]
+fn test_smooth_normals() {
+    let mut graph = Graph::new();
+    let mesh = cube(&mut graph);
+    normals(&mut graph, &NormalsOptions::default());
+
+    let positions = attribute::<3>(&graph, mesh, Semantic::Positions);
+    let normals = attribute::<3>(&graph, mesh, Semantic::Normals);
+    assert_eq!(positions, CORNERS.to_vec());
+    let s = 1.0 / 3f32.sqrt();
+    for (p, n) in positions.iter().zip(&normals) {
+        assert_close(n, &p.map(|x| x.signum() * s));
+    }
+    assert_eq!(graph.count::<Accessor>(), 3);
+
+    let (document, _) = graph.to_document().unwrap();
+    let primitive = document
+        .meshes()
+        .next()
+        .unwrap()
+        .primitives()
+        .next()
+        .unwrap();
+    let bounds = primitive
+        .attributes()
+        .find(|(semantic, _)| *semantic == Semantic::Normals)
+        .map(|(_, accessor)| (accessor.min().unwrap(), accessor.max().unwrap()))
+        .unwrap();
+    let bounds: (Vec<f32>, Vec<f32>) = (
+        gltf::json::deserialize::from_value(bounds.0).unwrap(),
+        gltf::json::deserialize::from_value(bounds.1).unwrap(),
+    );
+    assert_eq!(bounds, (vec![-s; 3], vec![s; 3]));
+}
+
+#[test]
+fn test_area_weighting() {
+    let mut graph = Graph::new();
+    let mesh = cube(&mut graph);
+    let options = NormalsOptions {
+        weighting: NormalWeighting::Area,
+        ..Default::default()
+    };
+    normals(&mut graph, &options);
+
+    // Quads split through corner 0 give it two triangles on each face, so
+    // area weighting stays symmetric there but not at corner 1.
+    let normals = attribute::<3>(&graph, mesh, Semantic::Normals);
+    let s = 1.0 / 3f32.sqrt();
+    assert_close(&normals[0], &[-s, -s, -s]);
+    assert!((normals[1][0] - s).abs() > 0.01 || (normals[1][1] + s).abs() > 0.01);
+}
+
+#[test]
+fn test_crease_angle() {
+    let mut graph = Graph::new();
+    let mesh = cube(&mut graph);
+    let options = NormalsOptions {
+        crease_angle: 45f32.to_radians(),
+        ..Default::default()
+    };
+    normals(&mut graph, &options);
+
+    let positions = attribute::<3>(&graph, mesh, Semantic::Positions);
+    let normals = attribute::<3>(&graph, mesh, Semantic::Normals);
+    assert_eq!(positions.len(), 24);
+    let indices = graph[graph[mesh].primitives[0].indices.unwrap()].to_u32();
+    assert_eq!(indices.len(), 36);
+    for t in indices.chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| t[i] as usize);
+        assert_eq!(normals[a], normals[b]);
+        assert_eq!(normals[a], normals[c]);
+        // The face normal points away from the center, along one axis.
+        let n = normals[a];
+        assert_eq!(n.iter().filter(|x| x.abs() == 1.0).count(), 1);
+        for i in 0..3 {
+            assert!(n[i] * positions[a][i] >= 0.0);
+        }
+    }
+    assert_eq!(graph.count::<Accessor>(), 3);
+}
+
+#[test]
+fn test_flat_normals() {
+    let mut graph = Graph::new();
+    let positions: Vec<_> = cube_indices()
+        .iter()
+        .map(|x| CORNERS[*x as usize])
+        .collect();
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    let mesh = add_mesh(&mut graph, primitive);
+    let options = NormalsOptions {
+        flat: true,
+        ..Default::default()
+    };
+    normals(&mut graph, &options);
+
+    assert!(graph[mesh].primitives[0].indices.is_none());
+    let normals = attribute::<3>(&graph, mesh, Semantic::Normals);
+    assert_eq!(normals.len(), 36);
+    let axes = [
+        [0.0, 0.0, -1.0],
+        [0.0, 0.0, 1.0],
+        [0.0, -1.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [-1.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+    ];
+    for (normals, axis) in normals.chunks(6).zip(axes) {
+        for n in normals {
+            assert_close(n, &axis);
+        }
+    }
+}
+
+#[test]
+fn test_smooth_across_seams() {
+    let mut graph = Graph::new();
+    // Two triangles folded along the Y axis, with the shared edge duplicated
+    // for different texture coordinates.
+    let positions = [
+        [0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [-1.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 1.0],
+        [0.0, 1.0, 0.0],
+    ];
+    let uvs = [
+        [0.0, 0.0],
+        [0.0, 1.0],
+        [-1.0, 0.0],
+        [1.0, 0.0],
+        [2.0, 0.0],
+        [1.0, 1.0],
+    ];
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    primitive.set(Semantic::TexCoords(0), graph.add(Accessor::from_f32(&uvs)));
+    let mesh = add_mesh(&mut graph, primitive);
+    normals(&mut graph, &NormalsOptions::default());
+
+    let normals = attribute::<3>(&graph, mesh, Semantic::Normals);
+    assert_eq!(normals[0], normals[3]);
+    assert_eq!(normals[1], normals[5]);
+    assert_ne!(normals[2], normals[4]);
+}
+
+#[test]
+fn test_keep_existing_normals() {
+    let mut graph = Graph::new();
+    let mesh = cube(&mut graph);
+    let existing = graph.add(Accessor::from_f32(&[[0.0, 1.0, 0.0]; 8]));
+    graph[mesh].primitives[0].set(Semantic::Normals, existing);
+
+    normals(&mut graph, &NormalsOptions::default());
+    assert_eq!(
+        graph[mesh].primitives[0].get(&Semantic::Normals),
+        Some(existing)
+    );
+
+    let options = NormalsOptions {
+        overwrite: true,
+        ..Default::default()
+    };
+    normals(&mut graph, &options);
+    assert_ne!(
+        graph[mesh].primitives[0].get(&Semantic::Normals),
+        Some(existing)
+    );
+    assert!(!graph.contains(existing));
+}
@@ -0,0 +1,112 @@
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{
+    Accessor, Animation, Channel, Graph, Handle, Image, Material, Mesh, Node, Primitive, Scene,
+};
+use gltf::transform::{prune, PruneOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+fn mesh(graph: &mut Graph) -> Handle<Mesh> {
+    let positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    primitive.material = Some(graph.add(Material::default()));
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+/// Builds a scene with one visible mesh, one named empty leaf, and one
+/// animated node that is not part of any scene.
+fn fixture() -> (Graph, Handle<Node>, Handle<Node>) {
+    let mut graph = Graph::new();
+    let visible = mesh(&mut graph);
+    let hidden = mesh(&mut graph);
+    graph.add(Material::default());
+    graph.add(Image {
+        data: b"\x89PNG".to_vec(),
+        ..Default::default()
+    });
+
+    let child = graph.add(Node {
+        mesh: Some(visible),
+        ..Default::default()
+    });
+    let socket = graph.add(Node {
+        name: Some("socket".to_string()),
+        ..Default::default()
+    });
+    let root = graph.add(Node {
+        children: vec![child, socket],
+        ..Default::default()
+    });
+    let orphan = graph.add(Node {
+        mesh: Some(hidden),
+        ..Default::default()
+    });
+    let scene = graph.add(Scene {
+        nodes: vec![root],
+        ..Default::default()
+    });
+    graph.default_scene = Some(scene);
+
+    let input = graph.add(Accessor::from_f32(&[[0.0], [1.0]]));
+    let output = graph.add(Accessor::from_f32(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]]));
+    graph.add(Animation {
+        channels: vec![Channel {
+            node: orphan,
+            property: Property::Translation,
+            input,
+            output,
+            interpolation: Interpolation::Linear,
+            extras: Default::default(),
+        }],
+        ..Default::default()
+    });
+    graph
+        .extensions_used
+        .push("KHR_mesh_quantization".to_string());
+    graph
+        .extensions_required
+        .push("KHR_mesh_quantization".to_string());
+
+    (graph, socket, orphan)
+}
+
+#[test]
+fn test_prune_unreachable() {
+    let (mut graph, socket, orphan) = fixture();
+    prune(&mut graph, &PruneOptions::default());
+
+    assert!(!graph.contains(orphan));
+    assert!(!graph.contains(socket));
+    assert_eq!(graph.count::<Node>(), 2);
+    assert_eq!(graph.count::<Mesh>(), 1);
+    assert_eq!(graph.count::<Material>(), 1);
+    assert_eq!(graph.count::<Accessor>(), 1);
+    assert_eq!(graph.count::<Animation>(), 0);
+    assert_eq!(graph.count::<Image>(), 0);
+    assert!(graph.extensions_used.is_empty());
+    assert!(graph.extensions_required.is_empty());
+
+    let (document, _) = graph.to_document().unwrap();
+    assert_eq!(document.nodes().count(), 2);
+    assert_eq!(document.accessors().count(), 1);
+    let root = document.default_scene().unwrap().nodes().next().unwrap();
+    assert_eq!(root.children().count(), 1);
+}
+
+#[test]
+fn test_prune_keep_named_leaves() {
+    let (mut graph, socket, _) = fixture();
+    let options = PruneOptions {
+        keep_named_leaves: true,
+    };
+    prune(&mut graph, &options);
+    assert!(graph.contains(socket));
+    assert_eq!(graph.count::<Node>(), 3);
+}
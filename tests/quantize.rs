@@ -0,0 +1,156 @@
+mod common;
+
+use common::{add_mesh, primitive};
+use gltf::accessor::DataType;
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Channel, Graph, Handle, Mesh, MorphTarget, Node};
+use gltf::scene::Transform;
+use gltf::transform::{quantize, QuantizeOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+const POSITIONS: [[f32; 3]; 3] = [[-2.0, 1.0, 0.0], [4.0, 1.0, 0.0], [-2.0, 3.0, 1.0]];
+
+fn mesh(graph: &mut Graph) -> Handle<Mesh> {
+    let mut primitive = primitive(graph, Accessor::from_f32(&POSITIONS));
+    let normals = [[0.0, 0.0, 1.0]; 3];
+    primitive.set(Semantic::Normals, graph.add(Accessor::from_f32(&normals)));
+    let uvs = [[0.0, 0.0], [1.0, 0.0], [0.25, 2.0]];
+    primitive.set(Semantic::TexCoords(0), graph.add(Accessor::from_f32(&uvs)));
+    let weights = [[0.3, 0.3, 0.4, 0.0]; 3];
+    primitive.set(
+        Semantic::Weights(0),
+        graph.add(Accessor::from_f32(&weights)),
+    );
+    add_mesh(graph, vec![primitive])
+}
+
+fn apply(matrix: [[f32; 4]; 4], p: [f32; 3]) -> [f32; 3] {
+    let mut out = [0.0; 3];
+    for (i, x) in out.iter_mut().enumerate() {
+        *x = matrix[3][i] + (0..3).map(|j| matrix[j][i] * p[j]).sum::<f32>();
+    }
+    out
+}
+
+#[test]
+fn test_quantize_attributes() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    quantize(&mut graph, &QuantizeOptions::default());
+
+    let primitive = &graph[mesh].primitives[0];
+    let accessor = |semantic| &graph[primitive.get(&semantic).unwrap()];
+    assert_eq!(accessor(Semantic::Positions).data_type(), DataType::I16);
+    assert_eq!(accessor(Semantic::Normals).data_type(), DataType::I16);
+    assert_eq!(accessor(Semantic::TexCoords(0)).data_type(), DataType::F32);
+    assert_eq!(accessor(Semantic::Weights(0)).data_type(), DataType::U8);
+    assert!(accessor(Semantic::Positions).normalized);
+    assert_eq!(graph.count::<Accessor>(), 4);
+
+    let weights = accessor(Semantic::Weights(0)).to_u32();
+    assert_eq!(weights[..4].iter().sum::<u32>(), 255);
+
+    let matrix = graph[node].transform.clone().matrix();
+    let positions = accessor(Semantic::Positions).to_f32::<3>();
+    for (quantized, expected) in positions.iter().zip(POSITIONS.iter()) {
+        let actual = apply(matrix, *quantized);
+        for i in 0..3 {
+            assert!((actual[i] - expected[i]).abs() < 1e-3);
+        }
+    }
+
+    assert_eq!(graph.extensions_used, vec!["KHR_mesh_quantization"]);
+    assert_eq!(graph.extensions_required, vec!["KHR_mesh_quantization"]);
+}
+
+#[test]
+fn test_quantize_moves_mesh_below_parent() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let child = graph.add(Node::default());
+    let parent = graph.add(Node {
+        mesh: Some(mesh),
+        children: vec![child],
+        transform: Transform::Decomposed {
+            translation: [1.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0; 3],
+        },
+        ..Default::default()
+    });
+    quantize(&mut graph, &QuantizeOptions::default());
+
+    assert_eq!(graph[parent].mesh, None);
+    assert_eq!(graph[parent].children.len(), 2);
+    let holder = graph[parent].children[1];
+    assert_eq!(graph[holder].mesh, Some(mesh));
+    assert_eq!(
+        graph[child].transform.clone().matrix()[3],
+        [0.0, 0.0, 0.0, 1.0]
+    );
+    let offset = graph[holder].transform.clone().matrix()[3];
+    assert_eq!(offset[..3], [1.0, 2.0, 0.5]);
+}
+
+#[test]
+fn test_quantize_moves_morph_weight_channels() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let displacements = graph.add(Accessor::from_f32(&[[0.0, 1.0, 0.0]; 3]));
+    graph[mesh].primitives[0].targets.push(MorphTarget {
+        positions: Some(displacements),
+        ..Default::default()
+    });
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        weights: Some(vec![0.0]),
+        ..Default::default()
+    });
+    let channel = Channel {
+        node,
+        property: Property::MorphTargetWeights,
+        input: graph.add(Accessor::from_f32(&[[0.0], [1.0]])),
+        output: graph.add(Accessor::from_f32(&[[0.0], [1.0]])),
+        interpolation: Interpolation::Linear,
+        extras: Default::default(),
+    };
+    let animation = graph.add(Animation {
+        channels: vec![channel],
+        ..Default::default()
+    });
+    quantize(&mut graph, &QuantizeOptions::default());
+
+    let positions = graph[mesh].primitives[0].get(&Semantic::Positions).unwrap();
+    assert_eq!(graph[positions].data_type(), DataType::I16);
+    assert_eq!(graph[node].mesh, None);
+    assert_eq!(graph[node].weights, None);
+    let holder = graph[node].children[0];
+    assert_eq!(graph[holder].mesh, Some(mesh));
+    assert_eq!(graph[holder].weights, Some(vec![0.0]));
+    assert_eq!(graph[animation].channels[0].node, holder);
+}
+
+#[test]
+fn test_quantize_keeps_lods_with_mesh() {
+    let mut graph = Graph::new();
+    let mesh = mesh(&mut graph);
+    let child = graph.add(Node::default());
+    let base = graph.add(Node {
+        mesh: Some(mesh),
+        children: vec![child],
+        ..Default::default()
+    });
+    let lod = graph.add_lod(base, mesh);
+    quantize(&mut graph, &QuantizeOptions::default());
+
+    let positions = graph[mesh].primitives[0].get(&Semantic::Positions).unwrap();
+    assert_eq!(graph[positions].data_type(), DataType::F32);
+    assert_eq!(graph[base].mesh, Some(mesh));
+    assert_eq!(graph[base].children, vec![child]);
+    assert_eq!(graph[base].lods, vec![lod]);
+}
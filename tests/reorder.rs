@@ -0,0 +1,140 @@
+use gltf::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use gltf::transform::{reorder, ReorderOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+const SIZE: u32 = 12;
+
+/// Builds a grid of quads with its triangles in a scrambled order.
+fn grid(graph: &mut Graph) -> Handle<Mesh> {
+    let mut positions = Vec::new();
+    for y in 0..=SIZE {
+        for x in 0..=SIZE {
+            let z = ((x * 7 + y * 3) % 5) as f32 * 0.1;
+            positions.push([x as f32, y as f32, z]);
+        }
+    }
+    let mut triangles = Vec::new();
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let a = y * (SIZE + 1) + x;
+            triangles.push([a, a + 1, a + SIZE + 1]);
+            triangles.push([a + SIZE + 1, a + 1, a + SIZE + 2]);
+        }
+    }
+    let mut scrambled = Vec::new();
+    for i in 0..triangles.len() {
+        scrambled.extend_from_slice(&triangles[i * 97 % triangles.len()]);
+    }
+
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    primitive.indices = Some(graph.add(Accessor::from_indices(&scrambled, positions.len())));
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+/// Returns the triangles of a primitive as positions, each rotated to start
+/// at its smallest vertex, sorted.
+fn triangles(graph: &Graph, primitive: &Primitive) -> Vec<[[i32; 3]; 3]> {
+    let positions = graph[primitive.get(&Semantic::Positions).unwrap()].to_f32::<3>();
+    let indices = graph[primitive.indices.unwrap()].to_u32();
+    let mut triangles: Vec<_> = indices
+        .chunks(3)
+        .map(|t| {
+            let p = [0, 1, 2].map(|i| positions[t[i] as usize].map(|x| (x * 10.0) as i32));
+            let first = (0..3).min_by_key(|i| p[*i]).unwrap();
+            [0, 1, 2].map(|i| p[(first + i) % 3])
+        })
+        .collect();
+    triangles.sort();
+    triangles
+}
+
+/// Returns the average number of cache misses per triangle with a FIFO
+/// cache of 16 vertices.
+fn acmr(indices: &[u32]) -> f32 {
+    let mut cache = std::collections::VecDeque::new();
+    let mut misses = 0;
+    for index in indices {
+        if !cache.contains(index) {
+            misses += 1;
+            cache.push_back(*index);
+            if cache.len() > 16 {
+                cache.pop_front();
+            }
+        }
+    }
+    misses as f32 / (indices.len() / 3) as f32
+}
+
+#[test]
+fn test_reorder_grid() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph);
+    let before = graph[mesh].primitives[0].clone();
+    let expected = triangles(&graph, &before);
+    let scrambled = acmr(&graph[before.indices.unwrap()].to_u32());
+
+    reorder(&mut graph, &ReorderOptions::default());
+    let primitive = &graph[mesh].primitives[0];
+    assert_eq!(triangles(&graph, primitive), expected);
+    assert_eq!(graph.count::<Accessor>(), 2);
+
+    let indices = graph[primitive.indices.unwrap()].to_u32();
+    assert!(acmr(&indices) < scrambled * 0.6);
+
+    // Vertices are stored in the order they are first used.
+    let mut next = 0;
+    for index in indices {
+        assert!(index <= next);
+        if index == next {
+            next += 1;
+        }
+    }
+    assert_eq!(next, (SIZE + 1) * (SIZE + 1));
+}
+
+#[test]
+fn test_reorder_is_deterministic() {
+    let mut first = Graph::new();
+    grid(&mut first);
+    let mut second = first.clone();
+    reorder(&mut first, &ReorderOptions::default());
+    reorder(&mut second, &ReorderOptions::default());
+    assert_eq!(first.to_glb().unwrap(), second.to_glb().unwrap());
+}
+
+#[test]
+fn test_reorder_keeps_shared_vertices() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph);
+    let mut other = graph[mesh].primitives[0].clone();
+    other.set(
+        Semantic::TexCoords(0),
+        graph.add(Accessor::from_f32(&vec![[0.0, 0.0]; 169])),
+    );
+    graph[mesh].primitives.push(other);
+    let positions =
+        graph[graph[mesh].primitives[0].get(&Semantic::Positions).unwrap()].to_f32::<3>();
+
+    reorder(
+        &mut graph,
+        &ReorderOptions {
+            overdraw_threshold: 0.0,
+            ..Default::default()
+        },
+    );
+    let primitives = &graph[mesh].primitives;
+    let shared = primitives[0].get(&Semantic::Positions).unwrap();
+    assert_eq!(graph[shared].to_f32::<3>(), positions);
+    let indices = |i: usize| graph[primitives[i].indices.unwrap()].to_u32();
+    assert_eq!(indices(0), indices(1));
+    let expected = triangles(&graph, &primitives[0]);
+    assert_eq!(triangles(&graph, &primitives[1]), expected);
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use common::channel;
+use gltf::accessor::Dimensions;
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Channel, Graph, Mesh, Node, Values};
+use gltf::transform::{resample, ResampleOptions, ResampleReport};
+use gltf_transform_rs as gltf;
+
+fn times(graph: &Graph, channel: &Channel) -> Vec<f32> {
+    graph[channel.input].to_f32::<1>().concat()
+}
+
+#[test]
+fn test_remove_interpolated_keyframes() {
+    let mut graph = Graph::new();
+    let node = graph.add(Node::default());
+    let translations = Accessor::from_f32(&[
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [2.0, 0.0, 0.0],
+        [2.0, 1.0, 0.0],
+        [2.0, 2.0, 0.0],
+    ]);
+    let translation = channel(
+        &mut graph,
+        node,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0, 1.0, 2.0, 3.0, 4.0],
+        translations,
+    );
+    // A half turn about Z in quantized steps of an eighth turn.
+    let rotations: Vec<i16> = (0..5)
+        .flat_map(|i| {
+            let (sin, cos) = (i as f32 * std::f32::consts::FRAC_PI_8).sin_cos();
+            [0.0, 0.0, sin, cos].map(|x| (x * 32767.0).round() as i16)
+        })
+        .collect();
+    let rotations = Accessor {
+        normalized: true,
+        ..Accessor::new(Dimensions::Vec4, Values::I16(rotations))
+    };
+    let rotation = channel(
+        &mut graph,
+        node,
+        Property::Rotation,
+        Interpolation::Linear,
+        &[0.0, 1.0, 2.0, 3.0, 4.0],
+        rotations,
+    );
+    let scale = channel(
+        &mut graph,
+        node,
+        Property::Scale,
+        Interpolation::Step,
+        &[0.0, 1.0, 2.0, 3.0],
+        Accessor::from_f32(&[[1.0; 3], [1.0; 3], [2.0; 3], [2.0; 3]]),
+    );
+    let animation = graph.add(Animation {
+        channels: vec![translation, rotation, scale],
+        ..Default::default()
+    });
+    assert_eq!(graph.count::<Accessor>(), 6);
+
+    let options = ResampleOptions { tolerance: 1e-3 };
+    let report = resample(&mut graph, &options);
+    assert_eq!(
+        report,
+        ResampleReport {
+            keyframes: 6,
+            channels: 0,
+            animations: 0,
+        }
+    );
+    let channels = &graph[animation].channels;
+    assert_eq!(times(&graph, &channels[0]), vec![0.0, 2.0, 4.0]);
+    assert_eq!(graph[channels[0].output].to_f32::<3>()[1], [2.0, 0.0, 0.0]);
+    assert_eq!(times(&graph, &channels[1]), vec![0.0, 4.0]);
+    let rotations = &graph[channels[1].output];
+    assert_eq!(rotations.data_type(), gltf::accessor::DataType::I16);
+    assert!(rotations.normalized);
+    assert_eq!(times(&graph, &channels[2]), vec![0.0, 2.0, 3.0]);
+    // The replaced accessors are removed.
+    assert_eq!(graph.count::<Accessor>(), 6);
+}
+
+#[test]
+fn test_remove_rest_channels() {
+    let mut graph = Graph::new();
+    let mut primitive = gltf::graph::Primitive::default();
+    for _ in 0..2 {
+        primitive.targets.push(Default::default());
+    }
+    let mesh = graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    });
+    let node = graph.add(Node {
+        mesh: Some(mesh),
+        ..Default::default()
+    });
+    let held = channel(
+        &mut graph,
+        node,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0, 1.0, 2.0],
+        Accessor::from_f32(&[[1.0, 0.0, 0.0]; 3]),
+    );
+    let weights = channel(
+        &mut graph,
+        node,
+        Property::MorphTargetWeights,
+        Interpolation::Linear,
+        &[0.0, 1.0, 2.0],
+        Accessor::from_f32(&[[0.0], [1.0], [0.5], [0.5], [1.0], [0.0]]),
+    );
+    let animated = graph.add(Animation {
+        channels: vec![held, weights],
+        ..Default::default()
+    });
+    let rest = channel(
+        &mut graph,
+        node,
+        Property::MorphTargetWeights,
+        Interpolation::CubicSpline,
+        &[0.0, 1.0],
+        Accessor::from_f32(&[[0.0]; 12]),
+    );
+    let still = graph.add(Animation {
+        channels: vec![rest],
+        ..Default::default()
+    });
+
+    let report = resample(&mut graph, &ResampleOptions::default());
+    assert_eq!(report.channels, 1);
+    assert_eq!(report.animations, 1);
+    assert!(!graph.contains(still));
+
+    let channels = &graph[animated].channels;
+    // The constant translation keeps its first and last keyframes.
+    assert_eq!(times(&graph, &channels[0]), vec![0.0, 2.0]);
+    assert_eq!(graph[channels[0].output].count(), 2);
+    // Both morph target weights change linearly.
+    assert_eq!(times(&graph, &channels[1]), vec![0.0, 2.0]);
+    assert_eq!(
+        graph[channels[1].output].to_f32::<1>().concat(),
+        vec![0.0, 1.0, 1.0, 0.0]
+    );
+    assert_eq!(report.keyframes, 2);
+    assert_eq!(graph.count::<Accessor>(), 4);
+}
+
+#[test]
+fn test_keep_time_range() {
+    let mut graph = Graph::new();
+    let node = graph.add(Node::default());
+    let held = channel(
+        &mut graph,
+        node,
+        Property::Scale,
+        Interpolation::Linear,
+        &[0.0, 1.0, 2.0],
+        Accessor::from_f32(&[[2.0; 3]; 3]),
+    );
+    let moved = channel(
+        &mut graph,
+        node,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0, 1.0],
+        Accessor::from_f32(&[[0.0; 3], [1.0; 3]]),
+    );
+    let rest = channel(
+        &mut graph,
+        node,
+        Property::Rotation,
+        Interpolation::Linear,
+        &[0.0, 3.0, 4.0],
+        Accessor::from_f32(&[[0.0, 0.0, 0.0, 1.0]; 3]),
+    );
+    let covered = channel(
+        &mut graph,
+        node,
+        Property::Rotation,
+        Interpolation::Step,
+        &[1.0, 2.0],
+        Accessor::from_f32(&[[0.0, 0.0, 0.0, 1.0]; 2]),
+    );
+    let animation = graph.add(Animation {
+        channels: vec![held, moved, rest, covered],
+        ..Default::default()
+    });
+
+    let report = resample(&mut graph, &ResampleOptions::default());
+    assert_eq!(report.channels, 1);
+    let channels = &graph[animation].channels;
+    assert_eq!(channels.len(), 3);
+    assert_eq!(times(&graph, &channels[0]), vec![0.0, 2.0]);
+    assert_eq!(times(&graph, &channels[1]), vec![0.0, 1.0]);
+    // The rest rotation is kept since it extends the animation to 4 seconds.
+    assert_eq!(channels[2].property, Property::Rotation);
+    assert_eq!(times(&graph, &channels[2]), vec![0.0, 4.0]);
+}
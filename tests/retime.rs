@@ -0,0 +1,143 @@
+mod common;
+
+use common::{assert_close, channel};
+use gltf::animation::{Interpolation, Property};
+use gltf::graph::{Accessor, Animation, Graph, Handle, Node};
+use gltf::transform::{merge_animations, retime_animation, split_animation, trim_animation, Clip};
+use gltf_transform_rs as gltf;
+
+/// Adds an animation that moves a node along X by one unit per second.
+fn slide(graph: &mut Graph) -> Handle<Animation> {
+    let node = graph.add(Node::default());
+    let values = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [2.0, 0.0, 0.0],
+        [3.0, 0.0, 0.0],
+    ];
+    let channel = channel(
+        graph,
+        node,
+        Property::Translation,
+        Interpolation::Linear,
+        &[0.0, 1.0, 2.0, 3.0],
+        Accessor::from_f32(&values),
+    );
+    graph.add(Animation {
+        channels: vec![channel],
+        ..Default::default()
+    })
+}
+
+/// Adds an animation with one cubic spline channel that eases in and out.
+fn spline(graph: &mut Graph) -> Handle<Animation> {
+    let node = graph.add(Node::default());
+    let values = [
+        [0.0; 3],
+        [0.0; 3],
+        [1.0, 0.0, 0.0],
+        [0.0, 2.0, 0.0],
+        [1.0, 1.0, 1.0],
+        [0.0; 3],
+    ];
+    let channel = channel(
+        graph,
+        node,
+        Property::Translation,
+        Interpolation::CubicSpline,
+        &[0.0, 1.0],
+        Accessor::from_f32(&values),
+    );
+    graph.add(Animation {
+        channels: vec![channel],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_trim_and_split() {
+    let mut graph = Graph::new();
+    let animation = slide(&mut graph);
+    trim_animation(&mut graph, animation, 0.5, 2.5);
+    let keyframes = graph[animation].channels[0].keyframes(&graph).unwrap();
+    assert_eq!(keyframes.inputs(), &[0.5, 1.0, 2.0, 2.5]);
+    assert_eq!(keyframes.value(0), &[0.5, 0.0, 0.0]);
+    assert_eq!(keyframes.value(3), &[2.5, 0.0, 0.0]);
+    assert_eq!(graph.count::<Accessor>(), 2);
+
+    let clips = [
+        Clip {
+            name: "start".to_string(),
+            start: 0.0,
+            end: 1.0,
+        },
+        Clip {
+            name: "end".to_string(),
+            start: 1.5,
+            end: 3.0,
+        },
+    ];
+    assert!(split_animation(&mut graph, animation, &[]).is_empty());
+    assert!(graph.contains(animation));
+    let animations = split_animation(&mut graph, animation, &clips);
+    assert!(!graph.contains(animation));
+    assert_eq!(graph.count::<Animation>(), 2);
+    assert_eq!(graph[animations[0]].name.as_deref(), Some("start"));
+    let keyframes = graph[animations[0]].channels[0].keyframes(&graph).unwrap();
+    assert_eq!(keyframes.inputs(), &[0.5, 1.0]);
+    assert_eq!(keyframes.value(0), &[0.5, 0.0, 0.0]);
+    let keyframes = graph[animations[1]].channels[0].keyframes(&graph).unwrap();
+    assert_eq!(keyframes.inputs(), &[0.0, 0.5, 1.0]);
+    assert_eq!(keyframes.value(0), &[1.5, 0.0, 0.0]);
+    assert_eq!(graph.count::<Accessor>(), 4);
+}
+
+#[test]
+fn test_cubic_spline() {
+    let mut graph = Graph::new();
+    let animation = spline(&mut graph);
+    let before = graph[animation].channels[0].keyframes(&graph).unwrap();
+
+    trim_animation(&mut graph, animation, 0.25, 0.75);
+    let trimmed = graph[animation].channels[0].keyframes(&graph).unwrap();
+    assert_eq!(trimmed.inputs(), &[0.25, 0.75]);
+    for i in 0..=10 {
+        let t = 0.25 + 0.05 * i as f32;
+        assert_close(&trimmed.sample(t), &before.sample(t));
+    }
+
+    retime_animation(&mut graph, animation, 1.0, 2.0);
+    let retimed = graph[animation].channels[0].keyframes(&graph).unwrap();
+    assert_eq!(retimed.inputs(), &[1.5, 2.5]);
+    for i in 0..=10 {
+        let t = 0.25 + 0.05 * i as f32;
+        assert_close(&retimed.sample(1.0 + 2.0 * t), &before.sample(t));
+    }
+}
+
+#[test]
+fn test_merge() {
+    let mut graph = Graph::new();
+    let a = slide(&mut graph);
+    let b = spline(&mut graph);
+    let node = graph[a].channels[0].node;
+    let conflict = channel(
+        &mut graph,
+        node,
+        Property::Translation,
+        Interpolation::Step,
+        &[0.0],
+        Accessor::from_f32(&[[0.0; 3]]),
+    );
+    let c = graph.add(Animation {
+        channels: vec![conflict],
+        ..Default::default()
+    });
+
+    assert_eq!(merge_animations(&mut graph, &[a, b, c]), None);
+    assert_eq!(graph.count::<Animation>(), 3);
+    assert_eq!(merge_animations(&mut graph, &[b, a, b]), Some(b));
+    assert!(!graph.contains(a));
+    assert_eq!(graph[b].channels.len(), 2);
+    assert_eq!(graph[b].channels[1].node, node);
+}
@@ -0,0 +1,169 @@
+use gltf::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use gltf::transform::{simplify, simplify_primitive, SimplifyOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+const SIZE: u32 = 16;
+
+/// Builds a grid of quads over the unit square, with heights given by `z`.
+///
+/// With `seam`, the vertices of the middle column are duplicated so that the
+/// left and right halves have separate texture coordinates.
+fn grid(graph: &mut Graph, z: impl Fn(f32, f32) -> f32, seam: bool) -> Handle<Mesh> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut ids = vec![[0; 2]; ((SIZE + 1) * (SIZE + 1)) as usize];
+    for y in 0..=SIZE {
+        for x in 0..=SIZE {
+            let (u, v) = (x as f32 / SIZE as f32, y as f32 / SIZE as f32);
+            let id = (y * (SIZE + 1) + x) as usize;
+            ids[id] = [positions.len() as u32; 2];
+            positions.push([u, v, z(u, v)]);
+            uvs.push([u * 0.5, v]);
+            if seam && x == SIZE / 2 {
+                ids[id][1] = positions.len() as u32;
+                positions.push([u, v, z(u, v)]);
+                uvs.push([u * 0.5 + 0.5, v]);
+            }
+        }
+    }
+    let mut indices = Vec::new();
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let side = (x >= SIZE / 2) as usize;
+            let id = |dx: u32, dy: u32| ids[((y + dy) * (SIZE + 1) + x + dx) as usize][side];
+            indices.extend_from_slice(&[id(0, 0), id(1, 0), id(0, 1)]);
+            indices.extend_from_slice(&[id(0, 1), id(1, 0), id(1, 1)]);
+        }
+    }
+
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    primitive.set(Semantic::TexCoords(0), graph.add(Accessor::from_f32(&uvs)));
+    primitive.indices = Some(graph.add(Accessor::from_indices(&indices, positions.len())));
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+/// Returns the area of every triangle, signed by the Z axis of its normal.
+fn areas(positions: &[[f32; 3]], indices: &[u32]) -> Vec<f32> {
+    indices
+        .chunks(3)
+        .map(|t| {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[t[i] as usize]);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) * 0.5
+        })
+        .collect()
+}
+
+fn positions(graph: &Graph, mesh: Handle<Mesh>) -> Vec<[f32; 3]> {
+    let primitive = &graph[mesh].primitives[0];
+    graph[primitive.get(&Semantic::Positions).unwrap()].to_f32()
+}
+
+#[test]
+fn test_simplify_plane() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, |_, _| 0.0, false);
+    let options = SimplifyOptions {
+        ratio: 0.1,
+        ..Default::default()
+    };
+    let result = simplify_primitive(&graph, &graph[mesh].primitives[0], &options).unwrap();
+
+    let triangles = result.indices.len() / 3;
+    assert!(triangles <= (SIZE * SIZE * 2 / 10) as usize);
+    assert!(result.error < 1e-6);
+    let areas = areas(&positions(&graph, mesh), &result.indices);
+    assert!(areas.iter().all(|x| *x > 0.0));
+    assert!((areas.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_simplify_lock_border() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, |_, _| 0.0, false);
+    let options = SimplifyOptions {
+        ratio: 0.0,
+        lock_border: true,
+        ..Default::default()
+    };
+    let result = simplify_primitive(&graph, &graph[mesh].primitives[0], &options).unwrap();
+
+    let positions = positions(&graph, mesh);
+    for (i, p) in positions.iter().enumerate() {
+        let border = p[0] == 0.0 || p[0] == 1.0 || p[1] == 0.0 || p[1] == 1.0;
+        if border {
+            assert!(result.indices.contains(&(i as u32)), "{:?}", p);
+        }
+    }
+    assert!(result.indices.len() < (SIZE * SIZE * 6) as usize / 2);
+}
+
+#[test]
+fn test_simplify_keeps_seams() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, |_, _| 0.0, true);
+    let options = SimplifyOptions {
+        ratio: 0.1,
+        ..Default::default()
+    };
+    let result = simplify_primitive(&graph, &graph[mesh].primitives[0], &options).unwrap();
+    assert!(result.indices.len() / 3 < (SIZE * SIZE) as usize);
+
+    // Each triangle uses texture coordinates from one half only.
+    let primitive = &graph[mesh].primitives[0];
+    let uvs = graph[primitive.get(&Semantic::TexCoords(0)).unwrap()].to_f32::<2>();
+    for t in result.indices.chunks(3) {
+        let left = t.iter().filter(|x| uvs[**x as usize][0] <= 0.25).count();
+        let right = t.iter().filter(|x| uvs[**x as usize][0] >= 0.25).count();
+        assert!(left == 3 || right == 3);
+    }
+    let areas = areas(&positions(&graph, mesh), &result.indices);
+    assert!(areas.iter().all(|x| *x > 0.0));
+    assert!((areas.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_simplify_error_limit() {
+    let bump = |u: f32, v: f32| ((u - 0.5).powi(2) + (v - 0.5).powi(2)) * 0.8;
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, bump, false);
+    let primitive = graph[mesh].primitives[0].clone();
+
+    let coarse = SimplifyOptions {
+        ratio: 0.0,
+        error: 0.05,
+        ..Default::default()
+    };
+    let fine = SimplifyOptions {
+        error: 0.005,
+        ..coarse.clone()
+    };
+    let coarse = simplify_primitive(&graph, &primitive, &coarse).unwrap();
+    let fine = simplify_primitive(&graph, &primitive, &fine).unwrap();
+    assert!(coarse.error <= 0.05);
+    assert!(fine.error <= 0.005);
+    assert!(coarse.indices.len() < fine.indices.len());
+    assert!(fine.indices.len() < (SIZE * SIZE * 6) as usize);
+}
+
+#[test]
+fn test_simplify_graph() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, |_, _| 0.0, false);
+    let indices = graph[mesh].primitives[0].indices.unwrap();
+    simplify(&mut graph, &SimplifyOptions::default());
+
+    let primitive = &graph[mesh].primitives[0];
+    assert_ne!(primitive.indices, Some(indices));
+    assert!(graph.get(indices).is_none());
+    let count = graph[primitive.indices.unwrap()].count();
+    assert!(count <= (SIZE * SIZE * 3) as usize);
+    assert_eq!(graph.count::<Accessor>(), 3);
+}
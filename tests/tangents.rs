@@ -0,0 +1,260 @@
+mod common;
+
+use common::assert_close;
+use gltf::graph::{
+    Accessor, Graph, Handle, Image, Material, Mesh, Primitive, Texture, TextureInfo,
+};
+use gltf::transform::{tangents, TangentsOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+/// Adds a material whose normal texture uses `TEXCOORD_<tex_coord>`.
+fn normal_mapped(graph: &mut Graph, tex_coord: u32) -> Handle<Material> {
+    let source = graph.add(Image {
+        data: b"\x89PNG".to_vec(),
+        ..Default::default()
+    });
+    let texture = graph.add(Texture {
+        name: None,
+        source,
+        sampler: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+    graph.add(Material {
+        normal_texture: Some(TextureInfo {
+            texture,
+            tex_coord,
+            extensions: Default::default(),
+        }),
+        ..Default::default()
+    })
+}
+
+/// Builds a grid of quads over `[-1, 1]²` in the XY plane, facing `+Z`, with
+/// texture coordinates given by `uv` in set `tex_coord`.
+fn grid(graph: &mut Graph, tex_coord: u32, uv: impl Fn(f32, f32) -> [f32; 2]) -> Handle<Mesh> {
+    const SIZE: u32 = 4;
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    for y in 0..=SIZE {
+        for x in 0..=SIZE {
+            let (x, y) = (x as f32 / 2.0 - 1.0, y as f32 / 2.0 - 1.0);
+            positions.push([x, y, 0.0]);
+            uvs.push(uv(x, y));
+        }
+    }
+    let mut indices = Vec::new();
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let a = y * (SIZE + 1) + x;
+            let (b, c, d) = (a + 1, a + SIZE + 1, a + SIZE + 2);
+            indices.extend_from_slice(&[a, b, d, a, d, c]);
+        }
+    }
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    primitive.set(
+        Semantic::Normals,
+        graph.add(Accessor::from_f32(&vec![[0.0, 0.0, 1.0]; positions.len()])),
+    );
+    primitive.set(
+        Semantic::TexCoords(tex_coord),
+        graph.add(Accessor::from_f32(&uvs)),
+    );
+    primitive.indices = Some(graph.add(Accessor::from_indices(&indices, positions.len())));
+    primitive.material = Some(normal_mapped(graph, tex_coord));
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+fn attribute<const N: usize>(
+    graph: &Graph,
+    mesh: Handle<Mesh>,
+    semantic: Semantic,
+) -> Vec<[f32; N]> {
+    graph[graph[mesh].primitives[0].get(&semantic).unwrap()].to_f32()
+}
+
+#[test]
+fn test_planar_tangents() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, 0, |x, y| [x, 1.0 - y]);
+    tangents(&mut graph, &TangentsOptions::default());
+
+    let tangents = attribute::<4>(&graph, mesh, Semantic::Tangents);
+    assert_eq!(tangents.len(), 25);
+    for tangent in tangents {
+        // `v` decreases along `+Y`, so the bitangent `N × T · w` is `-Y`.
+        assert_close(&tangent, &[1.0, 0.0, 0.0, -1.0]);
+    }
+}
+
+#[test]
+fn test_rotated_texture_coordinates() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, 1, |x, y| [y, -x]);
+    tangents(&mut graph, &TangentsOptions::default());
+
+    let tangents = attribute::<4>(&graph, mesh, Semantic::Tangents);
+    for tangent in tangents {
+        assert_close(&tangent, &[0.0, 1.0, 0.0, 1.0]);
+    }
+}
+
+#[test]
+fn test_mirrored_seam() {
+    let mut graph = Graph::new();
+    // The right half mirrors the left half of the texture around `x = 0`.
+    let mesh = grid(&mut graph, 0, |x, y| [1.0 - x.abs(), y]);
+    tangents(&mut graph, &TangentsOptions::default());
+
+    // The five vertices on the seam are split.
+    let positions = attribute::<3>(&graph, mesh, Semantic::Positions);
+    let uvs = attribute::<2>(&graph, mesh, Semantic::TexCoords(0));
+    let tangents = attribute::<4>(&graph, mesh, Semantic::Tangents);
+    assert_eq!(positions.len(), 30);
+    assert_eq!(uvs.len(), 30);
+    let indices = graph[graph[mesh].primitives[0].indices.unwrap()].to_u32();
+    for t in indices.chunks(3) {
+        let center = t.iter().map(|x| positions[*x as usize][0]).sum::<f32>() / 3.0;
+        let expected = if center < 0.0 {
+            [1.0, 0.0, 0.0, 1.0]
+        } else {
+            [-1.0, 0.0, 0.0, -1.0]
+        };
+        for index in t {
+            assert_close(&tangents[*index as usize], &expected);
+        }
+    }
+}
+
+#[test]
+fn test_curved_surface() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, 0, |x, y| [x * 0.5, y * y]);
+    // Bend the grid into a bump and tilt the normals accordingly.
+    let primitive = graph[mesh].primitives[0].clone();
+    let positions: Vec<_> = attribute::<3>(&graph, mesh, Semantic::Positions)
+        .iter()
+        .map(|[x, y, _]| [*x, *y, 1.0 - (x * x + y * y) * 0.25])
+        .collect();
+    let normals: Vec<_> = positions
+        .iter()
+        .map(|[x, y, _]| {
+            let n = [x * 0.5, y * 0.5, 1.0];
+            let length = (n[0] * n[0] + n[1] * n[1] + 1.0f32).sqrt();
+            n.map(|c| c / length)
+        })
+        .collect();
+    let handle = primitive.get(&Semantic::Positions).unwrap();
+    graph[handle] = Accessor::from_f32(&positions);
+    let handle = primitive.get(&Semantic::Normals).unwrap();
+    graph[handle] = Accessor::from_f32(&normals);
+    tangents(&mut graph, &TangentsOptions::default());
+
+    let normals = attribute::<3>(&graph, mesh, Semantic::Normals);
+    let tangents = attribute::<4>(&graph, mesh, Semantic::Tangents);
+    for (n, t) in normals.iter().zip(&tangents) {
+        let dot = n[0] * t[0] + n[1] * t[1] + n[2] * t[2];
+        let length = (t[0] * t[0] + t[1] * t[1] + t[2] * t[2]).sqrt();
+        assert!(dot.abs() < 1e-5);
+        assert!((length - 1.0).abs() < 1e-5);
+        assert!(t[0] > 0.5);
+        assert_eq!(t[3].abs(), 1.0);
+    }
+}
+
+#[test]
+fn test_reference_tangents() {
+    // A strip of two quads folded along `x = 0`, where the texture is
+    // mirrored, with smooth normals and skewed texture coordinates.
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    for y in [-1.0f32, 1.0] {
+        for x in [-1.0f32, 0.0, 1.0] {
+            positions.push([x, y, 0.5 - 0.25 * x * x + 0.2 * x * y]);
+            let n = [0.5 * x - 0.2 * y, -0.2 * x, 1.0];
+            let length = (n[0] * n[0] + n[1] * n[1] + 1.0f32).sqrt();
+            normals.push(n.map(|c| c / length));
+            uvs.push([1.0 - x.abs(), 0.5 * y + 0.5 + 0.125 * x]);
+        }
+    }
+    let indices = [0, 1, 4, 0, 4, 3, 1, 2, 5, 1, 5, 4];
+    let mut graph = Graph::new();
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(&positions)),
+    );
+    primitive.set(Semantic::Normals, graph.add(Accessor::from_f32(&normals)));
+    primitive.set(Semantic::TexCoords(0), graph.add(Accessor::from_f32(&uvs)));
+    primitive.indices = Some(graph.add(Accessor::from_indices(&indices, 6)));
+    primitive.material = Some(normal_mapped(&mut graph, 0));
+    let mesh = graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    });
+    tangents(&mut graph, &TangentsOptions::default());
+
+    // Computed with the reference MikkTSpace implementation, per corner.
+    let expected = [
+        [0.923358, -0.212807, 0.319569, 1.0],
+        [0.949597, -0.249389, -0.189920, 1.0],
+        [0.953979, -0.231348, 0.190796, 1.0],
+        [0.923358, -0.212807, 0.319569, 1.0],
+        [0.953979, -0.231348, 0.190796, 1.0],
+        [0.785351, -0.192715, 0.588289, 1.0],
+        [-0.953979, 0.231348, 0.190796, -1.0],
+        [-0.785351, 0.192715, 0.588289, -1.0],
+        [-0.923358, 0.212806, 0.319569, -1.0],
+        [-0.953979, 0.231348, 0.190796, -1.0],
+        [-0.923358, 0.212806, 0.319569, -1.0],
+        [-0.949597, 0.249389, -0.189920, -1.0],
+    ];
+    let tangents = attribute::<4>(&graph, mesh, Semantic::Tangents);
+    let indices = graph[graph[mesh].primitives[0].indices.unwrap()].to_u32();
+    assert_eq!(indices.len(), expected.len());
+    for (index, expected) in indices.iter().zip(&expected) {
+        assert_close(&tangents[*index as usize], expected);
+    }
+}
+
+#[test]
+fn test_requires_normal_texture() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, 0, |x, y| [x, y]);
+    graph[mesh].primitives[0].material = Some(graph.add(Material::default()));
+    tangents(&mut graph, &TangentsOptions::default());
+    assert!(graph[mesh].primitives[0].get(&Semantic::Tangents).is_none());
+
+    // The normal texture reads a texture coordinate set the primitive lacks.
+    graph[mesh].primitives[0].material = Some(normal_mapped(&mut graph, 1));
+    tangents(&mut graph, &TangentsOptions::default());
+    assert!(graph[mesh].primitives[0].get(&Semantic::Tangents).is_none());
+}
+
+#[test]
+fn test_keep_existing_tangents() {
+    let mut graph = Graph::new();
+    let mesh = grid(&mut graph, 0, |x, y| [x, y]);
+    let existing = graph.add(Accessor::from_f32(&[[0.0, 1.0, 0.0, 1.0]; 25]));
+    graph[mesh].primitives[0].set(Semantic::Tangents, existing);
+
+    tangents(&mut graph, &TangentsOptions::default());
+    assert_eq!(
+        graph[mesh].primitives[0].get(&Semantic::Tangents),
+        Some(existing)
+    );
+
+    tangents(&mut graph, &TangentsOptions { overwrite: true });
+    let tangents = attribute::<4>(&graph, mesh, Semantic::Tangents);
+    assert_close(&tangents[0], &[1.0, 0.0, 0.0, 1.0]);
+    assert!(!graph.contains(existing));
+}
@@ -0,0 +1,166 @@
+use gltf::graph::{Accessor, Graph, Handle, Mesh, Primitive};
+use gltf::mesh::Mode;
+use gltf::transform::unify_topology;
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+/// A zigzag of vertices along the X axis, alternating between `y = 1` and
+/// `y = 0`, which draws counter-clockwise triangles as a strip.
+fn zigzag(count: usize) -> Vec<[f32; 3]> {
+    (0..count)
+        .map(|i| [(i / 2) as f32, (1 - i % 2) as f32, 0.0])
+        .collect()
+}
+
+fn add_primitive(
+    graph: &mut Graph,
+    mode: Mode,
+    positions: &[[f32; 3]],
+    indices: Option<&[u32]>,
+) -> Handle<Mesh> {
+    let mut primitive = Primitive {
+        mode,
+        ..Default::default()
+    };
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(positions)),
+    );
+    primitive.indices = indices.map(|x| graph.add(Accessor::from_indices(x, positions.len())));
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+fn indices(graph: &Graph, mesh: Handle<Mesh>) -> Vec<u32> {
+    graph[graph[mesh].primitives[0].indices.unwrap()].to_u32()
+}
+
+/// Returns the `z` component of the face normal of every triangle.
+fn facing(positions: &[[f32; 3]], triangles: &[[u32; 3]]) -> Vec<f32> {
+    triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|x| positions[x as usize]);
+            (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+        })
+        .collect()
+}
+
+#[test]
+fn test_triangle_strip() {
+    let mut graph = Graph::new();
+    let positions = zigzag(5);
+    let mesh = add_primitive(&mut graph, Mode::TriangleStrip, &positions, None);
+
+    let triangles: Vec<_> = graph[mesh].primitives[0].triangles(&graph).collect();
+    assert_eq!(triangles, vec![[0, 1, 2], [1, 3, 2], [2, 3, 4]]);
+    assert!(facing(&positions, &triangles).iter().all(|z| *z > 0.0));
+
+    unify_topology(&mut graph);
+    let primitive = &graph[mesh].primitives[0];
+    assert_eq!(primitive.mode, Mode::Triangles);
+    assert_eq!(indices(&graph, mesh), vec![0, 1, 2, 1, 3, 2, 2, 3, 4]);
+    let unified: Vec<_> = primitive.triangles(&graph).collect();
+    assert_eq!(unified, triangles);
+}
+
+#[test]
+fn test_stitched_strips() {
+    let mut graph = Graph::new();
+    let positions = zigzag(8);
+    // Two strips of two triangles each, joined by repeating 3 and 4.
+    let strip = [0, 1, 2, 3, 3, 4, 4, 5, 6, 7];
+    let mesh = add_primitive(&mut graph, Mode::TriangleStrip, &positions, Some(&strip));
+    assert_eq!(graph[mesh].primitives[0].triangles(&graph).len(), 8);
+
+    unify_topology(&mut graph);
+    let triangles: Vec<_> = graph[mesh].primitives[0].triangles(&graph).collect();
+    assert_eq!(triangles, vec![[0, 1, 2], [1, 3, 2], [4, 5, 6], [5, 7, 6]]);
+    assert!(facing(&positions, &triangles).iter().all(|z| *z > 0.0));
+    assert_eq!(graph.count::<Accessor>(), 2);
+}
+
+#[test]
+fn test_triangle_fan() {
+    let mut graph = Graph::new();
+    let positions = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0],
+    ];
+    let mesh = add_primitive(
+        &mut graph,
+        Mode::TriangleFan,
+        &positions,
+        Some(&[0, 1, 2, 3, 4]),
+    );
+
+    unify_topology(&mut graph);
+    let triangles: Vec<_> = graph[mesh].primitives[0].triangles(&graph).collect();
+    assert_eq!(triangles, vec![[1, 2, 0], [2, 3, 0], [3, 4, 0]]);
+    assert!(facing(&positions, &triangles).iter().all(|z| *z > 0.0));
+}
+
+#[test]
+fn test_lines() {
+    let mut graph = Graph::new();
+    let positions = zigzag(4);
+    let strip = add_primitive(&mut graph, Mode::LineStrip, &positions, None);
+    let looped = add_primitive(&mut graph, Mode::LineLoop, &positions, Some(&[3, 2, 1, 0]));
+    let lines = add_primitive(&mut graph, Mode::Lines, &positions, None);
+    assert_eq!(graph[strip].primitives[0].triangles(&graph).count(), 0);
+
+    unify_topology(&mut graph);
+    for mesh in [strip, looped, lines] {
+        assert_eq!(graph[mesh].primitives[0].mode, Mode::Lines);
+    }
+    assert_eq!(indices(&graph, strip), vec![0, 1, 1, 2, 2, 3]);
+    assert_eq!(indices(&graph, looped), vec![3, 2, 2, 1, 1, 0, 0, 3]);
+    assert_eq!(indices(&graph, lines), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_unchanged_primitives() {
+    let mut graph = Graph::new();
+    let positions = zigzag(4);
+    let list = add_primitive(
+        &mut graph,
+        Mode::Triangles,
+        &positions,
+        Some(&[0, 1, 2, 2, 1, 3]),
+    );
+    let points = add_primitive(&mut graph, Mode::Points, &positions, None);
+    // Too short to draw a triangle.
+    let short = add_primitive(&mut graph, Mode::TriangleStrip, &positions[..2], None);
+    let before = graph[list].primitives[0].indices;
+
+    unify_topology(&mut graph);
+    assert_eq!(graph[list].primitives[0].indices, before);
+    assert!(graph[points].primitives[0].indices.is_none());
+    assert_eq!(graph[short].primitives[0].mode, Mode::TriangleStrip);
+    assert!(graph[short].primitives[0].indices.is_none());
+}
+
+#[test]
+fn test_non_indexed_triangles() {
+    let mut graph = Graph::new();
+    let positions = zigzag(6);
+    let mesh = add_primitive(&mut graph, Mode::Triangles, &positions, None);
+
+    unify_topology(&mut graph);
+    assert_eq!(indices(&graph, mesh), (0..6).collect::<Vec<_>>());
+    let (document, _) = graph.to_document().unwrap();
+    let primitive = document
+        .meshes()
+        .next()
+        .unwrap()
+        .primitives()
+        .next()
+        .unwrap();
+    assert_eq!(primitive.mode(), Mode::Triangles);
+    assert_eq!(primitive.indices().unwrap().count(), 6);
+}
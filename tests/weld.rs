@@ -0,0 +1,86 @@
+use gltf::accessor::DataType;
+use gltf::graph::{Accessor, Graph, Handle, Mesh, MorphTarget, Primitive};
+use gltf::transform::{weld, WeldOptions};
+use gltf::Semantic;
+use gltf_transform_rs as gltf;
+
+/// Two unindexed triangles forming a quad, with two shared corners.
+const QUAD: [[f32; 3]; 6] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0],
+];
+
+fn quad(graph: &mut Graph, positions: &[[f32; 3]]) -> Handle<Mesh> {
+    let mut primitive = Primitive::default();
+    primitive.set(
+        Semantic::Positions,
+        graph.add(Accessor::from_f32(positions)),
+    );
+    graph.add(Mesh {
+        primitives: vec![primitive],
+        ..Default::default()
+    })
+}
+
+fn welded(graph: &Graph, mesh: Handle<Mesh>) -> (Vec<[f32; 3]>, Vec<u32>, DataType) {
+    let primitive = &graph[mesh].primitives[0];
+    let positions = graph[primitive.get(&Semantic::Positions).unwrap()].to_f32();
+    let indices = &graph[primitive.indices.unwrap()];
+    (positions, indices.to_u32(), indices.data_type())
+}
+
+#[test]
+fn test_weld_exact() {
+    let mut graph = Graph::new();
+    let mesh = quad(&mut graph, &QUAD);
+    weld(&mut graph, &WeldOptions::default());
+
+    let (positions, indices, data_type) = welded(&graph, mesh);
+    assert_eq!(positions.len(), 4);
+    assert_eq!(data_type, DataType::U8);
+    assert_eq!(indices, vec![0, 1, 2, 0, 2, 3]);
+    for (index, expected) in indices.iter().zip(QUAD.iter()) {
+        assert_eq!(positions[*index as usize], *expected);
+    }
+    assert_eq!(graph.count::<Accessor>(), 2);
+}
+
+#[test]
+fn test_weld_tolerance() {
+    let mut nearly = QUAD;
+    nearly[3][0] += 1e-5;
+    nearly[4][1] -= 1e-5;
+
+    let mut graph = Graph::new();
+    let mesh = quad(&mut graph, &nearly);
+    weld(&mut graph, &WeldOptions::default());
+    assert_eq!(welded(&graph, mesh).0.len(), 6);
+
+    let options = WeldOptions { tolerance: 1e-3 };
+    weld(&mut graph, &options);
+    assert_eq!(welded(&graph, mesh).0.len(), 4);
+}
+
+#[test]
+fn test_weld_respects_morph_targets() {
+    let mut graph = Graph::new();
+    let mesh = quad(&mut graph, &QUAD);
+    let mut displacements = [[0.0; 3]; 6];
+    displacements[3] = [0.0, 0.0, 1.0];
+    let target = graph.add(Accessor::from_f32(&displacements));
+    graph[mesh].primitives[0].targets.push(MorphTarget {
+        positions: Some(target),
+        normals: None,
+        tangents: None,
+    });
+    weld(&mut graph, &WeldOptions::default());
+
+    let (positions, _, _) = welded(&graph, mesh);
+    assert_eq!(positions.len(), 5);
+    let target = graph[mesh].primitives[0].targets[0].positions.unwrap();
+    assert_eq!(graph[target].count(), 5);
+}